@@ -16,7 +16,7 @@ use std::sync::{atomic, Arc};
 use std::time::Instant;
 
 use clap::{App, Arg, ArgMatches, SubCommand};
-use psqlpack::{ChainedError, PsqlpackResult, Semver};
+use psqlpack::{ChainedError, FormatOptions, KeywordCase, PsqlpackResult, Semver};
 use slog::{Drain, Logger};
 
 /// A thread safe toggle.
@@ -136,6 +136,40 @@ fn main() {
                         .help("The folder location to export the psqlpack to"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("format")
+                .about("Rewrites the SQL files of a project in a canonical form")
+                .arg(
+                    Arg::with_name("SOURCE")
+                        .long("source")
+                        .short("s")
+                        .required(false)
+                        .takes_value(true)
+                        .help("The path to the source 'psqlproj' project file."),
+                )
+                .arg(
+                    Arg::with_name("INDENT")
+                        .long("indent")
+                        .required(false)
+                        .takes_value(true)
+                        .help("The number of spaces to indent list items by (defaults to 4)"),
+                )
+                .arg(
+                    Arg::with_name("KEYWORD_CASE")
+                        .long("keyword-case")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(&["upper", "lower"])
+                        .help("Whether keywords are written in upper or lower case (defaults to upper)"),
+                )
+                .arg(
+                    Arg::with_name("LINE_WIDTH")
+                        .long("line-width")
+                        .required(false)
+                        .takes_value(true)
+                        .help("The width lines are kept within where possible (defaults to 80)"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("new")
                 .about("Creates a new project or publish profile based upon the specified template")
@@ -373,6 +407,41 @@ fn handle(log: &Logger, matches: &ArgMatches) -> HandleResult {
             let result = operation::extract_database(log, &source, output);
             HandleResult::Outcome(command.to_owned(), result)
         }
+        (command @ "format", Some(format)) => {
+            let log = log.new(o!("command" => command.to_owned()));
+            // If the source is provided, use that, else use the current dir + project.json
+            let source = match format.value_of("SOURCE") {
+                Some(cmd_source) => cmd_source.into(),
+                None => {
+                    let mut path = env::current_dir().unwrap().to_path_buf();
+                    path.push("project.json");
+                    path
+                }
+            };
+            info!(log, "Project file path"; "source" => source.to_str().unwrap());
+            let mut options = FormatOptions::default();
+            if let Some(indent) = format.value_of("INDENT") {
+                options.indent = match indent.parse() {
+                    Ok(indent) => indent,
+                    Err(_) => return HandleResult::InvalidArgument("indent".into(), "Expected a number".into()),
+                };
+            }
+            if let Some(keyword_case) = format.value_of("KEYWORD_CASE") {
+                options.keyword_case = match keyword_case {
+                    "upper" => KeywordCase::Upper,
+                    "lower" => KeywordCase::Lower,
+                    _ => return HandleResult::InvalidArgument("keyword-case".into(), "Expected upper or lower".into()),
+                };
+            }
+            if let Some(line_width) = format.value_of("LINE_WIDTH") {
+                options.line_width = match line_width.parse() {
+                    Ok(line_width) => line_width,
+                    Err(_) => return HandleResult::InvalidArgument("line-width".into(), "Expected a number".into()),
+                };
+            }
+            let result = operation::format(log, &source, &options);
+            HandleResult::Outcome(command.to_owned(), result)
+        }
         (command @ "new", Some(new)) => {
             let log = log.new(o!("command" => command.to_owned()));
             let template = String::from(new.value_of("TEMPLATE").unwrap());
@@ -3,7 +3,8 @@ use std::path::Path;
 use slog::Logger;
 
 use psqlpack::{
-    template, Capabilities, Delta, Package, Project, PsqlpackErrorKind, PsqlpackResult, PublishProfile, Semver,
+    template, Capabilities, Delta, FormatOptions, Package, Project, PsqlpackErrorKind, PsqlpackResult, PublishProfile,
+    Semver,
 };
 
 pub fn package<L: Into<Logger>>(log: L, project_file: &Path, output_path: &Path) -> PsqlpackResult<()> {
@@ -16,6 +17,21 @@ pub fn package<L: Into<Logger>>(log: L, project_file: &Path, output_path: &Path)
     package.write_to(output_path)
 }
 
+pub fn format<L: Into<Logger>>(log: L, project_file: &Path, options: &FormatOptions) -> PsqlpackResult<()> {
+    let log = log.into().new(o!("operation" => "format"));
+    trace!(log, "Loading Project from project file"; "source" => project_file.to_str().unwrap());
+    let project = Project::from_project_file(&log, project_file)?;
+    trace!(log, "Formatting Project files");
+    let files = project.format_files(&log, options)?;
+    for path in files.changed {
+        info!(log, "Formatted file"; "file" => path.to_str().unwrap());
+    }
+    for (path, reason) in files.skipped {
+        warn!(log, "Skipped file"; "file" => path.to_str().unwrap(), "reason" => reason);
+    }
+    Ok(())
+}
+
 pub fn extract_database<L: Into<Logger>>(
     log: L,
     source_connection_string: &str,
@@ -100,12 +116,13 @@ pub fn publish<L: Into<Logger>>(
     publish_profile: &Path,
 ) -> PsqlpackResult<()> {
     let log = log.into().new(o!("operation" => "publish"));
-    let package = Package::from_path(&log, source_file)?;
+    let mut package = Package::from_path(&log, source_file)?;
     let publish_profile = PublishProfile::from_path(publish_profile)?;
     let connection = target_connection_string.parse()?;
 
     trace!(log, "Loading Server Capabilities");
     let capabilities = Capabilities::from_connection(&log, &connection)?;
+    package.resolve_views(&log, &connection, &capabilities)?;
 
     // Now we generate our instructions
     let target_package = Package::from_connection(&log, &connection, &capabilities)?;
@@ -129,12 +146,13 @@ pub fn generate_sql<L: Into<Logger>>(
     output_file: &Path,
 ) -> PsqlpackResult<()> {
     let log = log.into().new(o!("operation" => "generate_sql"));
-    let package = Package::from_path(&log, source_package_path)?;
+    let mut package = Package::from_path(&log, source_package_path)?;
     let publish_profile = PublishProfile::from_path(publish_profile)?;
     let connection = target_connection_string.parse()?;
 
     trace!(log, "Loading Server Capabilities");
     let capabilities = Capabilities::from_connection(&log, &connection)?;
+    package.resolve_views(&log, &connection, &capabilities)?;
 
     // Now we generate our instructions
     let target_package = Package::from_connection(&log, &connection, &capabilities)?;
@@ -158,12 +176,13 @@ pub fn generate_report<L: Into<Logger>>(
     output_file: &Path,
 ) -> PsqlpackResult<()> {
     let log = log.into().new(o!("operation" => "generate_report"));
-    let package = Package::from_path(&log, source_package_path)?;
+    let mut package = Package::from_path(&log, source_package_path)?;
     let publish_profile = PublishProfile::from_path(publish_profile)?;
     let connection = target_connection_string.parse()?;
 
     trace!(log, "Loading Server Capabilities");
     let capabilities = Capabilities::from_connection(&log, &connection)?;
+    package.resolve_views(&log, &connection, &capabilities)?;
 
     // Now we generate our instructions
     let target_package = Package::from_connection(&log, &connection, &capabilities)?;
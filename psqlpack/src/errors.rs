@@ -6,6 +6,7 @@ pub use error_chain::ChainedError;
 pub use lalrpop_util::ParseError;
 
 pub use crate::ast::ErrorKind;
+use crate::ast::Location;
 use crate::connection::{ConnectionError, ConnectionErrorKind};
 pub use crate::model::ValidationKind;
 use crate::sql::lexer;
@@ -66,6 +67,12 @@ error_chain! {
         PackageQueryFunctionsError {
             description("Couldn't query functions")
         }
+        PackageQueryProceduresError {
+            description("Couldn't query procedures")
+        }
+        PackageQueryAggregatesError {
+            description("Couldn't query aggregates")
+        }
         PackageQueryTablesError {
             description("Couldn't query tables")
         }
@@ -78,6 +85,33 @@ error_chain! {
         PackageQueryIndexesError {
             description("Couldn't query indexes")
         }
+        PackageQueryViewsError {
+            description("Couldn't query views")
+        }
+        PackageQueryMaterializedViewsError {
+            description("Couldn't query materialized views")
+        }
+        PackageResolveViewsError {
+            description("Couldn't resolve views against the target database")
+        }
+        PackageQueryTriggersError {
+            description("Couldn't query triggers")
+        }
+        PackageQuerySequencesError {
+            description("Couldn't query sequences")
+        }
+        PackageQueryPrivilegesError {
+            description("Couldn't query privileges")
+        }
+        PackageQueryCommentsError {
+            description("Couldn't query comments")
+        }
+        PackageQueryPoliciesError {
+            description("Couldn't query row level security policies")
+        }
+        PackageQueryPartitionsError {
+            description("Couldn't query table partitions")
+        }
         PackageFunctionArgsInspectError(args: String) {
             description("Couldn't inspect function args")
             display("Couldn't inspect function args: {}", args)
@@ -86,6 +120,26 @@ error_chain! {
             description("Couldn't inspect function return type")
             display("Couldn't inspect function return type: {}", return_type)
         }
+        PackageDomainDefaultInspectError(default: String) {
+            description("Couldn't inspect domain default")
+            display("Couldn't inspect domain default: {}", default)
+        }
+        PackageColumnDefaultInspectError(default: String) {
+            description("Couldn't inspect column default")
+            display("Couldn't inspect column default: {}", default)
+        }
+        PackageIndexInspectError(definition: String) {
+            description("Couldn't inspect index definition")
+            display("Couldn't inspect index definition: {}", definition)
+        }
+        PackageTriggerInspectError(definition: String) {
+            description("Couldn't inspect trigger definition")
+            display("Couldn't inspect trigger definition: {}", definition)
+        }
+        PackagePartitionInspectError(definition: String) {
+            description("Couldn't inspect partition definition")
+            display("Couldn't inspect partition definition: {}", definition)
+        }
         PublishInvalidOperationError(message: String) {
             description("Couldn't publish database due to an invalid operation")
             display("Couldn't publish database due to an invalid operation: {}", message)
@@ -113,11 +167,11 @@ error_chain! {
                 "SQL syntax error encountered in {} on line {},{}:\n{}",
                 file, line_number, *start, LineFormatter(line, *start, *end))
         }
-        ParseError(file: String, errors: Vec<ParseError<(), lexer::Token, &'static str>>) {
+        ParseError(file: String, errors: Vec<ParseError<Location, lexer::Token, &'static str>>) {
             description("Parser error")
             display("Parser errors in {}:\n{}", file, ParseErrorsFormatter(errors))
         }
-        InlineParseError(error: ParseError<(), lexer::Token, &'static str>) {
+        InlineParseError(error: Box<ParseError<Location, lexer::Token, &'static str>>) {
             description("Parser error")
             display("Parser error: {}", ParseErrorFormatter(error))
         }
@@ -175,21 +229,24 @@ error_chain! {
     }
 }
 
-fn write_err(f: &mut Formatter, error: &ParseError<(), lexer::Token, &'static str>) -> Result {
+fn write_err(f: &mut Formatter, error: &ParseError<Location, lexer::Token, &'static str>) -> Result {
     match *error {
-        ParseError::InvalidToken { .. } => write!(f, "Invalid token"),
+        ParseError::InvalidToken { ref location } => write!(f, "Invalid token at {}", location),
         ParseError::UnrecognizedToken {
             ref token,
             ref expected,
         } => {
-            writeln!(f, "Unexpected {:?}", token.1)?;
+            writeln!(f, "Unexpected {:?} at {}", token.1, token.0)?;
             write!(f, "   Expected one of:\n   {}", expected.join(", "))
         }
-        ParseError::UnrecognizedEOF { ref expected, .. } => {
-            writeln!(f, "Unexpected end of file")?;
+        ParseError::UnrecognizedEOF {
+            ref location,
+            ref expected,
+        } => {
+            writeln!(f, "Unexpected end of file at {}", location)?;
             write!(f, "   Expected one of:\n   {}", expected.join(", "))
         }
-        ParseError::ExtraToken { ref token } => write!(f, "Extra token detected: {:?}", token),
+        ParseError::ExtraToken { ref token } => write!(f, "Extra token {:?} detected at {}", token.1, token.0),
         ParseError::User { ref error } => write!(f, "{:?}", error),
     }
 }
@@ -220,7 +277,7 @@ impl<'fmt> Display for LineFormatter<'fmt> {
     }
 }
 
-struct ParseErrorsFormatter<'fmt>(&'fmt Vec<ParseError<(), lexer::Token, &'static str>>);
+struct ParseErrorsFormatter<'fmt>(&'fmt Vec<ParseError<Location, lexer::Token, &'static str>>);
 
 impl<'fmt> Display for ParseErrorsFormatter<'fmt> {
     fn fmt(&self, f: &mut Formatter) -> Result {
@@ -232,7 +289,7 @@ impl<'fmt> Display for ParseErrorsFormatter<'fmt> {
     }
 }
 
-struct ParseErrorFormatter<'fmt>(&'fmt ParseError<(), lexer::Token, &'static str>);
+struct ParseErrorFormatter<'fmt>(&'fmt ParseError<Location, lexer::Token, &'static str>);
 
 impl<'fmt> Display for ParseErrorFormatter<'fmt> {
     fn fmt(&self, f: &mut Formatter) -> Result {
@@ -256,7 +313,10 @@ struct ValidationErrorFormatter<'fmt>(&'fmt Vec<ValidationKind>);
 impl<'fmt> Display for ValidationErrorFormatter<'fmt> {
     fn fmt(&self, f: &mut Formatter) -> Result {
         for error in self.0.iter() {
-            writeln!(f, " - {}", error)?;
+            match error.span() {
+                Some(span) => writeln!(f, " - {}: {}", span, error)?,
+                None => writeln!(f, " - {}", error)?,
+            }
         }
         Ok(())
     }
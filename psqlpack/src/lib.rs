@@ -8,6 +8,8 @@ extern crate lalrpop_util;
 extern crate lazy_static;
 extern crate petgraph;
 extern crate postgres;
+#[cfg(test)]
+extern crate quickcheck;
 extern crate regex;
 extern crate rust_decimal;
 extern crate serde;
@@ -36,9 +38,11 @@ pub mod ast {
 pub use crate::connection::ConnectionBuilder;
 pub use crate::errors::{PsqlpackErrorKind, PsqlpackResult};
 pub use crate::model::{
-    template, Capabilities, Delta, Dependency, GenerationOptions, Package, Project, PublishProfile, Toggle,
+    template, Capabilities, Delta, Dependency, FormattedFiles, GenerationOptions, Package, Project, PublishProfile,
+    Toggle, UnsupportedStatements,
 };
 pub use crate::semver::Semver;
+pub use crate::sql::formatter::{FormatOptions, Formatter, KeywordCase};
 
 /// Allows usage of no logging, std `log`, or slog.
 pub enum LogConfig {
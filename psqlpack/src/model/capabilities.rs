@@ -8,10 +8,13 @@ use crate::errors::{PsqlpackError, PsqlpackResult, PsqlpackResultExt};
 use crate::model::Extension;
 use crate::semver::Semver;
 use crate::sql::lexer;
-use crate::sql::parser::{FunctionArgumentListParser, FunctionReturnTypeParser, SqlTypeParser};
+use crate::sql::parser::{
+    AnyValueParser, FunctionArgumentListParser, FunctionReturnTypeParser, IndexDefinitionParser, PartitionBoundParser,
+    PartitionKeyParser, SqlTypeParser, TriggerDefinitionParser,
+};
 
 use postgres::row::Row;
-use postgres::types::{FromSql, Type};
+use postgres::types::{FromSql, ToSql, Type};
 use postgres::Client as PostgresClient;
 use regex::Regex;
 use slog::Logger;
@@ -93,9 +96,17 @@ pub(crate) struct ExtensionCapabilities<'a> {
 pub trait DefinableCatalog {
     fn schemata(&self, client: &mut PostgresClient, database: &str) -> PsqlpackResult<Vec<SchemaDefinition>>;
     fn types(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TypeDefinition>>;
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>>;
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>>;
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>>;
+    fn aggregates(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<AggregateDefinition>>;
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>>;
     fn indexes(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<IndexDefinition>>;
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>>;
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>>;
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>>;
+    fn privileges(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<PrivilegeDefinition>>;
+    fn comments(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<CommentDefinition>>;
 }
 
 impl DefinableCatalog for Capabilities {
@@ -114,15 +125,26 @@ impl DefinableCatalog for Capabilities {
             .query(&format!("{} {}", CTE_TYPES, Q_CTE_STANDARD)[..], &[])
             .chain_err(|| PackageQueryTypesError)?
             .iter()
-            .map(|row| row.into())
-            .collect();
+            .map(parse_type)
+            .collect::<PsqlpackResult<Vec<_>>>()?;
         Ok(types)
     }
 
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>> {
+        query_sequences(client, &self.server_version, Q_CTE_STANDARD, &[])
+    }
+
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>> {
         let mut functions = Vec::new();
+        let cte = if self.server_version < Semver::new(9, 6, None) {
+            CTE_FUNCTIONS_94_THRU_96
+        } else if self.server_version < Semver::new(11, 0, None) {
+            CTE_FUNCTIONS_96_THRU_11
+        } else {
+            CTE_FUNCTIONS
+        };
         let query = &client
-            .query(&format!("{} {}", CTE_FUNCTIONS, Q_CTE_STANDARD)[..], &[])
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
             .chain_err(|| PackageQueryFunctionsError)?;
         for row in query {
             let function = parse_function(&row)?;
@@ -131,6 +153,38 @@ impl DefinableCatalog for Capabilities {
         Ok(functions)
     }
 
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>> {
+        // Procedures were introduced in 11
+        if self.server_version < Semver::new(11, 0, None) {
+            return Ok(Vec::new());
+        }
+        let mut procedures = Vec::new();
+        let query = &client
+            .query(&format!("{} {}", CTE_PROCEDURES, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryProceduresError)?;
+        for row in query {
+            let procedure = parse_procedure(&row)?;
+            procedures.push(procedure);
+        }
+        Ok(procedures)
+    }
+
+    fn aggregates(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<AggregateDefinition>> {
+        let mut aggregates = Vec::new();
+        let cte = match self.server_version.cmp(&Semver::new(9, 6, None)) {
+            ::std::cmp::Ordering::Less => CTE_AGGREGATES_94_THRU_96,
+            _ => CTE_AGGREGATES,
+        };
+        let query = &client
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryAggregatesError)?;
+        for row in query {
+            let aggregate = parse_aggregate(&row)?;
+            aggregates.push(aggregate);
+        }
+        Ok(aggregates)
+    }
+
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>> {
         let mut tables = HashMap::new();
         let query = &client
@@ -138,7 +192,22 @@ impl DefinableCatalog for Capabilities {
             .chain_err(|| PackageQueryTablesError)?;
         for row in query {
             let table: TableDefinition = row.into();
-            tables.insert(table.name.to_string(), table);
+            tables.insert(format!("{}.{}", table.name.schema(), table.name.name), table);
+        }
+
+        // Stored generated columns were introduced in 12. Since the generation expression is stored as the
+        // column default these are retrieved first.
+        let mut generated = HashMap::new();
+        if self.server_version >= Semver::new(12, 0, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_GENERATED_COLUMNS, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryColumnsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                let name: String = row.get(2);
+                let expression: String = row.get(3);
+                generated.insert((fqn, name), expression);
+            }
         }
 
         // Get a list of columns and map them to the appropriate tables
@@ -151,7 +220,22 @@ impl DefinableCatalog for Capabilities {
         for row in query {
             let fqn: String = row.get(1);
             if let Some(definition) = tables.get_mut(&fqn) {
-                definition.columns.push(row.into());
+                let name: String = row.get(5);
+                let expression = generated.remove(&(fqn, name));
+                definition.columns.push(parse_column(&row, expression)?);
+            }
+        }
+
+        // Identity columns were introduced in 10
+        if self.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_IDENTITY_COLUMNS, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryColumnsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    parse_identity(&row, definition);
+                }
             }
         }
 
@@ -169,6 +253,75 @@ impl DefinableCatalog for Capabilities {
             }
         }
 
+        // Check constraints aren't described by key column usage so are retrieved separately
+        let query = &client
+            .query(
+                &format!("{} {} ORDER BY fqn, name", CTE_CHECK_CONSTRAINTS, Q_CTE_STANDARD)[..],
+                &[],
+            )
+            .chain_err(|| PackageQueryTableConstraintsError)?;
+        for row in query {
+            let fqn: String = row.get(1);
+            if let Some(definition) = tables.get_mut(&fqn) {
+                definition.constraints.push(parse_check_constraint(&row));
+            }
+        }
+
+        // As are unique and exclusion constraints since we need the index details
+        let query = &client
+            .query(
+                &format!("{} {} ORDER BY fqn, name", CTE_UNIQUE_CONSTRAINTS, Q_CTE_STANDARD)[..],
+                &[],
+            )
+            .chain_err(|| PackageQueryTableConstraintsError)?;
+        for row in query {
+            let fqn: String = row.get(1);
+            if let Some(definition) = tables.get_mut(&fqn) {
+                definition.constraints.push(parse_unique_constraint(&row));
+            }
+        }
+
+        // Row level security was introduced in 9.5, with restrictive policies following in 10
+        if self.server_version >= Semver::new(9, 5, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_ROW_SECURITY, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryPoliciesError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    definition.row_security = row.get(2);
+                    definition.force_row_security = row.get(3);
+                }
+            }
+
+            let cte = match self.server_version.cmp(&Semver::new(10, 0, None)) {
+                ::std::cmp::Ordering::Less => CTE_POLICIES_95_THRU_96,
+                _ => CTE_POLICIES,
+            };
+            let query = &client
+                .query(&format!("{} {} ORDER BY fqn, name", cte, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryPoliciesError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    definition.policies.push(parse_policy(&row));
+                }
+            }
+        }
+
+        // Declarative partitioning was introduced in 10
+        if self.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_PARTITIONS, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryPartitionsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    parse_partition(&row, definition)?;
+                }
+            }
+        }
+
         Ok(tables.into_iter().map(|(_, b)| b).collect())
     }
 
@@ -183,11 +336,59 @@ impl DefinableCatalog for Capabilities {
             .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
             .chain_err(|| PackageQueryIndexesError)?;
         for row in query {
-            let index: IndexDefinition = row.into();
-            indexes.push(index);
+            indexes.push(parse_index(&row)?);
         }
         Ok(indexes)
     }
+
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>> {
+        let mut triggers = Vec::new();
+        let query = &client
+            .query(&format!("{} {}", CTE_TRIGGERS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryTriggersError)?;
+        for row in query {
+            triggers.push(parse_trigger(&row)?);
+        }
+        Ok(triggers)
+    }
+
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>> {
+        let views = client
+            .query(&format!("{} {}", CTE_VIEWS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>> {
+        let views = client
+            .query(&format!("{} {}", CTE_MATERIALIZED_VIEWS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryMaterializedViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn privileges(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<PrivilegeDefinition>> {
+        client
+            .query(&format!("{} {}", CTE_PRIVILEGES, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryPrivilegesError)?
+            .iter()
+            .filter_map(|row| parse_privilege(row).transpose())
+            .collect()
+    }
+
+    fn comments(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<CommentDefinition>> {
+        client
+            .query(&format!("{} {}", CTE_COMMENTS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryCommentsError)?
+            .iter()
+            .filter_map(|row| parse_comment(row).transpose())
+            .collect()
+    }
 }
 
 impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
@@ -204,18 +405,31 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             )
             .chain_err(|| PackageQueryTypesError)?
             .iter()
-            .map(|row| row.into())
-            .collect();
+            .map(parse_type)
+            .collect::<PsqlpackResult<Vec<_>>>()?;
         Ok(types)
     }
 
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>> {
+        query_sequences(
+            client,
+            &self.capabilities.server_version,
+            Q_CTE_EXTENSION,
+            &[&self.extension.name],
+        )
+    }
+
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>> {
         let mut functions = Vec::new();
+        let cte = if self.capabilities.server_version < Semver::new(9, 6, None) {
+            CTE_FUNCTIONS_94_THRU_96
+        } else if self.capabilities.server_version < Semver::new(11, 0, None) {
+            CTE_FUNCTIONS_96_THRU_11
+        } else {
+            CTE_FUNCTIONS
+        };
         let query = &client
-            .query(
-                &format!("{} {}", CTE_FUNCTIONS, Q_CTE_EXTENSION)[..],
-                &[&self.extension.name],
-            )
+            .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
             .chain_err(|| PackageQueryFunctionsError)?;
         for row in query {
             let function = parse_function(&row)?;
@@ -224,6 +438,41 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
         Ok(functions)
     }
 
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>> {
+        // Procedures were introduced in 11
+        if self.capabilities.server_version < Semver::new(11, 0, None) {
+            return Ok(Vec::new());
+        }
+        let mut procedures = Vec::new();
+        let query = &client
+            .query(
+                &format!("{} {}", CTE_PROCEDURES, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryProceduresError)?;
+        for row in query {
+            let procedure = parse_procedure(&row)?;
+            procedures.push(procedure);
+        }
+        Ok(procedures)
+    }
+
+    fn aggregates(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<AggregateDefinition>> {
+        let mut aggregates = Vec::new();
+        let cte = match self.capabilities.server_version.cmp(&Semver::new(9, 6, None)) {
+            ::std::cmp::Ordering::Less => CTE_AGGREGATES_94_THRU_96,
+            _ => CTE_AGGREGATES,
+        };
+        let query = &client
+            .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+            .chain_err(|| PackageQueryAggregatesError)?;
+        for row in query {
+            let aggregate = parse_aggregate(&row)?;
+            aggregates.push(aggregate);
+        }
+        Ok(aggregates)
+    }
+
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>> {
         let mut tables = HashMap::new();
         let query = &client
@@ -234,7 +483,25 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             .chain_err(|| PackageQueryTablesError)?;
         for row in query {
             let table: TableDefinition = row.into();
-            tables.insert(table.name.to_string(), table);
+            tables.insert(format!("{}.{}", table.name.schema(), table.name.name), table);
+        }
+
+        // Stored generated columns were introduced in 12. Since the generation expression is stored as the
+        // column default these are retrieved first.
+        let mut generated = HashMap::new();
+        if self.capabilities.server_version >= Semver::new(12, 0, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_GENERATED_COLUMNS, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryColumnsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                let name: String = row.get(2);
+                let expression: String = row.get(3);
+                generated.insert((fqn, name), expression);
+            }
         }
 
         // Get a list of columns and map them to the appropriate tables
@@ -247,7 +514,25 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
         for row in query {
             let fqn: String = row.get(1);
             if let Some(definition) = tables.get_mut(&fqn) {
-                definition.columns.push(row.into());
+                let name: String = row.get(5);
+                let expression = generated.remove(&(fqn, name));
+                definition.columns.push(parse_column(&row, expression)?);
+            }
+        }
+
+        // Identity columns were introduced in 10
+        if self.capabilities.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_IDENTITY_COLUMNS, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryColumnsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    parse_identity(&row, definition);
+                }
             }
         }
 
@@ -265,6 +550,78 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             }
         }
 
+        // Check constraints aren't described by key column usage so are retrieved separately
+        let query = &client
+            .query(
+                &format!("{} {} ORDER BY fqn, name", CTE_CHECK_CONSTRAINTS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryTableConstraintsError)?;
+        for row in query {
+            let fqn: String = row.get(1);
+            if let Some(definition) = tables.get_mut(&fqn) {
+                definition.constraints.push(parse_check_constraint(&row));
+            }
+        }
+
+        // As are unique and exclusion constraints since we need the index details
+        let query = &client
+            .query(
+                &format!("{} {} ORDER BY fqn, name", CTE_UNIQUE_CONSTRAINTS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryTableConstraintsError)?;
+        for row in query {
+            let fqn: String = row.get(1);
+            if let Some(definition) = tables.get_mut(&fqn) {
+                definition.constraints.push(parse_unique_constraint(&row));
+            }
+        }
+
+        // Row level security was introduced in 9.5, with restrictive policies following in 10
+        if self.capabilities.server_version >= Semver::new(9, 5, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_ROW_SECURITY, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+                .chain_err(|| PackageQueryPoliciesError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    definition.row_security = row.get(2);
+                    definition.force_row_security = row.get(3);
+                }
+            }
+
+            let cte = match self.capabilities.server_version.cmp(&Semver::new(10, 0, None)) {
+                ::std::cmp::Ordering::Less => CTE_POLICIES_95_THRU_96,
+                _ => CTE_POLICIES,
+            };
+            let query = &client
+                .query(&format!("{} {} ORDER BY fqn, name", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+                .chain_err(|| PackageQueryPoliciesError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    definition.policies.push(parse_policy(&row));
+                }
+            }
+        }
+
+        // Declarative partitioning was introduced in 10
+        if self.capabilities.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_PARTITIONS, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryPartitionsError)?;
+            for row in query {
+                let fqn: String = row.get(1);
+                if let Some(definition) = tables.get_mut(&fqn) {
+                    parse_partition(&row, definition)?;
+                }
+            }
+        }
+
         Ok(tables.into_iter().map(|(_, b)| b).collect())
     }
 
@@ -279,11 +636,60 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
             .chain_err(|| PackageQueryIndexesError)?;
         for row in query {
-            let index: IndexDefinition = row.into();
-            indexes.push(index);
+            indexes.push(parse_index(&row)?);
         }
         Ok(indexes)
     }
+
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>> {
+        let mut triggers = Vec::new();
+        let query = &client
+            .query(
+                &format!("{} {}", CTE_TRIGGERS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryTriggersError)?;
+        for row in query {
+            triggers.push(parse_trigger(&row)?);
+        }
+        Ok(triggers)
+    }
+
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>> {
+        let views = client
+            .query(
+                &format!("{} {}", CTE_VIEWS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>> {
+        let views = client
+            .query(
+                &format!("{} {}", CTE_MATERIALIZED_VIEWS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryMaterializedViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn privileges(&self, _client: &mut PostgresClient) -> PsqlpackResult<Vec<PrivilegeDefinition>> {
+        // Privileges on extension objects are managed by the extension itself
+        Ok(Vec::new())
+    }
+
+    fn comments(&self, _client: &mut PostgresClient) -> PsqlpackResult<Vec<CommentDefinition>> {
+        // Likewise for comments
+        Ok(Vec::new())
+    }
 }
 
 impl<'a> FromSql<'a> for Semver {
@@ -301,10 +707,11 @@ impl<'a> FromSql<'a> for Semver {
 static Q_DATABASE_EXISTS: &str = "SELECT 1 FROM pg_database WHERE datname=$1;";
 static Q_EXTENSIONS: &str = "SELECT name, version, installed, requires
                                      FROM pg_available_extension_versions ";
+// A partitioned table internally depends upon its own key columns so self references are ignored
 static Q_CTE_STANDARD: &str = "
     SELECT c.*
     FROM cte c
-    WHERE NOT EXISTS (SELECT 1 FROM pg_depend WHERE pg_depend.objid=c.oid AND deptype IN ('e','i'))";
+    WHERE NOT EXISTS (SELECT 1 FROM pg_depend WHERE pg_depend.objid=c.oid AND pg_depend.refobjid<>c.oid AND deptype IN ('e','i'))";
 static Q_CTE_EXTENSION: &str = "
     SELECT c.*
     FROM cte c
@@ -326,54 +733,230 @@ static Q_SCHEMAS: &str = "SELECT schema_name FROM information_schema.schemata
                                   WHERE catalog_name = $1 AND schema_name !~* 'pg_|information_schema'";
 impl<'row> From<&Row> for SchemaDefinition {
     fn from(row: &Row) -> Self {
-        SchemaDefinition { name: row.get(0) }
+        SchemaDefinition {
+            name: row.get(0),
+            span: Span::default(),
+        }
     }
 }
 
 // Types: https://www.postgresql.org/docs/9.6/sql-createtype.html
 // typcategory: https://www.postgresql.org/docs/9.6/catalog-pg-type.html#CATALOG-TYPCATEGORY-TABLE
+// Type names are only qualified by format_type when they're outside of the search path. Since source
+// packages always qualify custom types we do the same here.
 static CTE_TYPES: &str = "
     WITH cte AS (
-        SELECT pg_type.oid, typcategory, nspname, typname, array_agg(labels.enumlabel) AS enumlabels
-        FROM pg_type
-        INNER JOIN pg_namespace ON pg_namespace.oid=typnamespace
-        LEFT JOIN (
-            SELECT enumtypid, enumlabel
-            FROM pg_catalog.pg_enum
-            ORDER BY enumtypid, enumsortorder
-        ) labels ON labels.enumtypid=pg_type.oid
+        SELECT
+            t.oid,
+            t.typtype,
+            ns.nspname,
+            t.typname,
+            ARRAY(
+                SELECT e.enumlabel::text
+                FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+                ORDER BY e.enumsortorder
+            ) AS enum_labels,
+            ARRAY(
+                SELECT a.attname::text
+                FROM pg_attribute a
+                WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+            ) AS attribute_names,
+            ARRAY(
+                SELECT CASE WHEN an.nspname IN ('pg_catalog', 'information_schema')
+                              OR format_type(a.atttypid, a.atttypmod) LIKE '%.%'
+                            THEN format_type(a.atttypid, a.atttypmod)
+                            ELSE quote_ident(an.nspname) || '.' || format_type(a.atttypid, a.atttypmod)
+                       END
+                FROM pg_attribute a
+                INNER JOIN pg_type at ON at.oid = a.atttypid
+                INNER JOIN pg_namespace an ON an.oid = at.typnamespace
+                WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+            ) AS attribute_types,
+            CASE WHEN t.typtype <> 'd' THEN NULL
+                 WHEN bn.nspname IN ('pg_catalog', 'information_schema')
+                   OR format_type(t.typbasetype, t.typtypmod) LIKE '%.%'
+                 THEN format_type(t.typbasetype, t.typtypmod)
+                 ELSE quote_ident(bn.nspname) || '.' || format_type(t.typbasetype, t.typtypmod)
+            END AS base_type,
+            t.typnotnull,
+            t.typdefault,
+            ARRAY(
+                SELECT con.conname::text
+                FROM pg_constraint con
+                WHERE con.contypid = t.oid AND con.contype = 'c'
+                ORDER BY con.conname
+            ) AS constraint_names,
+            ARRAY(
+                SELECT pg_get_constraintdef(con.oid)
+                FROM pg_constraint con
+                WHERE con.contypid = t.oid AND con.contype = 'c'
+                ORDER BY con.conname
+            ) AS constraint_definitions,
+            CASE WHEN t.typtype <> 'r' THEN NULL
+                 WHEN sn.nspname IN ('pg_catalog', 'information_schema')
+                   OR format_type(r.rngsubtype, NULL) LIKE '%.%'
+                 THEN format_type(r.rngsubtype, NULL)
+                 ELSE quote_ident(sn.nspname) || '.' || format_type(r.rngsubtype, NULL)
+            END AS subtype,
+            CASE WHEN opc.opcdefault THEN NULL ELSE opc.opcname::text END AS subtype_opclass,
+            CASE WHEN r.rngcollation = 0 OR r.rngcollation = st.typcollation THEN NULL
+                 ELSE coll.collname::text
+            END AS collation,
+            CASE WHEN r.rngcanonical::oid = 0 THEN NULL ELSE r.rngcanonical::text END AS canonical,
+            CASE WHEN r.rngsubdiff::oid = 0 THEN NULL ELSE r.rngsubdiff::text END AS subtype_diff
+        FROM pg_type t
+        INNER JOIN pg_namespace ns ON ns.oid = t.typnamespace
+        LEFT JOIN pg_type bt ON bt.oid = t.typbasetype
+        LEFT JOIN pg_namespace bn ON bn.oid = bt.typnamespace
+        LEFT JOIN pg_range r ON r.rngtypid = t.oid
+        LEFT JOIN pg_type st ON st.oid = r.rngsubtype
+        LEFT JOIN pg_namespace sn ON sn.oid = st.typnamespace
+        LEFT JOIN pg_opclass opc ON opc.oid = r.rngsubopc
+        LEFT JOIN pg_collation coll ON coll.oid = r.rngcollation
         WHERE
             -- exclude pg schemas and information catalog
-            nspname !~* 'pg_|information_schema' AND
+            ns.nspname !~* 'pg_|information_schema' AND
             -- Types beginning with _ are auto created (e.g. arrays)
-            typname !~ '^_'
-        GROUP BY pg_type.oid, typcategory, nspname, typname
-        ORDER BY pg_type.oid, typcategory, nspname, typname
+            t.typname !~ '^_' AND
+            -- Table row types and multiranges are created implicitly alongside their owner
+            t.typtype IN ('b', 'c', 'd', 'e', 'r') AND
+            (t.typtype <> 'c' OR EXISTS (SELECT 1 FROM pg_class c WHERE c.oid = t.typrelid AND c.relkind = 'c'))
+        ORDER BY t.oid, ns.nspname, t.typname
     )
 ";
 
-impl<'row> From<&Row> for TypeDefinition {
-    fn from(row: &Row) -> Self {
-        let category: i8 = row.get(1);
-        let category = category as u8;
-        let schema = row.get(2);
-        let name = row.get(3);
-        let kind = match category as char {
-            // TODO: All types
-            'C' => TypeDefinitionKind::Composite, // TODO add composite details
-            'E' => TypeDefinitionKind::Enum(row.get(4)),
-            'R' => TypeDefinitionKind::Range, // TODO add range details
-            'U' => TypeDefinitionKind::UserDefined,
-            kind => panic!("Unexpected kind: {}", kind),
-        };
-
-        TypeDefinition {
-            name: ObjectName { schema, name },
-            kind,
+fn parse_type(row: &Row) -> PsqlpackResult<TypeDefinition> {
+    let kind: i8 = row.get(1);
+    let schema = row.get(2);
+    let name = row.get(3);
+    let kind = match kind as u8 as char {
+        'c' => {
+            let names: Vec<String> = row.get(5);
+            let types: Vec<String> = row.get(6);
+            TypeDefinitionKind::Composite(
+                names
+                    .into_iter()
+                    .zip(types)
+                    .map(|(name, sql_type)| CompositeTypeAttribute {
+                        name,
+                        sql_type: sql_type.into(),
+                    })
+                    .collect(),
+            )
         }
-    }
+        'd' => {
+            let base_type: String = row.get(7);
+            let default: Option<String> = row.get(9);
+            let default = match default {
+                Some(default) => Some(
+                    lexer::tokenize_body(&default)
+                        .map_err(lexical)
+                        .and_then(|tokens| AnyValueParser::new().parse(tokens).map_err(parse))
+                        .chain_err(|| PackageDomainDefaultInspectError(default))?,
+                ),
+                None => None,
+            };
+            let names: Vec<String> = row.get(10);
+            let definitions: Vec<String> = row.get(11);
+            TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: base_type.into(),
+                default,
+                not_null: row.get(8),
+                constraints: names
+                    .into_iter()
+                    .zip(definitions)
+                    .map(|(name, definition)| DomainConstraint {
+                        name: Some(name),
+                        expression: check_expression(&definition),
+                    })
+                    .collect(),
+            })
+        }
+        'e' => TypeDefinitionKind::Enum(row.get(4)),
+        'r' => {
+            let subtype: String = row.get(12);
+            TypeDefinitionKind::Range(RangeDefinition {
+                subtype: subtype.into(),
+                subtype_opclass: row.get(13),
+                collation: row.get(14),
+                canonical: row.get(15),
+                subtype_diff: row.get(16),
+            })
+        }
+        _ => TypeDefinitionKind::UserDefined,
+    };
+
+    Ok(TypeDefinition {
+        name: ObjectName { schema, name },
+        kind,
+        span: Span::default(),
+    })
 }
 
+// Function query < 9.6, before parallel safety was introduced
+static CTE_FUNCTIONS_94_THRU_96: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            nspname,
+            proname,
+            prosrc,
+            pg_get_function_arguments(pg_proc.oid),
+            lanname,
+            pg_get_function_result(pg_proc.oid),
+            provolatile::text AS volatility,
+            proisstrict,
+            prosecdef,
+            proleakproof,
+            'u' AS parallel,
+            procost::integer AS cost,
+            prorows::integer AS rows,
+            proretset,
+            proconfig
+        FROM pg_proc
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_language ON
+            pg_language.oid = pg_proc.prolang
+        WHERE nspname !~* 'pg_|information_schema' AND
+            proname !~ '^_' AND
+            NOT proisagg
+    )";
+
+// Function query >= 9.6 and < 11, before procedures were introduced
+static CTE_FUNCTIONS_96_THRU_11: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            nspname,
+            proname,
+            prosrc,
+            pg_get_function_arguments(pg_proc.oid),
+            lanname,
+            pg_get_function_result(pg_proc.oid),
+            provolatile::text AS volatility,
+            proisstrict,
+            prosecdef,
+            proleakproof,
+            proparallel::text AS parallel,
+            procost::integer AS cost,
+            prorows::integer AS rows,
+            proretset,
+            proconfig
+        FROM pg_proc
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_language ON
+            pg_language.oid = pg_proc.prolang
+        WHERE nspname !~* 'pg_|information_schema' AND
+            proname !~ '^_' AND
+            NOT proisagg
+    )";
+
+// Function query >= 11
 static CTE_FUNCTIONS: &str = "
     WITH cte AS (
         SELECT
@@ -383,16 +966,172 @@ static CTE_FUNCTIONS: &str = "
             prosrc,
             pg_get_function_arguments(pg_proc.oid),
             lanname,
-            pg_get_function_result(pg_proc.oid)
+            pg_get_function_result(pg_proc.oid),
+            provolatile::text AS volatility,
+            proisstrict,
+            prosecdef,
+            proleakproof,
+            proparallel::text AS parallel,
+            procost::integer AS cost,
+            prorows::integer AS rows,
+            proretset,
+            proconfig
+        FROM pg_proc
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_language ON
+            pg_language.oid = pg_proc.prolang
+        WHERE nspname !~* 'pg_|information_schema' AND
+            proname !~ '^_' AND
+            prokind = 'f'
+    )";
+
+// Procedures share the function layout so that attributes are parsed in the same way. The result is always null.
+static CTE_PROCEDURES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            nspname,
+            proname,
+            prosrc,
+            pg_get_function_arguments(pg_proc.oid),
+            lanname,
+            pg_get_function_result(pg_proc.oid),
+            provolatile::text AS volatility,
+            proisstrict,
+            prosecdef,
+            proleakproof,
+            proparallel::text AS parallel,
+            procost::integer AS cost,
+            prorows::integer AS rows,
+            proretset,
+            proconfig
         FROM pg_proc
         JOIN pg_namespace ON
             pg_namespace.oid = pg_proc.pronamespace
         JOIN pg_language ON
             pg_language.oid = pg_proc.prolang
         WHERE nspname !~* 'pg_|information_schema' AND
-            proname !~ '^_'
+            proname !~ '^_' AND
+            prokind = 'p'
     )";
 
+// Aggregate query < 9.6, before combine functions and parallel safety were introduced
+static CTE_AGGREGATES_94_THRU_96: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            pg_namespace.nspname,
+            pg_proc.proname,
+            pg_get_function_arguments(pg_proc.oid),
+            format_type(aggtranstype, NULL),
+            transfn_namespace.nspname AS transfn_schema,
+            transfn.proname AS transfn_name,
+            finalfn_namespace.nspname AS finalfn_schema,
+            finalfn.proname AS finalfn_name,
+            NULL::name AS combinefn_schema,
+            NULL::name AS combinefn_name,
+            agginitval,
+            'u' AS parallel
+        FROM pg_aggregate
+        JOIN pg_proc ON
+            pg_proc.oid = pg_aggregate.aggfnoid
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_proc transfn ON
+            transfn.oid = pg_aggregate.aggtransfn
+        JOIN pg_namespace transfn_namespace ON
+            transfn_namespace.oid = transfn.pronamespace
+        LEFT JOIN pg_proc finalfn ON
+            finalfn.oid = pg_aggregate.aggfinalfn
+        LEFT JOIN pg_namespace finalfn_namespace ON
+            finalfn_namespace.oid = finalfn.pronamespace
+        WHERE pg_namespace.nspname !~* 'pg_|information_schema' AND
+            aggkind = 'n'
+    )";
+
+// Aggregate query >= 9.6
+static CTE_AGGREGATES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            pg_namespace.nspname,
+            pg_proc.proname,
+            pg_get_function_arguments(pg_proc.oid),
+            format_type(aggtranstype, NULL),
+            transfn_namespace.nspname AS transfn_schema,
+            transfn.proname AS transfn_name,
+            finalfn_namespace.nspname AS finalfn_schema,
+            finalfn.proname AS finalfn_name,
+            combinefn_namespace.nspname AS combinefn_schema,
+            combinefn.proname AS combinefn_name,
+            agginitval,
+            pg_proc.proparallel::text AS parallel
+        FROM pg_aggregate
+        JOIN pg_proc ON
+            pg_proc.oid = pg_aggregate.aggfnoid
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_proc transfn ON
+            transfn.oid = pg_aggregate.aggtransfn
+        JOIN pg_namespace transfn_namespace ON
+            transfn_namespace.oid = transfn.pronamespace
+        LEFT JOIN pg_proc finalfn ON
+            finalfn.oid = pg_aggregate.aggfinalfn
+        LEFT JOIN pg_namespace finalfn_namespace ON
+            finalfn_namespace.oid = finalfn.pronamespace
+        LEFT JOIN pg_proc combinefn ON
+            combinefn.oid = pg_aggregate.aggcombinefn
+        LEFT JOIN pg_namespace combinefn_namespace ON
+            combinefn_namespace.oid = combinefn.pronamespace
+        WHERE pg_namespace.nspname !~* 'pg_|information_schema' AND
+            aggkind = 'n'
+    )";
+
+fn lexical(errors: Vec<lexer::LexicalError>) -> PsqlpackError {
+    let mut errors = errors
+        .into_iter()
+        .map(|err| {
+            LexicalError(
+                err.reason.to_owned(),
+                err.line.to_owned(),
+                err.line_number,
+                err.start_pos,
+                err.end_pos,
+            )
+            .into()
+        })
+        .collect::<Vec<PsqlpackError>>();
+    if errors.len() == 1 {
+        errors.remove(0)
+    } else {
+        MultipleErrors(errors).into()
+    }
+}
+
+fn parse(err: lalrpop_util::ParseError<Location, lexer::Token, &'static str>) -> PsqlpackError {
+    InlineParseError(Box::new(err)).into()
+}
+
+fn parse_function_language(lan_name: &str) -> FunctionLanguage {
+    match lan_name {
+        "internal" => FunctionLanguage::Internal,
+        "c" => FunctionLanguage::C,
+        "sql" => FunctionLanguage::SQL,
+        _ => FunctionLanguage::PostgreSQL,
+    }
+}
+
+fn parse_function_arguments(raw_args: String) -> PsqlpackResult<Vec<FunctionArgument>> {
+    if raw_args.is_empty() {
+        return Ok(Vec::new());
+    }
+    lexer::tokenize_body(&raw_args)
+        .map_err(lexical)
+        .and_then(|tokens| FunctionArgumentListParser::new().parse(tokens).map_err(parse))
+        .chain_err(|| PackageFunctionArgsInspectError(raw_args))
+}
+
 fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
     let schema_name: String = row.get(1);
     let function_name: String = row.get(2);
@@ -402,39 +1141,13 @@ fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
     let raw_result: String = row.get(6);
 
     // Parse some of the results
-    let language = match &lan_name[..] {
-        "internal" => FunctionLanguage::Internal,
-        "c" => FunctionLanguage::C,
-        "sql" => FunctionLanguage::SQL,
-        _ => FunctionLanguage::PostgreSQL,
-    };
-
-    fn lexical(err: lexer::LexicalError) -> PsqlpackError {
-        LexicalError(
-            err.reason.to_owned(),
-            err.line.to_owned(),
-            err.line_number,
-            err.start_pos,
-            err.end_pos,
-        )
-        .into()
-    };
-    fn parse(err: lalrpop_util::ParseError<(), lexer::Token, &'static str>) -> PsqlpackError {
-        InlineParseError(err).into()
-    };
-
-    let function_args = if raw_args.is_empty() {
-        Vec::new()
-    } else {
-        lexer::tokenize_body(&raw_args)
-            .map_err(lexical)
-            .and_then(|tokens| FunctionArgumentListParser::new().parse(tokens).map_err(parse))
-            .chain_err(|| PackageFunctionArgsInspectError(raw_args))?
-    };
+    let language = parse_function_language(&lan_name);
+    let function_args = parse_function_arguments(raw_args)?;
     let return_type = lexer::tokenize_body(&raw_result)
         .map_err(&lexical)
         .and_then(|tokens| FunctionReturnTypeParser::new().parse(tokens).map_err(parse))
         .chain_err(|| PackageFunctionReturnTypeInspectError(raw_result))?;
+    let attributes = parse_function_attributes(row, &language);
 
     // Set up the function definition
     Ok(FunctionDefinition {
@@ -446,9 +1159,132 @@ fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
         return_type,
         body: function_src,
         language,
+        attributes,
+        span: Span::default(),
+    })
+}
+
+fn parse_procedure(row: &Row) -> PsqlpackResult<ProcedureDefinition> {
+    let schema_name: String = row.get(1);
+    let procedure_name: String = row.get(2);
+    let procedure_src: String = row.get(3);
+    let raw_args: String = row.get(4);
+    let lan_name: String = row.get(5);
+
+    let language = parse_function_language(&lan_name);
+    let arguments = parse_function_arguments(raw_args)?;
+    let attributes = parse_function_attributes(row, &language);
+    Ok(ProcedureDefinition {
+        name: ObjectName {
+            schema: Some(schema_name),
+            name: procedure_name,
+        },
+        arguments,
+        body: procedure_src,
+        language,
+        attributes,
+        span: Span::default(),
+    })
+}
+
+fn parse_aggregate(row: &Row) -> PsqlpackResult<AggregateDefinition> {
+    let schema_name: String = row.get(1);
+    let aggregate_name: String = row.get(2);
+    let raw_args: String = row.get(3);
+    let state_type: String = row.get(4);
+
+    // Support functions are optional other than the state transition function
+    fn function_name(row: &Row, index: usize) -> Option<ObjectName> {
+        let name: Option<String> = row.get(index + 1);
+        name.map(|name| ObjectName {
+            schema: row.get(index),
+            name,
+        })
+    }
+
+    let parallel: String = row.get(12);
+    Ok(AggregateDefinition {
+        name: ObjectName {
+            schema: Some(schema_name),
+            name: aggregate_name,
+        },
+        arguments: parse_function_arguments(raw_args)?,
+        state_function: ObjectName {
+            schema: row.get(5),
+            name: row.get(6),
+        },
+        state_type: state_type.into(),
+        final_function: function_name(row, 7),
+        combine_function: function_name(row, 9),
+        initial_condition: row.get(11),
+        parallel: match &parallel[..] {
+            "r" => Some(ParallelSafety::Restricted),
+            "s" => Some(ParallelSafety::Safe),
+            _ => None,
+        },
+        span: Span::default(),
     })
 }
 
+// Only attributes that differ from the defaults are kept so that they compare with the source definition
+fn parse_function_attributes(row: &Row, language: &FunctionLanguage) -> Vec<FunctionAttribute> {
+    let mut attributes = Vec::new();
+    let volatility: String = row.get(7);
+    match &volatility[..] {
+        "i" => attributes.push(FunctionAttribute::Volatility(FunctionVolatility::Immutable)),
+        "s" => attributes.push(FunctionAttribute::Volatility(FunctionVolatility::Stable)),
+        _ => {}
+    }
+    if row.get(8) {
+        attributes.push(FunctionAttribute::Strict(true));
+    }
+    if row.get(9) {
+        attributes.push(FunctionAttribute::SecurityDefiner(true));
+    }
+    if row.get(10) {
+        attributes.push(FunctionAttribute::Leakproof(true));
+    }
+    let parallel: String = row.get(11);
+    match &parallel[..] {
+        "r" => attributes.push(FunctionAttribute::Parallel(ParallelSafety::Restricted)),
+        "s" => attributes.push(FunctionAttribute::Parallel(ParallelSafety::Safe)),
+        _ => {}
+    }
+
+    // The default cost depends on the language, and rows are only estimated for set returning functions
+    let cost: i32 = row.get(12);
+    let default_cost = match *language {
+        FunctionLanguage::C | FunctionLanguage::Internal => 1,
+        _ => 100,
+    };
+    if cost != default_cost {
+        attributes.push(FunctionAttribute::Cost(cost as u32));
+    }
+    let rows: i32 = row.get(13);
+    let returns_set: bool = row.get(14);
+    if returns_set && rows != 1000 {
+        attributes.push(FunctionAttribute::Rows(rows as u32));
+    }
+
+    // Configuration is stored as name=value, with list elements separated and quoted when required
+    let config: Option<Vec<String>> = row.get(15);
+    for setting in config.unwrap_or_default() {
+        if let Some(pos) = setting.find('=') {
+            let name = setting[..pos].to_owned();
+            let values = if name.eq_ignore_ascii_case("search_path") {
+                setting[pos + 1..]
+                    .split(", ")
+                    .map(|value| value.trim_matches('"').replace("\"\"", "\""))
+                    .collect()
+            } else {
+                vec![setting[pos + 1..].to_owned()]
+            };
+            attributes.push(FunctionAttribute::Set(name, values));
+        }
+    }
+    attributes
+}
+
 static CTE_TABLES: &str = "
     WITH cte AS (
         SELECT
@@ -457,7 +1293,7 @@ static CTE_TABLES: &str = "
             relname
         FROM pg_class
         JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
-        WHERE pg_class.relkind='r' AND
+        WHERE pg_class.relkind IN ('r', 'p') AND
               nspname !~* 'pg_|information_schema'
     )";
 
@@ -468,8 +1304,14 @@ impl<'row> From<&Row> for TableDefinition {
                 schema: Some(row.get(1)),
                 name: row.get(2),
             },
-            columns: Vec::new(),     // This gets loaded later
-            constraints: Vec::new(), // This gets loaded later
+            columns: Vec::new(),       // This gets loaded later
+            constraints: Vec::new(),   // This gets loaded later
+            row_security: false,       // This gets loaded later
+            force_row_security: false, // This gets loaded later
+            policies: Vec::new(),      // This gets loaded later
+            partition_by: None,        // This gets loaded later
+            partition_of: None,        // This gets loaded later
+            span: Span::default(),
         }
     }
 }
@@ -487,12 +1329,17 @@ static CTE_COLUMNS: &str = "
                   AND pg_get_expr(def.adbin, def.adrelid) = 'nextval('''
                         || (pg_get_serial_sequence (a.attrelid::regclass::text, a.attname))::regclass
                         || '''::regclass)'
+                  AND pg_get_serial_sequence (a.attrelid::regclass::text, a.attname)
+                        = quote_ident(ns.nspname) || '.' || quote_ident(pgc.relname || '_' || a.attname || '_seq')
                 THEN CASE a.atttypid
                         WHEN 'int'::regtype  THEN 'serial'
                         WHEN 'int8'::regtype THEN 'bigserial'
                         WHEN 'int2'::regtype THEN 'smallserial'
                      END
-                ELSE format_type(a.atttypid, a.atttypmod)
+                WHEN tn.nspname IN ('pg_catalog', 'information_schema')
+                  OR format_type(a.atttypid, a.atttypmod) LIKE '%.%'
+                THEN format_type(a.atttypid, a.atttypmod)
+                ELSE quote_ident(tn.nspname) || '.' || format_type(a.atttypid, a.atttypmod)
             END AS data_type,
             a.attnotnull as notnull,
             coalesce(i.indisprimary,false) as primary_key,
@@ -500,37 +1347,154 @@ static CTE_COLUMNS: &str = "
         FROM pg_attribute a
         INNER JOIN pg_class pgc ON pgc.oid = a.attrelid
         INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
+        INNER JOIN pg_type t ON t.oid = a.atttypid
+        INNER JOIN pg_namespace tn ON tn.oid = t.typnamespace
         LEFT JOIN pg_index i ON pgc.oid = i.indrelid AND i.indkey[0] = a.attnum
         LEFT JOIN pg_attrdef def ON a.attrelid = def.adrelid AND a.attnum = def.adnum
-        WHERE attnum > 0 AND pgc.relkind='r' AND NOT a.attisdropped AND ns.nspname !~* 'pg_|information_schema'
+        WHERE attnum > 0 AND pgc.relkind IN ('r', 'p') AND NOT a.attisdropped AND ns.nspname !~* 'pg_|information_schema'
         ORDER BY pgc.relname, a.attnum
     )";
 
-impl<'row> From<&Row> for ColumnDefinition {
-    fn from(row: &Row) -> Self {
-        // Do the column constraints first
-        let mut constraints = Vec::new();
-        let not_null: bool = row.get(7);
-        let primary_key: bool = row.get(8);
-        // TODO: Default value + unique
-        constraints.push(if not_null {
-            ColumnConstraint::NotNull
-        } else {
-            ColumnConstraint::Null
-        });
-        if primary_key {
-            constraints.push(ColumnConstraint::PrimaryKey);
-        }
-        let sql_type: String = row.get(6);
+fn parse_column(row: &Row, generated: Option<String>) -> PsqlpackResult<ColumnDefinition> {
+    // Do the column constraints first
+    let mut constraints = Vec::new();
+    let not_null: bool = row.get(7);
+    let primary_key: bool = row.get(8);
+    // TODO: Unique
+    constraints.push(if not_null {
+        ColumnConstraint::NotNull
+    } else {
+        ColumnConstraint::Null
+    });
+    if primary_key {
+        constraints.push(ColumnConstraint::PrimaryKey);
+    }
+    let sql_type: SqlType = row.get::<_, String>(6).into();
+
+    // The default of a serial column is implied by the type
+    let serial = match sql_type {
+        SqlType::Simple(SimpleSqlType::SmallSerial, _)
+        | SqlType::Simple(SimpleSqlType::Serial, _)
+        | SqlType::Simple(SimpleSqlType::BigSerial, _) => true,
+        _ => false,
+    };
+    let default: Option<String> = row.get(9);
+    if let Some(expression) = generated {
+        constraints.push(ColumnConstraint::Generated(expression));
+    } else if let Some(default) = default.filter(|_| !serial) {
+        let default = lexer::tokenize_body(&default)
+            .map_err(lexical)
+            .and_then(|tokens| AnyValueParser::new().parse(tokens).map_err(parse))
+            .chain_err(|| PackageColumnDefaultInspectError(default))?;
+        constraints.push(ColumnConstraint::Default(default));
+    }
 
-        ColumnDefinition {
-            name: row.get(5),
-            sql_type: sql_type.into(),
-            constraints,
-        }
+    Ok(ColumnDefinition {
+        name: row.get(5),
+        sql_type,
+        constraints,
+    })
+}
+
+// Identity sequences are internal to their column so options are reported alongside the column. As with
+// sequences, bounds are only returned when they differ from the defaults for the sequence type.
+static CTE_IDENTITY_COLUMNS: &str = "
+    WITH cte AS (
+        SELECT
+            pgc.oid,
+            CONCAT(ns.nspname, '.', pgc.relname) AS fqn,
+            a.attname::text AS name,
+            a.attidentity::text AS identity,
+            seqincrement,
+            CASE WHEN seqmin = CASE WHEN seqincrement > 0 THEN 1
+                                    WHEN seqtypid = 'int2'::regtype THEN -32768
+                                    WHEN seqtypid = 'int4'::regtype THEN -2147483648
+                                    ELSE -9223372036854775808 END
+                 THEN NULL ELSE seqmin END AS min_value,
+            CASE WHEN seqmax = CASE WHEN seqincrement < 0 THEN -1
+                                    WHEN seqtypid = 'int2'::regtype THEN 32767
+                                    WHEN seqtypid = 'int4'::regtype THEN 2147483647
+                                    ELSE 9223372036854775807 END
+                 THEN NULL ELSE seqmax END AS max_value,
+            seqstart,
+            seqcache,
+            seqcycle
+        FROM pg_attribute a
+        INNER JOIN pg_class pgc ON pgc.oid = a.attrelid
+        INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
+        INNER JOIN pg_depend d ON d.refobjid = pgc.oid AND
+                                  d.refobjsubid = a.attnum AND
+                                  d.classid = 'pg_class'::regclass AND
+                                  d.refclassid = 'pg_class'::regclass AND
+                                  d.deptype = 'i'
+        INNER JOIN pg_sequence ON pg_sequence.seqrelid = d.objid
+        WHERE a.attidentity IN ('a', 'd') AND pgc.relkind IN ('r', 'p') AND NOT a.attisdropped AND
+              ns.nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_identity(row: &Row, table: &mut TableDefinition) {
+    let name: String = row.get(2);
+    let column = match table.columns.iter_mut().find(|c| c.name == name) {
+        Some(column) => column,
+        None => return,
+    };
+
+    let identity: String = row.get(3);
+    let kind = if identity == "a" {
+        IdentityKind::Always
+    } else {
+        IdentityKind::ByDefault
+    };
+
+    // Only options differing from the defaults are kept to mirror how they'd typically be written
+    let increment: i64 = row.get(4);
+    let min_value: Option<i64> = row.get(5);
+    let max_value: Option<i64> = row.get(6);
+    let start: i64 = row.get(7);
+    let cache: i64 = row.get(8);
+    let cycle: bool = row.get(9);
+    let default_start = if increment > 0 {
+        min_value.unwrap_or(1)
+    } else {
+        max_value.unwrap_or(-1)
+    };
+    let mut options = Vec::new();
+    if increment != 1 {
+        options.push(SequenceOption::Increment(increment));
+    }
+    if min_value.is_some() {
+        options.push(SequenceOption::MinValue(min_value));
     }
+    if max_value.is_some() {
+        options.push(SequenceOption::MaxValue(max_value));
+    }
+    if start != default_start {
+        options.push(SequenceOption::Start(start));
+    }
+    if cache != 1 {
+        options.push(SequenceOption::Cache(cache));
+    }
+    if cycle {
+        options.push(SequenceOption::Cycle(cycle));
+    }
+    column.constraints.push(ColumnConstraint::Identity(kind, options));
 }
 
+static CTE_GENERATED_COLUMNS: &str = "
+    WITH cte AS (
+        SELECT
+            pgc.oid,
+            CONCAT(ns.nspname, '.', pgc.relname) AS fqn,
+            a.attname::text AS name,
+            pg_get_expr(def.adbin, def.adrelid) AS expression
+        FROM pg_attribute a
+        INNER JOIN pg_class pgc ON pgc.oid = a.attrelid
+        INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
+        INNER JOIN pg_attrdef def ON a.attrelid = def.adrelid AND a.attnum = def.adnum
+        WHERE a.attgenerated = 's' AND pgc.relkind IN ('r', 'p') AND NOT a.attisdropped AND
+              ns.nspname !~* 'pg_|information_schema'
+    )";
+
 static CTE_TABLE_CONSTRAINTS: &str = "
     WITH cte AS (
         SELECT
@@ -572,29 +1536,129 @@ static CTE_TABLE_CONSTRAINTS: &str = "
             confdeltype,
             confmatchtype::text
     )";
-lazy_static! {
-    static ref FILL_FACTOR: Regex = Regex::new("fillfactor=(\\d+)").unwrap();
+static CTE_CHECK_CONSTRAINTS: &str = "
+    WITH cte AS (
+        SELECT
+            tcls.oid,
+            CONCAT(ns.nspname, '.', tcls.relname) AS fqn,
+            con.conname AS name,
+            pg_get_constraintdef(con.oid, true) AS definition,
+            con.connoinherit AS no_inherit
+        FROM pg_constraint con
+        JOIN pg_class tcls ON tcls.oid = con.conrelid
+        JOIN pg_namespace ns ON ns.oid = tcls.relnamespace
+        WHERE con.contype = 'c' AND ns.nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_check_constraint(row: &Row) -> TableConstraint {
+    let definition: String = row.get(3);
+    TableConstraint::Check {
+        name: row.get(2),
+        expression: check_expression(&definition),
+        no_inherit: row.get(4),
+    }
 }
 
-fn parse_index_parameters(raw_parameters: Option<Vec<String>>) -> Option<Vec<IndexParameter>> {
-    match raw_parameters {
-        Some(parameters) => {
-            // We only have one type at the moment
-            if let Some(parameters) = parameters.first() {
-                let ff = FILL_FACTOR.captures(&parameters[..]);
-                if let Some(ff) = ff {
-                    Some(vec![IndexParameter::FillFactor(ff[1].parse::<u32>().unwrap())])
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+fn check_expression(definition: &str) -> String {
+    // The definition is described as CHECK (expression) with any modifiers trailing
+    let definition = definition
+        .trim_end_matches(" NOT VALID")
+        .trim_end_matches(" NO INHERIT");
+    let expression = definition.trim_start_matches("CHECK").trim();
+    let expression = if expression.starts_with('(') && expression.ends_with(')') {
+        &expression[1..expression.len() - 1]
+    } else {
+        expression
+    };
+    expression.to_owned()
+}
+
+static CTE_UNIQUE_CONSTRAINTS: &str = "
+    WITH cte AS (
+        SELECT
+            tcls.oid,
+            CONCAT(ns.nspname, '.', tcls.relname) AS fqn,
+            con.conname AS name,
+            con.contype::text AS constraint_type,
+            ARRAY(
+                SELECT a.attname::text
+                FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+                JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                ORDER BY k.ord
+            ) AS columns,
+            ARRAY(
+                SELECT op.oprname::text
+                FROM unnest(con.conexclop) WITH ORDINALITY AS o(oid, ord)
+                JOIN pg_operator op ON op.oid = o.oid
+                ORDER BY o.ord
+            ) AS operators,
+            am.amname AS index_type,
+            ic.reloptions AS parameters,
+            pg_get_constraintdef(con.oid) AS definition
+        FROM pg_constraint con
+        JOIN pg_class tcls ON tcls.oid = con.conrelid
+        JOIN pg_namespace ns ON ns.oid = tcls.relnamespace
+        JOIN pg_class ic ON ic.oid = con.conindid
+        JOIN pg_am am ON am.oid = ic.relam
+        WHERE con.contype IN ('u', 'x') AND ns.nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_unique_constraint(row: &Row) -> TableConstraint {
+    let name: String = row.get(2);
+    let constraint_type: String = row.get(3);
+    let columns: Vec<String> = row.get(4);
+    let parameters = parse_index_parameters(row.get(7));
+    if constraint_type == "x" {
+        let operators: Vec<String> = row.get(5);
+        let index_type: String = row.get(6);
+        TableConstraint::Exclude {
+            name,
+            index_type: Some(IndexType::from_name(&index_type)),
+            elements: columns
+                .into_iter()
+                .zip(operators)
+                .map(|(column, operator)| ExclusionElement { column, operator })
+                .collect(),
+            parameters,
+        }
+    } else {
+        // Included columns are only described within the definition
+        let definition: String = row.get(8);
+        let include = INCLUDE_COLUMNS.captures(&definition).map(|captures| {
+            captures[1]
+                .split(',')
+                .map(|c| c.trim().trim_matches('"').to_owned())
+                .collect::<Vec<_>>()
+        });
+        TableConstraint::Unique {
+            name,
+            columns,
+            include,
+            parameters,
         }
-        None => None,
     }
 }
 
+lazy_static! {
+    static ref INCLUDE_COLUMNS: Regex = Regex::new("INCLUDE \\(([^)]*)\\)").unwrap();
+}
+
+fn parse_index_parameters(raw_parameters: Option<Vec<String>>) -> Option<Vec<IndexParameter>> {
+    raw_parameters.map(|parameters| {
+        parameters
+            .iter()
+            .map(|parameter| {
+                let mut parts = parameter.splitn(2, '=');
+                let name = parts.next().unwrap_or_default();
+                let value = parts.next().unwrap_or_default();
+                // Postgres validates reloptions, so anything we can't interpret is kept verbatim
+                IndexParameter::from_option(name, value)
+                    .unwrap_or_else(|_| IndexParameter::Custom(name.to_owned(), value.to_owned()))
+            })
+            .collect()
+    })
+}
+
 impl<'row> From<&Row> for TableConstraint {
     fn from(row: &Row) -> Self {
         let schema: String = row.get(2);
@@ -684,13 +1748,15 @@ static CTE_INDEXES_94_THRU_96: &str = "
                     generate_subscripts(idx.indkey, 1) AS k
                 ORDER BY k
             ) AS index_keys,
-            ic.reloptions AS storage_parameters
+            ic.reloptions AS storage_parameters,
+            pg_get_indexdef(idx.indexrelid) AS definition
         FROM pg_index AS idx
         JOIN pg_class AS ic ON ic.oid = idx.indexrelid
         JOIN pg_am AS am ON ic.relam = am.oid
         JOIN pg_namespace AS ns ON ic.relnamespace = ns.OID
         JOIN pg_class AS tc ON tc.oid = idx.indrelid
-        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false
+        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false AND
+              NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = idx.indexrelid AND con.contype IN ('u', 'x'))
     )
 ";
 
@@ -717,78 +1783,708 @@ static CTE_INDEXES: &str = "
                     generate_subscripts(idx.indkey, 1) AS k
                 ORDER BY k
             ) AS index_keys,
-            ic.reloptions AS storage_parameters
+            ic.reloptions AS storage_parameters,
+            pg_get_indexdef(idx.indexrelid) AS definition
         FROM pg_index AS idx
         JOIN pg_class AS ic ON ic.oid = idx.indexrelid
         JOIN pg_am AS am ON ic.relam = am.oid
         JOIN pg_namespace AS ns ON ic.relnamespace = ns.OID
         JOIN pg_class AS tc ON tc.oid = idx.indrelid
-        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false
+        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false AND
+              NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = idx.indexrelid AND con.contype IN ('u', 'x')) AND
+              NOT EXISTS (SELECT 1 FROM pg_inherits inh WHERE inh.inhrelid = idx.indexrelid)
     )
 ";
 
-impl<'row> From<&Row> for IndexDefinition {
-    fn from(row: &Row) -> Self {
-        let schema: String = row.get(1);
-        let table: String = row.get(2);
-        let name: String = row.get(3);
-        let unique: bool = row.get(4);
-        let index_type: String = row.get(5);
-        let index_type = match &index_type[..] {
-            "btree" => Some(IndexType::BTree),
-            "gin" => Some(IndexType::Gin),
-            "gist" => Some(IndexType::Gist),
-            "hash" => Some(IndexType::Hash),
-            _ => None,
+fn parse_index(row: &Row) -> PsqlpackResult<IndexDefinition> {
+    let definition: String = row.get(8);
+
+    let mut index = lexer::tokenize_stmt(&definition)
+        .map_err(lexical)
+        .and_then(|tokens| IndexDefinitionParser::new().parse(tokens).map_err(parse))
+        .chain_err(|| PackageIndexInspectError(definition.to_owned()))?;
+
+    // pg_get_indexdef omits default orderings so we take them from the column properties instead
+    let keys: Vec<serde_json::Value> = row.get(6);
+    for (column, map) in index
+        .columns
+        .iter_mut()
+        .zip(keys.iter().map(|k| k.as_object().unwrap()))
+    {
+        if !map["orderable"].as_bool().unwrap_or(false) {
+            continue;
+        }
+        column.order = if map["asc"].as_bool().unwrap_or(false) {
+            Some(IndexOrder::Ascending)
+        } else if map["desc"].as_bool().unwrap_or(false) {
+            Some(IndexOrder::Descending)
+        } else {
+            None
+        };
+        column.null_position = if map["nulls_first"].as_bool().unwrap_or(false) {
+            Some(IndexPosition::First)
+        } else if map["nulls_last"].as_bool().unwrap_or(false) {
+            Some(IndexPosition::Last)
+        } else {
+            None
         };
-        let columns: Vec<serde_json::Value> = row.get(6);
-        let columns = columns
+    }
+
+    let index_type: String = row.get(5);
+    index.table = ObjectName {
+        schema: Some(row.get(1)),
+        name: row.get(2),
+    };
+    index.index_type = Some(IndexType::from_name(&index_type));
+    index.storage_parameters = parse_index_parameters(row.get(7));
+    Ok(index)
+}
+
+// Sequences implicitly created for serial columns are excluded since the column type implies them.
+// Bounds are only returned when they differ from the defaults for the sequence type.
+static CTE_SEQUENCES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            pg_namespace.nspname,
+            pg_class.relname,
+            seqincrement,
+            CASE WHEN seqmin = CASE WHEN seqincrement > 0 THEN 1
+                                    WHEN seqtypid = 'int2'::regtype THEN -32768
+                                    WHEN seqtypid = 'int4'::regtype THEN -2147483648
+                                    ELSE -9223372036854775808 END
+                 THEN NULL ELSE seqmin END AS min_value,
+            CASE WHEN seqmax = CASE WHEN seqincrement < 0 THEN -1
+                                    WHEN seqtypid = 'int2'::regtype THEN 32767
+                                    WHEN seqtypid = 'int4'::regtype THEN 2147483647
+                                    ELSE 9223372036854775807 END
+                 THEN NULL ELSE seqmax END AS max_value,
+            seqstart,
+            seqcache,
+            seqcycle,
+            owner_ns.nspname AS owner_schema,
+            owner.relname AS owner_table,
+            owner_column.attname::text AS owner_column
+        FROM pg_sequence
+        JOIN pg_class ON pg_class.oid = pg_sequence.seqrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        LEFT JOIN pg_depend owned ON owned.objid = pg_class.oid AND
+                                     owned.classid = 'pg_class'::regclass AND
+                                     owned.refclassid = 'pg_class'::regclass AND
+                                     owned.deptype = 'a'
+        LEFT JOIN pg_class owner ON owner.oid = owned.refobjid
+        LEFT JOIN pg_namespace owner_ns ON owner_ns.oid = owner.relnamespace
+        LEFT JOIN pg_attribute owner_column ON owner_column.attrelid = owned.refobjid AND
+                                               owner_column.attnum = owned.refobjsubid
+        WHERE pg_namespace.nspname !~* 'pg_|information_schema' AND
+              NOT EXISTS (
+                  SELECT 1
+                  FROM pg_attrdef def
+                  WHERE def.adrelid = owned.refobjid AND
+                        def.adnum = owned.refobjsubid AND
+                        owner_column.atttypid = ANY ('{int,int8,int2}'::regtype[]) AND
+                        pg_get_expr(def.adbin, def.adrelid) = 'nextval(''' || pg_class.oid::regclass || '''::regclass)'
+                        AND pg_class.relname = owner.relname || '_' || owner_column.attname || '_seq'
+              )
+    )";
+
+// Before 10 the parameters of a sequence are held within the sequence itself rather than pg_sequence, so they are
+// read from each sequence separately (see Q_SEQUENCE_PARAMETERS_94_THRU_10). Sequences were always bigint.
+static CTE_SEQUENCES_94_THRU_10: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            pg_namespace.nspname,
+            pg_class.relname,
+            0::bigint AS seqincrement,
+            NULL::bigint AS min_value,
+            NULL::bigint AS max_value,
+            0::bigint AS seqstart,
+            0::bigint AS seqcache,
+            false AS seqcycle,
+            owner_ns.nspname AS owner_schema,
+            owner.relname AS owner_table,
+            owner_column.attname::text AS owner_column
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        LEFT JOIN pg_depend owned ON owned.objid = pg_class.oid AND
+                                     owned.classid = 'pg_class'::regclass AND
+                                     owned.refclassid = 'pg_class'::regclass AND
+                                     owned.deptype = 'a'
+        LEFT JOIN pg_class owner ON owner.oid = owned.refobjid
+        LEFT JOIN pg_namespace owner_ns ON owner_ns.oid = owner.relnamespace
+        LEFT JOIN pg_attribute owner_column ON owner_column.attrelid = owned.refobjid AND
+                                               owner_column.attnum = owned.refobjsubid
+        WHERE pg_class.relkind = 'S' AND
+              pg_namespace.nspname !~* 'pg_|information_schema' AND
+              NOT EXISTS (
+                  SELECT 1
+                  FROM pg_attrdef def
+                  WHERE def.adrelid = owned.refobjid AND
+                        def.adnum = owned.refobjsubid AND
+                        owner_column.atttypid = ANY ('{int,int8,int2}'::regtype[]) AND
+                        pg_get_expr(def.adbin, def.adrelid) = 'nextval(''' || pg_class.oid::regclass || '''::regclass)'
+                        AND pg_class.relname = owner.relname || '_' || owner_column.attname || '_seq'
+              )
+    )";
+
+// The parameters of a single sequence before 10, with default limits reported as NULL as above
+static Q_SEQUENCE_PARAMETERS_94_THRU_10: &str = "
+    SELECT
+        increment_by,
+        CASE WHEN min_value = CASE WHEN increment_by > 0 THEN 1 ELSE -9223372036854775807 END
+             THEN NULL ELSE min_value END,
+        CASE WHEN max_value = CASE WHEN increment_by < 0 THEN -1 ELSE 9223372036854775807 END
+             THEN NULL ELSE max_value END,
+        start_value,
+        cache_value,
+        is_cycled
+    FROM";
+
+fn query_sequences(
+    client: &mut PostgresClient,
+    server_version: &Semver,
+    filter: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> PsqlpackResult<Vec<SequenceDefinition>> {
+    if *server_version >= Semver::new(10, 0, None) {
+        let sequences = client
+            .query(&format!("{} {}", CTE_SEQUENCES, filter)[..], params)
+            .chain_err(|| PackageQuerySequencesError)?
             .iter()
-            .map(|c| c.as_object().unwrap())
-            .map(|map| IndexColumn {
-                name: map["colname"].as_str().unwrap().to_owned(),
-                order: if map["orderable"].as_bool().unwrap_or(false) {
-                    if map["asc"].as_bool().unwrap_or(false) {
-                        Some(IndexOrder::Ascending)
-                    } else if map["desc"].as_bool().unwrap_or(false) {
-                        Some(IndexOrder::Descending)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                },
-                null_position: if map["orderable"].as_bool().unwrap_or(false) {
-                    if map["nulls_first"].as_bool().unwrap_or(false) {
-                        Some(IndexPosition::First)
-                    } else if map["nulls_last"].as_bool().unwrap_or(false) {
-                        Some(IndexPosition::Last)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                },
-            })
+            .map(|row| row.into())
             .collect();
-        let storage_parameters = parse_index_parameters(row.get(7));
+        return Ok(sequences);
+    }
 
-        IndexDefinition {
-            name,
-            table: ObjectName {
-                schema: Some(schema),
-                name: table,
+    let mut sequences = client
+        .query(&format!("{} {}", CTE_SEQUENCES_94_THRU_10, filter)[..], params)
+        .chain_err(|| PackageQuerySequencesError)?
+        .iter()
+        .map(SequenceDefinition::from)
+        .collect::<Vec<_>>();
+    for sequence in &mut sequences {
+        let row = client
+            .query_one(
+                &format!("{} {}", Q_SEQUENCE_PARAMETERS_94_THRU_10, sequence.name)[..],
+                &[],
+            )
+            .chain_err(|| PackageQuerySequencesError)?;
+        sequence.increment = Some(row.get(0));
+        sequence.min_value = row.get(1);
+        sequence.max_value = row.get(2);
+        sequence.start = Some(row.get(3));
+        sequence.cache = Some(row.get(4));
+        sequence.cycle = row.get(5);
+    }
+    Ok(sequences)
+}
+
+impl<'row> From<&Row> for SequenceDefinition {
+    fn from(row: &Row) -> Self {
+        let owner_table: Option<String> = row.get(10);
+        let owner_column: Option<String> = row.get(11);
+        SequenceDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
             },
-            columns,
+            increment: Some(row.get(3)),
+            min_value: row.get(4),
+            max_value: row.get(5),
+            start: Some(row.get(6)),
+            cache: Some(row.get(7)),
+            cycle: row.get(8),
+            owned_by: match (owner_table, owner_column) {
+                (Some(table), Some(column)) => Some(SequenceOwner {
+                    table: ObjectName {
+                        schema: row.get(9),
+                        name: table,
+                    },
+                    column,
+                }),
+                _ => None,
+            },
+            span: Span::default(),
+        }
+    }
+}
+
+static CTE_VIEWS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            nspname,
+            relname,
+            ARRAY(
+                SELECT attname::text
+                FROM pg_attribute
+                WHERE attrelid = pg_class.oid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS columns,
+            pg_get_viewdef(pg_class.oid) AS query
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE pg_class.relkind='v' AND
+              nspname !~* 'pg_|information_schema'
+    )";
+static CTE_MATERIALIZED_VIEWS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            nspname,
+            relname,
+            ARRAY(
+                SELECT attname::text
+                FROM pg_attribute
+                WHERE attrelid = pg_class.oid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS columns,
+            pg_get_viewdef(pg_class.oid) AS query,
+            relispopulated
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE pg_class.relkind='m' AND
+              nspname !~* 'pg_|information_schema'
+    )";
+
+static CTE_ROW_SECURITY: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            CONCAT(nspname, '.', relname) AS fqn,
+            relrowsecurity,
+            relforcerowsecurity
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE pg_class.relkind IN ('r', 'p') AND
+              (relrowsecurity OR relforcerowsecurity) AND
+              nspname !~* 'pg_|information_schema'
+    )";
+
+// Like triggers, policies are grouped with their table when determining whether they belong to an extension.
+// Roles are sorted by name since the order they were granted in is irrelevant.
+static CTE_POLICIES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            CONCAT(nspname, '.', relname) AS fqn,
+            nspname AS table_schema,
+            relname AS table_name,
+            polname AS name,
+            polpermissive AS permissive,
+            polcmd::text AS command,
+            ARRAY(
+                SELECT CASE WHEN role = 0 THEN 'PUBLIC' ELSE pg_get_userbyid(role)::text END
+                FROM unnest(polroles) AS role
+                ORDER BY 1
+            ) AS roles,
+            pg_get_expr(polqual, polrelid) AS using,
+            pg_get_expr(polwithcheck, polrelid) AS with_check
+        FROM pg_policy
+        JOIN pg_class ON pg_class.oid = pg_policy.polrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE nspname !~* 'pg_|information_schema'
+    )";
+
+static CTE_POLICIES_95_THRU_96: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            CONCAT(nspname, '.', relname) AS fqn,
+            nspname AS table_schema,
+            relname AS table_name,
+            polname AS name,
+            true AS permissive,
+            polcmd::text AS command,
+            ARRAY(
+                SELECT CASE WHEN role = 0 THEN 'PUBLIC' ELSE pg_get_userbyid(role)::text END
+                FROM unnest(polroles) AS role
+                ORDER BY 1
+            ) AS roles,
+            pg_get_expr(polqual, polrelid) AS using,
+            pg_get_expr(polwithcheck, polrelid) AS with_check
+        FROM pg_policy
+        JOIN pg_class ON pg_class.oid = pg_policy.polrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_policy(row: &Row) -> PolicyDefinition {
+    let command: String = row.get(6);
+    PolicyDefinition {
+        name: row.get(4),
+        table: ObjectName {
+            schema: Some(row.get(2)),
+            name: row.get(3),
+        },
+        permissive: row.get(5),
+        command: match &command[..] {
+            "r" => PolicyCommand::Select,
+            "a" => PolicyCommand::Insert,
+            "w" => PolicyCommand::Update,
+            "d" => PolicyCommand::Delete,
+            _ => PolicyCommand::All,
+        },
+        roles: row.get(7),
+        using: row.get(8),
+        with_check: row.get(9),
+        span: Span::default(),
+    }
+}
+
+// Partitioned tables report their key while partitions report their parent and bound. Indexes cascading
+// from a partitioned table are excluded from the index query since they are implied by the parent index.
+static CTE_PARTITIONS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            CONCAT(ns.nspname, '.', pg_class.relname) AS fqn,
+            CASE WHEN pg_class.relkind = 'p' THEN pg_get_partkeydef(pg_class.oid) END AS partition_key,
+            parent_ns.nspname AS parent_schema,
+            parent.relname AS parent_name,
+            pg_get_expr(pg_class.relpartbound, pg_class.oid) AS partition_bound
+        FROM pg_class
+        JOIN pg_namespace ns ON ns.oid = pg_class.relnamespace
+        LEFT JOIN pg_inherits ON pg_inherits.inhrelid = pg_class.oid AND pg_class.relispartition
+        LEFT JOIN pg_class parent ON parent.oid = pg_inherits.inhparent
+        LEFT JOIN pg_namespace parent_ns ON parent_ns.oid = parent.relnamespace
+        WHERE pg_class.relkind IN ('r', 'p') AND
+              (pg_class.relkind = 'p' OR pg_class.relispartition) AND
+              ns.nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_partition(row: &Row, table: &mut TableDefinition) -> PsqlpackResult<()> {
+    let partition_key: Option<String> = row.get(2);
+    if let Some(key) = partition_key {
+        let partition_by = lexer::tokenize_partition(&key)
+            .map_err(lexical)
+            .and_then(|tokens| PartitionKeyParser::new().parse(tokens).map_err(parse))
+            .chain_err(|| PackagePartitionInspectError(key.to_owned()))?;
+        table.partition_by = Some(partition_by);
+    }
+
+    let parent_name: Option<String> = row.get(4);
+    if let Some(parent_name) = parent_name {
+        let bound: String = row.get(5);
+        let bound = lexer::tokenize_partition(&bound)
+            .map_err(lexical)
+            .and_then(|tokens| PartitionBoundParser::new().parse(tokens).map_err(parse))
+            .chain_err(|| PackagePartitionInspectError(bound.to_owned()))?;
+        table.partition_of = Some(TablePartition {
+            parent: ObjectName {
+                schema: row.get(3),
+                name: parent_name,
+            },
+            bound,
+        });
+    }
+    Ok(())
+}
+
+// Triggers are grouped with their table when determining whether they belong to an extension, hence
+// the table oid. Internal triggers (e.g. those implementing foreign keys) are excluded.
+static CTE_TRIGGERS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            table_ns.nspname AS table_schema,
+            pg_class.relname AS table_name,
+            pg_trigger.tgname,
+            pg_get_triggerdef(pg_trigger.oid) AS definition,
+            function_ns.nspname AS function_schema,
+            pg_proc.proname AS function_name
+        FROM pg_trigger
+        JOIN pg_class ON pg_class.oid = pg_trigger.tgrelid
+        JOIN pg_namespace table_ns ON table_ns.oid = pg_class.relnamespace
+        JOIN pg_proc ON pg_proc.oid = pg_trigger.tgfoid
+        JOIN pg_namespace function_ns ON function_ns.oid = pg_proc.pronamespace
+        WHERE NOT pg_trigger.tgisinternal AND
+              table_ns.nspname !~* 'pg_|information_schema'
+    )";
+
+fn parse_trigger(row: &Row) -> PsqlpackResult<TriggerDefinition> {
+    let definition: String = row.get(4);
 
-            unique,
-            index_type,
+    let mut trigger = lexer::tokenize_stmt(&definition)
+        .map_err(lexical)
+        .and_then(|tokens| TriggerDefinitionParser::new().parse(tokens).map_err(parse))
+        .chain_err(|| PackageTriggerInspectError(definition.to_owned()))?;
 
-            storage_parameters,
+    // pg_get_triggerdef only qualifies names outside of the search path so we set them explicitly
+    trigger.table = ObjectName {
+        schema: Some(row.get(1)),
+        name: row.get(2),
+    };
+    trigger.function = ObjectName {
+        schema: Some(row.get(5)),
+        name: row.get(6),
+    };
+    Ok(trigger)
+}
+
+impl<'row> From<&Row> for ViewDefinition {
+    fn from(row: &Row) -> Self {
+        // pg_get_viewdef returns a complete statement so we trim the terminator
+        let query: String = row.get(4);
+        ViewDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
+            },
+            columns: Some(row.get(3)),
+            query: query.trim().trim_end_matches(';').trim_end().to_owned(),
+            resolved: None,
+            span: Span::default(),
+        }
+    }
+}
+
+impl<'row> From<&Row> for MaterializedViewDefinition {
+    fn from(row: &Row) -> Self {
+        let query: String = row.get(4);
+        MaterializedViewDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
+            },
+            columns: Some(row.get(3)),
+            query: query.trim().trim_end_matches(';').trim_end().to_owned(),
+            with_data: row.get(5),
+            resolved: None,
+            span: Span::default(),
         }
     }
 }
 
+// Privileges are exploded from each object's aclitem array. A NULL array means the default privileges
+// apply, which are made explicit so that they can be compared (e.g. EXECUTE on functions for PUBLIC).
+// Owner privileges are implicit so they're excluded. Role memberships are reported as a MEMBER privilege
+// on the role, and default privileges are reported against the role that they apply to unless that is
+// the current user.
+static CTE_PRIVILEGES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_namespace.oid,
+            'schema'::text AS kind,
+            NULL::text AS object_schema,
+            nspname::text AS object_name,
+            NULL::text AS default_role,
+            CASE acl.grantee WHEN 0 THEN 'PUBLIC' ELSE pg_get_userbyid(acl.grantee)::text END AS grantee,
+            acl.privilege_type,
+            acl.is_grantable,
+            NULL::text AS arguments
+        FROM pg_namespace,
+             aclexplode(COALESCE(nspacl, acldefault('n', nspowner))) acl
+        WHERE acl.grantee <> nspowner AND
+              nspname !~* 'pg_|information_schema'
+        UNION ALL
+        SELECT
+            pg_class.oid,
+            CASE pg_class.relkind WHEN 'S' THEN 'sequence' ELSE 'table' END,
+            nspname::text,
+            relname::text,
+            NULL,
+            CASE acl.grantee WHEN 0 THEN 'PUBLIC' ELSE pg_get_userbyid(acl.grantee)::text END,
+            acl.privilege_type,
+            acl.is_grantable,
+            NULL
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace,
+             aclexplode(COALESCE(relacl, acldefault(CASE relkind WHEN 'S' THEN 's' ELSE 'r' END::\"char\", relowner))) acl
+        WHERE pg_class.relkind IN ('r', 'p', 'v', 'm', 'S') AND
+              acl.grantee <> relowner AND
+              nspname !~* 'pg_|information_schema'
+        UNION ALL
+        SELECT
+            pg_proc.oid,
+            'function',
+            nspname::text,
+            proname::text,
+            NULL,
+            CASE acl.grantee WHEN 0 THEN 'PUBLIC' ELSE pg_get_userbyid(acl.grantee)::text END,
+            acl.privilege_type,
+            acl.is_grantable,
+            pg_get_function_identity_arguments(pg_proc.oid)
+        FROM pg_proc
+        JOIN pg_namespace ON pg_namespace.oid = pg_proc.pronamespace,
+             aclexplode(COALESCE(proacl, acldefault('f', proowner))) acl
+        WHERE acl.grantee <> proowner AND
+              nspname !~* 'pg_|information_schema' AND
+              proname !~ '^_'
+        UNION ALL
+        SELECT
+            pg_default_acl.oid,
+            CASE defaclobjtype WHEN 'r' THEN 'tables' WHEN 'S' THEN 'sequences' ELSE 'functions' END,
+            nspname::text,
+            NULL,
+            CASE WHEN defaclrole = (SELECT oid FROM pg_roles WHERE rolname = current_user) THEN NULL
+                 ELSE pg_get_userbyid(defaclrole)::text END,
+            CASE acl.grantee WHEN 0 THEN 'PUBLIC' ELSE pg_get_userbyid(acl.grantee)::text END,
+            acl.privilege_type,
+            acl.is_grantable,
+            NULL
+        FROM pg_default_acl
+        LEFT JOIN pg_namespace ON pg_namespace.oid = pg_default_acl.defaclnamespace,
+             aclexplode(defaclacl) acl
+        WHERE defaclobjtype IN ('r', 'S', 'f') AND
+              acl.grantee <> defaclrole
+        UNION ALL
+        SELECT
+            pg_auth_members.roleid,
+            'role',
+            NULL,
+            pg_get_userbyid(roleid)::text,
+            NULL,
+            pg_get_userbyid(member)::text,
+            'MEMBER',
+            admin_option,
+            NULL
+        FROM pg_auth_members
+        WHERE pg_get_userbyid(roleid) !~ '^pg_'
+    )";
+
+fn parse_privilege(row: &Row) -> PsqlpackResult<Option<PrivilegeDefinition>> {
+    let kind: String = row.get(1);
+    let schema: Option<String> = row.get(2);
+    let name: Option<String> = row.get(3);
+    let role: Option<String> = row.get(4);
+    let privilege_type: String = row.get(6);
+
+    let object_name = || ObjectName {
+        schema: schema.clone(),
+        name: name.clone().unwrap_or_default(),
+    };
+    let default_scope = |kind| {
+        PrivilegeObject::Default(DefaultPrivilegeScope {
+            role: role.clone(),
+            schema: schema.clone(),
+            kind,
+        })
+    };
+    let object = match &kind[..] {
+        "schema" => PrivilegeObject::Schema(name.clone().unwrap_or_default()),
+        "table" => PrivilegeObject::Table(object_name()),
+        "sequence" => PrivilegeObject::Sequence(object_name()),
+        "function" => PrivilegeObject::function(object_name(), Some(parse_function_arguments(row.get(8))?)),
+        "role" => PrivilegeObject::Role(name.clone().unwrap_or_default()),
+        "tables" => default_scope(DefaultPrivilegeKind::Tables),
+        "sequences" => default_scope(DefaultPrivilegeKind::Sequences),
+        "functions" => default_scope(DefaultPrivilegeKind::Functions),
+        _ => return Ok(None),
+    };
+
+    // Privileges we don't model (e.g. MAINTAIN in later versions) are ignored
+    let privilege = match Privilege::from_name(&privilege_type) {
+        Some(privilege) => privilege,
+        None => return Ok(None),
+    };
+    Ok(Some(PrivilegeDefinition {
+        action: PrivilegeAction::Grant,
+        object,
+        privilege,
+        grantee: row.get(5),
+        grant_option: row.get(7),
+        span: Span::default(),
+    }))
+}
+
+// Columns and constraints are described by their table, whereas functions are also identified by their arguments
+static CTE_COMMENTS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            CASE WHEN relkind IN ('i', 'I') THEN 'index' ELSE 'table' END AS kind,
+            nspname::text AS object_schema,
+            relname::text AS object_name,
+            NULL::text AS member,
+            obj_description(pg_class.oid, 'pg_class') AS comment
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE relkind IN ('r', 'p', 'i', 'I') AND
+              nspname !~* 'pg_|information_schema' AND
+              obj_description(pg_class.oid, 'pg_class') IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_class.oid,
+            'column',
+            nspname::text,
+            relname::text,
+            attname::text,
+            col_description(pg_class.oid, attnum)
+        FROM pg_attribute
+        JOIN pg_class ON pg_class.oid = pg_attribute.attrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE relkind IN ('r', 'p') AND
+              attnum > 0 AND
+              NOT attisdropped AND
+              nspname !~* 'pg_|information_schema' AND
+              col_description(pg_class.oid, attnum) IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_proc.oid,
+            'function',
+            nspname::text,
+            proname::text,
+            pg_get_function_identity_arguments(pg_proc.oid),
+            obj_description(pg_proc.oid, 'pg_proc')
+        FROM pg_proc
+        JOIN pg_namespace ON pg_namespace.oid = pg_proc.pronamespace
+        WHERE pg_proc.oid NOT IN (SELECT aggfnoid FROM pg_aggregate) AND
+              nspname !~* 'pg_|information_schema' AND
+              obj_description(pg_proc.oid, 'pg_proc') IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_type.oid,
+            'type',
+            nspname::text,
+            typname::text,
+            NULL,
+            obj_description(pg_type.oid, 'pg_type')
+        FROM pg_type
+        JOIN pg_namespace ON pg_namespace.oid = pg_type.typnamespace
+        WHERE typtype IN ('c', 'd', 'e', 'r') AND
+              nspname !~* 'pg_|information_schema' AND
+              obj_description(pg_type.oid, 'pg_type') IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_constraint.oid,
+            'constraint',
+            nspname::text,
+            relname::text,
+            conname::text,
+            obj_description(pg_constraint.oid, 'pg_constraint')
+        FROM pg_constraint
+        JOIN pg_class ON pg_class.oid = pg_constraint.conrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE nspname !~* 'pg_|information_schema' AND
+              obj_description(pg_constraint.oid, 'pg_constraint') IS NOT NULL
+    )";
+
+fn parse_comment(row: &Row) -> PsqlpackResult<Option<CommentDefinition>> {
+    let kind: String = row.get(1);
+    let name = ObjectName {
+        schema: Some(row.get(2)),
+        name: row.get(3),
+    };
+    let member: Option<String> = row.get(4);
+
+    let object = match &kind[..] {
+        "table" => CommentObject::Table(name),
+        "index" => CommentObject::Index(name),
+        "column" => CommentObject::Column(name, member.unwrap_or_default()),
+        "function" => CommentObject::function(name, parse_function_arguments(member.unwrap_or_default())?),
+        "type" => CommentObject::Type(name),
+        "constraint" => CommentObject::Constraint(name, member.unwrap_or_default()),
+        _ => return Ok(None),
+    };
+    Ok(Some(CommentDefinition {
+        object,
+        comment: row.get(5),
+        span: Span::default(),
+    }))
+}
+
 impl From<String> for SqlType {
     fn from(s: String) -> Self {
         // TODO: Error handling for this
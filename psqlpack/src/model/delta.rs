@@ -1,3 +1,4 @@
+use std::cmp;
 use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
@@ -10,36 +11,62 @@ use slog::Logger;
 use crate::connection::Connection;
 use crate::errors::PsqlpackErrorKind::*;
 use crate::errors::{PsqlpackResult, PsqlpackResultExt};
+use crate::model::expression::{same_expression, same_value};
 use crate::model::{Capabilities, Dependency, Node, Package, PublishProfile, Toggle};
 use crate::sql::ast::*;
+use crate::sql::formatter::{domain_constraint_sql, identity_sql, role_list, Formatter};
 use crate::Semver;
 
 enum DbObject<'a> {
+    Aggregate(&'a AggregateDefinition), // 8 (ordered)
     Column(&'a TableDefinition, &'a ColumnDefinition),
+    Comment(&'a CommentDefinition), // 15
     Constraint(&'a TableDefinition, &'a TableConstraint),
-    ExtensionRequest(&'a Dependency), // 2
-    Function(&'a FunctionDefinition), // 6 (ordered)
-    Index(&'a IndexDefinition),       // 7
-    Schema(&'a SchemaDefinition),     // 3
-    Script(&'a ScriptDefinition),     // 1, 8
-    Table(&'a TableDefinition),       // 5 (ordered)
-    Type(&'a TypeDefinition),         // 4
+    ExtensionRequest(&'a Dependency),                 // 2
+    Function(&'a FunctionDefinition),                 // 8 (ordered)
+    Index(&'a IndexDefinition),                       // 11
+    MaterializedView(&'a MaterializedViewDefinition), // 9
+    Policy(&'a PolicyDefinition),                     // 13
+    Privilege(&'a PrivilegeDefinition),               // 14
+    Procedure(&'a ProcedureDefinition),               // 8 (ordered)
+    Schema(&'a SchemaDefinition),                     // 3
+    Script(&'a ScriptDefinition),                     // 1, 16
+    Sequence(&'a SequenceDefinition),                 // 5
+    SequenceOwner(&'a SequenceDefinition),            // 7
+    Table(&'a TableDefinition),                       // 6 (ordered)
+    Trigger(&'a TriggerDefinition),                   // 12 (ordered)
+    Type(&'a TypeDefinition),                         // 4
+    View(&'a ViewDefinition),                         // 10
 }
 
 impl<'a> fmt::Display for DbObject<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            DbObject::Aggregate(aggregate) => write!(f, "Aggregate: {}", aggregate.signature()),
             DbObject::Column(table, column) => write!(f, "Table: {}, Column: {}", table.name, column.name),
+            DbObject::Comment(comment) => write!(f, "Comment: {}", comment.object),
             DbObject::Constraint(table, constraint) => {
                 write!(f, "Table: {}, Constraint: {}", table.name, constraint.name())
             }
             DbObject::ExtensionRequest(extension) => write!(f, "ExtensionRequest: {}", extension.name),
-            DbObject::Function(function) => write!(f, "Function: {}", function.name),
+            DbObject::Function(function) => write!(f, "Function: {}", function.signature()),
             DbObject::Index(index) => write!(f, "Index: {}", index.name),
+            DbObject::MaterializedView(view) => write!(f, "Materialized View: {}", view.name),
+            DbObject::Policy(policy) => write!(f, "Policy: {} on {}", policy.name, policy.table),
+            DbObject::Privilege(privilege) => write!(
+                f,
+                "Privilege: {} on {} for {}",
+                privilege.privilege, privilege.object, privilege.grantee
+            ),
+            DbObject::Procedure(procedure) => write!(f, "Procedure: {}", procedure.signature()),
             DbObject::Schema(schema) => write!(f, "Schema: {}", schema.name),
             DbObject::Script(script) => write!(f, "Script: {}", script.name),
+            DbObject::Sequence(sequence) => write!(f, "Sequence: {}", sequence.name),
+            DbObject::SequenceOwner(sequence) => write!(f, "Sequence Owner: {}", sequence.name),
             DbObject::Table(table) => write!(f, "Table: {}", table.name),
+            DbObject::Trigger(trigger) => write!(f, "Trigger: {} on {}", trigger.name, trigger.table),
             DbObject::Type(tipe) => write!(f, "Type: {}", tipe.name),
+            DbObject::View(view) => write!(f, "View: {}", view.name),
         }
     }
 }
@@ -65,11 +92,17 @@ impl<'a> Diffable<'a, Package> for DbObject<'a> {
         log: &Logger,
     ) -> PsqlpackResult<()> {
         match *self {
+            DbObject::Aggregate(aggregate) => {
+                aggregate.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Column(table, column) => LinkedColumn {
                 table: &table,
                 column: &column,
             }
             .generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Comment(comment) => {
+                comment.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Constraint(table, constraint) => LinkedTableConstraint {
                 table: &table,
                 constraint: &constraint,
@@ -84,10 +117,30 @@ impl<'a> Diffable<'a, Package> for DbObject<'a> {
                 function.generate(change_set, target, target_capabilities, publish_profile, log)
             }
             DbObject::Index(index) => index.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::MaterializedView(view) => {
+                view.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::Policy(policy) => policy.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Privilege(privilege) => {
+                privilege.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::Procedure(procedure) => {
+                procedure.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Schema(schema) => schema.generate(change_set, target, target_capabilities, publish_profile, log),
             DbObject::Script(script) => script.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Sequence(sequence) => {
+                sequence.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::SequenceOwner(sequence) => {
+                SequenceOwnership { sequence }.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Table(table) => table.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Trigger(trigger) => {
+                trigger.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Type(ty) => ty.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::View(view) => view.generate(change_set, target, target_capabilities, publish_profile, log),
         }
     }
 }
@@ -193,19 +246,278 @@ impl<'a> Diffable<'a, Package> for &'a FunctionDefinition {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
-        _target: &Package,
+        target: &Package,
         _target_capabilities: &Capabilities,
-        _publish_profile: &PublishProfile,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let existing = match target.functions.iter().find(|f| f.is_same_function(self)) {
+            Some(existing) => existing,
+            None => {
+                change_set.push(ChangeInstruction::AddFunction(self));
+                return Ok(());
+            }
+        };
+
+        // CREATE OR REPLACE can't change the result type or argument names so these require recreating the function
+        if !same_function_interface(self, existing) {
+            match publish_profile.generation_options.drop_functions {
+                Toggle::Allow => {
+                    change_set.push(ChangeInstruction::DropFunction(existing.signature()));
+                    change_set.push(ChangeInstruction::AddFunction(self));
+                }
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Function {} can only be changed by recreating it however dropping functions is currently disabled",
+                    self.signature()
+                ))),
+                _ => {}
+            }
+        } else if !same_function_definition(self, existing) {
+            let diff = body_diff(&existing.body, &self.body);
+            change_set.push(ChangeInstruction::ModifyFunction(self, diff));
+        }
+        Ok(())
+    }
+}
+
+// Argument modes, names and types along with the return type make up how a function is called
+fn same_function_interface(source: &FunctionDefinition, target: &FunctionDefinition) -> bool {
+    let same_return_type = match (&source.return_type, &target.return_type) {
+        (&FunctionReturnType::Table(ref src), &FunctionReturnType::Table(ref tgt)) => {
+            src.len() == tgt.len()
+                && src
+                    .iter()
+                    .zip(tgt.iter())
+                    .all(|(s, t)| s.name == t.name && s.sql_type.is_same_argument_type(&t.sql_type))
+        }
+        (&FunctionReturnType::SetOf(ref src), &FunctionReturnType::SetOf(ref tgt))
+        | (&FunctionReturnType::SqlType(ref src), &FunctionReturnType::SqlType(ref tgt)) => {
+            src.is_same_argument_type(tgt)
+        }
+        _ => false,
+    };
+    same_return_type && same_arguments(&source.arguments, &target.arguments)
+}
+
+// Defaults can be added or changed by replacing a function or procedure but not removed
+fn same_arguments(source: &[FunctionArgument], target: &[FunctionArgument]) -> bool {
+    fn same_mode(source: &Option<FunctionArgumentMode>, target: &Option<FunctionArgumentMode>) -> bool {
+        source.as_ref().unwrap_or(&FunctionArgumentMode::In) == target.as_ref().unwrap_or(&FunctionArgumentMode::In)
+    }
+    fn same_argument(source: &FunctionArgument, target: &FunctionArgument) -> bool {
+        same_mode(&source.mode, &target.mode)
+            && source.name == target.name
+            && source.sql_type.is_same_argument_type(&target.sql_type)
+            && (source.default.is_some() || target.default.is_none())
+    }
+
+    source.len() == target.len() && source.iter().zip(target.iter()).all(|(s, t)| same_argument(s, t))
+}
+
+// Assumes the interface is the same. The database only records attributes that differ from their defaults.
+fn same_function_definition(source: &FunctionDefinition, target: &FunctionDefinition) -> bool {
+    fn returns_set(function: &FunctionDefinition) -> bool {
+        match function.return_type {
+            FunctionReturnType::SqlType(_) => false,
+            _ => true,
+        }
+    }
+
+    source.language == target.language
+        && normalize_body(&source.body) == normalize_body(&target.body)
+        && same_defaults(&source.arguments, &target.arguments)
+        && function_attributes(&source.attributes, &source.language, returns_set(source))
+            == function_attributes(&target.attributes, &target.language, returns_set(target))
+}
+
+// As with functions, this assumes the arguments are the same
+fn same_procedure_definition(source: &ProcedureDefinition, target: &ProcedureDefinition) -> bool {
+    source.language == target.language
+        && normalize_body(&source.body) == normalize_body(&target.body)
+        && same_defaults(&source.arguments, &target.arguments)
+        && function_attributes(&source.attributes, &source.language, false)
+            == function_attributes(&target.attributes, &target.language, false)
+}
+
+fn same_defaults(source: &[FunctionArgument], target: &[FunctionArgument]) -> bool {
+    source
+        .iter()
+        .zip(target.iter())
+        .all(|(s, t)| match (&s.default, &t.default) {
+            (Some(src), Some(tgt)) => same_default(src, tgt),
+            (None, None) => true,
+            _ => false,
+        })
+}
+
+fn function_attributes(
+    attributes: &[FunctionAttribute],
+    language: &FunctionLanguage,
+    returns_set: bool,
+) -> Vec<FunctionAttribute> {
+    let default_cost = match *language {
+        FunctionLanguage::C | FunctionLanguage::Internal => 1,
+        _ => 100,
+    };
+    let mut attributes = attributes
+        .iter()
+        .filter(|attribute| match **attribute {
+            FunctionAttribute::Volatility(FunctionVolatility::Volatile)
+            | FunctionAttribute::Strict(false)
+            | FunctionAttribute::SecurityDefiner(false)
+            | FunctionAttribute::Leakproof(false)
+            | FunctionAttribute::Parallel(ParallelSafety::Unsafe) => false,
+            FunctionAttribute::Cost(cost) => cost != default_cost,
+            FunctionAttribute::Rows(rows) => returns_set && rows != 1000,
+            _ => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    attributes.sort();
+    attributes
+}
+
+// Leading and trailing whitespace is insignificant, as are line endings
+fn normalize_body(body: &str) -> String {
+    body.trim().lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+}
+
+// A line by line diff of the body for the report. Removed lines are prefixed with `-`, added lines with `+`.
+fn body_diff(target: &str, source: &str) -> Vec<String> {
+    let target = normalize_body(target);
+    let source = normalize_body(source);
+    if target == source {
+        return Vec::new();
+    }
+    let old = target.lines().collect::<Vec<_>>();
+    let new = source.lines().collect::<Vec<_>>();
+
+    // The length of the longest common subsequence from each pair of lines onwards
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for (i, old_line) in old.iter().enumerate().rev() {
+        for (j, new_line) in new.iter().enumerate().rev() {
+            common[i][j] = if old_line == new_line {
+                common[i + 1][j + 1] + 1
+            } else {
+                cmp::max(common[i + 1][j], common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            diff.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|line| format!("- {}", line)));
+    diff.extend(new[j..].iter().map(|line| format!("+ {}", line)));
+    diff
+}
+
+impl<'a> Diffable<'a, Package> for &'a ProcedureDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let existing = match target.procedures.iter().find(|p| p.is_same_procedure(self)) {
+            Some(existing) => existing,
+            None => {
+                change_set.push(ChangeInstruction::AddProcedure(self));
+                return Ok(());
+            }
+        };
+
+        // Like functions, CREATE OR REPLACE can't change argument names so these require recreating the procedure
+        if !same_arguments(&self.arguments, &existing.arguments) {
+            match publish_profile.generation_options.drop_functions {
+                Toggle::Allow => {
+                    change_set.push(ChangeInstruction::DropProcedure(existing.signature()));
+                    change_set.push(ChangeInstruction::AddProcedure(self));
+                }
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Procedure {} can only be changed by recreating it however dropping functions is currently disabled",
+                    self.signature()
+                ))),
+                _ => {}
+            }
+        } else if !same_procedure_definition(self, existing) {
+            change_set.push(ChangeInstruction::ModifyProcedure(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a AggregateDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
-        // Since we don't really need to worry about this in PG we just
-        // add it as is and rely on CREATE OR REPLACE. In the future, it'd
-        // be good to check the hash or something to only do this when required
-        change_set.push(ChangeInstruction::ModifyFunction(self));
+        let existing = match target.aggregates.iter().find(|a| a.is_same_aggregate(self)) {
+            Some(existing) => existing,
+            None => {
+                change_set.push(ChangeInstruction::AddAggregate(self));
+                return Ok(());
+            }
+        };
+        if same_aggregate_definition(self, existing) {
+            return Ok(());
+        }
+
+        // Replacing an aggregate can't change its result type so it is always recreated instead
+        match publish_profile.generation_options.drop_functions {
+            Toggle::Allow => {
+                change_set.push(ChangeInstruction::DropAggregate(existing.signature()));
+                change_set.push(ChangeInstruction::AddAggregate(self));
+            }
+            Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                "Aggregate {} can only be changed by recreating it however dropping functions is currently disabled",
+                self.signature()
+            ))),
+            _ => {}
+        }
         Ok(())
     }
 }
 
+// Support functions in the source may rely on the search path, whereas the database always qualifies them
+fn same_aggregate_definition(source: &AggregateDefinition, target: &AggregateDefinition) -> bool {
+    fn same_function(source: &ObjectName, target: &ObjectName) -> bool {
+        source.name == target.name && (source.schema.is_none() || source.schema == target.schema)
+    }
+    fn same_optional_function(source: &Option<ObjectName>, target: &Option<ObjectName>) -> bool {
+        match (source, target) {
+            (Some(source), Some(target)) => same_function(source, target),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    same_function(&source.state_function, &target.state_function)
+        && source.state_type == target.state_type
+        && same_optional_function(&source.final_function, &target.final_function)
+        && same_optional_function(&source.combine_function, &target.combine_function)
+        && source.initial_condition == target.initial_condition
+        && source.parallel.unwrap_or(ParallelSafety::Unsafe) == target.parallel.unwrap_or(ParallelSafety::Unsafe)
+}
+
 impl<'a> Diffable<'a, Package> for &'a SchemaDefinition {
     fn generate(
         &self,
@@ -249,55 +561,195 @@ impl<'a> Diffable<'a, Package> for &'a TableDefinition {
     ) -> PsqlpackResult<()> {
         let table_result = target.tables.iter().find(|t| t.name == self.name);
         if let Some(target_table) = table_result {
-            // We check for column removals here
-            for tgt in target_table.columns.iter() {
-                if !self.columns.iter().any(|src| tgt.name.eq(&src.name)) {
-                    // Column in target but not in source
-                    match publish_profile.generation_options.drop_columns {
-                        Toggle::Allow => change_set.push(ChangeInstruction::DropColumn(self, tgt.name.to_owned())),
-                        Toggle::Error => {
-                            bail!(PublishUnsafeOperationError(format!(
-                                "Unable to drop column as dropping columns is currently disabled: {}",
-                                tgt.name
-                            )));
+            // A partition takes its columns and constraints from its parent so there is nothing to remove
+            if self.partition_of.is_none() {
+                // We check for column removals here
+                for tgt in target_table.columns.iter() {
+                    if !self.columns.iter().any(|src| tgt.name.eq(&src.name)) {
+                        // Column in target but not in source
+                        match publish_profile.generation_options.drop_columns {
+                            Toggle::Allow => change_set.push(ChangeInstruction::DropColumn(self, tgt.name.to_owned())),
+                            Toggle::Error => {
+                                bail!(PublishUnsafeOperationError(format!(
+                                    "Unable to drop column as dropping columns is currently disabled: {}",
+                                    tgt.name
+                                )));
+                            }
+                            _ => {}
                         }
-                        _ => {}
                     }
                 }
-            }
 
-            // We also check for table constraint removals here
-            for tgt in target_table.constraints.iter() {
-                if !self.constraints.iter().any(|src| tgt.name().eq(src.name())) {
-                    let remove_ok = match tgt {
-                        TableConstraint::Primary { .. } => {
-                            match publish_profile.generation_options.drop_primary_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to drop constraint as dropping PKs is currently disabled: {}",
-                                    tgt.name()
-                                ))),
+                // We also check for table constraint removals here
+                for tgt in target_table.constraints.iter() {
+                    if !self.constraints.iter().any(|src| tgt.name().eq(src.name())) {
+                        let remove_ok = match tgt {
+                            TableConstraint::Primary { .. } => {
+                                match publish_profile.generation_options.drop_primary_key_constraints {
+                                    Toggle::Allow => true,
+                                    Toggle::Ignore => false,
+                                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                        "Unable to drop constraint as dropping PKs is currently disabled: {}",
+                                        tgt.name()
+                                    ))),
+                                }
                             }
-                        }
-                        TableConstraint::Foreign { .. } => {
-                            match publish_profile.generation_options.drop_foreign_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to drop constraint as dropping FKs is currently disabled: {}",
-                                    tgt.name()
-                                ))),
+                            TableConstraint::Foreign { .. } => {
+                                match publish_profile.generation_options.drop_foreign_key_constraints {
+                                    Toggle::Allow => true,
+                                    Toggle::Ignore => false,
+                                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                        "Unable to drop constraint as dropping FKs is currently disabled: {}",
+                                        tgt.name()
+                                    ))),
+                                }
+                            }
+                            TableConstraint::Check { .. } => {
+                                match publish_profile.generation_options.drop_check_constraints {
+                                    Toggle::Allow => true,
+                                    Toggle::Ignore => false,
+                                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                        "Unable to drop constraint as dropping check constraints is currently disabled: {}",
+                                        tgt.name()
+                                    ))),
+                                }
                             }
+                            TableConstraint::Unique { .. } | TableConstraint::Exclude { .. } => {
+                                match publish_profile.generation_options.drop_unique_constraints {
+                                    Toggle::Allow => true,
+                                    Toggle::Ignore => false,
+                                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                        "Unable to drop constraint as dropping unique constraints is currently disabled: {}",
+                                        tgt.name()
+                                    ))),
+                                }
+                            }
+                        };
+                        if remove_ok {
+                            change_set.push(ChangeInstruction::DropConstraint(self, tgt.name().to_owned()));
                         }
-                    };
-                    if remove_ok {
-                        change_set.push(ChangeInstruction::DropConstraint(self, tgt.name().to_owned()));
                     }
                 }
             }
+
+            if partition_key_different(&self.partition_by, &target_table.partition_by) {
+                bail!(PublishInvalidOperationError(format!(
+                    "Unable to change how an existing table is partitioned: {}",
+                    self.name
+                )));
+            }
+            if partition_different(&self.partition_of, &target_table.partition_of) {
+                if let Some(ref partition) = target_table.partition_of {
+                    change_set.push(ChangeInstruction::DetachPartition(
+                        partition.parent.to_string(),
+                        self.name.to_string(),
+                    ));
+                }
+                if self.partition_of.is_some() {
+                    change_set.push(ChangeInstruction::AttachPartition(self));
+                }
+            }
+
+            if self.row_security != target_table.row_security {
+                let action = if self.row_security {
+                    AlterTableAction::EnableRowSecurity
+                } else {
+                    AlterTableAction::DisableRowSecurity
+                };
+                change_set.push(ChangeInstruction::AlterTable(self.name.to_string(), action));
+            }
+            if self.force_row_security != target_table.force_row_security {
+                let action = if self.force_row_security {
+                    AlterTableAction::ForceRowSecurity
+                } else {
+                    AlterTableAction::NoForceRowSecurity
+                };
+                change_set.push(ChangeInstruction::AlterTable(self.name.to_string(), action));
+            }
         } else {
             change_set.push(ChangeInstruction::AddTable(self));
+            if self.row_security {
+                change_set.push(ChangeInstruction::AlterTable(
+                    self.name.to_string(),
+                    AlterTableAction::EnableRowSecurity,
+                ));
+            }
+            if self.force_row_security {
+                change_set.push(ChangeInstruction::AlterTable(
+                    self.name.to_string(),
+                    AlterTableAction::ForceRowSecurity,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a SequenceDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let sequence = target.sequences.iter().find(|s| s.name == self.name);
+        if let Some(sequence) = sequence {
+            // Unspecified options take on their default so we compare the effective values. Ownership
+            // is handled separately since the owning table may not exist yet.
+            let mut options = Vec::new();
+            if self.effective_increment() != sequence.effective_increment() {
+                options.push(SequenceOption::Increment(self.effective_increment()));
+            }
+            if self.effective_min_value() != sequence.effective_min_value() {
+                options.push(SequenceOption::MinValue(self.min_value));
+            }
+            if self.effective_max_value() != sequence.effective_max_value() {
+                options.push(SequenceOption::MaxValue(self.max_value));
+            }
+            if self.effective_start() != sequence.effective_start() {
+                options.push(SequenceOption::Start(self.effective_start()));
+            }
+            if self.effective_cache() != sequence.effective_cache() {
+                options.push(SequenceOption::Cache(self.effective_cache()));
+            }
+            if self.cycle != sequence.cycle {
+                options.push(SequenceOption::Cycle(self.cycle));
+            }
+            if !options.is_empty() {
+                change_set.push(ChangeInstruction::ModifySequence(self, options));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddSequence(self));
+        }
+        Ok(())
+    }
+}
+
+struct SequenceOwnership<'a> {
+    sequence: &'a SequenceDefinition,
+}
+
+impl<'a> Diffable<'a, Package> for SequenceOwnership<'a> {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let owned_by = target
+            .sequences
+            .iter()
+            .find(|s| s.name == self.sequence.name)
+            .and_then(|s| s.owned_by.as_ref());
+        if self.sequence.owned_by.as_ref() != owned_by {
+            change_set.push(ChangeInstruction::ModifySequence(
+                self.sequence,
+                vec![SequenceOption::OwnedBy(self.sequence.owned_by.clone())],
+            ));
         }
         Ok(())
     }
@@ -308,13 +760,103 @@ struct LinkedColumn<'a> {
     column: &'a ColumnDefinition,
 }
 
+impl<'a> LinkedColumn<'a> {
+    fn generate_default(&self, change_set: &mut Vec<ChangeInstruction<'a>>, target_column: &ColumnDefinition) {
+        match (default_value(self.column), default_value(target_column)) {
+            (Some(src), Some(tgt)) => {
+                if !same_default(src, tgt) {
+                    change_set.push(ChangeInstruction::ModifyColumnDefault(self.table, self.column));
+                }
+            }
+            (Some(_), None) => change_set.push(ChangeInstruction::ModifyColumnDefault(self.table, self.column)),
+            (None, Some(_)) => change_set.push(ChangeInstruction::DropColumnDefault(
+                self.table,
+                self.column.name.to_owned(),
+            )),
+            (None, None) => {}
+        }
+    }
+
+    fn generate_identity(&self, change_set: &mut Vec<ChangeInstruction<'a>>, target_column: &ColumnDefinition) {
+        match (identity(self.column), identity(target_column)) {
+            (Some(_), None) => change_set.push(ChangeInstruction::AddColumnIdentity(self.table, self.column)),
+            (None, Some(_)) => change_set.push(ChangeInstruction::DropColumnIdentity(
+                self.table,
+                self.column.name.to_owned(),
+            )),
+            (Some((src_kind, src_options)), Some((tgt_kind, tgt_options))) => {
+                let kind = if src_kind != tgt_kind { Some(src_kind) } else { None };
+                let tgt_options = identity_options(tgt_options);
+                let options = identity_options(src_options)
+                    .into_iter()
+                    .filter(|option| !tgt_options.contains(option))
+                    .collect::<Vec<_>>();
+                if kind.is_some() || !options.is_empty() {
+                    change_set.push(ChangeInstruction::ModifyColumnIdentity(
+                        self.table,
+                        self.column,
+                        kind,
+                        options,
+                    ));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn generate_expression(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target_column: &ColumnDefinition,
+        target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+    ) -> PsqlpackResult<()> {
+        let recreate = match (generation_expression(self.column), generation_expression(target_column)) {
+            (Some(src), Some(tgt)) => {
+                if same_expression(src, tgt) {
+                    false
+                } else if target_capabilities.server_version >= Semver::new(17, 0, None) {
+                    change_set.push(ChangeInstruction::ModifyColumnExpression(self.table, self.column));
+                    false
+                } else {
+                    true
+                }
+            }
+            (None, Some(_)) => {
+                change_set.push(ChangeInstruction::DropColumnExpression(
+                    self.table,
+                    self.column.name.to_owned(),
+                ));
+                false
+            }
+            // An existing column can't become generated so it must be recreated
+            (Some(_), None) => true,
+            (None, None) => false,
+        };
+        if recreate {
+            match publish_profile.generation_options.drop_columns {
+                Toggle::Allow => {
+                    change_set.push(ChangeInstruction::DropColumn(self.table, self.column.name.to_owned()));
+                    change_set.push(ChangeInstruction::AddColumn(self.table, self.column));
+                }
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Unable to recreate generated column as dropping columns is currently disabled: {}",
+                    self.column.name
+                ))),
+                Toggle::Ignore => {}
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<'a> Diffable<'a, Package> for LinkedColumn<'a> {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
         target: &Package,
-        _target_capabilities: &Capabilities,
-        _publish_profile: &PublishProfile,
+        target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
         // We only generate items here if the table doesn't exist (for the time being)
@@ -347,19 +889,22 @@ impl<'a> Diffable<'a, Package> for LinkedColumn<'a> {
                         ColumnConstraint::NotNull => {
                             change_set.push(ChangeInstruction::ModifyColumnNull(self.table, &self.column))
                         }
-                        ColumnConstraint::Default(_) => {
-                            change_set.push(ChangeInstruction::ModifyColumnDefault(self.table, &self.column))
-                        }
-                        ColumnConstraint::Unique => change_set.push(ChangeInstruction::ModifyColumnUniqueConstraint(
-                            self.table,
-                            &self.column,
-                        )),
+                        // Defaults are compared below since they may be described differently
+                        ColumnConstraint::Default(_) => {}
                         ColumnConstraint::PrimaryKey => change_set.push(
                             ChangeInstruction::ModifyColumnPrimaryKeyConstraint(self.table, &self.column),
                         ),
+                        // Unique and check constraints are promoted to table constraints so are compared there
+                        ColumnConstraint::Unique | ColumnConstraint::Check { .. } => {}
+                        // Identity and generation are compared below since they may be added or removed
+                        ColumnConstraint::Identity(..) | ColumnConstraint::Generated(_) => {}
                     }
                 }
 
+                self.generate_default(change_set, target_column);
+                self.generate_identity(change_set, target_column);
+                self.generate_expression(change_set, target_column, target_capabilities, publish_profile)?;
+
             // TODO: src_sec - target_set (e.g. what column constraints have been removed)
             } else {
                 // Doesn't exist, add it
@@ -431,7 +976,7 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                                 vec_different(src_columns, columns)
                                     || optional_vec_different(src_parameters, parameters)
                             }
-                            TableConstraint::Foreign { .. } => true,
+                            _ => true,
                         }
                     }
                     TableConstraint::Foreign {
@@ -448,7 +993,6 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                         let src_match_type = match_type;
                         let src_events = events;
                         match target_constraint {
-                            TableConstraint::Primary { .. } => true,
                             TableConstraint::Foreign {
                                 ref columns,
                                 ref ref_table,
@@ -468,6 +1012,76 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                                     || vec_different(src_ref_columns, ref_columns)
                                     || optional_vec_different(src_events, events)
                             }
+                            _ => true,
+                        }
+                    }
+                    TableConstraint::Check {
+                        ref expression,
+                        no_inherit,
+                        ..
+                    } => {
+                        let src_expression = expression;
+                        let src_no_inherit = no_inherit;
+                        match target_constraint {
+                            TableConstraint::Check {
+                                ref expression,
+                                no_inherit,
+                                ..
+                            } => src_no_inherit != *no_inherit || !same_expression(src_expression, expression),
+                            _ => true,
+                        }
+                    }
+                    TableConstraint::Unique {
+                        ref columns,
+                        ref include,
+                        ref parameters,
+                        ..
+                    } => {
+                        let src_columns = columns;
+                        let src_include = include;
+                        let src_parameters = parameters;
+                        match target_constraint {
+                            TableConstraint::Unique {
+                                ref columns,
+                                ref include,
+                                ref parameters,
+                                ..
+                            } => {
+                                // An empty include list is the same as not specifying one
+                                let empty = Vec::new();
+                                vec_different(src_columns, columns)
+                                    || vec_different(
+                                        src_include.as_ref().unwrap_or(&empty),
+                                        include.as_ref().unwrap_or(&empty),
+                                    )
+                                    || optional_vec_different(src_parameters, parameters)
+                            }
+                            _ => true,
+                        }
+                    }
+                    TableConstraint::Exclude {
+                        ref index_type,
+                        ref elements,
+                        ref parameters,
+                        ..
+                    } => {
+                        let src_index_type = index_type;
+                        let src_elements = elements;
+                        let src_parameters = parameters;
+                        match target_constraint {
+                            TableConstraint::Exclude {
+                                ref index_type,
+                                ref elements,
+                                ref parameters,
+                                ..
+                            } => {
+                                // If not specified, btree is the default index type
+                                src_index_type.as_ref().unwrap_or(&IndexType::BTree)
+                                    != index_type.as_ref().unwrap_or(&IndexType::BTree)
+                                    || vec_different(src_elements, elements)
+                                    || optional_vec_different(src_parameters, parameters)
+                            }
+                            _ => true,
                         }
                     }
                 };
@@ -493,11 +1107,31 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                                 ))),
                             }
                         }
-                    };
-                    if remove_ok {
-                        change_set.push(ChangeInstruction::DropConstraint(
-                            self.table,
-                            self.constraint.name().to_owned(),
+                        TableConstraint::Check { .. } => {
+                            match publish_profile.generation_options.drop_check_constraints {
+                                Toggle::Allow => true,
+                                Toggle::Ignore => false,
+                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                    "Unable to modify constraint as dropping check constraints is currently disabled: {}",
+                                    self.constraint.name()
+                                ))),
+                            }
+                        }
+                        TableConstraint::Unique { .. } | TableConstraint::Exclude { .. } => {
+                            match publish_profile.generation_options.drop_unique_constraints {
+                                Toggle::Allow => true,
+                                Toggle::Ignore => false,
+                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                    "Unable to modify constraint as dropping unique constraints is currently disabled: {}",
+                                    self.constraint.name()
+                                ))),
+                            }
+                        }
+                    };
+                    if remove_ok {
+                        change_set.push(ChangeInstruction::DropConstraint(
+                            self.table,
+                            self.constraint.name().to_owned(),
                         ));
                         change_set.push(ChangeInstruction::AddConstraint(self.table, self.constraint));
                     }
@@ -524,10 +1158,26 @@ impl<'a> Diffable<'a, Package> for &'a IndexDefinition {
     ) -> PsqlpackResult<()> {
         // Indexes are unique across schema (implied by table)
         let index = target.indexes.iter().find(|idx| idx.is_same_index(self));
-        let concurrently = publish_profile.generation_options.force_concurrent_indexes;
-        if let Some(index) = index {
-            // We should be able to just use an eq for this since column ordering is significant
-            if index.ne(self) {
+        // Indexes on partitioned tables cannot be built concurrently
+        let partitioned = change_set.iter().any(|c| match *c {
+            ChangeInstruction::AddTable(table) => table.name == self.table && table.partition_by.is_some(),
+            _ => false,
+        }) || target
+            .tables
+            .iter()
+            .any(|table| table.name == self.table && table.partition_by.is_some());
+        let concurrently = publish_profile.generation_options.force_concurrent_indexes && !partitioned;
+        // If the materialized view this index belongs to is being recreated then the index will have
+        // been dropped along with it.
+        let table_name = self.table.to_string();
+        let table_recreated = change_set.iter().any(|c| match *c {
+            ChangeInstruction::DropMaterializedView(ref name) => name.eq(&table_name),
+            _ => false,
+        });
+        if table_recreated {
+            change_set.push(ChangeInstruction::AddIndex(self, concurrently));
+        } else if let Some(index) = index {
+            if index_different(self, index) {
                 change_set.push(ChangeInstruction::DropIndex(self.fully_qualified_name(), concurrently));
                 change_set.push(ChangeInstruction::AddIndex(self, concurrently));
             }
@@ -551,8 +1201,7 @@ impl<'a> Diffable<'a, Package> for &'a TypeDefinition {
         if let Some(ty) = ty {
             self.generate(change_set, ty, _target_capabilities, publish_profile, log)
         } else {
-            change_set.push(ChangeInstruction::AddType(self));
-            Ok(())
+            add_type(change_set, self)
         }
     }
 }
@@ -572,73 +1221,723 @@ impl<'a> Diffable<'a, TypeDefinition> for &'a TypeDefinition {
                 self.name, target.name
             )))
         }
-        // We can only diff types of the same kind. Only one type right now, but future proofing.
-        match self.kind {
-            TypeDefinitionKind::Enum(ref source_values) => {
-                match target.kind {
-                    TypeDefinitionKind::Enum(ref target_values) => {
-                        // Detect if anything needs to be deleted in the target
-                        let mut to_delete = target_values
-                            .iter()
-                            .filter(|v| !source_values.contains(v))
-                            .map(|v| TypeModificationAction::RemoveEnumValue { value: v.to_owned() })
-                            .collect::<Vec<_>>();
-                        if !to_delete.is_empty() {
-                            match publish_profile.generation_options.drop_enum_values {
-                                Toggle::Allow => {
-                                    change_set
-                                        .extend(to_delete.drain(..).map(|d| ChangeInstruction::ModifyType(self, d)));
-                                }
-                                Toggle::Error => {
-                                    bail!(PublishUnsafeOperationError(format!(
-                                        "Unable to remove enum value(s) as unsafe operations are disabled: {:?}",
-                                        to_delete
-                                    )));
-                                }
-                                _ => {}
-                            }
+        // Types of a different kind can't be altered so need to be recreated
+        match (&self.kind, &target.kind) {
+            (&TypeDefinitionKind::Enum(ref source_values), &TypeDefinitionKind::Enum(ref target_values)) => {
+                // Detect if anything needs to be deleted in the target
+                let mut to_delete = target_values
+                    .iter()
+                    .filter(|v| !source_values.contains(v))
+                    .map(|v| TypeModificationAction::RemoveEnumValue { value: v.to_owned() })
+                    .collect::<Vec<_>>();
+                if !to_delete.is_empty() {
+                    match publish_profile.generation_options.drop_enum_values {
+                        Toggle::Allow => {
+                            change_set.extend(to_delete.drain(..).map(|d| ChangeInstruction::ModifyType(self, d)));
+                        }
+                        Toggle::Error => {
+                            bail!(PublishUnsafeOperationError(format!(
+                                "Unable to remove enum value(s) as unsafe operations are disabled: {:?}",
+                                to_delete
+                            )));
+                        }
+                        _ => {}
+                    }
+                }
+
+                // Our working group after items being deleted
+                let mut working = target_values
+                    .iter()
+                    .filter(|v| source_values.contains(v))
+                    .collect::<Vec<_>>();
+
+                // Detect what needs adding
+                for (index, value) in source_values.iter().enumerate() {
+                    if !working.contains(&value) {
+                        if index == 0 {
+                            change_set.push(ChangeInstruction::ModifyType(
+                                self,
+                                TypeModificationAction::AddEnumValueBefore {
+                                    value: value.to_owned(),
+                                    before: working[0].to_owned(),
+                                },
+                            ));
+                            working.insert(0, value);
+                        } else {
+                            change_set.push(ChangeInstruction::ModifyType(
+                                self,
+                                TypeModificationAction::AddEnumValueAfter {
+                                    value: value.to_owned(),
+                                    after: working[index - 1].to_owned(),
+                                },
+                            ));
+                            working.insert(index, value);
+                        }
+                    }
+                }
+            }
+            (
+                &TypeDefinitionKind::Composite(ref source_attributes),
+                &TypeDefinitionKind::Composite(ref target_attributes),
+            ) => {
+                // Drop any attributes that no longer exist
+                for attribute in target_attributes {
+                    if !source_attributes.iter().any(|a| a.name.eq(&attribute.name)) {
+                        match publish_profile.generation_options.drop_types {
+                            Toggle::Allow => change_set.push(ChangeInstruction::ModifyType(
+                                self,
+                                TypeModificationAction::DropAttribute {
+                                    name: attribute.name.to_owned(),
+                                },
+                            )),
+                            Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                "Attempted to drop attribute {} from type {} however dropping types is currently disabled",
+                                attribute.name, self.name
+                            ))),
+                            _ => {}
                         }
+                    }
+                }
 
-                        // Our working group after items being deleted
-                        let mut working = target_values
-                            .iter()
-                            .filter(|v| source_values.contains(v))
-                            .collect::<Vec<_>>();
-
-                        // Detect what needs adding
-                        for (index, value) in source_values.iter().enumerate() {
-                            if !working.contains(&value) {
-                                if index == 0 {
-                                    change_set.push(ChangeInstruction::ModifyType(
-                                        self,
-                                        TypeModificationAction::AddEnumValueBefore {
-                                            value: value.to_owned(),
-                                            before: working[0].to_owned(),
-                                        },
-                                    ));
-                                    working.insert(0, value);
-                                } else {
-                                    change_set.push(ChangeInstruction::ModifyType(
-                                        self,
-                                        TypeModificationAction::AddEnumValueAfter {
-                                            value: value.to_owned(),
-                                            after: working[index - 1].to_owned(),
-                                        },
-                                    ));
-                                    working.insert(index, value);
-                                }
+                // Add or alter the rest
+                for attribute in source_attributes {
+                    match target_attributes.iter().find(|a| a.name.eq(&attribute.name)) {
+                        Some(existing) => {
+                            if existing.sql_type.ne(&attribute.sql_type) {
+                                change_set.push(ChangeInstruction::ModifyType(
+                                    self,
+                                    TypeModificationAction::AlterAttributeType {
+                                        name: attribute.name.to_owned(),
+                                        sql_type: attribute.sql_type.clone(),
+                                    },
+                                ));
                             }
                         }
+                        None => change_set.push(ChangeInstruction::ModifyType(
+                            self,
+                            TypeModificationAction::AddAttribute {
+                                name: attribute.name.to_owned(),
+                                sql_type: attribute.sql_type.clone(),
+                            },
+                        )),
+                    }
+                }
+            }
+            (&TypeDefinitionKind::Domain(ref source_domain), &TypeDefinitionKind::Domain(ref target_domain))
+                if source_domain.base_type.eq(&target_domain.base_type) =>
+            {
+                // Defaults are compared the same way as column defaults since PostgreSQL adds casts
+                let default_changed = match (&source_domain.default, &target_domain.default) {
+                    (&Some(ref src), &Some(ref tgt)) => !same_default(src, tgt),
+                    (&None, &None) => false,
+                    _ => true,
+                };
+                if default_changed {
+                    change_set.push(ChangeInstruction::ModifyType(
+                        self,
+                        TypeModificationAction::SetDomainDefault {
+                            default: source_domain.default.clone(),
+                        },
+                    ));
+                }
+                if source_domain.not_null != target_domain.not_null {
+                    change_set.push(ChangeInstruction::ModifyType(
+                        self,
+                        TypeModificationAction::SetDomainNotNull {
+                            not_null: source_domain.not_null,
+                        },
+                    ));
+                }
+
+                // Domain constraints are matched by name. Constraints can't be altered so they are dropped and
+                // recreated if the expression has changed.
+                for constraint in &target_domain.constraints {
+                    let changed = match source_domain.constraints.iter().find(|c| c.name.eq(&constraint.name)) {
+                        Some(source) => !same_expression(&source.expression, &constraint.expression),
+                        None => true,
+                    };
+                    if changed {
+                        let name = constraint.name.clone().unwrap_or_default();
+                        match publish_profile.generation_options.drop_check_constraints {
+                            Toggle::Allow => change_set.push(ChangeInstruction::ModifyType(
+                                self,
+                                TypeModificationAction::DropDomainConstraint { name },
+                            )),
+                            Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                "Attempted to drop constraint {} from domain {} however dropping check constraints is currently disabled",
+                                name, self.name
+                            ))),
+                            _ => {}
+                        }
+                    }
+                }
+                for constraint in &source_domain.constraints {
+                    let add = match target_domain.constraints.iter().find(|c| c.name.eq(&constraint.name)) {
+                        Some(target) => {
+                            publish_profile.generation_options.drop_check_constraints == Toggle::Allow
+                                && !same_expression(&constraint.expression, &target.expression)
+                        }
+                        None => true,
+                    };
+                    if add {
+                        change_set.push(ChangeInstruction::ModifyType(
+                            self,
+                            TypeModificationAction::AddDomainConstraint {
+                                constraint: constraint.clone(),
+                            },
+                        ));
+                    }
+                }
+            }
+            (&TypeDefinitionKind::Range(ref source_range), &TypeDefinitionKind::Range(ref target_range))
+                if source_range.eq(target_range) => {}
+            (&TypeDefinitionKind::UserDefined, &TypeDefinitionKind::UserDefined) => {}
+            _ => match publish_profile.generation_options.drop_types {
+                Toggle::Allow => {
+                    change_set.push(match target.kind {
+                        TypeDefinitionKind::Domain(_) => ChangeInstruction::DropDomain(target.name.to_string()),
+                        _ => ChangeInstruction::DropType(target.name.to_string()),
+                    });
+                    add_type(change_set, self)?;
+                }
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Type {} can only be changed by recreating it however dropping types is currently disabled",
+                    self.name
+                ))),
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+fn referenced_types(ty: &TypeDefinition) -> Vec<&ObjectName> {
+    let sql_types = match ty.kind {
+        TypeDefinitionKind::Composite(ref attributes) => attributes.iter().map(|a| &a.sql_type).collect(),
+        TypeDefinitionKind::Domain(ref domain) => vec![&domain.base_type],
+        TypeDefinitionKind::Range(ref range) => vec![&range.subtype],
+        TypeDefinitionKind::Enum(_) | TypeDefinitionKind::UserDefined => Vec::new(),
+    };
+    sql_types
+        .into_iter()
+        .filter_map(|sql_type| match *sql_type {
+            SqlType::Custom(ref name, _, _) => Some(name),
+            SqlType::Simple(..) => None,
+        })
+        .collect()
+}
+
+fn add_type<'a>(change_set: &mut Vec<ChangeInstruction<'a>>, ty: &'a TypeDefinition) -> PsqlpackResult<()> {
+    // Base types are implemented in C so can't be created from a definition alone
+    if let TypeDefinitionKind::UserDefined = ty.kind {
+        bail!(PublishInvalidOperationError(format!(
+            "Unable to create user defined type {}",
+            ty.name
+        )))
+    }
+    change_set.push(ChangeInstruction::AddType(ty));
+    Ok(())
+}
+
+impl<'a> Diffable<'a, Package> for &'a ViewDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // A view that depends on one being recreated has already been dropped along with it
+        let view_name = self.name.to_string();
+        let dropped = change_set.iter().any(|change| match *change {
+            ChangeInstruction::DropView(ref name) => name.eq(&view_name),
+            _ => false,
+        });
+        if dropped {
+            change_set.push(ChangeInstruction::AddView(self));
+            return Ok(());
+        }
+
+        let view = target.views.iter().find(|v| v.name == self.name);
+        if let Some(view) = view {
+            // Compare with how the database would store the view when it has been resolved against the target
+            let (columns, query) = match self.resolved {
+                Some(ref resolved) => (Some(&resolved.columns), &resolved.query),
+                None => (self.columns.as_ref(), &self.query),
+            };
+
+            // CREATE OR REPLACE can only add columns to the end of a view so we need to drop and recreate the
+            // view if any existing columns have been renamed or removed.
+            let columns_changed = match (columns, &view.columns) {
+                (Some(src), Some(tgt)) => !src.starts_with(tgt),
+                _ => false,
+            };
+            if columns_changed {
+                // Views that depend on this one can't remain while it's dropped. They are dropped first and,
+                // since views are built in dependency order, recreated once this view has been.
+                for dependent in target.dependent_views(&self.name)? {
+                    let name = dependent.name.to_string();
+                    let dropped = change_set.iter().any(|change| match *change {
+                        ChangeInstruction::DropView(ref view_name) => view_name.eq(&name),
+                        _ => false,
+                    });
+                    if !dropped {
+                        change_set.push(ChangeInstruction::DropView(name));
+                    }
+                }
+                change_set.push(ChangeInstruction::DropView(view_name));
+                change_set.push(ChangeInstruction::AddView(self));
+            } else if normalize_query(query).ne(&normalize_query(&view.query)) {
+                change_set.push(ChangeInstruction::ModifyView(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddView(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a MaterializedViewDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let view = target.materialized_views.iter().find(|v| v.name == self.name);
+        if let Some(view) = view {
+            // Compare with how the database would store the view when it has been resolved against the target
+            let (columns, query) = match self.resolved {
+                Some(ref resolved) => (Some(&resolved.columns), &resolved.query),
+                None => (self.columns.as_ref(), &self.query),
+            };
+
+            // Materialized views can't be replaced so any change to the definition requires the view to be
+            // dropped and recreated. We don't compare with_data since the target state changes upon refresh.
+            let columns_changed = match (columns, &view.columns) {
+                (Some(src), Some(tgt)) => src.ne(tgt),
+                _ => false,
+            };
+            if columns_changed || normalize_query(query).ne(&normalize_query(&view.query)) {
+                change_set.push(ChangeInstruction::DropMaterializedView(self.name.to_string()));
+                change_set.push(ChangeInstruction::AddMaterializedView(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddMaterializedView(self));
+        }
+        Ok(())
+    }
+}
+
+// Queries retrieved from the database are reformatted so we only compare the significant parts
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl<'a> Diffable<'a, Package> for &'a TriggerDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        fn sorted_events(trigger: &TriggerDefinition) -> Vec<&TriggerEvent> {
+            let mut events = trigger.events.iter().collect::<Vec<_>>();
+            events.sort();
+            events
+        }
+
+        // If the trigger is on a view which is being recreated then it will have been dropped with the view
+        let table_name = self.table.to_string();
+        let table_recreated = change_set.iter().any(|c| match *c {
+            ChangeInstruction::DropView(ref name) => name.eq(&table_name),
+            _ => false,
+        });
+
+        let trigger = target.triggers.iter().find(|t| t.is_same_trigger(self));
+        if table_recreated {
+            change_set.push(ChangeInstruction::AddTrigger(self));
+        } else if let Some(trigger) = trigger {
+            // Triggers can't be altered so any change requires the trigger to be dropped and recreated
+            let modified = self.timing != trigger.timing
+                || self.level != trigger.level
+                || sorted_events(self) != sorted_events(trigger)
+                || self.condition.as_ref().map(|c| normalize_condition(c))
+                    != trigger.condition.as_ref().map(|c| normalize_condition(c))
+                || self.function != trigger.function
+                || self.arguments != trigger.arguments;
+            if modified {
+                change_set.push(ChangeInstruction::DropTrigger(self.name.to_owned(), table_name));
+                change_set.push(ChangeInstruction::AddTrigger(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddTrigger(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a PolicyDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let policy = target
+            .tables
+            .iter()
+            .find(|t| t.name == self.table)
+            .and_then(|t| t.policies.iter().find(|p| p.is_same_policy(self)));
+        if let Some(policy) = policy {
+            fn sorted(roles: &[String]) -> Vec<&String> {
+                let mut roles = roles.iter().collect::<Vec<_>>();
+                roles.sort();
+                roles
+            }
+
+            // The type and command of a policy can't be altered, nor can an expression be removed
+            if self.permissive != policy.permissive
+                || self.command != policy.command
+                || (self.using.is_none() && policy.using.is_some())
+                || (self.with_check.is_none() && policy.with_check.is_some())
+            {
+                change_set.push(ChangeInstruction::DropPolicy(self.name.to_owned(), self.table.to_string()));
+                change_set.push(ChangeInstruction::AddPolicy(self));
+            } else if sorted(&self.roles) != sorted(&policy.roles)
+                || !same_optional_expression(&self.using, &policy.using)
+                || !same_optional_expression(&self.with_check, &policy.with_check)
+            {
+                change_set.push(ChangeInstruction::ModifyPolicy(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddPolicy(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a PrivilegeDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Privileges are lost along with a view that is being recreated
+        let recreated = match self.object {
+            PrivilegeObject::Table(ref name) => {
+                let name = name.to_string();
+                change_set.iter().any(|c| match *c {
+                    ChangeInstruction::DropView(ref view_name)
+                    | ChangeInstruction::DropMaterializedView(ref view_name) => view_name.eq(&name),
+                    _ => false,
+                })
+            }
+            _ => false,
+        };
+        let existing = if recreated {
+            None
+        } else {
+            target
+                .privileges
+                .iter()
+                .find(|p| p.action == PrivilegeAction::Grant && p.is_same_privilege(self))
+        };
+
+        match self.action {
+            PrivilegeAction::Grant => match existing {
+                None => push_privilege_change(change_set, PrivilegeAction::Grant, self, self.grant_option),
+                Some(existing) if self.grant_option && !existing.grant_option => {
+                    push_privilege_change(change_set, PrivilegeAction::Grant, self, true)
+                }
+                Some(existing) if !self.grant_option && existing.grant_option => {
+                    match publish_profile.generation_options.revoke_privileges {
+                        Toggle::Allow => push_privilege_change(change_set, PrivilegeAction::Revoke, self, true),
+                        Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                            "Attempted to revoke grant option for {} on {} from {} however revoking privileges is currently disabled",
+                            self.privilege, self.object, self.grantee
+                        ))),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            },
+            // An explicit revoke is always applied. Revoking the grant option only applies if it was granted.
+            PrivilegeAction::Revoke => {
+                if let Some(existing) = existing {
+                    if !self.grant_option || existing.grant_option {
+                        push_privilege_change(change_set, PrivilegeAction::Revoke, self, self.grant_option);
                     }
-                    ref unknown_target_kind => panic!("Unknown target kind: {}", unknown_target_kind), // TODO
                 }
             }
-            ref unknown_source_kind => panic!("Unknown source kind: {}", unknown_source_kind), // TODO
         }
         Ok(())
     }
 }
 
+impl<'a> Diffable<'a, Package> for &'a CommentDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // An object being created (or recreated) has no comment yet
+        let existing = if change_set.iter().any(|change| creates_object(change, &self.object)) {
+            None
+        } else {
+            target
+                .comments
+                .iter()
+                .find(|comment| comment.object.is_same_object(&self.object))
+                .and_then(|comment| comment.comment.as_ref())
+        };
+        if existing != self.comment.as_ref() {
+            change_set.push(ChangeInstruction::SetComment(self.object.clone(), self.comment.clone()));
+        }
+        Ok(())
+    }
+}
+
+fn creates_object(change: &ChangeInstruction, object: &CommentObject) -> bool {
+    match (change, object) {
+        (&ChangeInstruction::AddTable(table), &CommentObject::Table(ref name))
+        | (&ChangeInstruction::AddTable(table), &CommentObject::Column(ref name, _))
+        | (&ChangeInstruction::AddTable(table), &CommentObject::Constraint(ref name, _)) => table.name.eq(name),
+        (&ChangeInstruction::AddColumn(table, column), &CommentObject::Column(ref name, ref column_name)) => {
+            table.name.eq(name) && column.name.eq(column_name)
+        }
+        (
+            &ChangeInstruction::AddConstraint(table, constraint),
+            &CommentObject::Constraint(ref name, ref constraint_name),
+        ) => table.name.eq(name) && constraint.name().eq(constraint_name),
+        (&ChangeInstruction::AddFunction(function), &CommentObject::Function(ref name, ref types)) => {
+            function.is_identified_by(name, types)
+        }
+        (&ChangeInstruction::AddType(ty), &CommentObject::Type(ref name)) => ty.name.eq(name),
+        (&ChangeInstruction::AddIndex(index, _), &CommentObject::Index(ref name)) => {
+            index.name.eq(&name.name) && index.table.schema.eq(&name.schema)
+        }
+        _ => false,
+    }
+}
+
+// Privileges on the same object for the same grantee are combined into a single statement
+fn push_privilege_change(
+    change_set: &mut Vec<ChangeInstruction>,
+    action: PrivilegeAction,
+    privilege: &PrivilegeDefinition,
+    grant_option: bool,
+) {
+    if let Some(&mut ChangeInstruction::ModifyPrivileges(ref last_action, ref mut change)) = change_set.last_mut() {
+        if *last_action == action
+            && change.object == privilege.object
+            && change.grantee == privilege.grantee
+            && change.grant_option == grant_option
+        {
+            if !change.privileges.contains(&privilege.privilege) {
+                change.privileges.push(privilege.privilege.clone());
+            }
+            return;
+        }
+    }
+    change_set.push(ChangeInstruction::ModifyPrivileges(
+        action,
+        PrivilegeChange {
+            object: privilege.object.clone(),
+            privileges: vec![privilege.privilege.clone()],
+            grantee: privilege.grantee.to_owned(),
+            grant_option,
+        },
+    ));
+}
+
+// Conditions retrieved from the database are lower cased and bracketed, so we remove anything
+// that isn't significant (outside of quoted strings) before comparing.
+fn normalize_condition(condition: &str) -> String {
+    let mut normalized = String::new();
+    let mut quote = None;
+    for c in condition.chars() {
+        match quote {
+            Some(q) if q == c => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c.is_whitespace() => continue,
+            None => {
+                normalized.extend(c.to_lowercase());
+                continue;
+            }
+        }
+        normalized.push(c);
+    }
+
+    // PostgreSQL describes untyped string literals with an explicit text cast
+    let mut normalized = normalized.replace("'::text", "'");
+
+    // Strip any brackets surrounding the entire expression
+    loop {
+        let mut depth = 0;
+        let mut wrapped = normalized.starts_with('(');
+        for (i, c) in normalized.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 && i < normalized.len() - 1 {
+                wrapped = false;
+                break;
+            }
+        }
+        if !wrapped {
+            return normalized;
+        }
+        normalized = normalized[1..normalized.len() - 1].to_owned();
+    }
+}
+
+fn same_optional_expression(source: &Option<String>, target: &Option<String>) -> bool {
+    match (source, target) {
+        (Some(src), Some(tgt)) => same_expression(src, tgt),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn partition_key_different(source: &Option<PartitionKey>, target: &Option<PartitionKey>) -> bool {
+    match (source, target) {
+        (Some(src), Some(tgt)) => src.strategy != tgt.strategy || !same_expression(&src.key, &tgt.key),
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+// Bounds are compared as expressions since PostgreSQL reformats them, e.g. lowercasing MODULUS
+fn partition_different(source: &Option<TablePartition>, target: &Option<TablePartition>) -> bool {
+    match (source, target) {
+        (Some(src), Some(tgt)) => {
+            src.parent != tgt.parent || !same_expression(&src.bound.to_string(), &tgt.bound.to_string())
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+// Column ordering is significant however expressions, predicates and defaults are compared semantically
+fn index_different(source: &IndexDefinition, target: &IndexDefinition) -> bool {
+    fn descending(column: &IndexColumn) -> bool {
+        column.order == Some(IndexOrder::Descending)
+    }
+    fn nulls_first(column: &IndexColumn) -> bool {
+        match column.null_position {
+            Some(ref position) => *position == IndexPosition::First,
+            None => descending(column),
+        }
+    }
+    fn column_different(src: &IndexColumn, tgt: &IndexColumn) -> bool {
+        !same_expression(&src.key.to_string(), &tgt.key.to_string())
+            || src.collation != tgt.collation
+            || src.operator_class != tgt.operator_class
+            || descending(src) != descending(tgt)
+            || nulls_first(src) != nulls_first(tgt)
+    }
+
+    let empty = Vec::new();
+    source.unique != target.unique
+        || source.index_type.as_ref().unwrap_or(&IndexType::BTree)
+            != target.index_type.as_ref().unwrap_or(&IndexType::BTree)
+        || source.include.as_ref().unwrap_or(&empty) != target.include.as_ref().unwrap_or(&empty)
+        || source.storage_parameters != target.storage_parameters
+        || !same_optional_expression(&source.predicate, &target.predicate)
+        || source.columns.len() != target.columns.len()
+        || source
+            .columns
+            .iter()
+            .zip(target.columns.iter())
+            .any(|(src, tgt)| column_different(src, tgt))
+}
+
+fn default_value(column: &ColumnDefinition) -> Option<&AnyValue> {
+    column.constraints.iter().find_map(|c| match *c {
+        ColumnConstraint::Default(ref value) => Some(value),
+        _ => None,
+    })
+}
+
+// PostgreSQL describes defaults with every literal cast and sub-expressions bracketed, so the parsed values are
+// compared ignoring these. Sequences are only qualified when not on the search path.
+fn same_default(source: &AnyValue, target: &AnyValue) -> bool {
+    match (source, target) {
+        (&AnyValue::NextVal(ref src), &AnyValue::NextVal(ref tgt)) => {
+            src.name == tgt.name && (src.schema.is_none() || tgt.schema.is_none() || src.schema == tgt.schema)
+        }
+        _ => same_value(source, target),
+    }
+}
+
+fn identity(column: &ColumnDefinition) -> Option<(IdentityKind, &[SequenceOption])> {
+    column.constraints.iter().find_map(|c| match *c {
+        ColumnConstraint::Identity(kind, ref options) => Some((kind, &options[..])),
+        _ => None,
+    })
+}
+
+fn generation_expression(column: &ColumnDefinition) -> Option<&str> {
+    column.constraints.iter().find_map(|c| match *c {
+        ColumnConstraint::Generated(ref expression) => Some(&expression[..]),
+        _ => None,
+    })
+}
+
+// Expands identity options with their defaults so that options can be compared individually. Bounds
+// default to the limits of the column type so are left as None.
+fn identity_options(options: &[SequenceOption]) -> Vec<SequenceOption> {
+    let mut increment = 1;
+    let mut min_value = None;
+    let mut max_value = None;
+    let mut start = None;
+    let mut cache = 1;
+    let mut cycle = false;
+    for option in options {
+        match *option {
+            SequenceOption::Increment(value) => increment = value,
+            SequenceOption::MinValue(value) => min_value = value,
+            SequenceOption::MaxValue(value) => max_value = value,
+            SequenceOption::Start(value) => start = Some(value),
+            SequenceOption::Cache(value) => cache = value,
+            SequenceOption::Cycle(value) => cycle = value,
+            SequenceOption::OwnedBy(_) => {}
+        }
+    }
+    let start = start.unwrap_or_else(|| {
+        if increment > 0 {
+            min_value.unwrap_or(1)
+        } else {
+            max_value.unwrap_or(-1)
+        }
+    });
+    vec![
+        SequenceOption::Increment(increment),
+        SequenceOption::MinValue(min_value),
+        SequenceOption::MaxValue(max_value),
+        SequenceOption::Start(start),
+        SequenceOption::Cache(cache),
+        SequenceOption::Cycle(cycle),
+    ]
+}
+
 #[derive(Debug)]
 pub struct Delta<'package>(Vec<ChangeInstruction<'package>>);
 
@@ -696,19 +1995,132 @@ impl<'package> Delta<'package> {
             build_order.push(DbObject::Schema(schema));
         }
 
-        // Types
-        for t in &package.types {
-            build_order.push(DbObject::Type(t));
+        // Types may reference other types (e.g. a domain over an enum) so referenced types are created first
+        let mut pending_types = package.types.iter().collect::<Vec<_>>();
+        while !pending_types.is_empty() {
+            let position = pending_types
+                .iter()
+                .position(|t| {
+                    !referenced_types(t)
+                        .iter()
+                        .any(|r| pending_types.iter().any(|p| p.name.eq(r)))
+                })
+                .unwrap_or(0);
+            build_order.push(DbObject::Type(pending_types.remove(position)));
         }
 
-        // Drop indexes first
-        for index in &target_package.indexes {
-            if !package.indexes.iter().any(|idx| idx.is_same_index(&index)) {
-                match publish_profile.generation_options.drop_indexes {
-                    Toggle::Allow => change_set.push(ChangeInstruction::DropIndex(
-                        index.fully_qualified_name(),
-                        publish_profile.generation_options.force_concurrent_indexes,
-                    )),
+        // Revoke privileges missing from the source before any objects are dropped. Role memberships are
+        // shared across databases so only memberships of roles that the source manages are revoked.
+        let managed_roles = package
+            .privileges
+            .iter()
+            .filter_map(|p| match p.object {
+                PrivilegeObject::Role(ref role) => Some(role),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        for privilege in &target_package.privileges {
+            if package.privileges.iter().any(|p| p.is_same_privilege(privilege)) {
+                continue;
+            }
+            if let PrivilegeObject::Role(ref role) = privilege.object {
+                if !managed_roles.contains(role) {
+                    continue;
+                }
+            }
+            match publish_profile.generation_options.revoke_privileges {
+                Toggle::Allow => push_privilege_change(&mut change_set, PrivilegeAction::Revoke, privilege, false),
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Attempted to revoke {} on {} from {} however revoking privileges is currently disabled",
+                    privilege.privilege, privilege.object, privilege.grantee
+                ))),
+                _ => {}
+            }
+        }
+
+        // Drop triggers first since they depend on both tables and functions
+        for trigger in &target_package.triggers {
+            if !package.triggers.iter().any(|t| t.is_same_trigger(trigger)) {
+                match publish_profile.generation_options.drop_triggers {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropTrigger(
+                        trigger.name.to_owned(),
+                        trigger.table.to_string(),
+                    )),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop trigger {} however dropping triggers is currently disabled",
+                        trigger.name
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Policies are dropped alongside triggers since they may also depend on functions. Policies on tables
+        // missing from the source are left for the table to take with it.
+        for table in &target_package.tables {
+            let source_table = match package.tables.iter().find(|t| t.name.eq(&table.name)) {
+                Some(source_table) => source_table,
+                None => continue,
+            };
+            for policy in &table.policies {
+                if !source_table.policies.iter().any(|p| p.is_same_policy(policy)) {
+                    match publish_profile.generation_options.drop_policies {
+                        Toggle::Allow => change_set.push(ChangeInstruction::DropPolicy(
+                            policy.name.to_owned(),
+                            policy.table.to_string(),
+                        )),
+                        Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                            "Attempted to drop policy {} however dropping policies is currently disabled",
+                            policy.name
+                        ))),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Drop views next since they may depend on anything below. Views that depend on others are dropped first.
+        for view in target_package.views_in_dependency_order()?.into_iter().rev() {
+            if !package.views.iter().any(|v| v.name.eq(&view.name)) {
+                match publish_profile.generation_options.drop_views {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropView(view.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop view {} however dropping views is currently disabled",
+                        view.name
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Materialized views are dropped after views since views are likely to depend on them
+        for view in &target_package.materialized_views {
+            if !package.materialized_views.iter().any(|v| v.name.eq(&view.name)) {
+                match publish_profile.generation_options.drop_materialized_views {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropMaterializedView(view.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop materialized view {} however dropping materialized views is currently disabled",
+                        view.name
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Drop indexes next
+        for index in &target_package.indexes {
+            if !package.indexes.iter().any(|idx| idx.is_same_index(&index)) {
+                match publish_profile.generation_options.drop_indexes {
+                    Toggle::Allow => {
+                        let partitioned = target_package
+                            .tables
+                            .iter()
+                            .any(|table| table.name == index.table && table.partition_by.is_some());
+                        change_set.push(ChangeInstruction::DropIndex(
+                            index.fully_qualified_name(),
+                            publish_profile.generation_options.force_concurrent_indexes && !partitioned,
+                        ))
+                    }
                     Toggle::Error => bail!(PublishUnsafeOperationError(format!(
                         "Attempted to drop index {} however dropping indexes is currently disabled",
                         index.name
@@ -718,14 +2130,41 @@ impl<'package> Delta<'package> {
             }
         }
 
-        // Drop functions next - first figure out if there are any to drop
+        // Aggregates are dropped before functions since they depend on them
+        for aggregate in &target_package.aggregates {
+            if !package.aggregates.iter().any(|a| a.is_same_aggregate(aggregate)) {
+                match publish_profile.generation_options.drop_functions {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropAggregate(aggregate.signature())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop aggregate {} however dropping functions is currently disabled",
+                        aggregate.signature()
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Drop functions next - first figure out if there are any to drop. Functions are identified by their
+        // argument types so that a function whose arguments have changed is dropped by its previous signature.
         for function in &target_package.functions {
-            if !package.functions.iter().any(|t| t.name.eq(&function.name)) {
+            if !package.functions.iter().any(|f| f.is_same_function(function)) {
                 match publish_profile.generation_options.drop_functions {
-                    Toggle::Allow => change_set.push(ChangeInstruction::DropFunction(function.name.to_string())),
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropFunction(function.signature())),
                     Toggle::Error => bail!(PublishUnsafeOperationError(format!(
                         "Attempted to drop function {} however dropping functions is currently disabled",
-                        function.name
+                        function.signature()
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+        for procedure in &target_package.procedures {
+            if !package.procedures.iter().any(|p| p.is_same_procedure(procedure)) {
+                match publish_profile.generation_options.drop_functions {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropProcedure(procedure.signature())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop procedure {} however dropping functions is currently disabled",
+                        procedure.signature()
                     ))),
                     _ => {}
                 }
@@ -746,12 +2185,36 @@ impl<'package> Delta<'package> {
             }
         }
 
+        // Drop sequences once the tables that may use them have been dropped
+        for sequence in &target_package.sequences {
+            if !package.sequences.iter().any(|s| s.name.eq(&sequence.name)) {
+                match publish_profile.generation_options.drop_sequences {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropSequence(sequence.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop sequence {} however dropping sequences is currently disabled",
+                        sequence.name
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Sequences are created before tables so that they can be used as column defaults
+        for sequence in &package.sequences {
+            build_order.push(DbObject::Sequence(sequence));
+        }
+
         // Now add everything else per the topological sort
+        let mut triggers = Vec::new();
         for item in package.generate_dependency_graph(&log)? {
             match item {
                 Node::Function(_) => {
                     // for the moment, add these later.
                 }
+                Node::Trigger(trigger) => {
+                    // These need to come after functions, so keep the order but add them later.
+                    triggers.push(DbObject::Trigger(trigger));
+                }
                 Node::Table(table) => {
                     build_order.push(DbObject::Table(table));
                 }
@@ -764,15 +2227,56 @@ impl<'package> Delta<'package> {
             }
         }
 
+        // Sequence ownership can only be set once the owning table exists
+        for sequence in &package.sequences {
+            build_order.push(DbObject::SequenceOwner(sequence));
+        }
+
         for function in &package.functions {
             build_order.push(DbObject::Function(function));
         }
+        for procedure in &package.procedures {
+            build_order.push(DbObject::Procedure(procedure));
+        }
+
+        // Aggregates are built from functions so come after them
+        for aggregate in &package.aggregates {
+            build_order.push(DbObject::Aggregate(aggregate));
+        }
+
+        // Views are added once the tables and functions they depend on exist
+        for view in &package.materialized_views {
+            build_order.push(DbObject::MaterializedView(view));
+        }
+        for view in package.views_in_dependency_order()? {
+            build_order.push(DbObject::View(view));
+        }
 
         // Indexes come into play now (all objects and constraints are created)
         for index in &package.indexes {
             build_order.push(DbObject::Index(index));
         }
 
+        // Triggers require their table (or view) and function to exist
+        build_order.extend(triggers);
+
+        // Policies require their table and any functions used within their expressions to exist
+        for table in &package.tables {
+            for policy in &table.policies {
+                build_order.push(DbObject::Policy(policy));
+            }
+        }
+
+        // Privileges are granted once every object they apply to exists
+        for privilege in &package.privileges {
+            build_order.push(DbObject::Privilege(privilege));
+        }
+
+        // As are comments
+        for comment in &package.comments {
+            build_order.push(DbObject::Comment(comment));
+        }
+
         // Add in post deployment scripts
         for script in &package.scripts {
             if script.kind == ScriptKind::PostDeployment {
@@ -791,6 +2295,26 @@ impl<'package> Delta<'package> {
             )?;
         }
 
+        // Comments missing from the source are removed, unless the object is no longer in the source. In that
+        // case the comment is dropped along with the object.
+        for comment in &target_package.comments {
+            let object = &comment.object;
+            if !package.defines(object)
+                || package.comments.iter().any(|c| c.object.is_same_object(object))
+                || change_set.iter().any(|change| creates_object(change, object))
+            {
+                continue;
+            }
+            change_set.push(ChangeInstruction::SetComment(object.clone(), None));
+        }
+
+        // Finally, refresh materialized views now that any data they depend on is in place
+        if publish_profile.generation_options.refresh_materialized_views {
+            for view in &package.materialized_views {
+                change_set.push(ChangeInstruction::RefreshMaterializedView(view.name.to_string()));
+            }
+        }
+
         Ok(Delta(change_set))
     }
 
@@ -881,18 +2405,37 @@ pub enum ChangeInstruction<'input> {
     AddType(&'input TypeDefinition),
     ModifyType(&'input TypeDefinition, TypeModificationAction),
     DropType(String),
+    DropDomain(String),
+
+    // Sequences
+    AddSequence(&'input SequenceDefinition),
+    ModifySequence(&'input SequenceDefinition, Vec<SequenceOption>),
+    DropSequence(String),
 
     // Tables
     AddTable(&'input TableDefinition),
+    AlterTable(String, AlterTableAction),
     DropTable(String),
+    AttachPartition(&'input TableDefinition),
+    DetachPartition(String, String),
 
     // Columns
     AddColumn(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnType(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnNull(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnDefault(&'input TableDefinition, &'input ColumnDefinition),
-    ModifyColumnUniqueConstraint(&'input TableDefinition, &'input ColumnDefinition),
+    DropColumnDefault(&'input TableDefinition, String),
     ModifyColumnPrimaryKeyConstraint(&'input TableDefinition, &'input ColumnDefinition),
+    AddColumnIdentity(&'input TableDefinition, &'input ColumnDefinition),
+    ModifyColumnIdentity(
+        &'input TableDefinition,
+        &'input ColumnDefinition,
+        Option<IdentityKind>,
+        Vec<SequenceOption>,
+    ),
+    DropColumnIdentity(&'input TableDefinition, String),
+    ModifyColumnExpression(&'input TableDefinition, &'input ColumnDefinition),
+    DropColumnExpression(&'input TableDefinition, String),
     DropColumn(&'input TableDefinition, String),
 
     // Constraints
@@ -905,8 +2448,50 @@ pub enum ChangeInstruction<'input> {
 
     // Functions
     AddFunction(&'input FunctionDefinition),
-    ModifyFunction(&'input FunctionDefinition), // This is identical to add however it's for future possible support
+    ModifyFunction(&'input FunctionDefinition, Vec<String>), // Includes a diff of the body for the report
     DropFunction(String),
+
+    // Procedures
+    AddProcedure(&'input ProcedureDefinition),
+    ModifyProcedure(&'input ProcedureDefinition),
+    DropProcedure(String),
+
+    // Aggregates
+    AddAggregate(&'input AggregateDefinition),
+    DropAggregate(String),
+
+    // Views
+    AddView(&'input ViewDefinition),
+    ModifyView(&'input ViewDefinition),
+    DropView(String),
+
+    // Triggers
+    AddTrigger(&'input TriggerDefinition),
+    DropTrigger(String, String),
+
+    // Policies
+    AddPolicy(&'input PolicyDefinition),
+    ModifyPolicy(&'input PolicyDefinition),
+    DropPolicy(String, String),
+
+    // Materialized Views
+    AddMaterializedView(&'input MaterializedViewDefinition),
+    DropMaterializedView(String),
+    RefreshMaterializedView(String),
+
+    // Privileges
+    ModifyPrivileges(PrivilegeAction, PrivilegeChange),
+
+    // Comments
+    SetComment(CommentObject, Option<String>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrivilegeChange {
+    object: PrivilegeObject,
+    privileges: Vec<Privilege>,
+    grantee: String,
+    grant_option: bool,
 }
 
 #[allow(dead_code)]
@@ -915,6 +2500,13 @@ pub enum TypeModificationAction {
     AddEnumValueBefore { value: String, before: String },
     AddEnumValueAfter { value: String, after: String },
     RemoveEnumValue { value: String },
+    AddAttribute { name: String, sql_type: SqlType },
+    AlterAttributeType { name: String, sql_type: SqlType },
+    DropAttribute { name: String },
+    SetDomainDefault { default: Option<AnyValue> },
+    SetDomainNotNull { not_null: bool },
+    AddDomainConstraint { constraint: DomainConstraint },
+    DropDomainConstraint { name: String },
 }
 
 impl<'input> fmt::Display for ChangeInstruction<'input> {
@@ -960,14 +2552,32 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
                     TypeModificationAction::AddEnumValueBefore { .. } => "inserting an enum value",
                     TypeModificationAction::AddEnumValueAfter { .. } => "inserting an enum value",
                     TypeModificationAction::RemoveEnumValue { .. } => "removing enum value",
+                    TypeModificationAction::AddAttribute { .. } => "adding an attribute",
+                    TypeModificationAction::AlterAttributeType { .. } => "altering an attribute type",
+                    TypeModificationAction::DropAttribute { .. } => "dropping an attribute",
+                    TypeModificationAction::SetDomainDefault { .. } => "setting the domain default",
+                    TypeModificationAction::SetDomainNotNull { .. } => "setting domain nullability",
+                    TypeModificationAction::AddDomainConstraint { .. } => "adding a domain constraint",
+                    TypeModificationAction::DropDomainConstraint { .. } => "dropping a domain constraint",
                 },
                 ty.name
             ),
             DropType(ref type_name) => write!(f, "Drop type: {}", type_name),
+            DropDomain(ref domain_name) => write!(f, "Drop domain: {}", domain_name),
+
+            // Sequences
+            AddSequence(sequence) => write!(f, "Add sequence: {}", sequence.name),
+            ModifySequence(sequence, _) => write!(f, "Modify sequence: {}", sequence.name),
+            DropSequence(ref sequence_name) => write!(f, "Drop sequence: {}", sequence_name),
 
             // Tables
             AddTable(table) => write!(f, "Add table: {}", table.name),
+            AlterTable(ref table_name, action) => write!(f, "Alter table: {} {}", table_name, action),
             DropTable(ref table_name) => write!(f, "Drop table: {}", table_name),
+            AttachPartition(table) => write!(f, "Attach partition: {}", table.name),
+            DetachPartition(ref parent, ref table_name) => {
+                write!(f, "Detach partition: {} from {}", table_name, parent)
+            }
 
             // Columns
             AddColumn(table, column) => write!(f, "Add column: {} to table: {}", column.name, table.name),
@@ -980,16 +2590,37 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             ModifyColumnDefault(table, column) => {
                 write!(f, "Modify default for column: {} on table: {}", column.name, table.name)
             }
-            ModifyColumnUniqueConstraint(table, column) => write!(
+            DropColumnDefault(table, ref column_name) => {
+                write!(f, "Drop default for column: {} on table: {}", column_name, table.name)
+            }
+            ModifyColumnPrimaryKeyConstraint(table, column) => write!(
                 f,
-                "Modify unique constraint for column: {} on table: {}",
+                "Modify primary key constraint for column: {} on table: {}",
                 column.name, table.name
             ),
-            ModifyColumnPrimaryKeyConstraint(table, column) => write!(
+            AddColumnIdentity(table, column) => {
+                write!(f, "Add identity for column: {} on table: {}", column.name, table.name)
+            }
+            ModifyColumnIdentity(table, column, _, _) => {
+                write!(
+                    f,
+                    "Modify identity for column: {} on table: {}",
+                    column.name, table.name
+                )
+            }
+            DropColumnIdentity(table, ref column_name) => {
+                write!(f, "Drop identity for column: {} on table: {}", column_name, table.name)
+            }
+            ModifyColumnExpression(table, column) => write!(
                 f,
-                "Modify primary key constraint for column: {} on table: {}",
+                "Modify generation expression for column: {} on table: {}",
                 column.name, table.name
             ),
+            DropColumnExpression(table, ref column_name) => write!(
+                f,
+                "Drop generation expression for column: {} on table: {}",
+                column_name, table.name
+            ),
             DropColumn(table, ref column_name) => write!(f, "Drop column: {} on table: {}", column_name, table.name),
 
             // Constraints
@@ -1013,16 +2644,66 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             ),
 
             // Functions
-            AddFunction(function) => write!(f, "Add function: {}", function.name),
+            AddFunction(function) => write!(f, "Add function: {}", function.signature()),
             // Modify is identical to add however it's for future possible support
-            ModifyFunction(function) => write!(f, "Modify function: {}", function.name),
+            ModifyFunction(function, _) => write!(f, "Modify function: {}", function.signature()),
             DropFunction(ref function_name) => write!(f, "Drop function: {}", function_name),
+
+            // Procedures
+            AddProcedure(procedure) => write!(f, "Add procedure: {}", procedure.signature()),
+            ModifyProcedure(procedure) => write!(f, "Modify procedure: {}", procedure.signature()),
+            DropProcedure(ref procedure_name) => write!(f, "Drop procedure: {}", procedure_name),
+
+            // Aggregates
+            AddAggregate(aggregate) => write!(f, "Add aggregate: {}", aggregate.signature()),
+            DropAggregate(ref signature) => write!(f, "Drop aggregate: {}", signature),
+
+            // Views
+            AddView(view) => write!(f, "Add view: {}", view.name),
+            ModifyView(view) => write!(f, "Modify view: {}", view.name),
+            DropView(ref view_name) => write!(f, "Drop view: {}", view_name),
+
+            // Triggers
+            AddTrigger(trigger) => write!(f, "Add trigger: {} to table: {}", trigger.name, trigger.table),
+            DropTrigger(ref name, ref table) => write!(f, "Drop trigger: {} from table: {}", name, table),
+
+            // Policies
+            AddPolicy(policy) => write!(f, "Add policy: {} to table: {}", policy.name, policy.table),
+            ModifyPolicy(policy) => write!(f, "Modify policy: {} on table: {}", policy.name, policy.table),
+            DropPolicy(ref name, ref table) => write!(f, "Drop policy: {} from table: {}", name, table),
+
+            // Materialized Views
+            AddMaterializedView(view) => write!(f, "Add materialized view: {}", view.name),
+            DropMaterializedView(ref view_name) => write!(f, "Drop materialized view: {}", view_name),
+            RefreshMaterializedView(ref view_name) => write!(f, "Refresh materialized view: {}", view_name),
+
+            // Privileges
+            ModifyPrivileges(ref action, ref change) => write!(
+                f,
+                "{} privileges: {} on {} {} {}",
+                match *action {
+                    PrivilegeAction::Grant => "Grant",
+                    PrivilegeAction::Revoke => "Revoke",
+                },
+                change.privileges.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "),
+                change.object,
+                match *action {
+                    PrivilegeAction::Grant => "to",
+                    PrivilegeAction::Revoke => "from",
+                },
+                change.grantee
+            ),
+
+            // Comments
+            SetComment(ref object, Some(_)) => write!(f, "Set comment: {}", object),
+            SetComment(ref object, None) => write!(f, "Remove comment: {}", object),
         }
     }
 }
 
 impl<'input> ChangeInstruction<'input> {
     fn to_sql(&self, log: &Logger) -> String {
+        let formatter = Formatter::default();
         match *self {
             // Database level
             ChangeInstruction::KillConnections(ref db) => {
@@ -1032,23 +2713,27 @@ impl<'input> ChangeInstruction<'input> {
                 drop.push_str(&format!("WHERE pg_stat_activity.datname = '{}';", db));
                 drop
             }
-            ChangeInstruction::CreateDatabase(ref db) => format!("CREATE DATABASE {}", db),
-            ChangeInstruction::DropDatabase(ref db) => format!("DROP DATABASE {}", db),
+            ChangeInstruction::CreateDatabase(ref db) => format!("CREATE DATABASE {}", quote_ident(db)),
+            ChangeInstruction::DropDatabase(ref db) => format!("DROP DATABASE {}", quote_ident(db)),
             ChangeInstruction::UseDatabase(ref db) => format!("-- Using database `{}`", db),
 
             // ExtensionRequest level
             ChangeInstruction::CreateExtension(ref name, ref version) => {
                 if let Some(ref version) = version {
-                    format!("CREATE EXTENSION IF NOT EXISTS {} WITH VERSION \"{}\"", name, version)
+                    format!(
+                        "CREATE EXTENSION IF NOT EXISTS {} WITH VERSION \"{}\"",
+                        quote_ident(name),
+                        version
+                    )
                 } else {
-                    format!("CREATE EXTENSION IF NOT EXISTS {}", name)
+                    format!("CREATE EXTENSION IF NOT EXISTS {}", quote_ident(name))
                 }
             }
             ChangeInstruction::UpgradeExtension(ref name, ref version) => {
                 if let Some(ref version) = version {
-                    format!("ALTER EXTENSION {} UPDATE TO \"{}\"", name, version)
+                    format!("ALTER EXTENSION {} UPDATE TO \"{}\"", quote_ident(name), version)
                 } else {
-                    format!("ALTER EXTENSION {} UPDATE", name)
+                    format!("ALTER EXTENSION {} UPDATE", quote_ident(name))
                 }
             }
 
@@ -1057,32 +2742,12 @@ impl<'input> ChangeInstruction<'input> {
                 if schema.name == "public" {
                     format!("CREATE SCHEMA IF NOT EXISTS {}", schema.name)
                 } else {
-                    format!("CREATE SCHEMA {}", schema.name)
+                    formatter.schema(schema)
                 }
             }
 
             // Type level
-            ChangeInstruction::AddType(ty) => {
-                let mut def = String::new();
-                def.push_str(&format!("CREATE TYPE {} AS ", ty.name)[..]);
-                match ty.kind {
-                    TypeDefinitionKind::Enum(ref values) => {
-                        def.push_str("ENUM (\n");
-                        let mut enum_comma_required = false;
-                        for value in values {
-                            if enum_comma_required {
-                                def.push_str(",\n");
-                            } else {
-                                enum_comma_required = true;
-                            }
-                            def.push_str(&format!("  '{}'", value)[..]);
-                        }
-                        def.push_str("\n)");
-                    }
-                    ref unknown => panic!("Unknown kind: {}", unknown), // TODO
-                }
-                def
-            }
+            ChangeInstruction::AddType(ty) => formatter.type_definition(ty),
             ChangeInstruction::ModifyType(ty, ref action) => match *action {
                 TypeModificationAction::AddEnumValueBefore { ref value, ref before } => {
                     format!("ALTER TYPE {} ADD VALUE '{}' BEFORE '{}'", ty.name, value, before)
@@ -1101,113 +2766,168 @@ impl<'input> ChangeInstruction<'input> {
                         format!("typname='{}'", ty.name.name)
                     },
                 ),
+                TypeModificationAction::AddAttribute { ref name, ref sql_type } => {
+                    format!(
+                        "ALTER TYPE {} ADD ATTRIBUTE {} {}",
+                        ty.name,
+                        quote_ident(name),
+                        sql_type
+                    )
+                }
+                TypeModificationAction::AlterAttributeType { ref name, ref sql_type } => {
+                    format!(
+                        "ALTER TYPE {} ALTER ATTRIBUTE {} TYPE {}",
+                        ty.name,
+                        quote_ident(name),
+                        sql_type
+                    )
+                }
+                TypeModificationAction::DropAttribute { ref name } => {
+                    format!("ALTER TYPE {} DROP ATTRIBUTE {}", ty.name, quote_ident(name))
+                }
+                TypeModificationAction::SetDomainDefault { ref default } => match *default {
+                    Some(ref default) => format!("ALTER DOMAIN {} SET DEFAULT {}", ty.name, default),
+                    None => format!("ALTER DOMAIN {} DROP DEFAULT", ty.name),
+                },
+                TypeModificationAction::SetDomainNotNull { not_null } => format!(
+                    "ALTER DOMAIN {} {} NOT NULL",
+                    ty.name,
+                    if not_null { "SET" } else { "DROP" }
+                ),
+                TypeModificationAction::AddDomainConstraint { ref constraint } => {
+                    format!("ALTER DOMAIN {} ADD{}", ty.name, domain_constraint_sql(constraint))
+                }
+                TypeModificationAction::DropDomainConstraint { ref name } => {
+                    format!("ALTER DOMAIN {} DROP CONSTRAINT {}", ty.name, quote_ident(name))
+                }
             },
             ChangeInstruction::DropType(ref type_name) => format!("DROP TYPE IF EXISTS {}", type_name),
+            ChangeInstruction::DropDomain(ref domain_name) => format!("DROP DOMAIN IF EXISTS {}", domain_name),
 
             // Function level
-            ChangeInstruction::AddFunction(function) | ChangeInstruction::ModifyFunction(function) => {
-                let mut func = String::new();
-                func.push_str(&format!("CREATE OR REPLACE FUNCTION {} (", function.name)[..]);
-                let mut arg_comma_required = false;
-                for arg in &function.arguments {
-                    if arg_comma_required {
-                        func.push_str(", ");
-                    } else {
-                        arg_comma_required = true;
-                    }
-
-                    func.push_str(&arg.to_string());
-                }
-                func.push_str(")\n");
-                func.push_str("RETURNS ");
-                match function.return_type {
-                    FunctionReturnType::Table(ref columns) => {
-                        func.push_str("TABLE (\n");
-                        let mut column_comma_required = false;
-                        for column in columns {
-                            if column_comma_required {
-                                func.push_str(",\n");
-                            } else {
-                                column_comma_required = true;
-                            }
-                            func.push_str(&format!("  {} {}", column.name, column.sql_type)[..]);
-                        }
-                        func.push_str("\n)\n");
-                    }
-                    FunctionReturnType::SetOf(ref sql_type) => {
-                        func.push_str(&format!("SETOF {}", sql_type)[..]);
-                    }
-                    FunctionReturnType::SqlType(ref sql_type) => {
-                        func.push_str(&format!("{} ", sql_type)[..]);
-                    }
-                }
-                func.push_str("AS $$");
-                func.push_str(&function.body[..]);
-                func.push_str("$$\n");
-                func.push_str("LANGUAGE ");
-                func.push_str(&function.language.to_string());
-                func
+            ChangeInstruction::AddFunction(function) | ChangeInstruction::ModifyFunction(function, _) => {
+                formatter.function(function)
             }
             ChangeInstruction::DropFunction(ref function_name) => format!("DROP FUNCTION IF EXISTS {}", function_name),
 
-            // Table level
-            ChangeInstruction::AddTable(def) => {
-                let mut instr = String::new();
-                instr.push_str(&format!("CREATE TABLE {} (", def.name));
-                for (position, column) in def.columns.iter().enumerate() {
-                    if position > 0 {
-                        instr.push_str(",");
-                    }
-                    instr.push_str("\n\t");
-                    instr.push_str(&format!("{} {}", column.name, column.sql_type));
-                    for constraint in column.constraints.iter() {
-                        match *constraint {
-                            ColumnConstraint::Default(ref any_type) => {
-                                instr.push_str(&format!(" DEFAULT {}", any_type))
-                            }
-                            ColumnConstraint::NotNull => instr.push_str(" NOT NULL"),
-                            ColumnConstraint::Null => instr.push_str(" NULL"),
-                            ColumnConstraint::Unique => instr.push_str(" UNIQUE"),
-                            ColumnConstraint::PrimaryKey => instr.push_str(" PRIMARY KEY"),
-                        }
-                    }
+            // Procedure level
+            ChangeInstruction::AddProcedure(procedure) | ChangeInstruction::ModifyProcedure(procedure) => {
+                formatter.procedure(procedure)
+            }
+            ChangeInstruction::DropProcedure(ref procedure_name) => {
+                format!("DROP PROCEDURE IF EXISTS {}", procedure_name)
+            }
+
+            // Aggregate level
+            ChangeInstruction::AddAggregate(aggregate) => formatter.aggregate(aggregate),
+            ChangeInstruction::DropAggregate(ref signature) => format!("DROP AGGREGATE IF EXISTS {}", signature),
+
+            // View level
+            ChangeInstruction::AddView(view) => formatter.view(view, false),
+            ChangeInstruction::ModifyView(view) => formatter.view(view, true),
+            ChangeInstruction::DropView(ref view_name) => format!("DROP VIEW IF EXISTS {}", view_name),
+
+            // Trigger level
+            ChangeInstruction::AddTrigger(trigger) => formatter.trigger(trigger),
+            ChangeInstruction::DropTrigger(ref name, ref table) => {
+                format!("DROP TRIGGER IF EXISTS {} ON {}", quote_ident(name), table)
+            }
+
+            // Policy level
+            ChangeInstruction::AddPolicy(policy) => formatter.policy(policy),
+            ChangeInstruction::ModifyPolicy(policy) => {
+                let mut instr = format!(
+                    "ALTER POLICY {} ON {} TO {}",
+                    quote_ident(&policy.name),
+                    policy.table,
+                    role_list(&policy.roles)
+                );
+                if let Some(ref using) = policy.using {
+                    instr.push_str(&format!(" USING ({})", using));
+                }
+                if let Some(ref with_check) = policy.with_check {
+                    instr.push_str(&format!(" WITH CHECK ({})", with_check));
                 }
-                // Table constraints are added later
-                instr.push_str("\n)");
                 instr
             }
+            ChangeInstruction::DropPolicy(ref name, ref table) => {
+                format!("DROP POLICY IF EXISTS {} ON {}", quote_ident(name), table)
+            }
+
+            // Materialized view level
+            ChangeInstruction::AddMaterializedView(view) => formatter.materialized_view(view),
+            ChangeInstruction::DropMaterializedView(ref view_name) => {
+                format!("DROP MATERIALIZED VIEW IF EXISTS {}", view_name)
+            }
+            ChangeInstruction::RefreshMaterializedView(ref view_name) => {
+                format!("REFRESH MATERIALIZED VIEW {}", view_name)
+            }
+
+            // Privileges
+            ChangeInstruction::ModifyPrivileges(ref action, ref change) => formatter.privileges(
+                action,
+                &change.object,
+                &change.privileges,
+                &change.grantee,
+                change.grant_option,
+            ),
+
+            // Comments
+            ChangeInstruction::SetComment(ref object, ref comment) => {
+                formatter.comment(object, comment.as_ref().map(|c| &c[..]))
+            }
+
+            // Sequence level
+            // Ownership is set once the owning table exists
+            ChangeInstruction::AddSequence(sequence) => formatter.sequence(sequence, false),
+            ChangeInstruction::ModifySequence(sequence, ref options) => {
+                let options = options.iter().map(|o| o.to_string()).collect::<Vec<_>>();
+                format!("ALTER SEQUENCE {} {}", sequence.name, options.join(" "))
+            }
+            ChangeInstruction::DropSequence(ref name) => format!("DROP SEQUENCE IF EXISTS {}", name),
+
+            // Table level
+            // Table constraints are added later
+            ChangeInstruction::AddTable(def) => formatter.table(def, false),
+            ChangeInstruction::AlterTable(ref table_name, action) => format!("ALTER TABLE {} {}", table_name, action),
             ChangeInstruction::DropTable(ref table_name) => format!("DROP TABLE IF EXISTS {}", table_name),
+            ChangeInstruction::AttachPartition(def) => {
+                let partition = def.partition_of.as_ref().unwrap();
+                format!(
+                    "ALTER TABLE {} ATTACH PARTITION {} {}",
+                    partition.parent, def.name, partition.bound
+                )
+            }
+            ChangeInstruction::DetachPartition(ref parent, ref table_name) => {
+                format!("ALTER TABLE {} DETACH PARTITION {}", parent, table_name)
+            }
 
             // Column level
             ChangeInstruction::AddColumn(table, column) => {
-                let mut instr = String::new();
-                instr.push_str(&format!(
-                    "ALTER TABLE {} ADD COLUMN {} {}",
-                    table.name, column.name, column.sql_type
-                ));
-                for constraint in column.constraints.iter() {
-                    match *constraint {
-                        ColumnConstraint::Default(ref any_type) => instr.push_str(&format!(" DEFAULT {}", any_type)),
-                        ColumnConstraint::NotNull => instr.push_str(" NOT NULL"),
-                        ColumnConstraint::Null => instr.push_str(" NULL"),
-                        ColumnConstraint::Unique => instr.push_str(" UNIQUE"),
-                        ColumnConstraint::PrimaryKey => instr.push_str(" PRIMARY KEY"),
-                    }
-                }
-                instr
+                format!("ALTER TABLE {} ADD COLUMN {}", table.name, formatter.column(column))
             }
             ChangeInstruction::ModifyColumnType(table, column) => format!(
                 "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
-                table.name, column.name, column.sql_type
+                table.name,
+                quote_ident(&column.name),
+                column.sql_type
             ),
             ChangeInstruction::ModifyColumnNull(table, column) => {
                 for constraint in column.constraints.iter() {
                     match *constraint {
                         ColumnConstraint::NotNull => {
-                            return format!("ALTER TABLE {} ALTER COLUMN {} SET NOT NULL", table.name, column.name);
+                            return format!(
+                                "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL",
+                                table.name,
+                                quote_ident(&column.name)
+                            );
                         }
                         ColumnConstraint::Null => {
-                            return format!("ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL", table.name, column.name);
+                            return format!(
+                                "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL",
+                                table.name,
+                                quote_ident(&column.name)
+                            );
                         }
                         _ => {}
                     }
@@ -1222,8 +2942,10 @@ impl<'input> ChangeInstruction<'input> {
                 for constraint in column.constraints.iter() {
                     if let ColumnConstraint::Default(ref any_type) = *constraint {
                         return format!(
-                            ";\nALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
-                            table.name, column.name, any_type
+                            "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
+                            table.name,
+                            quote_ident(&column.name),
+                            any_type
                         );
                     }
                 }
@@ -1233,17 +2955,12 @@ impl<'input> ChangeInstruction<'input> {
                 );
                 "".to_owned()
             }
-            ChangeInstruction::ModifyColumnUniqueConstraint(table, column) => {
-                for constraint in column.constraints.iter() {
-                    if let ColumnConstraint::Unique = *constraint {
-                        // TODO: These have to be table level constraints. Ignore??
-                        warn!(
-                            log,
-                            "Ignoring UNIQUE column constraint for {}.{}", table.name, column.name
-                        );
-                    }
-                }
-                "".to_owned()
+            ChangeInstruction::DropColumnDefault(table, ref column_name) => {
+                format!(
+                    "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT",
+                    table.name,
+                    quote_ident(column_name)
+                )
             }
             ChangeInstruction::ModifyColumnPrimaryKeyConstraint(table, column) => {
                 for constraint in column.constraints.iter() {
@@ -1257,67 +2974,70 @@ impl<'input> ChangeInstruction<'input> {
                 }
                 "".to_owned()
             }
+            ChangeInstruction::AddColumnIdentity(table, column) => match identity(column) {
+                Some((kind, options)) => format!(
+                    "ALTER TABLE {} ALTER COLUMN {} ADD{}",
+                    table.name,
+                    quote_ident(&column.name),
+                    identity_sql(kind, options)
+                ),
+                None => {
+                    error!(
+                        log,
+                        "Expected to add identity for column {}.{}", table.name, column.name
+                    );
+                    "".to_owned()
+                }
+            },
+            ChangeInstruction::ModifyColumnIdentity(table, column, kind, ref options) => {
+                let mut instr = format!("ALTER TABLE {} ALTER COLUMN {}", table.name, quote_ident(&column.name));
+                if let Some(kind) = kind {
+                    instr.push_str(&format!(" SET GENERATED {}", kind));
+                }
+                for option in options {
+                    instr.push_str(&format!(" SET {}", option));
+                }
+                instr
+            }
+            ChangeInstruction::DropColumnIdentity(table, ref column_name) => format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP IDENTITY IF EXISTS",
+                table.name,
+                quote_ident(column_name)
+            ),
+            ChangeInstruction::ModifyColumnExpression(table, column) => match generation_expression(column) {
+                Some(expression) => format!(
+                    "ALTER TABLE {} ALTER COLUMN {} SET EXPRESSION AS ({})",
+                    table.name,
+                    quote_ident(&column.name),
+                    expression
+                ),
+                None => {
+                    error!(
+                        log,
+                        "Expected to modify generation expression for column {}.{}", table.name, column.name
+                    );
+                    "".to_owned()
+                }
+            },
+            ChangeInstruction::DropColumnExpression(table, ref column_name) => format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP EXPRESSION",
+                table.name,
+                quote_ident(column_name)
+            ),
             ChangeInstruction::DropColumn(table, ref column_name) => {
-                format!("ALTER TABLE {} DROP COLUMN {}", table.name, column_name)
+                format!("ALTER TABLE {} DROP COLUMN {}", table.name, quote_ident(column_name))
             }
 
             ChangeInstruction::AddConstraint(table, constraint) => {
-                let mut instr = String::new();
-                instr.push_str(&format!("ALTER TABLE {}\nADD ", table.name));
-                match *constraint {
-                    TableConstraint::Primary {
-                        ref name,
-                        ref columns,
-                        ref parameters,
-                    } => {
-                        instr.push_str(&format!("CONSTRAINT {} PRIMARY KEY ({})", name, columns.join(", ")));
-
-                        // Do the WITH options too
-                        if let Some(ref unwrapped) = *parameters {
-                            instr.push_str(" WITH (");
-                            for (position, value) in unwrapped.iter().enumerate() {
-                                if position > 0 {
-                                    instr.push_str(", ");
-                                }
-                                match *value {
-                                    IndexParameter::FillFactor(i) => instr.push_str(&format!("FILLFACTOR={}", i)[..]),
-                                }
-                            }
-                            instr.push_str(")");
-                        }
-                    }
-                    TableConstraint::Foreign {
-                        ref name,
-                        ref columns,
-                        ref ref_table,
-                        ref ref_columns,
-                        ref match_type,
-                        ref events,
-                    } => {
-                        instr.push_str(&format!("CONSTRAINT {} FOREIGN KEY ({})", name, columns.join(", "))[..]);
-                        instr.push_str(&format!(" REFERENCES {} ({})", ref_table, ref_columns.join(", "))[..]);
-                        if let Some(ref m) = *match_type {
-                            instr.push_str(&format!(" {}", m));
-                        }
-                        if let Some(ref events) = *events {
-                            for e in events {
-                                match *e {
-                                    ForeignConstraintEvent::Delete(ref action) => {
-                                        instr.push_str(&format!(" ON DELETE {}", action))
-                                    }
-                                    ForeignConstraintEvent::Update(ref action) => {
-                                        instr.push_str(&format!(" ON UPDATE {}", action))
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                instr
+                format!(
+                    "ALTER TABLE {} ADD {}",
+                    table.name,
+                    formatter.table_constraint(constraint)
+                )
             }
 
             ChangeInstruction::DropConstraint(table, ref name) => {
-                format!("ALTER TABLE {}\nDROP CONSTRAINT {}", table.name, name)
+                format!("ALTER TABLE {} DROP CONSTRAINT {}", table.name, quote_ident(name))
             }
 
             // Raw scripts
@@ -1330,60 +3050,7 @@ impl<'input> ChangeInstruction<'input> {
             }
 
             // Indexes
-            ChangeInstruction::AddIndex(index, concurrently) => {
-                let mut instr = String::new();
-                instr.push_str("CREATE ");
-                if index.unique {
-                    instr.push_str("UNIQUE ");
-                }
-                instr.push_str("INDEX ");
-                if concurrently {
-                    instr.push_str("CONCURRENTLY ");
-                }
-                instr.push_str(&format!("{} ON {}", index.name, index.table));
-                if let Some(ref method) = index.index_type {
-                    instr.push_str(" USING ");
-                    instr.push_str(match method {
-                        IndexType::BTree => "btree",
-                        IndexType::Gin => "gin",
-                        IndexType::Gist => "gist",
-                        IndexType::Hash => "hash",
-                    });
-                }
-                instr.push_str(" (");
-                for (position, col) in index.columns.iter().enumerate() {
-                    if position > 0 {
-                        instr.push_str(", ");
-                    }
-                    instr.push_str(&col.name);
-                    if let Some(ref order) = col.order {
-                        instr.push_str(match order {
-                            IndexOrder::Ascending => " ASC",
-                            IndexOrder::Descending => " DESC",
-                        });
-                    }
-                    if let Some(ref pos) = col.null_position {
-                        instr.push_str(match pos {
-                            IndexPosition::First => " NULLS FIRST",
-                            IndexPosition::Last => " NULLS LAST",
-                        });
-                    }
-                }
-                instr.push_str(")");
-                if let Some(ref storage_parameters) = index.storage_parameters {
-                    instr.push_str(" WITH (");
-                    for (position, value) in storage_parameters.iter().enumerate() {
-                        if position > 0 {
-                            instr.push_str(", ");
-                        }
-                        match *value {
-                            IndexParameter::FillFactor(i) => instr.push_str(&format!("FILLFACTOR={}", i)),
-                        }
-                    }
-                    instr.push_str(")");
-                }
-                instr
-            }
+            ChangeInstruction::AddIndex(index, concurrently) => formatter.index(index, concurrently),
             ChangeInstruction::DropIndex(ref index_name, concurrently) => {
                 let mut instr = String::new();
                 instr.push_str("DROP INDEX ");
@@ -1406,6 +3073,7 @@ mod tests {
     use crate::sql::ast;
     use crate::Semver;
 
+    use rust_decimal::Decimal;
     use slog::{Discard, Drain, Logger};
     use spectral::prelude::*;
 
@@ -1420,6 +3088,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["red".into(), "green".into(), "blue".into()]),
+            span: ast::Span::default(),
         }
     }
 
@@ -1469,7 +3138,7 @@ mod tests {
 
         // Check the SQL generation
         assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("CREATE TYPE public.colors AS ENUM (\n  'red',\n  'green',\n  'blue'\n)".to_owned());
+            .is_equal_to("CREATE TYPE public.colors AS ENUM ('red', 'green', 'blue')".to_owned());
     }
 
     #[test]
@@ -1515,6 +3184,7 @@ mod tests {
                 "blue".to_owned(),
                 "black".to_owned(),
             ]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1577,6 +3247,7 @@ mod tests {
                 "green".to_owned(),
                 "blue".to_owned(),
             ]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1639,6 +3310,7 @@ mod tests {
                 "black".to_owned(),
                 "blue".to_owned(),
             ]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1696,6 +3368,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["black".to_owned(), "green".to_owned(), "blue".to_owned()]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1781,6 +3454,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["black".to_owned(), "green".to_owned(), "blue".to_owned()]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1817,6 +3491,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["green".to_owned(), "blue".to_owned()]),
+            span: ast::Span::default(),
         };
 
         // Create a package with the type already defined
@@ -1870,95 +3545,102 @@ mod tests {
         );
     }
 
-    fn base_table() -> ast::TableDefinition {
-        ast::TableDefinition {
-            name: ObjectName {
-                schema: Some("my".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![
-                ColumnDefinition {
-                    name: "id".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::Serial, None),
-                    constraints: vec![ColumnConstraint::NotNull, ColumnConstraint::PrimaryKey],
-                },
-                ColumnDefinition {
-                    name: "company_id".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
-                    constraints: vec![ColumnConstraint::NotNull],
-                },
-                ColumnDefinition {
-                    name: "first_name".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-                    constraints: vec![ColumnConstraint::NotNull],
-                },
-            ],
-            constraints: Vec::new(),
+    fn composite_type(attributes: Vec<(&str, SqlType)>) -> ast::TypeDefinition {
+        ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "address".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Composite(
+                attributes
+                    .into_iter()
+                    .map(|(name, sql_type)| CompositeTypeAttribute {
+                        name: name.to_owned(),
+                        sql_type,
+                    })
+                    .collect(),
+            ),
+            span: ast::Span::default(),
         }
     }
 
     #[test]
-    fn it_can_add_new_table() {
+    fn it_can_modify_composite_type_attributes() {
         let log = empty_logger();
-        let source_table = base_table();
+        let source_type = composite_type(vec![
+            (
+                "street",
+                SqlType::Simple(SimpleSqlType::VariableLengthString(200), None),
+            ),
+            ("city", SqlType::Simple(SimpleSqlType::Text, None)),
+        ]);
 
-        // Create an empty database
-        let existing_database = Package::new();
+        // Create a package with the type already defined
+        let mut existing_database = Package::new();
+        existing_database.types.push(composite_type(vec![
+            (
+                "street",
+                SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            ),
+            ("zip", SqlType::Simple(SimpleSqlType::Integer, None)),
+        ]));
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
 
+        // Dropping an attribute is rejected by default
         let mut change_set = Vec::new();
-        let result = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert_that!(result).is_ok();
-
-        // We should have a single instruction to create a new table
-        assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::AddTable(ref table) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(table.columns).has_length(3);
-                assert_that!(table.columns[0].name).is_equal_to("id".to_owned());
-                assert_that!(table.columns[1].name).is_equal_to("company_id".to_owned());
-                assert_that!(table.columns[2].name).is_equal_to("first_name".to_owned());
-                assert_that!(table.constraints).is_empty();
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
+        assert_that!(result).is_err();
 
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
-            "CREATE TABLE my.contacts (\n\
-             \tid serial NOT NULL PRIMARY KEY,\n\
-             \tcompany_id bigint NOT NULL,\n\
-             \tfirst_name varchar(100) NOT NULL\n\
-             )"
-            .to_owned(),
+        publish_profile.generation_options.drop_types = Toggle::Allow;
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
         );
+        assert_that!(result).is_ok();
+        let sql = change_set.iter().map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_that!(sql).is_equal_to(vec![
+            "ALTER TYPE public.address DROP ATTRIBUTE zip".to_owned(),
+            "ALTER TYPE public.address ALTER ATTRIBUTE street TYPE varchar(200)".to_owned(),
+            "ALTER TYPE public.address ADD ATTRIBUTE city text".to_owned(),
+        ]);
     }
 
     #[test]
-    fn it_can_add_column_to_existing_table() {
+    fn it_can_add_domain_type() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "positive".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                default: Some(AnyValue::Integer(1, None)),
+                not_null: true,
+                constraints: vec![DomainConstraint {
+                    name: Some("positive_check".to_owned()),
+                    expression: "VALUE > 0".to_owned(),
+                }],
+            }),
+            span: ast::Span::default(),
+        };
 
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
+        let existing_database = Package::new();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
@@ -1967,22 +3649,77 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert_that!(change_set).is_empty();
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE DOMAIN public.positive AS int\nDEFAULT 1\nNOT NULL\nCONSTRAINT positive_check CHECK (VALUE > 0)"
+                .to_owned(),
+        );
+    }
 
-        // Now we check with a linked column
-        let result = LinkedColumn {
-            table: &source_table,
-            column: &source_table.columns.last().unwrap(),
-        }
-        .generate(
+    #[test]
+    fn it_can_modify_domain_type() {
+        let log = empty_logger();
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "label".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: SqlType::Simple(SimpleSqlType::Text, None),
+                default: Some(AnyValue::String("none".to_owned(), None)),
+                not_null: true,
+                constraints: vec![
+                    DomainConstraint {
+                        name: Some("label_length".to_owned()),
+                        expression: "char_length(VALUE) < 20".to_owned(),
+                    },
+                    DomainConstraint {
+                        name: Some("label_check".to_owned()),
+                        expression: "VALUE <> ''".to_owned(),
+                    },
+                ],
+            }),
+            span: ast::Span::default(),
+        };
+
+        // Create a package with the domain as PostgreSQL would describe it
+        let mut existing_database = Package::new();
+        existing_database.types.push(ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "label".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: SqlType::Simple(SimpleSqlType::Text, None),
+                default: Some(AnyValue::String(
+                    "none".to_owned(),
+                    Some(SqlType::Simple(SimpleSqlType::Text, None)),
+                )),
+                not_null: false,
+                constraints: vec![DomainConstraint {
+                    name: Some("label_length".to_owned()),
+                    expression: "char_length(VALUE) < 10".to_owned(),
+                }],
+            }),
+            span: ast::Span::default(),
+        });
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -1990,69 +3727,60 @@ mod tests {
             &log,
         );
         assert_that!(result).is_ok();
-
-        // We should have a single instruction to create a new table
-        assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::AddColumn(ref table, ref column) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(column.name).is_equal_to("last_name".to_owned());
-                assert_that!(column.sql_type)
-                    .is_equal_to(SqlType::Simple(SimpleSqlType::VariableLengthString(100), None));
-                assert_that!(column.constraints).has_length(1);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts ADD COLUMN last_name varchar(100) NOT NULL".to_owned());
+        let sql = change_set.iter().map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_that!(sql).is_equal_to(vec![
+            "ALTER DOMAIN public.label SET NOT NULL".to_owned(),
+            "ALTER DOMAIN public.label DROP CONSTRAINT label_length".to_owned(),
+            "ALTER DOMAIN public.label ADD CONSTRAINT label_length CHECK (char_length(VALUE) < 20)".to_owned(),
+            "ALTER DOMAIN public.label ADD CONSTRAINT label_check CHECK (VALUE <> '')".to_owned(),
+        ]);
     }
 
     #[test]
-    fn it_can_widen_column_on_existing_table() {
+    fn it_recreates_a_range_type_when_modified() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(200), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
+        fn range_type(subtype: SimpleSqlType) -> ast::TypeDefinition {
+            ast::TypeDefinition {
+                name: ast::ObjectName {
+                    schema: Some("public".to_string()),
+                    name: "floatrange".to_string(),
+                },
+                kind: ast::TypeDefinitionKind::Range(RangeDefinition {
+                    subtype: SqlType::Simple(subtype, None),
+                    subtype_opclass: None,
+                    collation: None,
+                    canonical: None,
+                    subtype_diff: Some("float8mi".to_owned()),
+                }),
+                span: ast::Span::default(),
+            }
+        }
+        let source_type = range_type(SimpleSqlType::Double);
 
-        // Create a database with the base table already defined.
+        // Create a package with the type already defined
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
-
-        existing_database.tables.push(existing_table);
+        existing_database.types.push(range_type(SimpleSqlType::Single));
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
 
+        // Recreating a type is rejected by default
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert_that!(change_set).is_empty();
+        assert_that!(result).is_err();
 
-        // Now we check with a linked column
-        let result = LinkedColumn {
-            table: &source_table,
-            column: &source_table.columns.last().unwrap(),
-        }
-        .generate(
+        publish_profile.generation_options.drop_types = Toggle::Allow;
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2060,110 +3788,441 @@ mod tests {
             &log,
         );
         assert_that!(result).is_ok();
-
-        // We should have a single instruction to create a new table
-        assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::ModifyColumnType(ref table, ref column) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(column.name).is_equal_to("last_name".to_owned());
-                assert_that!(column.sql_type)
-                    .is_equal_to(SqlType::Simple(SimpleSqlType::VariableLengthString(200), None));
-                assert_that!(column.constraints).has_length(1);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts ALTER COLUMN last_name TYPE varchar(200)".to_owned());
+        let sql = change_set.iter().map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_that!(sql).is_equal_to(vec![
+            "DROP TYPE IF EXISTS public.floatrange".to_owned(),
+            "CREATE TYPE public.floatrange AS RANGE (\n    SUBTYPE = double precision,\n    SUBTYPE_DIFF = float8mi\n)"
+                .to_owned(),
+        ]);
     }
 
     #[test]
-    fn it_can_drop_column_on_existing_table() {
+    fn it_includes_function_attributes_when_replacing_a_function() {
         let log = empty_logger();
-        let source_table = base_table();
-
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
-
-        existing_database.tables.push(existing_table);
+        let function = ast::FunctionDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "current_tenant".to_owned(),
+            },
+            arguments: Vec::new(),
+            return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Integer, None)),
+            body: " SELECT 1 ".to_owned(),
+            language: FunctionLanguage::SQL,
+            attributes: vec![
+                FunctionAttribute::Volatility(FunctionVolatility::Stable),
+                FunctionAttribute::SecurityDefiner(true),
+                FunctionAttribute::Set(
+                    "search_path".to_owned(),
+                    vec!["public".to_owned(), "pg_temp".to_owned()],
+                ),
+            ],
+            span: ast::Span::default(),
+        };
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_columns = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
-            &log,
-        );
+        let result = (&function).generate(&mut change_set, &Package::new(), &capabilities, &publish_profile, &log);
         assert_that!(result).is_ok();
-
-        // We should have a single instruction to create a new table
         assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::DropColumn(ref table, ref column_name) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(*column_name).is_equal_to("last_name".to_owned());
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE OR REPLACE FUNCTION public.current_tenant ()\nRETURNS int\nAS $$ SELECT 1 $$\nLANGUAGE SQL\n\
+             STABLE\nSECURITY DEFINER\nSET search_path = public, pg_temp"
+                .to_owned(),
+        );
+    }
 
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts DROP COLUMN last_name".to_owned());
+    fn sum_aggregate(state_function: Option<&str>, initial_condition: &str) -> ast::AggregateDefinition {
+        ast::AggregateDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "my_sum".to_owned(),
+            },
+            arguments: vec![FunctionArgument {
+                mode: None,
+                name: None,
+                sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                default: None,
+            }],
+            state_function: ObjectName {
+                schema: state_function.map(|schema| schema.to_owned()),
+                name: "int4pl".to_owned(),
+            },
+            state_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            final_function: None,
+            combine_function: None,
+            initial_condition: Some(initial_condition.to_owned()),
+            parallel: None,
+            span: ast::Span::default(),
+        }
     }
 
     #[test]
-    fn it_can_add_a_new_primary_key() {
+    fn it_recreates_an_aggregate_when_its_definition_changes() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
-
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+        let mut target = Package::new();
+        target.aggregates.push(sum_aggregate(Some("pg_catalog"), "0"));
 
+        // Support functions found through the search path are the same as their qualified equivalent
+        let unchanged = sum_aggregate(None, "0");
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
+        let result = (&unchanged).generate(&mut change_set, &target, &capabilities, &publish_profile, &log);
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+
+        let changed = sum_aggregate(None, "10");
+        let result = (&changed).generate(&mut change_set, &target, &capabilities, &publish_profile, &log);
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to("DROP AGGREGATE IF EXISTS public.my_sum(int)".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE AGGREGATE public.my_sum (int) (\n    SFUNC = int4pl,\n    STYPE = int,\n    INITCOND = '10'\n)"
+                .to_owned(),
+        );
+    }
+
+    fn overloaded_function(argument_types: Vec<SqlType>) -> ast::FunctionDefinition {
+        ast::FunctionDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "describe".to_owned(),
+            },
+            arguments: argument_types
+                .into_iter()
+                .map(|sql_type| FunctionArgument {
+                    mode: None,
+                    name: None,
+                    sql_type,
+                    default: None,
+                })
+                .collect(),
+            return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Text, None)),
+            body: " SELECT 'value' ".to_owned(),
+            language: FunctionLanguage::SQL,
+            attributes: Vec::new(),
+            span: ast::Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_drops_functions_by_signature() {
+        let log = empty_logger();
+        let integer = || SqlType::Simple(SimpleSqlType::Integer, None);
+        let varchar = SqlType::Simple(SimpleSqlType::VariableLengthString(10), None);
+        let mut source_package = Package::new();
+        source_package.functions.push(overloaded_function(vec![varchar]));
+        let function = overloaded_function(vec![integer(), integer()]);
+        source_package.functions.push(function);
+
+        // The catalog describes arguments without their type modifiers. The integer overload has gained an
+        // argument so the previous signature needs to be dropped along with the text overload.
+        let varchar = SqlType::Simple(SimpleSqlType::UnsizedVariableLengthString, None);
+        let text = SqlType::Simple(SimpleSqlType::Text, None);
+        let mut existing_database = Package::new();
+        existing_database.functions.push(overloaded_function(vec![varchar]));
+        existing_database.functions.push(overloaded_function(vec![integer()]));
+        existing_database.functions.push(overloaded_function(vec![text]));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
             &capabilities,
             &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        let drops = change_set
+            .iter()
+            .filter(|change| match **change {
+                ChangeInstruction::DropFunction(_) => true,
+                _ => false,
+            })
+            .map(|change| change.to_sql(&log))
+            .collect::<Vec<_>>();
+        assert_that!(drops).is_equal_to(vec![
+            "DROP FUNCTION IF EXISTS public.describe(int)".to_owned(),
+            "DROP FUNCTION IF EXISTS public.describe(text)".to_owned(),
+        ]);
+        let added = change_set
+            .iter()
+            .filter(|change| match **change {
+                ChangeInstruction::AddFunction(_) => true,
+                ChangeInstruction::ModifyFunction(..) => true,
+                _ => false,
+            })
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        assert_that!(added).is_equal_to(vec!["Add function: public.describe(int, int)".to_owned()]);
+    }
+
+    #[test]
+    fn it_writes_argument_modes_and_defaults_when_creating_a_function() {
+        let log = empty_logger();
+        let mut function = overloaded_function(Vec::new());
+        function.arguments = vec![
+            FunctionArgument {
+                mode: Some(FunctionArgumentMode::InOut),
+                name: Some("total".to_owned()),
+                sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                default: Some(AnyValue::Integer(0, None)),
+            },
+            FunctionArgument {
+                mode: Some(FunctionArgumentMode::Variadic),
+                name: None,
+                sql_type: SqlType::Simple(SimpleSqlType::Text, Some(1)),
+                default: None,
+            },
+        ];
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&function).generate(&mut change_set, &Package::new(), &capabilities, &publish_profile, &log);
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE OR REPLACE FUNCTION public.describe (\n    INOUT total int DEFAULT 0,\n    VARIADIC text[]\n)\n\
+             RETURNS text\nAS $$ SELECT 'value' $$\nLANGUAGE SQL"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_only_replaces_functions_that_have_changed() {
+        let log = empty_logger();
+        let integer = || SqlType::Simple(SimpleSqlType::Integer, None);
+        let text = || SqlType::Simple(SimpleSqlType::Text, None);
+        let mut unchanged = overloaded_function(vec![integer()]);
+        unchanged.attributes = vec![
+            FunctionAttribute::Volatility(FunctionVolatility::Volatile),
+            FunctionAttribute::Cost(100),
+        ];
+        let mut modified = overloaded_function(vec![text()]);
+        modified.body = "\nSELECT 'value'\nUNION ALL\nSELECT 'other'\n".to_owned();
+        let mut recreated = overloaded_function(vec![integer(), integer()]);
+        recreated.return_type = FunctionReturnType::SqlType(integer());
+        let mut source_package = Package::new();
+        source_package.functions.push(unchanged);
+        source_package.functions.push(modified);
+        source_package.functions.push(recreated);
+
+        // The database trims nothing from the body, but does omit any default attributes
+        let mut existing_database = Package::new();
+        let mut unchanged = overloaded_function(vec![integer()]);
+        unchanged.body = "\r\n  SELECT 'value'  \r\n".to_owned();
+        existing_database.functions.push(unchanged);
+        let mut modified = overloaded_function(vec![text()]);
+        modified.body = "\nSELECT 'value'\n".to_owned();
+        existing_database.functions.push(modified);
+        let recreated = overloaded_function(vec![integer(), integer()]);
+        existing_database.functions.push(recreated);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+
+        let result = Delta::generate(
             &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
         );
-        assert_that!(change_set).is_empty();
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        let changes = change_set
+            .iter()
+            .filter(|change| match **change {
+                ChangeInstruction::AddFunction(_) => true,
+                ChangeInstruction::ModifyFunction(..) => true,
+                ChangeInstruction::DropFunction(_) => true,
+                _ => false,
+            })
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        assert_that!(changes).is_equal_to(vec![
+            "Modify function: public.describe(text)".to_owned(),
+            "Drop function: public.describe(int, int)".to_owned(),
+            "Add function: public.describe(int, int)".to_owned(),
+        ]);
+        let diff = change_set.iter().find_map(|change| match *change {
+            ChangeInstruction::ModifyFunction(_, ref diff) => Some(diff.clone()),
+            _ => None,
+        });
+        assert_that!(diff).is_equal_to(Some(vec![
+            "  SELECT 'value'".to_owned(),
+            "+ UNION ALL".to_owned(),
+            "+ SELECT 'other'".to_owned(),
+        ]));
+    }
 
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
+    fn overloaded_procedure(arguments: Vec<(&str, SqlType)>) -> ast::ProcedureDefinition {
+        ast::ProcedureDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "archive".to_owned(),
+            },
+            arguments: arguments
+                .into_iter()
+                .map(|(name, sql_type)| FunctionArgument {
+                    mode: None,
+                    name: Some(name.to_owned()),
+                    sql_type,
+                    default: None,
+                })
+                .collect(),
+            body: " DELETE FROM public.contacts ".to_owned(),
+            language: FunctionLanguage::SQL,
+            attributes: Vec::new(),
+            span: ast::Span::default(),
         }
-        .generate(
+    }
+
+    #[test]
+    fn it_only_replaces_procedures_that_have_changed() {
+        let log = empty_logger();
+        let integer = || SqlType::Simple(SimpleSqlType::Integer, None);
+        let text = || SqlType::Simple(SimpleSqlType::Text, None);
+        let bigint = || SqlType::Simple(SimpleSqlType::BigInteger, None);
+        let unchanged = overloaded_procedure(vec![("id", integer())]);
+        let mut modified = overloaded_procedure(vec![("name", text())]);
+        modified.body = "\nDELETE FROM public.contacts WHERE name = $1\n".to_owned();
+        let recreated = overloaded_procedure(vec![("id", integer()), ("count", integer())]);
+        let mut source_package = Package::new();
+        source_package.procedures.push(unchanged);
+        source_package.procedures.push(modified);
+        source_package.procedures.push(recreated);
+
+        // Renaming an argument requires the procedure to be recreated, while removed overloads are dropped by
+        // their signature
+        let mut existing_database = Package::new();
+        let mut unchanged = overloaded_procedure(vec![("id", integer())]);
+        unchanged.body = "\r\n  DELETE FROM public.contacts  \r\n".to_owned();
+        existing_database.procedures.push(unchanged);
+        let modified = overloaded_procedure(vec![("name", text())]);
+        existing_database.procedures.push(modified);
+        let renamed = overloaded_procedure(vec![("id", integer()), ("total", integer())]);
+        existing_database.procedures.push(renamed);
+        let removed = overloaded_procedure(vec![("id", bigint())]);
+        existing_database.procedures.push(removed);
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        let changes = change_set
+            .iter()
+            .filter(|change| match **change {
+                ChangeInstruction::AddProcedure(_) => true,
+                ChangeInstruction::ModifyProcedure(_) => true,
+                ChangeInstruction::DropProcedure(_) => true,
+                _ => false,
+            })
+            .map(|change| change.to_sql(&log))
+            .collect::<Vec<_>>();
+        assert_that!(changes).has_length(4);
+        assert_that!(changes[0]).is_equal_to("DROP PROCEDURE IF EXISTS public.archive(bigint)".to_owned());
+        assert_that!(changes[1]).starts_with("CREATE OR REPLACE PROCEDURE public.archive (name text)");
+        assert_that!(changes[2]).is_equal_to("DROP PROCEDURE IF EXISTS public.archive(int, int)".to_owned());
+        assert_that!(changes[3]).starts_with("CREATE OR REPLACE PROCEDURE public.archive (id int, count int)");
+    }
+
+    fn base_table() -> ast::TableDefinition {
+        ast::TableDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![
+                ColumnDefinition {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::Serial, None),
+                    constraints: vec![ColumnConstraint::NotNull, ColumnConstraint::PrimaryKey],
+                },
+                ColumnDefinition {
+                    name: "company_id".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
+                    constraints: vec![ColumnConstraint::NotNull],
+                },
+                ColumnDefinition {
+                    name: "first_name".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+                    constraints: vec![ColumnConstraint::NotNull],
+                },
+            ],
+            constraints: Vec::new(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            span: ast::Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_can_add_new_table() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create an empty database
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2172,60 +4231,3152 @@ mod tests {
         );
         assert_that!(result).is_ok();
 
-        // We should have a single instruction to add a constraint
+        // We should have a single instruction to create a new table
         assert_that!(change_set).has_length(1);
         match change_set[0] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
+            ChangeInstruction::AddTable(ref table) => {
                 assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                match *constraint {
-                    TableConstraint::Primary {
-                        name,
-                        columns,
-                        parameters,
-                    } => {
-                        assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
-                        assert_that!(*columns).has_length(1);
-                        assert_that!(columns.iter()).contains("id".to_owned());
-                        assert_that!(*parameters).is_some().has_length(1);
-                    }
-                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
-                }
+                assert_that!(table.columns).has_length(3);
+                assert_that!(table.columns[0].name).is_equal_to("id".to_owned());
+                assert_that!(table.columns[1].name).is_equal_to("company_id".to_owned());
+                assert_that!(table.columns[2].name).is_equal_to("first_name".to_owned());
+                assert_that!(table.constraints).is_empty();
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
 
         // Check the SQL generation
         assert_that!(change_set[0].to_sql(&log)).is_equal_to(
-            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
-                .to_owned(),
+            "CREATE TABLE my.contacts (\n    \
+             id serial NOT NULL PRIMARY KEY,\n    \
+             company_id bigint NOT NULL,\n    \
+             first_name varchar(100) NOT NULL\n\
+             )"
+            .to_owned(),
         );
     }
 
     #[test]
-    fn it_can_remove_an_existing_primary_key() {
+    fn it_quotes_identifiers_when_generating_sql() {
         let log = empty_logger();
-        let source_table = base_table();
+        let mut source_table = base_table();
+        source_table.name.name = "Order".to_owned();
+        source_table.columns[0].name = "Id".to_owned();
+        source_table.columns[2].name = "user".to_owned();
+        source_table.constraints.push(TableConstraint::Primary {
+            name: "Order_pkey".to_owned(),
+            columns: vec!["Id".into()],
+            parameters: None,
+        });
+
+        let change = ChangeInstruction::AddTable(&source_table);
+        assert_that!(change.to_sql(&log)).is_equal_to(
+            "CREATE TABLE my.\"Order\" (\n    \
+             \"Id\" serial NOT NULL PRIMARY KEY,\n    \
+             company_id bigint NOT NULL,\n    \
+             \"user\" varchar(100) NOT NULL\n\
+             )"
+            .to_owned(),
+        );
+        let change = ChangeInstruction::AddConstraint(&source_table, &source_table.constraints[0]);
+        assert_that!(change.to_sql(&log))
+            .is_equal_to("ALTER TABLE my.\"Order\" ADD CONSTRAINT \"Order_pkey\" PRIMARY KEY (\"Id\")".to_owned());
+        let change = ChangeInstruction::DropColumn(&source_table, "user".to_owned());
+        assert_that!(change.to_sql(&log)).is_equal_to("ALTER TABLE my.\"Order\" DROP COLUMN \"user\"".to_owned());
+    }
+
+    #[test]
+    fn it_can_add_column_to_existing_table() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+        });
 
         // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
-        existing_database.tables.push(existing_table);
+        existing_database.tables.push(base_table());
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // This change_set gets generated at the table level
-        let result = (&source_table).generate(
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked column
+        let result = LinkedColumn {
+            table: &source_table,
+            column: &source_table.columns.last().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new table
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddColumn(ref table, ref column) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(column.name).is_equal_to("last_name".to_owned());
+                assert_that!(column.sql_type)
+                    .is_equal_to(SqlType::Simple(SimpleSqlType::VariableLengthString(100), None));
+                assert_that!(column.constraints).has_length(1);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ADD COLUMN last_name varchar(100) NOT NULL".to_owned());
+    }
+
+    #[test]
+    fn it_can_widen_column_on_existing_table() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(200), None),
+            constraints: vec![ColumnConstraint::NotNull],
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+        });
+
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked column
+        let result = LinkedColumn {
+            table: &source_table,
+            column: &source_table.columns.last().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new table
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::ModifyColumnType(ref table, ref column) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(column.name).is_equal_to("last_name".to_owned());
+                assert_that!(column.sql_type)
+                    .is_equal_to(SqlType::Simple(SimpleSqlType::VariableLengthString(200), None));
+                assert_that!(column.constraints).has_length(1);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ALTER COLUMN last_name TYPE varchar(200)".to_owned());
+    }
+
+    #[test]
+    fn it_can_drop_column_on_existing_table() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+        });
+
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new table
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::DropColumn(ref table, ref column_name) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(*column_name).is_equal_to("last_name".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP COLUMN last_name".to_owned());
+    }
+
+    fn identity_table(kind: IdentityKind, options: Vec<SequenceOption>) -> ast::TableDefinition {
+        let mut table = base_table();
+        table.columns[1]
+            .constraints
+            .push(ColumnConstraint::Identity(kind, options));
+        table
+    }
+
+    fn generated_table(expression: &str) -> ast::TableDefinition {
+        let mut table = base_table();
+        table.columns[1].constraints = vec![ColumnConstraint::Generated(expression.to_owned())];
+        table
+    }
+
+    fn default_table(value: AnyValue) -> ast::TableDefinition {
+        let mut table = base_table();
+        table.columns[2].constraints.push(ColumnConstraint::Default(value));
+        table
+    }
+
+    fn generate_linked_column<'a>(
+        source_table: &'a ast::TableDefinition,
+        existing_table: ast::TableDefinition,
+        capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+    ) -> PsqlpackResult<Vec<ChangeInstruction<'a>>> {
+        let log = empty_logger();
+        let mut existing_database = Package::new();
+        existing_database.tables.push(existing_table);
+
+        let mut change_set = Vec::new();
+        LinkedColumn {
+            table: source_table,
+            column: &source_table.columns[1],
+        }
+        .generate(&mut change_set, &existing_database, capabilities, publish_profile, &log)?;
+        Ok(change_set)
+    }
+
+    #[test]
+    fn it_can_add_an_identity_to_an_existing_column() {
+        let log = empty_logger();
+        let source_table = identity_table(IdentityKind::Always, vec![SequenceOption::Start(100)]);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let result = generate_linked_column(&source_table, base_table(), &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ALTER COLUMN company_id ADD GENERATED ALWAYS AS IDENTITY (START WITH 100)"
+                .to_owned(),
+        );
+
+        // And removing it again
+        let plain_table = base_table();
+        let existing_table = identity_table(IdentityKind::Always, vec![SequenceOption::Start(100)]);
+        let result = generate_linked_column(&plain_table, existing_table, &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ALTER COLUMN company_id DROP IDENTITY IF EXISTS".to_owned());
+    }
+
+    #[test]
+    fn it_can_modify_an_identity_column() {
+        let log = empty_logger();
+        let source_table = identity_table(
+            IdentityKind::ByDefault,
+            vec![SequenceOption::Increment(5), SequenceOption::Cycle(true)],
+        );
+        let existing_table = identity_table(IdentityKind::Always, vec![SequenceOption::Increment(5)]);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let result = generate_linked_column(&source_table, existing_table, &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ALTER COLUMN company_id SET GENERATED BY DEFAULT SET CYCLE".to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_ignores_default_identity_options() {
+        let source_table = identity_table(IdentityKind::Always, Vec::new());
+        let existing_table = identity_table(
+            IdentityKind::Always,
+            vec![
+                SequenceOption::Increment(1),
+                SequenceOption::MinValue(None),
+                SequenceOption::Start(1),
+                SequenceOption::Cache(1),
+                SequenceOption::Cycle(false),
+            ],
+        );
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let result = generate_linked_column(&source_table, existing_table, &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        assert_that!(result.unwrap()).is_empty();
+    }
+
+    #[test]
+    fn it_can_modify_a_generated_column() {
+        let log = empty_logger();
+        let source_table = generated_table("id * 2");
+        let capabilities = Capabilities {
+            server_version: Semver::new(17, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        // Formatting differences are ignored
+        let result = generate_linked_column(
+            &source_table,
+            generated_table("(id * 2)"),
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(result.unwrap()).is_empty();
+        let length_table = generated_table("length(first_name)");
+        let existing_table = generated_table("length((first_name)::text)");
+        let result = generate_linked_column(&length_table, existing_table, &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        assert_that!(result.unwrap()).is_empty();
+
+        let result = generate_linked_column(
+            &source_table,
+            generated_table("id * 3"),
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ALTER COLUMN company_id SET EXPRESSION AS (id * 2)".to_owned());
+
+        // A generated column can become a regular column
+        let plain_table = base_table();
+        let result = generate_linked_column(&plain_table, generated_table("id * 2"), &capabilities, &publish_profile);
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ALTER COLUMN company_id DROP EXPRESSION".to_owned());
+    }
+
+    #[test]
+    fn it_recreates_a_generated_column_when_the_expression_cannot_be_modified() {
+        let log = empty_logger();
+        let source_table = generated_table("id * 2");
+        let capabilities = Capabilities {
+            server_version: Semver::new(12, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+
+        // Dropping columns is disabled by default
+        let result = generate_linked_column(
+            &source_table,
+            generated_table("id * 3"),
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
+        let result = generate_linked_column(
+            &source_table,
+            generated_table("id * 3"),
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = result.unwrap();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP COLUMN company_id".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ADD COLUMN company_id bigint GENERATED ALWAYS AS (id * 2) STORED".to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_compares_column_defaults_in_normalized_form() {
+        let log = empty_logger();
+        let now = || {
+            AnyValue::Expression(Expression::FunctionCall(
+                ObjectName {
+                    schema: None,
+                    name: "now".to_owned(),
+                },
+                Vec::new(),
+            ))
+        };
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+        let generate = |source_table: &ast::TableDefinition, existing_table: ast::TableDefinition| {
+            let mut existing_database = Package::new();
+            existing_database.tables.push(existing_table);
+            let mut change_set = Vec::new();
+            LinkedColumn {
+                table: source_table,
+                column: &source_table.columns[2],
+            }
+            .generate(
+                &mut change_set,
+                &existing_database,
+                &capabilities,
+                &publish_profile,
+                &log,
+            )
+            .unwrap();
+            change_set.iter().map(|c| c.to_sql(&log)).collect::<Vec<_>>()
+        };
+
+        // Postgres describes literals with a cast and function calls as is
+        let source_table = default_table(AnyValue::String("none".to_owned(), None));
+        let existing_table = default_table(AnyValue::String(
+            "none".to_owned(),
+            Some(SqlType::Simple(SimpleSqlType::VariableLengthString(100), None)),
+        ));
+        assert_that!(generate(&source_table, existing_table)).is_empty();
+        let source_table = default_table(AnyValue::Decimal(Decimal::new(-150, 2), None));
+        let existing_table = default_table(AnyValue::String(
+            "-1.50".to_owned(),
+            Some(SqlType::Simple(SimpleSqlType::Numeric(None), None)),
+        ));
+        assert_that!(generate(&source_table, existing_table)).is_empty();
+        let existing_table = default_table(AnyValue::Decimal(Decimal::new(150, 2), None));
+        assert_that!(generate(&source_table, existing_table)).is_equal_to(vec![
+            "ALTER TABLE my.contacts ALTER COLUMN first_name SET DEFAULT -1.50".to_owned(),
+        ]);
+        let source_table = default_table(now());
+        assert_that!(generate(&source_table, default_table(now()))).is_empty();
+
+        // Adding, changing and removing a default
+        assert_that!(generate(&source_table, base_table())).is_equal_to(vec![
+            "ALTER TABLE my.contacts ALTER COLUMN first_name SET DEFAULT now()".to_owned(),
+        ]);
+        let existing_table = default_table(AnyValue::String("none".to_owned(), None));
+        assert_that!(generate(&source_table, existing_table)).is_equal_to(vec![
+            "ALTER TABLE my.contacts ALTER COLUMN first_name SET DEFAULT now()".to_owned(),
+        ]);
+        let source_table = base_table();
+        assert_that!(generate(&source_table, default_table(now()))).is_equal_to(vec![
+            "ALTER TABLE my.contacts ALTER COLUMN first_name DROP DEFAULT".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn it_can_add_a_new_primary_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to add a constraint
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                match *constraint {
+                    TableConstraint::Primary {
+                        name,
+                        columns,
+                        parameters,
+                    } => {
+                        assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
+                        assert_that!(*columns).has_length(1);
+                        assert_that!(columns.iter()).contains("id".to_owned());
+                        assert_that!(*parameters).is_some().has_length(1);
+                    }
+                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)".to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_primary_key() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // This change_set gets generated at the table level
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to remove the constraint
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(ref table, ref name) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT pk_my_contacts_id".to_owned());
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_primary_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into(), "company_id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // Primary keys cannot be altered, so we drop/create
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(ref table, ref name) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                match *constraint {
+                    TableConstraint::Primary {
+                        name,
+                        columns,
+                        parameters,
+                    } => {
+                        assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
+                        assert_that!(*columns).has_length(1);
+                        assert_that!(columns.iter()).contains("id".to_owned());
+                        assert_that!(*parameters).is_some().has_length(1);
+                    }
+                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT pk_my_contacts_id".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)".to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_new_foreign_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new constraint
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                match *constraint {
+                    TableConstraint::Foreign {
+                        name,
+                        columns,
+                        ref_table,
+                        ref_columns,
+                        match_type,
+                        events,
+                    } => {
+                        assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
+                        assert_that!(*columns).has_length(1);
+                        assert_that!(columns.iter()).contains("company_id".to_owned());
+                        assert_that!(ref_table.to_string()).is_equal_to("my.companies".to_owned());
+                        assert_that!(*ref_columns).has_length(1);
+                        assert_that!(ref_columns.iter()).contains("id".to_owned());
+                        assert_that!(*match_type)
+                            .is_some()
+                            .is_equal_to(ForeignConstraintMatchType::Simple);
+                        assert_that!(*events).is_some().has_length(2); // We test this further below
+                    }
+                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts \
+             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
+             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE CASCADE ON DELETE NO ACTION"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_foreign_key() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // This change_set gets generated at the table level
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to remove a constraint
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(ref table, ref name) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT fk_my_contacts_my_companies".to_owned());
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_foreign_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::NoAction),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(change_set).is_empty();
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // Primary keys cannot be altered, so we drop/create
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(ref table, ref name) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
+                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
+                match *constraint {
+                    TableConstraint::Foreign {
+                        name,
+                        columns,
+                        ref_table,
+                        ref_columns,
+                        match_type,
+                        events,
+                    } => {
+                        assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
+                        assert_that!(*columns).has_length(1);
+                        assert_that!(columns.iter()).contains("company_id".to_owned());
+                        assert_that!(ref_table.to_string()).is_equal_to("my.companies".to_owned());
+                        assert_that!(*ref_columns).has_length(1);
+                        assert_that!(ref_columns.iter()).contains("id".to_owned());
+                        assert_that!(*match_type)
+                            .is_some()
+                            .is_equal_to(ForeignConstraintMatchType::Simple);
+                        assert_that!(*events).is_some().has_length(2); // We test this further below
+                    }
+                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT fk_my_contacts_my_companies".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts \
+             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
+             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_check_constraint_if_not_modified() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Check {
+            name: "ck_contacts_status".to_owned(),
+            expression: "status IN ('active', 'void') AND (age >= -1 OR age IS NULL)".to_owned(),
+            no_inherit: false,
+        });
+
+        // Create a database with the check constraint as it would be described by PostgreSQL
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Check {
+            name: "ck_contacts_status".to_owned(),
+            expression: "(status::text = ANY (ARRAY['active'::character varying, 'void'::character varying]::text[])) \
+                         AND (age >= '-1'::integer::numeric OR age IS NULL)"
+                .to_owned(),
+            no_inherit: false,
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_check_constraint() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Check {
+            name: "ck_contacts_age".to_owned(),
+            expression: "age >= 18".to_owned(),
+            no_inherit: true,
+        });
+
+        // Create a database with the base table already defined.
+        fn existing_db() -> Package {
+            let mut existing_database = Package::new();
+            let mut existing_table = base_table();
+            existing_table.constraints.push(TableConstraint::Check {
+                name: "ck_contacts_age".to_owned(),
+                expression: "(age >= 0)".to_owned(),
+                no_inherit: false,
+            });
+            existing_database.tables.push(existing_table);
+            existing_database
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_check_constraints = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(&mut change_set, &existing_db(), &capabilities, &publish_profile, &log);
+        assert_that!(result).is_err();
+
+        // Now allow it - check constraints cannot be altered, so we drop/create
+        publish_profile.generation_options.drop_check_constraints = Toggle::Allow;
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(&mut change_set, &existing_db(), &capabilities, &publish_profile, &log);
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT ck_contacts_age".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ADD CONSTRAINT ck_contacts_age CHECK (age >= 18) NO INHERIT".to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_detects_a_change_of_precedence_or_cast_in_a_check_constraint() {
+        let log = empty_logger();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_check_constraints = Toggle::Allow;
+        let generate = |source: &str, existing: &str| {
+            let check = |expression: &str| {
+                let mut table = base_table();
+                table.constraints.push(TableConstraint::Check {
+                    name: "ck_contacts_age".to_owned(),
+                    expression: expression.to_owned(),
+                    no_inherit: false,
+                });
+                table
+            };
+            let source_table = check(source);
+            let mut existing_database = Package::new();
+            existing_database.tables.push(check(existing));
+            let mut change_set = Vec::new();
+            LinkedTableConstraint {
+                table: &source_table,
+                constraint: &source_table.constraints[0],
+            }
+            .generate(
+                &mut change_set,
+                &existing_database,
+                &capabilities,
+                &publish_profile,
+                &log,
+            )
+            .unwrap();
+            change_set.len()
+        };
+
+        // The existing expressions are as PostgreSQL describes them
+        assert_that!(generate("a * (b + c) > 0", "((a * (b + c)) > 0)")).is_equal_to(0);
+        assert_that!(generate("a * b + c > 0", "((a * (b + c)) > 0)")).is_equal_to(2);
+        assert_that!(generate("a::text = '1'", "((a)::text = '1'::text)")).is_equal_to(0);
+        assert_that!(generate("a::int = 1", "((a)::text = '1'::text)")).is_equal_to(2);
+    }
+
+    #[test]
+    fn it_ignores_an_exclusion_constraint_if_not_modified() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Exclude {
+            name: "ex_contacts_company".to_owned(),
+            index_type: None,
+            elements: vec![ExclusionElement {
+                column: "company_id".to_owned(),
+                operator: "=".to_owned(),
+            }],
+            parameters: None,
+        });
+
+        // Create a database with the constraint as PostgreSQL would describe it
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Exclude {
+            name: "ex_contacts_company".to_owned(),
+            index_type: Some(IndexType::BTree),
+            elements: vec![ExclusionElement {
+                column: "company_id".to_owned(),
+                operator: "=".to_owned(),
+            }],
+            parameters: None,
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_unique_constraint() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Unique {
+            name: "uq_contacts_first_name".to_owned(),
+            columns: vec!["first_name".to_owned()],
+            include: Some(vec!["company_id".to_owned()]),
+            parameters: Some(vec![IndexParameter::FillFactor(70)]),
+        });
+
+        // Create a database with the base table already defined.
+        fn existing_db() -> Package {
+            let mut existing_database = Package::new();
+            let mut existing_table = base_table();
+            existing_table.constraints.push(TableConstraint::Unique {
+                name: "uq_contacts_first_name".to_owned(),
+                columns: vec!["first_name".to_owned()],
+                include: None,
+                parameters: None,
+            });
+            existing_database.tables.push(existing_table);
+            existing_database
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_unique_constraints = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(&mut change_set, &existing_db(), &capabilities, &publish_profile, &log);
+        assert_that!(result).is_err();
+
+        // Now allow it - unique constraints cannot be altered, so we drop/create
+        publish_profile.generation_options.drop_unique_constraints = Toggle::Allow;
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: &source_table.constraints.first().unwrap(),
+        }
+        .generate(&mut change_set, &existing_db(), &capabilities, &publish_profile, &log);
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DROP CONSTRAINT uq_contacts_first_name".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "ALTER TABLE my.contacts ADD CONSTRAINT uq_contacts_first_name UNIQUE (first_name) \
+             INCLUDE (company_id) WITH (FILLFACTOR=70)"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_new_index() {
+        let log = empty_logger();
+        let source_index = IndexDefinition {
+            name: "idx_contacts_first_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Column("first_name".to_owned()),
+                collation: None,
+                operator_class: None,
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: None,
+            predicate: None,
+            span: Span::default(),
+        };
+
+        // Create a database with no indexes defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new index
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddIndex(ref index, concurrently) => {
+                assert_that!(index.name).is_equal_to("idx_contacts_first_name".to_owned());
+                assert_that!(index.table.to_string()).is_equal_to("public.contacts".to_owned());
+                assert_that!(concurrently).is_true();
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE UNIQUE INDEX CONCURRENTLY idx_contacts_first_name ON public.contacts USING btree (\n    \
+             first_name ASC NULLS LAST\n\
+             )"
+            .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_index() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        // Create a database with the index already defined.
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.indexes.push(IndexDefinition {
+                name: "idx_contacts_first_name".to_owned(),
+                table: ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "contacts".to_owned(),
+                },
+                columns: vec![IndexColumn {
+                    key: IndexKey::Column("first_name".to_owned()),
+                    collation: None,
+                    operator_class: None,
+                    order: Some(IndexOrder::Ascending),
+                    null_position: Some(IndexPosition::Last),
+                }],
+                unique: true,
+                index_type: Some(IndexType::BTree),
+                include: None,
+                storage_parameters: None,
+                predicate: None,
+                span: Span::default(),
+            });
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_indexes = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+        // Now run it again - it should be ok now
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_indexes = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // We should have a single instruction to remove an index (first will be use database)
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::DropIndex(ref index, concurrently) => {
+                assert_that!(*index).is_equal_to("public.idx_contacts_first_name".to_owned());
+                assert_that!(concurrently).is_true();
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_first_name".to_owned());
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_index() {
+        let log = empty_logger();
+        let source_index = IndexDefinition {
+            name: "idx_contacts_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![
+                IndexColumn {
+                    key: IndexKey::Column("first_name".to_owned()),
+                    collation: None,
+                    operator_class: None,
+                    order: Some(IndexOrder::Ascending),
+                    null_position: Some(IndexPosition::Last),
+                },
+                IndexColumn {
+                    key: IndexKey::Column("last_name".to_owned()),
+                    collation: None,
+                    operator_class: None,
+                    order: Some(IndexOrder::Descending),
+                    null_position: Some(IndexPosition::First),
+                },
+            ],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: None,
+            predicate: None,
+            span: Span::default(),
+        };
+
+        // Create a database with a single index defined.
+        let mut existing_database = Package::new();
+        existing_database.indexes.push(IndexDefinition {
+            name: "idx_contacts_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Column("first_name".to_owned()),
+                collation: None,
+                operator_class: None,
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: None,
+            predicate: None,
+            span: Span::default(),
+        });
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have two instructions to drop/create a new index
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropIndex(ref index_name, concurrently) => {
+                assert_that!(*index_name).is_equal_to("public.idx_contacts_name".to_owned());
+                assert_that!(concurrently).is_true();
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddIndex(ref index, concurrently) => {
+                assert_that!(index.name).is_equal_to("idx_contacts_name".to_owned());
+                assert_that!(index.table.to_string()).is_equal_to("public.contacts".to_owned());
+                assert_that!(concurrently).is_true();
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_name".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE INDEX CONCURRENTLY idx_contacts_name ON public.contacts USING btree (\n    \
+             first_name ASC NULLS LAST,\n    \
+             last_name DESC NULLS FIRST\n\
+             )"
+            .to_owned(),
+        );
+    }
+
+    fn partial_expression_index() -> IndexDefinition {
+        IndexDefinition {
+            name: "idx_contacts_email".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Expression("lower(email)".to_owned()),
+                collation: Some("C".to_owned()),
+                operator_class: Some("text_pattern_ops".to_owned()),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            include: Some(vec!["first_name".to_owned()]),
+            storage_parameters: None,
+            predicate: Some("deleted_at IS NULL".to_owned()),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_ignores_a_partial_expression_index_if_semantically_equal() {
+        let log = empty_logger();
+        let source_index = partial_expression_index();
+
+        // The database reports the index as postgres would, with casts, brackets and no default ordering
+        let mut existing_database = Package::new();
+        let mut existing_index = partial_expression_index();
+        existing_index.columns[0].key = IndexKey::Expression("lower((email)::text)".to_owned());
+        existing_index.columns[0].order = None;
+        existing_index.columns[0].null_position = None;
+        existing_index.predicate = Some("(deleted_at IS NULL)".to_owned());
+        existing_database.indexes.push(existing_index);
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_modify_the_predicate_of_an_existing_index() {
+        let log = empty_logger();
+        let source_index = partial_expression_index();
+
+        let mut existing_database = Package::new();
+        let mut existing_index = partial_expression_index();
+        existing_index.predicate = Some("(deleted_at IS NOT NULL)".to_owned());
+        existing_database.indexes.push(existing_index);
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have two instructions to drop/create a new index
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_email".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE UNIQUE INDEX CONCURRENTLY idx_contacts_email ON public.contacts USING btree (\n    \
+             (lower(email)) COLLATE \"C\" text_pattern_ops ASC NULLS LAST\n\
+             )\n\
+             INCLUDE (first_name)\n\
+             WHERE deleted_at IS NULL"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_add_an_index_using_another_access_method() {
+        let log = empty_logger();
+        let brin_index = || {
+            let mut index = partial_expression_index();
+            index.name = "idx_contacts_created_at".to_owned();
+            index.columns = vec![IndexColumn {
+                key: IndexKey::Column("created_at".to_owned()),
+                collation: None,
+                operator_class: None,
+                order: None,
+                null_position: None,
+            }];
+            index.unique = false;
+            index.index_type = Some(IndexType::Brin);
+            index.include = None;
+            index.predicate = None;
+            index.storage_parameters = Some(vec![
+                IndexParameter::PagesPerRange(32),
+                IndexParameter::Autosummarize(true),
+            ]);
+            index
+        };
+        let source_index = brin_index();
+
+        // The same index, but with a storage parameter unknown to us
+        let mut custom_index = brin_index();
+        custom_index.index_type = Some(IndexType::Custom("bloom".to_owned()));
+        custom_index.storage_parameters = Some(vec![IndexParameter::Custom("length".to_owned(), "80".to_owned())]);
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        for index in &[&source_index, &custom_index] {
+            let result = index.generate(
+                &mut change_set,
+                &existing_database,
+                &capabilities,
+                &publish_profile,
+                &log,
+            );
+            assert_that!(result).is_ok();
+        }
+
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE INDEX CONCURRENTLY idx_contacts_created_at ON public.contacts USING brin (\n    \
+             created_at\n\
+             )\n\
+             WITH (pages_per_range=32, autosummarize=on)"
+                .to_owned(),
+        );
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE INDEX CONCURRENTLY idx_contacts_created_at ON public.contacts USING bloom (\n    \
+             created_at\n\
+             )\n\
+             WITH (length='80')"
+                .to_owned(),
+        );
+    }
+
+    fn base_view() -> ast::ViewDefinition {
+        ast::ViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contact_names".to_owned(),
+            },
+            columns: Some(vec!["id".to_owned(), "name".to_owned()]),
+            query: "SELECT id, first_name FROM public.contacts".to_owned(),
+            resolved: None,
+            span: ast::Span::default(),
+        }
+    }
+
+    // The view as extracted from the database, using the output of pg_get_viewdef
+    fn extracted_view() -> ast::ViewDefinition {
+        let mut view = base_view();
+        view.query = "SELECT contacts.id,\n    contacts.first_name AS name\n   FROM contacts".to_owned();
+        view
+    }
+
+    fn resolved_view(columns: &[&str], query: &str) -> Option<ast::ResolvedView> {
+        Some(ast::ResolvedView {
+            columns: columns.iter().map(|c| (*c).to_owned()).collect(),
+            query: query.to_owned(),
+        })
+    }
+
+    fn generate_view_changes(source_view: &ast::ViewDefinition) -> Vec<ChangeInstruction> {
+        let log = empty_logger();
+        let mut existing_database = Package::new();
+        existing_database.views.push(extracted_view());
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = source_view.generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        change_set
+    }
+
+    #[test]
+    fn it_can_add_a_new_view() {
+        let log = empty_logger();
+        let source_view = base_view();
+
+        // Create a database with no views defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new view
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE VIEW public.contact_names (id, name) AS\n\
+             SELECT id, first_name FROM public.contacts"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_view_if_not_modified() {
+        // The database rewrites the query so the view is resolved against the target before comparing
+        let mut source_view = base_view();
+        source_view.resolved = resolved_view(
+            &["id", "name"],
+            "SELECT contacts.id,\n    contacts.first_name AS name\n   FROM contacts",
+        );
+
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_replaces_a_view_that_could_not_be_resolved() {
+        // Without resolving we can't tell whether the rewritten query is the same so the view is replaced
+        let source_view = base_view();
+
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::ModifyView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_view() {
+        let log = empty_logger();
+        let mut source_view = base_view();
+        source_view.query = "SELECT id, last_name FROM public.contacts".to_owned();
+        source_view.resolved = resolved_view(
+            &["id", "name"],
+            "SELECT contacts.id,\n    contacts.last_name AS name\n   FROM contacts",
+        );
+
+        // The columns are the same so we should be able to replace it
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::ModifyView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE OR REPLACE VIEW public.contact_names (id, name) AS\n\
+             SELECT id, last_name FROM public.contacts"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_replace_a_view_when_columns_are_added() {
+        let mut source_view = base_view();
+        source_view.columns = Some(vec!["id".to_owned(), "name".to_owned(), "surname".to_owned()]);
+        source_view.query = "SELECT id, first_name, last_name FROM public.contacts".to_owned();
+        source_view.resolved = resolved_view(
+            &["id", "name", "surname"],
+            "SELECT contacts.id,\n    contacts.first_name AS name,\n    contacts.last_name AS surname\n   \
+             FROM contacts",
+        );
+
+        // New columns at the end of the view can be added by replacing it
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::ModifyView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_can_recreate_a_view_when_the_columns_change() {
+        let log = empty_logger();
+        let mut source_view = base_view();
+        source_view.columns = Some(vec!["id".to_owned(), "first_name".to_owned()]);
+        source_view.resolved = resolved_view(
+            &["id", "first_name"],
+            "SELECT contacts.id,\n    contacts.first_name\n   FROM contacts",
+        );
+
+        // We should have two instructions to drop/create the view
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to("DROP VIEW IF EXISTS public.contact_names".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE VIEW public.contact_names (id, first_name) AS\n\
+             SELECT id, first_name FROM public.contacts"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_recreate_a_view_when_a_column_is_removed() {
+        // The source doesn't specify a column list so the output columns are only known once resolved
+        let mut source_view = base_view();
+        source_view.columns = None;
+        source_view.query = "SELECT id FROM public.contacts".to_owned();
+        source_view.resolved = resolved_view(&["id"], "SELECT contacts.id\n   FROM contacts");
+
+        let change_set = generate_view_changes(&source_view);
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    fn dependent_view() -> ast::ViewDefinition {
+        ast::ViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contact_initials".to_owned(),
+            },
+            columns: None,
+            query: "SELECT id, left(name, 1) AS initial FROM contact_names".to_owned(),
+            resolved: None,
+            span: ast::Span::default(),
+        }
+    }
+
+    fn generate_package_view_changes(source_package: &Package, existing_database: Option<Package>) -> Vec<String> {
+        let log = empty_logger();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: existing_database.is_some(),
+        };
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            source_package,
+            existing_database,
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        match result.unwrap() {
+            Delta(change_set) => change_set
+                .iter()
+                .map(|change| change.to_string())
+                .filter(|change| change.contains("view"))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_creates_views_after_the_views_they_depend_on() {
+        // The dependent view comes first in the project but can only be created once the view it selects from exists
+        let mut source_package = Package::new();
+        source_package.views.push(dependent_view());
+        source_package.views.push(base_view());
+
+        let change_set = generate_package_view_changes(&source_package, None);
+        assert_that!(change_set).is_equal_to(vec![
+            "Add view: public.contact_names".to_owned(),
+            "Add view: public.contact_initials".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn it_recreates_dependent_views_along_with_a_view_whose_columns_change() {
+        let mut source_view = base_view();
+        source_view.columns = Some(vec!["id".to_owned(), "name".to_owned(), "first_name".to_owned()]);
+        source_view.query = "SELECT id, last_name, first_name FROM public.contacts".to_owned();
+        source_view.resolved = resolved_view(
+            &["id", "name", "first_name"],
+            "SELECT contacts.id,\n    contacts.last_name AS name,\n    contacts.first_name\n   FROM contacts",
+        );
+        let mut source_package = Package::new();
+        source_package.views.push(dependent_view());
+        source_package.views.push(source_view);
+
+        // The database holds a view depending on another view with columns that are being replaced
+        let mut existing_view = extracted_view();
+        existing_view.columns = Some(vec!["id".to_owned(), "first_name".to_owned()]);
+        let mut existing_database = Package::new();
+        existing_database.views.push(existing_view);
+        existing_database.views.push(dependent_view());
+
+        // The dependent view has to be dropped first and recreated last
+        let change_set = generate_package_view_changes(&source_package, Some(existing_database));
+        assert_that!(change_set).is_equal_to(vec![
+            "Drop view: public.contact_initials".to_owned(),
+            "Drop view: public.contact_names".to_owned(),
+            "Add view: public.contact_names".to_owned(),
+            "Add view: public.contact_initials".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_view() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        // Create a database with the view already defined.
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.views.push(base_view());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_views = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        // Now allow it - it should be ok now
+        publish_profile.generation_options.drop_views = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // We should have a single instruction to remove the view (first will be use database)
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::DropView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_names".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    fn base_materialized_view() -> ast::MaterializedViewDefinition {
+        ast::MaterializedViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contact_counts".to_owned(),
+            },
+            columns: Some(vec!["first_name".to_owned(), "total".to_owned()]),
+            query: "SELECT first_name, count(*) FROM public.contacts GROUP BY first_name".to_owned(),
+            with_data: false,
+            resolved: None,
+            span: ast::Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_new_materialized_view() {
+        let log = empty_logger();
+        let source_view = base_materialized_view();
+
+        // Create a database with no materialized views defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new materialized view
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddMaterializedView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE MATERIALIZED VIEW public.contact_counts (first_name, total) AS\n\
+             SELECT first_name, count(*) FROM public.contacts GROUP BY first_name\n\
+             WITH NO DATA"
+                .to_owned(),
+        );
+    }
+
+    // The materialized view as extracted from the database once populated, using the output of pg_get_viewdef
+    fn extracted_materialized_view() -> ast::MaterializedViewDefinition {
+        let mut view = base_materialized_view();
+        view.query = "SELECT contacts.first_name,\n    count(*) AS total\n   FROM contacts\n  \
+                      GROUP BY contacts.first_name"
+            .to_owned();
+        view.with_data = true;
+        view
+    }
+
+    fn generate_materialized_view_changes(source_view: &ast::MaterializedViewDefinition) -> Vec<ChangeInstruction> {
+        let log = empty_logger();
+        let mut existing_database = Package::new();
+        existing_database.materialized_views.push(extracted_materialized_view());
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = source_view.generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        change_set
+    }
+
+    #[test]
+    fn it_ignores_a_materialized_view_if_not_modified() {
+        // The database rewrites the query so the view is resolved against the target before comparing
+        let mut source_view = base_materialized_view();
+        source_view.resolved = resolved_view(
+            &["first_name", "total"],
+            "SELECT contacts.first_name,\n    count(*) AS total\n   FROM contacts\n  GROUP BY contacts.first_name",
+        );
+
+        let change_set = generate_materialized_view_changes(&source_view);
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_recreates_a_materialized_view_that_could_not_be_resolved() {
+        // Without resolving we can't tell whether the rewritten query is the same so the view is recreated
+        let source_view = base_materialized_view();
+
+        let change_set = generate_materialized_view_changes(&source_view);
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropMaterializedView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddMaterializedView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_can_recreate_a_modified_materialized_view_along_with_its_indexes() {
+        let log = empty_logger();
+        let index = || IndexDefinition {
+            name: "idx_contact_counts_first_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contact_counts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Column("first_name".to_owned()),
+                collation: None,
+                operator_class: None,
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: None,
+            predicate: None,
+            span: Span::default(),
+        };
+        let mut source_package = Package::new();
+        let mut source_view = base_materialized_view();
+        source_view.query = "SELECT first_name, count(id) FROM public.contacts GROUP BY first_name".to_owned();
+        source_view.resolved = resolved_view(
+            &["first_name", "total"],
+            "SELECT contacts.first_name,\n    count(contacts.id) AS total\n   FROM contacts\n  \
+             GROUP BY contacts.first_name",
+        );
+        source_package.materialized_views.push(source_view);
+        source_package.indexes.push(index());
+
+        // Create a database with the original materialized view and index defined
+        let mut existing_database = Package::new();
+        existing_database.materialized_views.push(extracted_materialized_view());
+        existing_database.indexes.push(index());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // The view is dropped and recreated, which means the index needs to be recreated too
+        assert_that!(change_set).has_length(4);
+        match change_set[1] {
+            ChangeInstruction::DropMaterializedView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[2] {
+            ChangeInstruction::AddMaterializedView(ref view) => {
+                assert_that!(view.name.to_string()).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[3] {
+            ChangeInstruction::AddIndex(ref index, _) => {
+                assert_that!(index.name).is_equal_to("idx_contact_counts_first_name".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("DROP MATERIALIZED VIEW IF EXISTS public.contact_counts".to_owned());
+    }
+
+    #[test]
+    fn it_can_refresh_materialized_views_after_publishing() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.materialized_views.push(base_materialized_view());
+
+        // Create a database with the materialized view already defined
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.materialized_views.push(base_materialized_view());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+
+        // By default we don't refresh
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        assert_that!(change_set).has_length(1);
+
+        // Now turn it on
+        publish_profile.generation_options.refresh_materialized_views = true;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::RefreshMaterializedView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("REFRESH MATERIALIZED VIEW public.contact_counts".to_owned());
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_materialized_view() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        // Create a database with the materialized view already defined.
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.materialized_views.push(base_materialized_view());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_materialized_views = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        // Now allow it - it should be ok now
+        publish_profile.generation_options.drop_materialized_views = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // We should have a single instruction to remove the view (first will be use database)
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::DropMaterializedView(ref view_name) => {
+                assert_that!(*view_name).is_equal_to("public.contact_counts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    fn base_trigger() -> ast::TriggerDefinition {
+        ast::TriggerDefinition {
+            name: "audit_contacts".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            timing: TriggerTiming::After,
+            events: vec![TriggerEvent::Update(None), TriggerEvent::Insert],
+            level: TriggerLevel::Row,
+            condition: Some("NEW.status <> 'void'".to_owned()),
+            function: ObjectName {
+                schema: Some("audit".to_owned()),
+                name: "log_change".to_owned(),
+            },
+            arguments: vec!["contacts".to_owned()],
+            span: ast::Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_new_trigger() {
+        let log = empty_logger();
+        let source_trigger = base_trigger();
+
+        // Create a database with no triggers defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have a single instruction to create a new trigger
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddTrigger(ref trigger) => {
+                assert_that!(trigger.name).is_equal_to("audit_contacts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE TRIGGER audit_contacts AFTER UPDATE OR INSERT\n\
+             ON public.contacts\n\
+             FOR EACH ROW\n\
+             WHEN (NEW.status <> 'void')\n\
+             EXECUTE PROCEDURE audit.log_change('contacts')"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_trigger_if_not_modified() {
+        let log = empty_logger();
+        let source_trigger = base_trigger();
+
+        // Create a database with the same trigger as it would be described by PostgreSQL
+        let mut existing_database = Package::new();
+        let mut existing_trigger = base_trigger();
+        existing_trigger.events = vec![TriggerEvent::Insert, TriggerEvent::Update(None)];
+        existing_trigger.condition = Some("(new.status <> 'void'::text)".to_owned());
+        existing_database.triggers.push(existing_trigger);
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_recreate_a_modified_trigger() {
+        let log = empty_logger();
+        let mut source_trigger = base_trigger();
+        source_trigger.timing = TriggerTiming::Before;
+
+        // Create a database with the original trigger defined
+        let mut existing_database = Package::new();
+        existing_database.triggers.push(base_trigger());
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+
+        // We should have two instructions to drop/create the trigger
+        assert_that!(change_set).has_length(2);
+        match change_set[0] {
+            ChangeInstruction::DropTrigger(ref name, ref table) => {
+                assert_that!(*name).is_equal_to("audit_contacts".to_owned());
+                assert_that!(*table).is_equal_to("public.contacts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddTrigger(ref trigger) => {
+                assert_that!(trigger.timing).is_equal_to(TriggerTiming::Before);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("DROP TRIGGER IF EXISTS audit_contacts ON public.contacts".to_owned());
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_trigger() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        // Create a database with the trigger already defined.
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.triggers.push(base_trigger());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_triggers = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        // Now allow it - it should be ok now
+        publish_profile.generation_options.drop_triggers = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // We should have a single instruction to remove the trigger (first will be use database)
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::DropTrigger(ref name, ref table) => {
+                assert_that!(*name).is_equal_to("audit_contacts".to_owned());
+                assert_that!(*table).is_equal_to("public.contacts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    fn contacts_privilege(privilege: Privilege, grantee: &str) -> PrivilegeDefinition {
+        PrivilegeDefinition {
+            action: PrivilegeAction::Grant,
+            object: PrivilegeObject::Table(ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            }),
+            privilege,
+            grantee: grantee.to_owned(),
+            grant_option: false,
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_can_grant_missing_privileges() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.privileges.push(contacts_privilege(Privilege::Select, "reporting"));
+        source_package.privileges.push(contacts_privilege(Privilege::Insert, "reporting"));
+        source_package.privileges.push(contacts_privilege(Privilege::Update, "reporting"));
+        source_package.privileges.push(PrivilegeDefinition {
+            action: PrivilegeAction::Grant,
+            object: PrivilegeObject::Role("staff".to_owned()),
+            privilege: Privilege::Member,
+            grantee: "reporting".to_owned(),
+            grant_option: true,
+            span: Span::default(),
+        });
+
+        // Create a database where the select privilege has already been granted
+        let mut existing_database = Package::new();
+        existing_database.privileges.push(contacts_privilege(Privilege::Select, "reporting"));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        for privilege in &source_package.privileges {
+            let result = privilege.generate(&mut change_set, &existing_database, &capabilities, &publish_profile, &log);
+            assert_that!(result).is_ok();
+        }
+
+        // The table privileges are combined into a single instruction
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("GRANT INSERT, UPDATE ON TABLE public.contacts TO reporting".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to("GRANT staff TO reporting WITH ADMIN OPTION".to_owned());
+    }
+
+    #[test]
+    fn it_can_grant_privileges_on_an_overloaded_function() {
+        let log = empty_logger();
+        let function_privilege = |sql_type: Option<SimpleSqlType>| PrivilegeDefinition {
+            action: PrivilegeAction::Grant,
+            object: PrivilegeObject::Function(
+                ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "fn_audit".to_owned(),
+                },
+                sql_type.map(|sql_type| vec![SqlType::Simple(sql_type, None)]),
+            ),
+            privilege: Privilege::Execute,
+            grantee: "reporting".to_owned(),
+            grant_option: false,
+            span: Span::default(),
+        };
+
+        // Create a database where execute has been granted on a different overload
+        let mut existing_database = Package::new();
+        existing_database
+            .privileges
+            .push(function_privilege(Some(SimpleSqlType::Text)));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let source_privilege = function_privilege(Some(SimpleSqlType::Integer));
+        let mut change_set = Vec::new();
+        let result = (&source_privilege).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("GRANT EXECUTE ON FUNCTION public.fn_audit(int) TO reporting".to_owned());
+
+        // A function named without arguments matches the existing grant
+        let source_privilege = function_privilege(None);
+        let mut change_set = Vec::new();
+        let result = (&source_privilege).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_grant_default_privileges() {
+        let log = empty_logger();
+        let source_privileges = PrivilegeDefinition::expand(
+            PrivilegeAction::Grant,
+            Some(vec![Privilege::Select, Privilege::Usage]),
+            vec![PrivilegeObject::Default(DefaultPrivilegeScope {
+                role: Some("admin".to_owned()),
+                schema: Some("public".to_owned()),
+                kind: DefaultPrivilegeKind::Sequences,
+            })],
+            vec!["reporting".to_owned()],
+            false,
+        )
+        .unwrap();
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        for privilege in &source_privileges {
+            let result = privilege.generate(&mut change_set, &existing_database, &capabilities, &publish_profile, &log);
+            assert_that!(result).is_ok();
+        }
+
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER DEFAULT PRIVILEGES FOR ROLE admin IN SCHEMA public GRANT SELECT, USAGE ON SEQUENCES TO reporting"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_revoke_an_explicitly_revoked_privilege() {
+        let log = empty_logger();
+        let mut source_privilege = contacts_privilege(Privilege::Delete, "PUBLIC");
+        source_privilege.action = PrivilegeAction::Revoke;
+
+        let mut existing_database = Package::new();
+        existing_database.privileges.push(contacts_privilege(Privilege::Delete, "PUBLIC"));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_privilege).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("REVOKE DELETE ON TABLE public.contacts FROM PUBLIC".to_owned());
+    }
+
+    #[test]
+    fn it_can_revoke_privileges_missing_from_the_source() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.privileges.push(contacts_privilege(Privilege::Select, "reporting"));
+
+        // Create a database with additional privileges and an unmanaged role membership
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.privileges.push(contacts_privilege(Privilege::Select, "reporting"));
+            existing_database.privileges.push(contacts_privilege(Privilege::Truncate, "reporting"));
+            existing_database.privileges.push(PrivilegeDefinition {
+                action: PrivilegeAction::Grant,
+                object: PrivilegeObject::Role("staff".to_owned()),
+                privilege: Privilege::Member,
+                grantee: "reporting".to_owned(),
+                grant_option: false,
+                span: Span::default(),
+            });
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+
+        // By default additional privileges are left alone
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        assert_that!(change_set).has_length(1);
+
+        // Make sure an error is generated when requested
+        publish_profile.generation_options.revoke_privileges = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        // Now allow it - only the table privilege should be revoked (first will be use database)
+        publish_profile.generation_options.revoke_privileges = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("REVOKE TRUNCATE ON TABLE public.contacts FROM reporting".to_owned());
+    }
+
+    fn base_policy() -> ast::PolicyDefinition {
+        ast::PolicyDefinition {
+            name: "tenant_isolation".to_owned(),
+            table: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            permissive: true,
+            command: PolicyCommand::All,
+            roles: vec!["PUBLIC".to_owned()],
+            using: Some("tenant_id = current_setting('app.tenant')::int".to_owned()),
+            with_check: None,
+            span: ast::Span::default(),
+        }
+    }
+
+    fn policy_db(policy: ast::PolicyDefinition) -> Package {
+        let mut existing_database = Package::new();
+        let mut table = base_table();
+        table.row_security = true;
+        table.policies.push(policy);
+        existing_database.tables.push(table);
+        existing_database
+    }
+
+    #[test]
+    fn it_can_enable_row_level_security_on_a_table() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.row_security = true;
+        source_table.force_row_security = true;
+
+        // Create a database with the table already defined without row level security
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ENABLE ROW LEVEL SECURITY".to_owned());
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts FORCE ROW LEVEL SECURITY".to_owned());
+    }
+
+    #[test]
+    fn it_can_add_a_new_policy() {
+        let log = empty_logger();
+        let mut source_policy = base_policy();
+        source_policy.permissive = false;
+        source_policy.command = PolicyCommand::Update;
+        source_policy.roles = vec!["app_user".to_owned(), "reporting".to_owned()];
+        source_policy.with_check = Some("tenant_id > 0".to_owned());
+
+        // Create a database with no policies defined
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        match change_set[0] {
+            ChangeInstruction::AddPolicy(policy) => {
+                assert_that!(policy.name).is_equal_to("tenant_isolation".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE POLICY tenant_isolation ON my.contacts\n\
+             AS RESTRICTIVE\n\
+             FOR UPDATE\n\
+             TO app_user, reporting\n\
+             USING (tenant_id = current_setting('app.tenant')::int)\n\
+             WITH CHECK (tenant_id > 0)"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_policy_if_not_modified() {
+        let log = empty_logger();
+        let source_policy = base_policy();
+
+        // Create a database with the same policy as it would be described by PostgreSQL
+        let mut existing_policy = base_policy();
+        existing_policy.using = Some("(tenant_id = (current_setting('app.tenant'::text))::integer)".to_owned());
+        let existing_database = policy_db(existing_policy);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).is_empty();
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_policy() {
+        let log = empty_logger();
+        let mut source_policy = base_policy();
+        source_policy.roles = vec!["app_user".to_owned()];
+
+        let existing_database = policy_db(base_policy());
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "ALTER POLICY tenant_isolation ON my.contacts TO app_user \
+             USING (tenant_id = current_setting('app.tenant')::int)"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_recreate_a_policy_when_the_command_changes() {
+        let log = empty_logger();
+        let mut source_policy = base_policy();
+        source_policy.command = PolicyCommand::Select;
+
+        let existing_database = policy_db(base_policy());
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("DROP POLICY IF EXISTS tenant_isolation ON my.contacts".to_owned());
+        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
+            "CREATE POLICY tenant_isolation ON my.contacts\n\
+             FOR SELECT\n\
+             TO PUBLIC\n\
+             USING (tenant_id = current_setting('app.tenant')::int)"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_policy() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        let mut table = base_table();
+        table.row_security = true;
+        source_package.tables.push(table);
+
+        // Create a database with the policy already defined
+        fn existing_db() -> Option<Package> {
+            Some(policy_db(base_policy()))
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_policies = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_err();
+
+        // Now allow it - it should be ok now
+        publish_profile.generation_options.drop_policies = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // We should have a single instruction to remove the policy (first will be use database)
+        assert_that!(change_set).has_length(2);
+        match change_set[1] {
+            ChangeInstruction::DropPolicy(ref name, ref table) => {
+                assert_that!(*name).is_equal_to("tenant_isolation".to_owned());
+                assert_that!(*table).is_equal_to("my.contacts".to_owned());
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    fn partitioned_table() -> ast::TableDefinition {
+        let mut table = base_table();
+        table.partition_by = Some(PartitionKey {
+            strategy: PartitionStrategy::List,
+            key: "company_id".to_owned(),
+        });
+        table
+    }
+
+    fn partition_table() -> ast::TableDefinition {
+        ast::TableDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts_acme".to_owned(),
+            },
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: Some(TablePartition {
+                parent: ObjectName {
+                    schema: Some("my".to_owned()),
+                    name: "contacts".to_owned(),
+                },
+                bound: PartitionBound::List("1, 2".to_owned()),
+            }),
+            span: ast::Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_partitioned_table_with_a_partition() {
+        let log = empty_logger();
+        let source_table = partitioned_table();
+        let source_partition = partition_table();
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        let result = (&source_partition).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
+            "CREATE TABLE my.contacts (\n    \
+             id serial NOT NULL PRIMARY KEY,\n    \
+             company_id bigint NOT NULL,\n    \
+             first_name varchar(100) NOT NULL\n\
+             )\n\
+             PARTITION BY LIST (company_id)"
+                .to_owned(),
+        );
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("CREATE TABLE my.contacts_acme PARTITION OF my.contacts FOR VALUES IN (1, 2)".to_owned());
+    }
+
+    #[test]
+    fn it_can_attach_an_existing_table_as_a_partition() {
+        let log = empty_logger();
+        let source_partition = partition_table();
+
+        // The table exists but is not yet a partition
+        let mut existing_database = Package::new();
+        existing_database.tables.push(partitioned_table());
+        let mut existing_table = partition_table();
+        existing_table.columns = base_table().columns;
+        existing_table.partition_of = None;
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_partition).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2234,65 +7385,66 @@ mod tests {
         );
         assert_that!(result).is_ok();
 
-        // We should have a single instruction to remove the constraint
+        // Partitions take their columns from the parent so none are dropped
         assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
         assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id".to_owned());
+            .is_equal_to("ALTER TABLE my.contacts ATTACH PARTITION my.contacts_acme FOR VALUES IN (1, 2)".to_owned());
     }
 
     #[test]
-    fn it_can_modify_an_existing_primary_key() {
+    fn it_can_change_the_bound_of_a_partition() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
+        let mut source_partition = partition_table();
+        source_partition.partition_of.as_mut().unwrap().bound = PartitionBound::Default;
 
-        // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into(), "company_id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
-        existing_database.tables.push(existing_table);
+        existing_database.tables.push(partitioned_table());
+        existing_database.tables.push(partition_table());
         let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
+            server_version: Semver::new(10, 0, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_partition).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert_that!(change_set).is_empty();
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(2);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts DETACH PARTITION my.contacts_acme".to_owned());
+        assert_that!(change_set[1].to_sql(&log))
+            .is_equal_to("ALTER TABLE my.contacts ATTACH PARTITION my.contacts_acme DEFAULT".to_owned());
+    }
 
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
-        }
-        .generate(
+    #[test]
+    fn it_ignores_partition_bound_formatting() {
+        let log = empty_logger();
+        let mut source_partition = partition_table();
+        source_partition.partition_of.as_mut().unwrap().bound =
+            PartitionBound::Hash("MODULUS 4, REMAINDER 1".to_owned());
+
+        // PostgreSQL describes hash bounds in lowercase
+        let mut existing_database = Package::new();
+        let mut existing_partition = partition_table();
+        existing_partition.partition_of.as_mut().unwrap().bound =
+            PartitionBound::Hash("modulus 4, remainder 1".to_owned());
+        existing_database.tables.push(existing_partition);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_partition).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2300,91 +7452,79 @@ mod tests {
             &log,
         );
         assert_that!(result).is_ok();
-
-        // Primary keys cannot be altered, so we drop/create
-        assert_that!(change_set).has_length(2);
-        match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        match change_set[1] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                match *constraint {
-                    TableConstraint::Primary {
-                        name,
-                        columns,
-                        parameters,
-                    } => {
-                        assert_that!(*name).is_equal_to("pk_my_contacts_id".to_owned());
-                        assert_that!(*columns).has_length(1);
-                        assert_that!(columns.iter()).contains("id".to_owned());
-                        assert_that!(*parameters).is_some().has_length(1);
-                    }
-                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id".to_owned());
-        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
-            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
-                .to_owned(),
-        );
+        assert_that!(change_set).is_empty();
     }
 
     #[test]
-    fn it_can_add_a_new_foreign_key() {
+    fn it_cannot_change_the_partition_key_of_a_table() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
+        let mut source_table = partitioned_table();
+        source_table.partition_by = Some(PartitionKey {
+            strategy: PartitionStrategy::Hash,
+            key: "id".to_owned(),
         });
 
-        // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
+        existing_database.tables.push(partitioned_table());
         let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
+            server_version: Semver::new(10, 0, None),
             extensions: Vec::new(),
             database_exists: true,
         };
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_table).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert_that!(change_set).is_empty();
+        assert_that!(result).is_err();
+        let err = result.unwrap_err();
+        assert_that!(format!("{}", err)).is_equal_to(
+            "Couldn't publish database due to an invalid operation: \
+             Unable to change how an existing table is partitioned: my.contacts"
+                .to_owned(),
+        );
+    }
 
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
-        }
-        .generate(
+    #[test]
+    fn it_does_not_index_a_partitioned_table_concurrently() {
+        let log = empty_logger();
+        let source_index = IndexDefinition {
+            name: "idx_contacts_first_name".to_owned(),
+            table: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Column("first_name".to_owned()),
+                collation: None,
+                operator_class: None,
+                order: None,
+                null_position: None,
+            }],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: None,
+            predicate: None,
+            span: Span::default(),
+        };
+
+        let mut existing_database = Package::new();
+        existing_database.tables.push(partitioned_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2392,81 +7532,50 @@ mod tests {
             &log,
         );
         assert_that!(result).is_ok();
-
-        // We should have a single instruction to create a new constraint
         assert_that!(change_set).has_length(1);
-        match change_set[0] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                match *constraint {
-                    TableConstraint::Foreign {
-                        name,
-                        columns,
-                        ref_table,
-                        ref_columns,
-                        match_type,
-                        events,
-                    } => {
-                        assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
-                        assert_that!(*columns).has_length(1);
-                        assert_that!(columns.iter()).contains("company_id".to_owned());
-                        assert_that!(ref_table.to_string()).is_equal_to("my.companies".to_owned());
-                        assert_that!(*ref_columns).has_length(1);
-                        assert_that!(ref_columns.iter()).contains("id".to_owned());
-                        assert_that!(*match_type)
-                            .is_some()
-                            .is_equal_to(ForeignConstraintMatchType::Simple);
-                        assert_that!(*events).is_some().has_length(2); // We test this further below
-                    }
-                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("CREATE INDEX idx_contacts_first_name ON my.contacts USING btree (first_name)".to_owned());
+    }
 
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
-            "ALTER TABLE my.contacts\n\
-             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
-             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE CASCADE ON DELETE NO ACTION"
-                .to_owned(),
-        );
+    fn base_sequence() -> ast::SequenceDefinition {
+        ast::SequenceDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "order_number".to_owned(),
+            },
+            increment: Some(5),
+            min_value: Some(100),
+            max_value: None,
+            start: Some(100),
+            cache: None,
+            cycle: false,
+            owned_by: Some(ast::SequenceOwner {
+                table: ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "orders".to_owned(),
+                },
+                column: "number".to_owned(),
+            }),
+            span: ast::Span::default(),
+        }
     }
 
     #[test]
-    fn it_can_remove_an_existing_foreign_key() {
+    fn it_can_add_a_new_sequence() {
         let log = empty_logger();
-        let source_table = base_table();
+        let source_sequence = base_sequence();
 
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
-        existing_database.tables.push(existing_table);
+        // Create a database with no sequences defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        // This change_set gets generated at the table level
-        let result = (&source_table).generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2475,81 +7584,51 @@ mod tests {
         );
         assert_that!(result).is_ok();
 
-        // We should have a single instruction to remove a constraint
+        // We should have a single instruction to create a new sequence
         assert_that!(change_set).has_length(1);
         match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
+            ChangeInstruction::AddSequence(sequence) => {
+                assert_that!(sequence.name.to_string()).is_equal_to("public.order_number".to_owned());
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
 
-        // Check the SQL generation
+        // Ownership is set separately once the table exists
         assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies".to_owned());
+            .is_equal_to("CREATE SEQUENCE public.order_number INCREMENT BY 5 MINVALUE 100 START WITH 100".to_owned());
     }
 
     #[test]
-    fn it_can_modify_an_existing_foreign_key() {
+    fn it_ignores_a_sequence_if_not_modified() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::NoAction),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
+        let source_sequence = base_sequence();
 
-        // Create a database with the base table already defined.
+        // Create a database with the same sequence as it would be described by PostgreSQL
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
-        existing_database.tables.push(existing_table);
+        let mut existing_sequence = base_sequence();
+        existing_sequence.cache = Some(1);
+        existing_sequence.max_value = None;
+        existing_database.sequences.push(existing_sequence);
+        let publish_profile = PublishProfile::default();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
+        assert_that!(result).is_ok();
         assert_that!(change_set).is_empty();
 
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
+        let result = SequenceOwnership {
+            sequence: &source_sequence,
         }
         .generate(
             &mut change_set,
@@ -2559,77 +7638,20 @@ mod tests {
             &log,
         );
         assert_that!(result).is_ok();
-
-        // Primary keys cannot be altered, so we drop/create
-        assert_that!(change_set).has_length(2);
-        match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        match change_set[1] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_that!(table.name.to_string()).is_equal_to("my.contacts".to_owned());
-                match *constraint {
-                    TableConstraint::Foreign {
-                        name,
-                        columns,
-                        ref_table,
-                        ref_columns,
-                        match_type,
-                        events,
-                    } => {
-                        assert_that!(*name).is_equal_to("fk_my_contacts_my_companies".to_owned());
-                        assert_that!(*columns).has_length(1);
-                        assert_that!(columns.iter()).contains("company_id".to_owned());
-                        assert_that!(ref_table.to_string()).is_equal_to("my.companies".to_owned());
-                        assert_that!(*ref_columns).has_length(1);
-                        assert_that!(ref_columns.iter()).contains("id".to_owned());
-                        assert_that!(*match_type)
-                            .is_some()
-                            .is_equal_to(ForeignConstraintMatchType::Simple);
-                        assert_that!(*events).is_some().has_length(2); // We test this further below
-                    }
-                    unxpected => panic!("Unexpected constraint: {:?}", unxpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies".to_owned());
-        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
-            "ALTER TABLE my.contacts\n\
-             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
-             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION"
-                .to_owned(),
-        );
+        assert_that!(change_set).is_empty();
     }
 
     #[test]
-    fn it_can_add_a_new_index() {
+    fn it_can_modify_an_existing_sequence() {
         let log = empty_logger();
-        let source_index = IndexDefinition {
-            name: "idx_contacts_first_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![IndexColumn {
-                name: "first_name".to_owned(),
-                order: Some(IndexOrder::Ascending),
-                null_position: Some(IndexPosition::Last),
-            }],
-            unique: true,
-            index_type: Some(IndexType::BTree),
-            storage_parameters: None,
-        };
+        let mut source_sequence = base_sequence();
+        source_sequence.increment = Some(2);
+        source_sequence.min_value = None;
+        source_sequence.cycle = true;
 
-        // Create a database with no indexes defined.
-        let existing_database = Package::new();
+        // Create a database with the original sequence defined
+        let mut existing_database = Package::new();
+        existing_database.sequences.push(base_sequence());
         let publish_profile = PublishProfile::default();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
@@ -2638,7 +7660,7 @@ mod tests {
         };
 
         let mut change_set = Vec::new();
-        let result = (&source_index).generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2647,48 +7669,60 @@ mod tests {
         );
         assert_that!(result).is_ok();
 
-        // We should have a single instruction to create a new index
+        // We should have a single instruction altering only the changed options
         assert_that!(change_set).has_length(1);
         match change_set[0] {
-            ChangeInstruction::AddIndex(ref index, concurrently) => {
-                assert_that!(index.name).is_equal_to("idx_contacts_first_name".to_owned());
-                assert_that!(index.table.to_string()).is_equal_to("public.contacts".to_owned());
-                assert_that!(concurrently).is_true();
+            ChangeInstruction::ModifySequence(_, ref options) => {
+                assert_that!(*options).has_length(3);
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER SEQUENCE public.order_number INCREMENT BY 2 NO MINVALUE CYCLE".to_owned());
+    }
 
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log)).is_equal_to(
-            "CREATE UNIQUE INDEX CONCURRENTLY idx_contacts_first_name \
-             ON public.contacts USING btree (first_name ASC NULLS LAST)"
-                .to_owned(),
+    #[test]
+    fn it_can_modify_the_owner_of_a_sequence() {
+        let log = empty_logger();
+        let mut source_sequence = base_sequence();
+        source_sequence.owned_by = None;
+
+        // Create a database with the original sequence defined
+        let mut existing_database = Package::new();
+        existing_database.sequences.push(base_sequence());
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = SequenceOwnership {
+            sequence: &source_sequence,
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
         );
+        assert_that!(result).is_ok();
+        assert_that!(change_set).has_length(1);
+        assert_that!(change_set[0].to_sql(&log))
+            .is_equal_to("ALTER SEQUENCE public.order_number OWNED BY NONE".to_owned());
     }
 
     #[test]
-    fn it_can_remove_an_existing_index() {
+    fn it_can_remove_an_existing_sequence() {
         let log = empty_logger();
         let source_package = Package::new();
 
-        // Create a database with the index already defined.
+        // Create a database with the sequence already defined.
         fn existing_db() -> Option<Package> {
             let mut existing_database = Package::new();
-            existing_database.indexes.push(IndexDefinition {
-                name: "idx_contacts_first_name".to_owned(),
-                table: ObjectName {
-                    schema: Some("public".to_owned()),
-                    name: "contacts".to_owned(),
-                },
-                columns: vec![IndexColumn {
-                    name: "first_name".to_owned(),
-                    order: Some(IndexOrder::Ascending),
-                    null_position: Some(IndexPosition::Last),
-                }],
-                unique: true,
-                index_type: Some(IndexType::BTree),
-                storage_parameters: None,
-            });
+            existing_database.sequences.push(base_sequence());
             Some(existing_database)
         }
         let capabilities = Capabilities {
@@ -2697,7 +7731,7 @@ mod tests {
             database_exists: true,
         };
         let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_indexes = Toggle::Error;
+        publish_profile.generation_options.drop_sequences = Toggle::Error;
 
         // First of all, make sure an error is generated
         let result = Delta::generate(
@@ -2709,14 +7743,9 @@ mod tests {
             &publish_profile,
         );
         assert_that!(result).is_err();
-        // Now run it again - it should be ok now
-        let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
-            extensions: Vec::new(),
-            database_exists: true,
-        };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_indexes = Toggle::Allow;
+
+        // Now allow it - it should be ok now
+        publish_profile.generation_options.drop_sequences = Toggle::Allow;
         let result = Delta::generate(
             &log,
             &source_package,
@@ -2730,107 +7759,14 @@ mod tests {
             Delta(c) => c,
         };
 
-        // We should have a single instruction to remove an index (first will be use database)
-        assert_that!(change_set).has_length(2);
-        match change_set[1] {
-            ChangeInstruction::DropIndex(ref index, concurrently) => {
-                assert_that!(*index).is_equal_to("public.idx_contacts_first_name".to_owned());
-                assert_that!(concurrently).is_true();
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_that!(change_set[1].to_sql(&log))
-            .is_equal_to("DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_first_name".to_owned());
-    }
-
-    #[test]
-    fn it_can_modify_an_existing_index() {
-        let log = empty_logger();
-        let source_index = IndexDefinition {
-            name: "idx_contacts_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![
-                IndexColumn {
-                    name: "first_name".to_owned(),
-                    order: Some(IndexOrder::Ascending),
-                    null_position: Some(IndexPosition::Last),
-                },
-                IndexColumn {
-                    name: "last_name".to_owned(),
-                    order: Some(IndexOrder::Descending),
-                    null_position: Some(IndexPosition::First),
-                },
-            ],
-            unique: false,
-            index_type: Some(IndexType::BTree),
-            storage_parameters: None,
-        };
-
-        // Create a database with a single index defined.
-        let mut existing_database = Package::new();
-        existing_database.indexes.push(IndexDefinition {
-            name: "idx_contacts_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![IndexColumn {
-                name: "first_name".to_owned(),
-                order: Some(IndexOrder::Ascending),
-                null_position: Some(IndexPosition::Last),
-            }],
-            unique: true,
-            index_type: Some(IndexType::BTree),
-            storage_parameters: None,
-        });
-        let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
-            extensions: Vec::new(),
-            database_exists: true,
-        };
-        let publish_profile = PublishProfile::default();
-
-        let mut change_set = Vec::new();
-        let result = (&source_index).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
-            &log,
-        );
-        assert_that!(result).is_ok();
-
-        // We should have two instructions to drop/create a new index
+        // We should have a single instruction to remove the sequence (first will be use database)
         assert_that!(change_set).has_length(2);
-        match change_set[0] {
-            ChangeInstruction::DropIndex(ref index_name, concurrently) => {
-                assert_that!(*index_name).is_equal_to("public.idx_contacts_name".to_owned());
-                assert_that!(concurrently).is_true();
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
         match change_set[1] {
-            ChangeInstruction::AddIndex(ref index, concurrently) => {
-                assert_that!(index.name).is_equal_to("idx_contacts_name".to_owned());
-                assert_that!(index.table.to_string()).is_equal_to("public.contacts".to_owned());
-                assert_that!(concurrently).is_true();
+            ChangeInstruction::DropSequence(ref name) => {
+                assert_that!(*name).is_equal_to("public.order_number".to_owned());
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
-
-        // Check the SQL generation
-        assert_that!(change_set[0].to_sql(&log))
-            .is_equal_to("DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_name".to_owned());
-        assert_that!(change_set[1].to_sql(&log)).is_equal_to(
-            "CREATE INDEX CONCURRENTLY idx_contacts_name \
-             ON public.contacts USING btree (first_name ASC NULLS LAST, last_name DESC NULLS FIRST)"
-                .to_owned(),
-        );
     }
 
     #[test]
@@ -3221,4 +8157,69 @@ mod tests {
                 .to_owned();
         assert_that!(format!("{}", err)).is_equal_to(&expect);
     }
+
+    fn contacts_comment(object: CommentObject, comment: &str) -> CommentDefinition {
+        CommentDefinition {
+            object,
+            comment: Some(comment.to_owned()),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn it_only_changes_comments_when_comments_change() {
+        let log = empty_logger();
+        let contacts = || ObjectName {
+            schema: Some("my".to_owned()),
+            name: "contacts".to_owned(),
+        };
+        let column = |name: &str| CommentObject::Column(contacts(), name.to_owned());
+        let mut source_package = Package::new();
+        source_package.tables.push(base_table());
+        source_package.comments = vec![
+            contacts_comment(CommentObject::Table(contacts()), "People we've met"),
+            contacts_comment(column("id"), "Identifier"),
+            contacts_comment(column("first_name"), "Given name"),
+        ];
+
+        // The table comment has changed, the id comment is unchanged and the company comment is no longer wanted
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        existing_database.comments = vec![
+            contacts_comment(CommentObject::Table(contacts()), "People"),
+            contacts_comment(column("id"), "Identifier"),
+            contacts_comment(column("company_id"), "Employer"),
+        ];
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert_that!(result).is_ok();
+        let change_set = match result.unwrap() {
+            Delta(c) => c,
+        };
+
+        // The table itself is left alone
+        let sql = change_set
+            .iter()
+            .skip(1)
+            .map(|change| change.to_sql(&log))
+            .collect::<Vec<_>>();
+        assert_that!(sql).is_equal_to(vec![
+            "COMMENT ON TABLE my.contacts IS 'People we''ve met'".to_owned(),
+            "COMMENT ON COLUMN my.contacts.first_name IS 'Given name'".to_owned(),
+            "COMMENT ON COLUMN my.contacts.company_id IS NULL".to_owned(),
+        ]);
+    }
 }
@@ -0,0 +1,872 @@
+//! Compares SQL expressions written in a project against the way PostgreSQL describes them.
+//!
+//! PostgreSQL brackets most sub-expressions, casts every literal and rewrites `IN` lists as array comparisons.
+//! Rather than stripping these from the text (which would lose the meaning of brackets and casts that were
+//! written), both expressions are parsed into a tree using PostgreSQL's precedence rules. Grouping is then
+//! implied by the shape of the tree, and only the decorations PostgreSQL adds are ignored:
+//!
+//! * casts on literals, and string literals holding a number,
+//! * casts the target adds to a sub-expression that the source left uncast (implicit coercions),
+//! * `x IN (a, b)` as `x = ANY (ARRAY[a, b])`, along with the other operators PostgreSQL rewrites.
+//!
+//! A cast written in the source which PostgreSQL drops as redundant is reported as a change, since it can't be
+//! told apart from a cast that was removed. Text which can't be parsed is compared ignoring case and whitespace.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::sql::ast::{AnyValue, Expression, ObjectName};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Lexeme {
+    Word(String), // Unquoted identifiers and keywords, lower cased
+    Quoted(String),
+    String(String),
+    Number(String),
+    Operator(String),
+    Open,
+    Close,
+    OpenSquare,
+    CloseSquare,
+    Comma,
+    Period,
+    Cast,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Term {
+    Literal(String), // Numbers unquoted, anything else quoted
+    Name(String),
+    Array(Vec<Term>),
+    Row(Vec<Term>),
+    Call(String, Vec<Term>),
+    Cast(Box<Term>, String),
+    Subscript(Box<Term>, Vec<Term>),
+    Unary(String, Box<Term>),
+    Postfix(Box<Term>, String),
+    Binary(Box<Term>, String, Box<Term>),
+    Sequence(Vec<Term>), // Anything else written one term after another, e.g. CASE WHEN ... END
+}
+
+// Keywords that are never a function name, even when followed by a bracket
+const NOT_CALLABLE: &[&str] = &[
+    "all", "and", "any", "between", "case", "else", "end", "from", "ilike", "in", "is", "like", "not", "or", "similar",
+    "some", "then", "to", "when",
+];
+
+// Type names that can prefix a string literal, e.g. interval '1 day'
+const LITERAL_TYPES: &[&str] = &[
+    "bigint",
+    "boolean",
+    "date",
+    "integer",
+    "interval",
+    "json",
+    "jsonb",
+    "numeric",
+    "text",
+    "time",
+    "timestamp",
+    "timestamptz",
+    "uuid",
+];
+
+/// Whether the source expression means the same as the target expression as described by PostgreSQL
+pub fn same_expression(source: &str, target: &str) -> bool {
+    match (parse(source), parse(target)) {
+        (Some(src), Some(tgt)) => same_term(&src, &tgt),
+        _ => simplify(source) == simplify(target),
+    }
+}
+
+/// Whether a parsed source value (e.g. a default) means the same as the target value as described by PostgreSQL
+pub fn same_value(source: &AnyValue, target: &AnyValue) -> bool {
+    same_term(&value_term(source), &value_term(target))
+}
+
+/// The names referred to within a query, along with the schema where one was written
+pub fn referenced_names(query: &str) -> Vec<(Option<String>, String)> {
+    let mut names = Vec::new();
+    let lexemes = match tokenize(query) {
+        Some(lexemes) => lexemes,
+        None => return names,
+    };
+    let identifier = |lexeme: Option<&Lexeme>| match lexeme {
+        Some(Lexeme::Word(word)) => Some(word.to_owned()),
+        Some(Lexeme::Quoted(name)) if name.starts_with('"') => Some(name[1..name.len() - 1].replace("\"\"", "\"")),
+        Some(Lexeme::Quoted(name)) => Some(name.to_owned()),
+        _ => None,
+    };
+    let mut position = 0;
+    while position < lexemes.len() {
+        if let Some(first) = identifier(lexemes.get(position)) {
+            if lexemes.get(position + 1) == Some(&Lexeme::Period) {
+                if let Some(second) = identifier(lexemes.get(position + 2)) {
+                    names.push((Some(first), second));
+                    position += 3;
+                    continue;
+                }
+            }
+            names.push((None, first));
+        }
+        position += 1;
+    }
+    names
+}
+
+// The parsed tree is already grouped, so brackets are dropped and literals are normalized as when parsing text
+fn value_term(value: &AnyValue) -> Term {
+    match *value {
+        AnyValue::Array(ref values, _) => Term::Array(values.iter().map(value_term).collect()),
+        AnyValue::Boolean(value, _) => Term::Literal(value.to_string()),
+        AnyValue::Decimal(ref value, _) => literal(&value.to_string()),
+        AnyValue::Integer(value, _) => literal(&value.to_string()),
+        AnyValue::String(ref value, _) => literal(value),
+        AnyValue::Null(_) => Term::Literal("null".to_owned()),
+        AnyValue::NextVal(ref sequence) => Term::Call("nextval".to_owned(), vec![literal(&qualified(sequence))]),
+        AnyValue::Expression(ref expression) => expression_term(expression),
+    }
+}
+
+fn expression_term(expression: &Expression) -> Term {
+    match *expression {
+        Expression::Value(ref value) => value_term(value),
+        Expression::Keyword(ref keyword) => Term::Name(keyword.to_lowercase()),
+        Expression::FunctionCall(ref name, ref arguments) => {
+            let name = qualified(name);
+            Term::Call(
+                name.trim_start_matches("pg_catalog.").to_owned(),
+                arguments.iter().map(expression_term).collect(),
+            )
+        }
+        Expression::Cast(ref expression, ref sql_type) => cast(expression_term(expression), &sql_type.to_string()),
+        Expression::UnaryOperator(ref operator, ref operand) => {
+            unary(&operator.to_lowercase(), expression_term(operand))
+        }
+        Expression::BinaryOperator(ref left, ref operator, ref right) => {
+            binary(expression_term(left), &operator.to_lowercase(), expression_term(right))
+        }
+        Expression::Nested(ref expression) => expression_term(expression),
+    }
+}
+
+fn qualified(name: &ObjectName) -> String {
+    match name.schema {
+        Some(ref schema) => format!("{}.{}", schema, name.name),
+        None => name.name.to_owned(),
+    }
+}
+
+fn simplify(text: &str) -> String {
+    text.split_whitespace().collect::<String>().to_lowercase()
+}
+
+fn same_term(source: &Term, target: &Term) -> bool {
+    match (source, target) {
+        (Term::Literal(src), Term::Literal(tgt)) | (Term::Name(src), Term::Name(tgt)) => src == tgt,
+        (Term::Array(src), Term::Array(tgt))
+        | (Term::Row(src), Term::Row(tgt))
+        | (Term::Sequence(src), Term::Sequence(tgt)) => same_terms(src, tgt),
+        (Term::Call(src, src_args), Term::Call(tgt, tgt_args)) => src == tgt && same_terms(src_args, tgt_args),
+        (Term::Cast(src, src_type), Term::Cast(tgt, tgt_type)) => src_type == tgt_type && same_term(src, tgt),
+        (Term::Subscript(src, src_index), Term::Subscript(tgt, tgt_index)) => {
+            same_term(src, tgt) && same_terms(src_index, tgt_index)
+        }
+        (Term::Unary(src_op, src), Term::Unary(tgt_op, tgt))
+        | (Term::Postfix(src, src_op), Term::Postfix(tgt, tgt_op)) => src_op == tgt_op && same_term(src, tgt),
+        (Term::Binary(src_left, src_op, src_right), Term::Binary(tgt_left, tgt_op, tgt_right)) => {
+            src_op == tgt_op && same_term(src_left, tgt_left) && same_term(src_right, tgt_right)
+        }
+        // A cast the source doesn't have is a coercion added by PostgreSQL
+        (_, Term::Cast(tgt, _)) => same_term(source, tgt),
+        _ => false,
+    }
+}
+
+fn same_terms(source: &[Term], target: &[Term]) -> bool {
+    source.len() == target.len() && source.iter().zip(target.iter()).all(|(src, tgt)| same_term(src, tgt))
+}
+
+fn literal(value: &str) -> Term {
+    // PostgreSQL describes negative numbers as strings, e.g. '-1'::integer
+    let numeric = {
+        let digits = value.strip_prefix('-').unwrap_or(value);
+        let mut parts = digits.splitn(2, '.');
+        let whole = parts.next().unwrap_or("");
+        let fraction = parts.next();
+        !whole.is_empty()
+            && whole.chars().all(|c| c.is_ascii_digit())
+            && fraction
+                .into_iter()
+                .all(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()))
+    };
+    if numeric {
+        Term::Literal(value.to_owned())
+    } else {
+        Term::Literal(format!("'{}'", value))
+    }
+}
+
+fn cast(term: Term, sql_type: &str) -> Term {
+    match term {
+        Term::Literal(_) => term,
+        term => Term::Cast(Box::new(term), type_name(sql_type)),
+    }
+}
+
+fn unary(operator: &str, term: Term) -> Term {
+    match (operator, term) {
+        ("-", Term::Literal(ref value)) if !value.starts_with('\'') && !value.starts_with('-') => {
+            Term::Literal(format!("-{}", value))
+        }
+        ("+", Term::Literal(value)) => Term::Literal(value),
+        (operator, term) => Term::Unary(operator.to_owned(), Box::new(term)),
+    }
+}
+
+fn binary(left: Term, operator: &str, right: Term) -> Term {
+    Term::Binary(Box::new(left), operator.to_owned(), Box::new(right))
+}
+
+/// Type names as PostgreSQL describes them, e.g. int4 and int are both integer
+fn type_name(sql_type: &str) -> String {
+    let sql_type = sql_type.to_lowercase();
+    let sql_type = sql_type.trim_start_matches("pg_catalog.");
+    let mut base = String::new();
+    let mut suffix = String::new();
+    let mut depth = 0;
+    for c in sql_type.chars() {
+        match c {
+            '(' | '[' => {
+                depth += 1;
+                suffix.push(c);
+            }
+            ')' | ']' => {
+                depth -= 1;
+                suffix.push(c);
+            }
+            c if depth > 0 => {
+                if !c.is_whitespace() {
+                    suffix.push(c);
+                }
+            }
+            c => base.push(c),
+        }
+    }
+    let base = base.split_whitespace().collect::<Vec<_>>().join(" ");
+    let base = match &base[..] {
+        "int" | "int4" => "integer",
+        "int8" => "bigint",
+        "int2" => "smallint",
+        "varchar" => "character varying",
+        "char" | "bpchar" => "character",
+        "bool" => "boolean",
+        "float8" => "double precision",
+        "float4" => "real",
+        "decimal" => "numeric",
+        "timestamp" => "timestamp without time zone",
+        "timestamptz" => "timestamp with time zone",
+        "time" => "time without time zone",
+        "timetz" => "time with time zone",
+        "varbit" => "bit varying",
+        base => base,
+    };
+    format!("{}{}", base, suffix)
+}
+
+fn parse(text: &str) -> Option<Term> {
+    let mut parser = Parser {
+        lexemes: tokenize(text)?,
+        position: 0,
+    };
+    let term = parser.list(None)?;
+    if parser.position == parser.lexemes.len() {
+        Some(term)
+    } else {
+        None
+    }
+}
+
+fn tokenize(text: &str) -> Option<Vec<Lexeme>> {
+    fn quoted(chars: &mut Peekable<Chars>, quote: char, backslash: bool) -> Option<String> {
+        let mut value = String::new();
+        loop {
+            match chars.next()? {
+                '\\' if backslash => {
+                    value.push('\\');
+                    value.push(chars.next()?);
+                }
+                c if c == quote => {
+                    if chars.peek() == Some(&quote) {
+                        chars.next();
+                        value.push(quote);
+                        value.push(quote);
+                    } else {
+                        return Some(value);
+                    }
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    const OPERATOR: &str = "+-*/<>=~!@#%^&|`?";
+    let mut lexemes = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let lexeme = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Lexeme::Open,
+            ')' => Lexeme::Close,
+            '[' => Lexeme::OpenSquare,
+            ']' => Lexeme::CloseSquare,
+            ',' => Lexeme::Comma,
+            '.' if chars.peek().filter(|c| c.is_ascii_digit()).is_none() => Lexeme::Period,
+            ':' if chars.peek() == Some(&':') => {
+                chars.next();
+                Lexeme::Cast
+            }
+            '\'' => Lexeme::String(quoted(&mut chars, '\'', false)?),
+            '"' => {
+                let name = quoted(&mut chars, '"', false)?;
+                let simple = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$');
+                Lexeme::Quoted(if simple { name } else { format!("\"{}\"", name) })
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    let exponent = (next == '+' || next == '-') && number.ends_with(['e', 'E']);
+                    if next.is_ascii_alphanumeric() || next == '.' || exponent {
+                        number.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Lexeme::Number(number)
+            }
+            '$' => {
+                let mut tag = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' {
+                        tag.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if chars.peek() == Some(&'$') && !tag.starts_with(|c: char| c.is_ascii_digit()) {
+                    // A dollar quoted string, e.g. $tag$text$tag$
+                    chars.next();
+                    let delimiter = format!("${}$", tag);
+                    let rest = chars.clone().collect::<String>();
+                    let end = rest.find(&delimiter)?;
+                    for _ in rest[..end + delimiter.len()].chars() {
+                        chars.next();
+                    }
+                    Lexeme::String(rest[..end].replace('\'', "''"))
+                } else {
+                    // A parameter, e.g. $1
+                    Lexeme::Word(format!("${}", tag))
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == '$' {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if (word == "E" || word == "e") && chars.peek() == Some(&'\'') {
+                    chars.next();
+                    Lexeme::String(quoted(&mut chars, '\'', true)?)
+                } else {
+                    Lexeme::Word(word.to_lowercase())
+                }
+            }
+            c if OPERATOR.contains(c) => {
+                let mut operator = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if OPERATOR.contains(next) {
+                        operator.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                // As with PostgreSQL, a multiple character operator only ends in + or - if it contains
+                // a character that can't appear in a standard operator, e.g. >=-1 is >= followed by -1.
+                let mut trailing = Vec::new();
+                while operator.len() > 1
+                    && operator.ends_with(['+', '-'])
+                    && !operator.contains(|c| "~!@#%^&|`?".contains(c))
+                {
+                    trailing.push(operator.pop().unwrap());
+                }
+                lexemes.push(Lexeme::Operator(operator));
+                for operator in trailing.into_iter().rev() {
+                    lexemes.push(Lexeme::Operator(operator.to_string()));
+                }
+                continue;
+            }
+            _ => return None,
+        };
+        lexemes.push(lexeme);
+    }
+    Some(lexemes)
+}
+
+struct Parser {
+    lexemes: Vec<Lexeme>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.position)
+    }
+
+    fn peek_word(&self, offset: usize) -> Option<&str> {
+        match self.lexemes.get(self.position + offset) {
+            Some(Lexeme::Word(word)) => Some(&word[..]),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Lexeme> {
+        let lexeme = self.lexemes.get(self.position).cloned();
+        self.position += 1;
+        lexeme
+    }
+
+    fn expect(&mut self, lexeme: Lexeme) -> Option<()> {
+        if self.next()? == lexeme {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Option<()> {
+        self.expect(Lexeme::Word(word.to_owned()))
+    }
+
+    fn at_end_of_item(&self) -> bool {
+        match self.peek() {
+            Some(lexeme) => [Lexeme::Comma, Lexeme::Close, Lexeme::CloseSquare].contains(lexeme),
+            None => true,
+        }
+    }
+
+    /// Comma separated items up to the closing bracket (or the end of the text)
+    fn list(&mut self, close: Option<Lexeme>) -> Option<Term> {
+        let items = self.items(close)?;
+        Some(if items.len() == 1 {
+            items.into_iter().next().unwrap()
+        } else {
+            Term::Row(items)
+        })
+    }
+
+    fn items(&mut self, close: Option<Lexeme>) -> Option<Vec<Term>> {
+        let mut items = Vec::new();
+        if close.is_some() && self.peek() == close.as_ref() {
+            self.next();
+            return Some(items);
+        }
+        loop {
+            items.push(self.item()?);
+            match self.peek() {
+                Some(&Lexeme::Comma) => {
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+        if let Some(close) = close {
+            self.expect(close)?;
+        }
+        Some(items)
+    }
+
+    // Expressions written one after another, e.g. a CASE expression
+    fn item(&mut self) -> Option<Term> {
+        let mut terms = vec![self.expression(0)?];
+        while !self.at_end_of_item() {
+            terms.push(self.expression(0)?);
+        }
+        Some(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Term::Sequence(terms)
+        })
+    }
+
+    fn expression(&mut self, min_power: u8) -> Option<Term> {
+        let mut term = self.prefix()?;
+        loop {
+            let (power, lexeme) = match self.peek() {
+                Some(&Lexeme::Cast) => (13, self.next()?),
+                Some(&Lexeme::OpenSquare) => (12, self.next()?),
+                Some(Lexeme::Operator(operator)) => (binary_power(operator), self.peek()?.clone()),
+                Some(Lexeme::Word(word)) => match &word[..] {
+                    "or" => (1, self.peek()?.clone()),
+                    "and" => (2, self.peek()?.clone()),
+                    "is" | "isnull" | "notnull" => (4, self.peek()?.clone()),
+                    "in" | "like" | "ilike" | "similar" | "between" => (6, self.peek()?.clone()),
+                    "not" => match self.peek_word(1) {
+                        Some("in") | Some("like") | Some("ilike") | Some("similar") | Some("between") => {
+                            (6, self.peek()?.clone())
+                        }
+                        _ => break,
+                    },
+                    _ => break,
+                },
+                _ => break,
+            };
+            if power < min_power {
+                // Postfix lexemes were consumed to be looked at, so put them back
+                if let Lexeme::Cast | Lexeme::OpenSquare = lexeme {
+                    self.position -= 1;
+                }
+                break;
+            }
+            term = match lexeme {
+                Lexeme::Cast => {
+                    let sql_type = self.sql_type()?;
+                    cast(term, &sql_type)
+                }
+                Lexeme::OpenSquare => Term::Subscript(Box::new(term), self.items(Some(Lexeme::CloseSquare))?),
+                Lexeme::Operator(operator) => {
+                    self.next();
+                    let operator = if operator == "!=" { "<>".to_owned() } else { operator };
+                    match self.peek_word(0) {
+                        Some(quantifier @ "any") | Some(quantifier @ "some") | Some(quantifier @ "all")
+                            if self.lexemes.get(self.position + 1) == Some(&Lexeme::Open) =>
+                        {
+                            let quantifier = if quantifier == "some" { "any" } else { quantifier }.to_owned();
+                            self.position += 2;
+                            let values = self.list(Some(Lexeme::Close))?;
+                            binary(term, &format!("{} {}", operator, quantifier), values)
+                        }
+                        _ => binary(term, &operator, self.expression(power + 1)?),
+                    }
+                }
+                Lexeme::Word(word) => {
+                    self.next();
+                    self.keyword_operator(term, &word)?
+                }
+                _ => unreachable!(),
+            };
+        }
+        Some(term)
+    }
+
+    // Operators written as keywords, rewritten to the form PostgreSQL describes them in
+    fn keyword_operator(&mut self, term: Term, word: &str) -> Option<Term> {
+        Some(match word {
+            "or" | "and" => {
+                let power = if word == "or" { 1 } else { 2 };
+                binary(term, word, self.expression(power + 1)?)
+            }
+            "isnull" => Term::Postfix(Box::new(term), "is null".to_owned()),
+            "notnull" => Term::Postfix(Box::new(term), "is not null".to_owned()),
+            "is" => {
+                let mut test = vec!["is".to_owned()];
+                if self.peek_word(0) == Some("not") {
+                    self.next();
+                    test.push("not".to_owned());
+                }
+                if self.peek_word(0) == Some("distinct") {
+                    self.next();
+                    self.expect_word("from")?;
+                    test.push("distinct from".to_owned());
+                    return Some(binary(term, &test.join(" "), self.expression(5)?));
+                }
+                match self.next()? {
+                    Lexeme::Word(value) => test.push(value),
+                    _ => return None,
+                }
+                Term::Postfix(Box::new(term), test.join(" "))
+            }
+            "not" => {
+                let word = match self.next()? {
+                    Lexeme::Word(word) => word,
+                    _ => return None,
+                };
+                self.negated_operator(term, &word, true)?
+            }
+            word => self.negated_operator(term, word, false)?,
+        })
+    }
+
+    fn negated_operator(&mut self, term: Term, word: &str, negated: bool) -> Option<Term> {
+        Some(match word {
+            "in" => {
+                // x IN (a, b) is described as x = ANY (ARRAY[a, b]), and x IN (a) as x = a
+                self.expect(Lexeme::Open)?;
+                let mut values = self.items(Some(Lexeme::Close))?;
+                if values.len() == 1 {
+                    binary(term, if negated { "<>" } else { "=" }, values.pop().unwrap())
+                } else {
+                    binary(term, if negated { "<> all" } else { "= any" }, Term::Array(values))
+                }
+            }
+            "like" | "ilike" => {
+                let operator = match (word, negated) {
+                    ("like", false) => "~~",
+                    ("like", true) => "!~~",
+                    (_, false) => "~~*",
+                    (_, true) => "!~~*",
+                };
+                binary(term, operator, self.expression(7)?)
+            }
+            "similar" => {
+                self.expect_word("to")?;
+                let operator = if negated { "not similar to" } else { "similar to" };
+                binary(term, operator, self.expression(7)?)
+            }
+            "between" => {
+                // x BETWEEN a AND b is described as x >= a AND x <= b
+                let low = self.expression(7)?;
+                self.expect_word("and")?;
+                let high = self.expression(7)?;
+                if negated {
+                    binary(binary(term.clone(), "<", low), "or", binary(term, ">", high))
+                } else {
+                    binary(binary(term.clone(), ">=", low), "and", binary(term, "<=", high))
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    fn prefix(&mut self) -> Option<Term> {
+        Some(match self.next()? {
+            Lexeme::Open => self.list(Some(Lexeme::Close))?,
+            Lexeme::String(value) => literal(&value),
+            Lexeme::Number(value) => literal(&value),
+            Lexeme::Operator(operator) => {
+                // An operator on its own, e.g. count(*)
+                if self.at_end_of_item() {
+                    Term::Name(operator)
+                } else {
+                    let operand = self.expression(11)?;
+                    unary(&operator, operand)
+                }
+            }
+            Lexeme::Word(ref word) if word == "not" && !self.at_end_of_item() => {
+                Term::Unary("not".to_owned(), Box::new(self.expression(3)?))
+            }
+            Lexeme::Word(ref word) if word == "null" || word == "true" || word == "false" => {
+                Term::Literal(word.to_owned())
+            }
+            Lexeme::Word(ref word) if word == "array" && self.peek() == Some(&Lexeme::OpenSquare) => {
+                self.next();
+                Term::Array(self.items(Some(Lexeme::CloseSquare))?)
+            }
+            Lexeme::Word(ref word) if LITERAL_TYPES.contains(&&word[..]) => match self.peek() {
+                Some(Lexeme::String(value)) => {
+                    let value = value.to_owned();
+                    self.next();
+                    literal(&value)
+                }
+                _ => self.name(word.to_owned())?,
+            },
+            Lexeme::Word(word) => self.name(word)?,
+            Lexeme::Quoted(name) => self.name(name)?,
+            _ => return None,
+        })
+    }
+
+    // A possibly qualified name, which is a function call if followed by arguments
+    fn name(&mut self, first: String) -> Option<Term> {
+        let callable = !NOT_CALLABLE.contains(&&first[..]);
+        let mut name = first;
+        while self.peek() == Some(&Lexeme::Period) {
+            self.next();
+            match self.next()? {
+                Lexeme::Word(part) | Lexeme::Quoted(part) => {
+                    name.push('.');
+                    name.push_str(&part);
+                }
+                Lexeme::Operator(ref operator) if operator == "*" => name.push_str(".*"),
+                _ => return None,
+            }
+        }
+        if callable && self.peek() == Some(&Lexeme::Open) {
+            self.next();
+            let arguments = self.items(Some(Lexeme::Close))?;
+            Some(Term::Call(name.trim_start_matches("pg_catalog.").to_owned(), arguments))
+        } else {
+            Some(Term::Name(name))
+        }
+    }
+
+    fn sql_type(&mut self) -> Option<String> {
+        let mut sql_type = match self.next()? {
+            Lexeme::Word(name) | Lexeme::Quoted(name) => name,
+            _ => return None,
+        };
+        while self.peek() == Some(&Lexeme::Period) {
+            self.next();
+            match self.next()? {
+                Lexeme::Word(part) | Lexeme::Quoted(part) => {
+                    sql_type.push('.');
+                    sql_type.push_str(&part);
+                }
+                _ => return None,
+            }
+        }
+        let continuation: &[&str] = match &sql_type[..] {
+            "double" => &["precision"],
+            "character" | "char" | "bit" => &["varying"],
+            _ => &[],
+        };
+        if let Some(word) = self
+            .peek_word(0)
+            .filter(|w| continuation.contains(w))
+            .map(|w| w.to_owned())
+        {
+            self.next();
+            sql_type.push(' ');
+            sql_type.push_str(&word);
+        }
+        if self.peek() == Some(&Lexeme::Open) {
+            self.next();
+            let mut modifiers = Vec::new();
+            loop {
+                match self.next()? {
+                    Lexeme::Number(number) => modifiers.push(number),
+                    Lexeme::Comma => {}
+                    Lexeme::Close => break,
+                    _ => return None,
+                }
+            }
+            sql_type.push_str(&format!("({})", modifiers.join(",")));
+        }
+        if sql_type.starts_with("time") {
+            if let Some(zone) = self
+                .peek_word(0)
+                .filter(|w| *w == "with" || *w == "without")
+                .map(|w| w.to_owned())
+            {
+                if self.peek_word(1) == Some("time") && self.peek_word(2) == Some("zone") {
+                    self.position += 3;
+                    let (base, modifiers) = match sql_type.find('(') {
+                        Some(index) => (sql_type[..index].to_owned(), sql_type[index..].to_owned()),
+                        None => (sql_type.clone(), String::new()),
+                    };
+                    sql_type = format!("{} {} time zone{}", base, zone, modifiers);
+                }
+            }
+        }
+        while self.peek() == Some(&Lexeme::OpenSquare) {
+            self.next();
+            if let Some(&Lexeme::Number(_)) = self.peek() {
+                self.next();
+            }
+            self.expect(Lexeme::CloseSquare)?;
+            sql_type.push_str("[]");
+        }
+        Some(sql_type)
+    }
+}
+
+fn binary_power(operator: &str) -> u8 {
+    match operator {
+        "^" => 10,
+        "*" | "/" | "%" => 9,
+        "+" | "-" => 8,
+        "<" | ">" | "=" | "<=" | ">=" | "<>" | "!=" => 5,
+        _ => 7,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::lexer;
+    use crate::sql::parser::AnyValueParser;
+
+    use spectral::prelude::*;
+
+    #[test]
+    fn it_ignores_brackets_and_casts_added_by_postgres() {
+        let same = |source: &str, target: &str| same_expression(source, target);
+        assert_that!(same(
+            "status IN ('active', 'void') AND (age >= -1 OR age IS NULL)",
+            "((status)::text = ANY (ARRAY['active'::character varying, 'void'::character varying]::text[])) \
+             AND ((age >= '-1'::integer::numeric) OR (age IS NULL))",
+        ))
+        .is_true();
+        assert_that!(same("a * (b + c) > 0", "((a * (b + c)) > 0)")).is_true();
+        assert_that!(same("a * b + c > 0", "(((a * b) + c) > 0)")).is_true();
+        assert_that!(same("lower(email)", "lower((email)::text)")).is_true();
+        assert_that!(same("price BETWEEN 1 AND 10", "((price >= 1) AND (price <= 10))")).is_true();
+        assert_that!(same("name NOT LIKE 'a%'", "((name)::text !~~ 'a%'::text)")).is_true();
+        assert_that!(same("a != 1", "(a <> 1)")).is_true();
+        assert_that!(same("a::int = 1", "((a)::integer = 1)")).is_true();
+        assert_that!(same(
+            "tenant_id = current_setting('app.tenant')::integer",
+            "(tenant_id = (current_setting('app.tenant'::text))::integer)",
+        ))
+        .is_true();
+        assert_that!(same("status NOT IN ('x')", "((status)::text <> 'x'::text)")).is_true();
+        assert_that!(same(
+            "CASE WHEN a > 0 THEN b > 0 ELSE c IS NULL END",
+            "\nCASE\n    WHEN (a > 0) THEN (b > 0)\n    ELSE (c IS NULL)\nEND",
+        ))
+        .is_true();
+        assert_that!(same("MODULUS 4, REMAINDER 1", "modulus 4, remainder 1")).is_true();
+    }
+
+    #[test]
+    fn it_detects_changes_to_precedence_and_casts() {
+        let same = |source: &str, target: &str| same_expression(source, target);
+        assert_that!(same("a * b + c > 0", "((a * (b + c)) > 0)")).is_false();
+        assert_that!(same("a * (b + c) > 0", "(((a * b) + c) > 0)")).is_false();
+        assert_that!(same("a::int = 1", "((a)::text = '1'::text)")).is_false();
+        assert_that!(same("a = 1 OR b = 2 AND c = 3", "(((a = 1) OR (b = 2)) AND (c = 3))")).is_false();
+        assert_that!(same("a IN (1, 2)", "(a <> ALL (ARRAY[1, 2]))")).is_false();
+        assert_that!(same("status = 'active'", "((status)::text = 'void'::text)")).is_false();
+    }
+
+    #[test]
+    fn it_compares_parsed_values_ignoring_casts_added_by_postgres() {
+        let value = |sql: &str| {
+            let tokens = lexer::tokenize_body(sql).unwrap();
+            AnyValueParser::new().parse(tokens).unwrap()
+        };
+        let same = |source: &str, target: &str| same_value(&value(source), &value(target));
+        assert_that!(same("now()", "now()")).is_true();
+        assert_that!(same("'none'", "'none'::character varying")).is_true();
+        assert_that!(same("-1", "'-1'::integer")).is_true();
+        assert_that!(same("-1.50", "'-1.50'::numeric")).is_true();
+        assert_that!(same("now() + '1 day'::interval", "(now() + '1 day'::interval)")).is_true();
+        assert_that!(same("lower('ABC' || 'def')", "lower(('ABC'::text || 'def'::text))")).is_true();
+        assert_that!(same("CURRENT_TIMESTAMP", "CURRENT_TIMESTAMP")).is_true();
+
+        assert_that!(same("1 + 2 * 3", "((1 + 2) * 3)")).is_false();
+        assert_that!(same("-1.50", "1.50")).is_false();
+        assert_that!(same("'none'", "'None'::character varying")).is_false();
+        assert_that!(same("now()", "clock_timestamp()")).is_false();
+        assert_that!(same("now()::date", "now()")).is_false();
+    }
+
+    #[test]
+    fn it_lists_the_names_referred_to_by_a_query() {
+        let names = referenced_names("SELECT v.id, 'other' FROM public.\"Contact Names\" v JOIN t ON $$x.y$$ = t.b");
+        assert_that!(names).contains((Some("public".to_owned()), "Contact Names".to_owned()));
+        assert_that!(names).contains((Some("v".to_owned()), "id".to_owned()));
+        assert_that!(names).contains((None, "t".to_owned()));
+        assert_that!(names).does_not_contain((Some("x".to_owned()), "y".to_owned()));
+        assert_that!(names).does_not_contain((None, "other".to_owned()));
+    }
+}
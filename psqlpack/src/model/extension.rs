@@ -25,19 +25,35 @@ impl Extension {
         let context = capabilities.with_context(self);
         let schemas = context.schemata(&mut client, connection.database())?;
         let types = context.types(&mut client)?;
+        let sequences = context.sequences(&mut client)?;
         let functions = context.functions(&mut client)?;
+        let procedures = context.procedures(&mut client)?;
+        let aggregates = context.aggregates(&mut client)?;
         let tables = context.tables(&mut client)?;
+        let triggers = context.triggers(&mut client)?;
         let indexes = context.indexes(&mut client)?;
+        let materialized_views = context.materialized_views(&mut client)?;
+        let views = context.views(&mut client)?;
+        let privileges = context.privileges(&mut client)?;
+        let comments = context.comments(&mut client)?;
 
         let mut package = Package {
             meta,
+            aggregates,
+            comments,
             extensions: Vec::new(),
             functions,
             indexes,
+            materialized_views,
+            privileges,
+            procedures,
             schemas,
             scripts: Vec::new(),
+            sequences,
             tables,
+            triggers,
             types,
+            views,
         };
 
         package.promote_primary_keys_to_table_constraints();
@@ -9,6 +9,7 @@ macro_rules! dbtry {
 
 mod capabilities;
 mod delta;
+mod expression;
 mod extension;
 mod package;
 mod profiles;
@@ -20,4 +21,4 @@ pub use self::delta::Delta;
 pub use self::extension::Extension;
 pub use self::package::{MetaInfo, Node, Package, SourceInfo, ValidationKind};
 pub use self::profiles::{GenerationOptions, PublishProfile, Toggle};
-pub use self::project::{Dependency, Project};
+pub use self::project::{Dependency, FormattedFiles, Project, UnsupportedStatements};
@@ -7,6 +7,7 @@ use std::str::FromStr;
 use chrono::prelude::*;
 use glob::glob;
 use petgraph;
+use postgres::Transaction;
 use serde_json;
 use slog::Logger;
 use zip::write::FileOptions;
@@ -15,9 +16,11 @@ use zip::{ZipArchive, ZipWriter};
 use crate::connection::Connection;
 use crate::errors::PsqlpackErrorKind::*;
 use crate::errors::{PsqlpackResult, PsqlpackResultExt};
+use crate::model::expression::referenced_names;
 use crate::model::{Capabilities, DefinableCatalog, Dependency, Project};
 use crate::semver::Semver;
 use crate::sql::ast::*;
+use crate::sql::formatter;
 
 macro_rules! ztry {
     ($expr:expr) => {{
@@ -30,14 +33,17 @@ macro_rules! ztry {
 
 macro_rules! zip_collection {
     ($zip:ident, $package:ident, $collection:ident) => {{
+        zip_collection!($zip, $package, $collection, |item| item.name)
+    }};
+    ($zip:ident, $package:ident, $collection:ident, |$item:ident| $file_name:expr) => {{
         let collection_name = stringify!($collection);
         ztry!($zip.add_directory(format!("{}/", collection_name), FileOptions::default()));
-        for item in &$package.$collection {
+        for $item in &$package.$collection {
             ztry!($zip.start_file(
-                format!("{}/{}.json", collection_name, item.name),
+                format!("{}/{}.json", collection_name, $file_name),
                 FileOptions::default()
             ));
-            let json = match serde_json::to_string_pretty(&item) {
+            let json = match serde_json::to_string_pretty(&$item) {
                 Ok(j) => j,
                 Err(e) => bail!(GenerationError(format!("Failed to write package: {}", e))),
             };
@@ -52,13 +58,21 @@ const DEFAULT_SEARCH_PATHS: [&str; 2] = ["./lib", "~/.psqlpack/lib"];
 #[derive(Debug)]
 pub struct Package {
     pub meta: MetaInfo,
+    pub aggregates: Vec<AggregateDefinition>,
+    pub comments: Vec<CommentDefinition>,
     pub extensions: Vec<Dependency>,
     pub functions: Vec<FunctionDefinition>,
     pub indexes: Vec<IndexDefinition>,
+    pub materialized_views: Vec<MaterializedViewDefinition>,
+    pub privileges: Vec<PrivilegeDefinition>,
+    pub procedures: Vec<ProcedureDefinition>,
     pub schemas: Vec<SchemaDefinition>,
     pub scripts: Vec<ScriptDefinition>,
+    pub sequences: Vec<SequenceDefinition>,
     pub tables: Vec<TableDefinition>,
+    pub triggers: Vec<TriggerDefinition>,
     pub types: Vec<TypeDefinition>,
+    pub views: Vec<ViewDefinition>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -155,13 +169,21 @@ impl Package {
             .and_then(|file| ZipArchive::new(file).chain_err(|| PackageUnarchiveError(source_path.to_path_buf())))?;
 
         let mut meta: Option<MetaInfo> = None;
+        let mut aggregates = Vec::new();
+        let mut comments = Vec::new();
         let mut extensions = Vec::new();
         let mut functions = Vec::new();
         let mut indexes = Vec::new();
+        let mut materialized_views = Vec::new();
+        let mut privileges = Vec::new();
+        let mut procedures = Vec::new();
         let mut schemas = Vec::new();
         let mut scripts = Vec::new();
+        let mut sequences = Vec::new();
         let mut tables = Vec::new();
+        let mut triggers = Vec::new();
         let mut types = Vec::new();
+        let mut views = Vec::new();
 
         for i in 0..archive.len() {
             let file = archive.by_index(i).unwrap();
@@ -175,20 +197,36 @@ impl Package {
                 }
                 let m = serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?;
                 meta = Some(m);
+            } else if name.starts_with("aggregates/") {
+                aggregates.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("comments/") {
+                comments.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("extensions/") {
                 extensions.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("functions/") {
                 functions.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("indexes") {
                 indexes.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("materialized_views/") {
+                materialized_views.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("privileges/") {
+                privileges.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("procedures/") {
+                procedures.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("schemas/") {
                 schemas.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("scripts/") {
                 scripts.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("sequences/") {
+                sequences.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("tables/") {
                 tables.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("triggers/") {
+                triggers.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("types/") {
                 types.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("views/") {
+                views.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             }
         }
 
@@ -199,13 +237,21 @@ impl Package {
                 // For now, it assumes a standard project
                 None => MetaInfo::new(SourceInfo::Project),
             },
+            aggregates,
+            comments,
             extensions,
             functions,
             indexes,
+            materialized_views,
+            privileges,
+            procedures,
             schemas,
             scripts,
+            sequences,
             tables,
+            triggers,
             types,
+            views,
         };
         package.promote_primary_keys_to_table_constraints();
         Ok(package)
@@ -240,25 +286,69 @@ impl Package {
         // TODO: Refactor connection so we only need to pass through that
         let schemas = capabilities.schemata(&mut client, connection.database())?;
         let types = capabilities.types(&mut client)?;
+        let sequences = capabilities.sequences(&mut client)?;
         let functions = capabilities.functions(&mut client)?;
+        let procedures = capabilities.procedures(&mut client)?;
+        let aggregates = capabilities.aggregates(&mut client)?;
         let tables = capabilities.tables(&mut client)?;
+        let triggers = capabilities.triggers(&mut client)?;
         let indexes = capabilities.indexes(&mut client)?;
+        let materialized_views = capabilities.materialized_views(&mut client)?;
+        let views = capabilities.views(&mut client)?;
+        let privileges = capabilities.privileges(&mut client)?;
+        let comments = capabilities.comments(&mut client)?;
 
         let mut package = Package {
             meta: MetaInfo::new(SourceInfo::Database),
+            aggregates,
+            comments,
             extensions,
             functions,
             indexes,
+            materialized_views,
+            privileges,
+            procedures,
             schemas,
             scripts: Vec::new(), // Scripts can't be known from a connection
+            sequences,
             tables,
+            triggers,
             types,
+            views,
         };
         package.promote_primary_keys_to_table_constraints();
 
         Ok(Some(package))
     }
 
+    // The database rewrites the query of a view when storing it (e.g. by qualifying columns) so views in the
+    // source can't be compared with those extracted from the target directly. Each view is instead created as a
+    // temporary view on the target, within a transaction that is rolled back, to read back how it would be
+    // stored. Views that can't be created yet, such as those depending on unpublished objects, are left as is.
+    pub fn resolve_views(
+        &mut self,
+        log: &Logger,
+        connection: &Connection,
+        capabilities: &Capabilities,
+    ) -> PsqlpackResult<()> {
+        let log = log.new(o!("package" => "resolve_views"));
+        if !capabilities.database_exists || (self.views.is_empty() && self.materialized_views.is_empty()) {
+            return Ok(());
+        }
+
+        trace!(log, "Connecting to database");
+        let mut client = connection.connect_database()?;
+        let mut transaction = client.transaction().chain_err(|| PackageResolveViewsError)?;
+        for view in &mut self.views {
+            view.resolved = resolve_view(&log, &mut transaction, &view.name, &view.columns, &view.query)?;
+        }
+        // Materialized views store their query in the same way as a regular view
+        for view in &mut self.materialized_views {
+            view.resolved = resolve_view(&log, &mut transaction, &view.name, &view.columns, &view.query)?;
+        }
+        transaction.rollback().chain_err(|| PackageResolveViewsError)
+    }
+
     pub fn write_to(&self, destination: &Path) -> PsqlpackResult<()> {
         if let Some(parent) = destination.parent() {
             match fs::create_dir_all(parent) {
@@ -278,13 +368,32 @@ impl Package {
                     Err(e) => bail!(GenerationError(format!("Failed to write package: {}", e))),
                 };
                 ztry!(zip.write_all(json.as_bytes()));
+                // Aggregates are identified by their argument types as well as their name
+                zip_collection!(zip, self, aggregates, |aggregate| aggregate.signature());
+                // Comments are identified by the object they describe
+                zip_collection!(zip, self, comments, |comment| comment.object);
                 zip_collection!(zip, self, extensions);
-                zip_collection!(zip, self, functions);
+                // Functions (and procedures) may be overloaded so are identified by their argument types as well
+                zip_collection!(zip, self, functions, |function| function.signature());
                 zip_collection!(zip, self, indexes);
+                zip_collection!(zip, self, materialized_views);
+                // Privileges are unnamed so they're identified by what they apply to
+                zip_collection!(zip, self, privileges, |privilege| format!(
+                    "{:?}.{}.{}.{}",
+                    privilege.action, privilege.object, privilege.privilege, privilege.grantee
+                ));
+                zip_collection!(zip, self, procedures, |procedure| procedure.signature());
                 zip_collection!(zip, self, schemas);
                 zip_collection!(zip, self, scripts);
+                zip_collection!(zip, self, sequences);
                 zip_collection!(zip, self, tables);
+                // Trigger names are only unique per table
+                zip_collection!(zip, self, triggers, |trigger| format!(
+                    "{}.{}",
+                    trigger.table, trigger.name
+                ));
                 zip_collection!(zip, self, types);
+                zip_collection!(zip, self, views);
 
                 ztry!(zip.finish());
 
@@ -296,16 +405,32 @@ impl Package {
         Package {
             // By default, our source is a project file
             meta: MetaInfo::new(SourceInfo::Project),
+            aggregates: Vec::new(),
+            comments: Vec::new(),
             extensions: Vec::new(),
             functions: Vec::new(),
             indexes: Vec::new(),
+            materialized_views: Vec::new(),
+            privileges: Vec::new(),
+            procedures: Vec::new(),
             schemas: Vec::new(),
             scripts: Vec::new(),
+            sequences: Vec::new(),
             tables: Vec::new(),
+            triggers: Vec::new(),
             types: Vec::new(),
+            views: Vec::new(),
         }
     }
 
+    pub fn push_aggregate(&mut self, aggregate: AggregateDefinition) {
+        self.aggregates.push(aggregate);
+    }
+
+    pub fn push_comment(&mut self, comment: CommentDefinition) {
+        self.comments.push(comment);
+    }
+
     pub fn push_extension(&mut self, extension: Dependency) {
         self.extensions.push(extension);
     }
@@ -318,6 +443,18 @@ impl Package {
         self.indexes.push(index);
     }
 
+    pub fn push_materialized_view(&mut self, view: MaterializedViewDefinition) {
+        self.materialized_views.push(view);
+    }
+
+    pub fn push_privilege(&mut self, privilege: PrivilegeDefinition) {
+        self.privileges.push(privilege);
+    }
+
+    pub fn push_procedure(&mut self, procedure: ProcedureDefinition) {
+        self.procedures.push(procedure);
+    }
+
     pub fn push_script(&mut self, script: ScriptDefinition) {
         self.scripts.push(script);
     }
@@ -326,14 +463,86 @@ impl Package {
         self.schemas.push(schema);
     }
 
+    pub fn push_sequence(&mut self, sequence: SequenceDefinition) {
+        self.sequences.push(sequence);
+    }
+
     pub fn push_table(&mut self, table: TableDefinition) {
         self.tables.push(table);
     }
 
+    pub fn push_trigger(&mut self, trigger: TriggerDefinition) {
+        self.triggers.push(trigger);
+    }
+
     pub fn push_type(&mut self, def: TypeDefinition) {
         self.types.push(def);
     }
 
+    pub fn push_view(&mut self, view: ViewDefinition) {
+        self.views.push(view);
+    }
+
+    // Table options and policies are declared separately to their table so they can only be applied
+    // once every table has been loaded.
+    pub fn alter_table(
+        &mut self,
+        name: &ObjectName,
+        action: AlterTableAction,
+        default_schema: &str,
+    ) -> PsqlpackResult<()> {
+        let table = match self.find_table_mut(name, default_schema) {
+            Some(table) => table,
+            None => bail!(ValidationError(vec![ValidationKind::AlterTableInvalidReferenceTable {
+                table: name.to_string(),
+            }])),
+        };
+        match action {
+            AlterTableAction::EnableRowSecurity => table.row_security = true,
+            AlterTableAction::DisableRowSecurity => table.row_security = false,
+            AlterTableAction::ForceRowSecurity => table.force_row_security = true,
+            AlterTableAction::NoForceRowSecurity => table.force_row_security = false,
+        }
+        Ok(())
+    }
+
+    pub fn push_policy(&mut self, policy: PolicyDefinition, default_schema: &str) -> PsqlpackResult<()> {
+        match self.find_table_mut(&policy.table, default_schema) {
+            Some(table) => {
+                table.policies.push(policy);
+                Ok(())
+            }
+            None => bail!(ValidationError(vec![ValidationKind::PolicyInvalidReferenceTable {
+                policy: policy.name.to_owned(),
+                table: policy.table.to_string(),
+                span: policy.span,
+            }])),
+        }
+    }
+
+    fn find_table_mut(&mut self, name: &ObjectName, default_schema: &str) -> Option<&mut TableDefinition> {
+        let schema = name.schema.as_ref().map_or(default_schema, |s| &s[..]);
+        self.tables
+            .iter_mut()
+            .find(|t| t.name.name.eq(&name.name) && t.name.schema.as_ref().map_or(default_schema, |s| &s[..]).eq(schema))
+    }
+
+    // A partition shares the columns of its parent so they are resolved through the partition tree
+    fn partition_root<'a>(&'a self, table: &'a TableDefinition) -> &'a TableDefinition {
+        let mut root = table;
+        for _ in 0..self.tables.len() {
+            let parent = root
+                .partition_of
+                .as_ref()
+                .and_then(|partition| self.tables.iter().find(|t| t.name.eq(&partition.parent)));
+            match parent {
+                Some(parent) => root = parent,
+                None => break,
+            }
+        }
+        root
+    }
+
     pub fn set_defaults(&mut self, project: &Project) {
         // Make sure the public schema exists
         let mut has_public = false;
@@ -346,12 +555,60 @@ impl Package {
         if !has_public {
             self.schemas.push(SchemaDefinition {
                 name: project.default_schema.to_owned(),
+                span: Span::default(),
             });
         }
+        fn set_custom_type_schema(sql_type: &mut SqlType, default_schema: &str) {
+            if let SqlType::Custom(ref mut custom_type, ref _opts, _dim) = *sql_type {
+                if custom_type.schema.is_none() {
+                    custom_type.schema = Some(default_schema.to_owned());
+                }
+            }
+        }
+
         for typ in &mut self.types {
             if typ.name.schema.is_none() {
                 typ.name.schema = Some(project.default_schema.clone());
             }
+            match typ.kind {
+                TypeDefinitionKind::Composite(ref mut attributes) => {
+                    for attribute in attributes.iter_mut() {
+                        set_custom_type_schema(&mut attribute.sql_type, &project.default_schema);
+                    }
+                }
+                TypeDefinitionKind::Domain(ref mut domain) => {
+                    set_custom_type_schema(&mut domain.base_type, &project.default_schema);
+
+                    // Unnamed domain constraints are named the same way PostgreSQL would name them
+                    for index in 0..domain.constraints.len() {
+                        if domain.constraints[index].name.is_some() {
+                            continue;
+                        }
+                        let base = format!("{}_check", typ.name.name);
+                        let mut name = base.clone();
+                        let mut suffix = 0;
+                        while domain.constraints.iter().any(|c| c.name.as_ref() == Some(&name)) {
+                            suffix += 1;
+                            name = format!("{}{}", base, suffix);
+                        }
+                        domain.constraints[index].name = Some(name);
+                    }
+                }
+                TypeDefinitionKind::Range(ref mut range) => {
+                    set_custom_type_schema(&mut range.subtype, &project.default_schema);
+                }
+                TypeDefinitionKind::Enum(_) | TypeDefinitionKind::UserDefined => {}
+            }
+        }
+        for sequence in &mut self.sequences {
+            if sequence.name.schema.is_none() {
+                sequence.name.schema = Some(project.default_schema.clone());
+            }
+            if let Some(ref mut owner) = sequence.owned_by {
+                if owner.table.schema.is_none() {
+                    owner.table.schema = Some(project.default_schema.clone());
+                }
+            }
         }
 
         fn ensure_not_null_column(column: &mut ColumnDefinition) {
@@ -373,6 +630,14 @@ impl Package {
             if table.name.schema.is_none() {
                 table.name.schema = Some(project.default_schema.clone());
             }
+            for policy in table.policies.iter_mut() {
+                policy.table = table.name.clone();
+            }
+            if let Some(ref mut partition) = table.partition_of {
+                if partition.parent.schema.is_none() {
+                    partition.parent.schema = Some(project.default_schema.clone());
+                }
+            }
 
             for constraint in table.constraints.iter_mut() {
                 match *constraint {
@@ -389,6 +654,9 @@ impl Package {
                             ref_table.schema = Some(project.default_schema.clone());
                         }
                     }
+                    TableConstraint::Check { .. }
+                    | TableConstraint::Unique { .. }
+                    | TableConstraint::Exclude { .. } => {}
                 }
             }
 
@@ -403,12 +671,91 @@ impl Package {
                     ensure_not_null_column(column);
                 }
 
+                // Identity columns are implicitly not null
+                let identity = column.constraints.iter().any(|c| match *c {
+                    ColumnConstraint::Identity(..) => true,
+                    _ => false,
+                });
+                if identity {
+                    ensure_not_null_column(column);
+                }
+
                 // Also, if the type is custom, then assume the default search path
-                if let SqlType::Custom(ref mut custom_type, ref _opts, _dim) = column.sql_type {
-                    if custom_type.schema.is_none() {
-                        custom_type.schema = Some(project.default_schema.clone());
+                set_custom_type_schema(&mut column.sql_type, &project.default_schema);
+
+                // The same goes for any sequence used as a default
+                for constraint in column.constraints.iter_mut() {
+                    if let ColumnConstraint::Default(AnyValue::NextVal(ref mut sequence)) = *constraint {
+                        if sequence.schema.is_none() {
+                            sequence.schema = Some(project.default_schema.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        for view in &mut self.views {
+            if view.name.schema.is_none() {
+                view.name.schema = Some(project.default_schema.clone());
+            }
+        }
+        for view in &mut self.materialized_views {
+            if view.name.schema.is_none() {
+                view.name.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        // Functions, procedures and aggregates are matched by name against those already in the database
+        for function in &mut self.functions {
+            if function.name.schema.is_none() {
+                function.name.schema = Some(project.default_schema.clone());
+            }
+        }
+        for procedure in &mut self.procedures {
+            if procedure.name.schema.is_none() {
+                procedure.name.schema = Some(project.default_schema.clone());
+            }
+        }
+        for aggregate in &mut self.aggregates {
+            if aggregate.name.schema.is_none() {
+                aggregate.name.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        for trigger in &mut self.triggers {
+            if trigger.table.schema.is_none() {
+                trigger.table.schema = Some(project.default_schema.clone());
+            }
+            if trigger.function.schema.is_none() {
+                trigger.function.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        for privilege in &mut self.privileges {
+            match privilege.object {
+                PrivilegeObject::Table(ref mut name)
+                | PrivilegeObject::Sequence(ref mut name)
+                | PrivilegeObject::Function(ref mut name, _) => {
+                    if name.schema.is_none() {
+                        name.schema = Some(project.default_schema.clone());
                     }
                 }
+                PrivilegeObject::Role(_) | PrivilegeObject::Schema(_) | PrivilegeObject::Default(_) => {}
+            }
+        }
+
+        // Comments are matched by the object they describe. An index shares the schema of its table.
+        for comment in &mut self.comments {
+            let name = match comment.object {
+                CommentObject::Table(ref mut name)
+                | CommentObject::Column(ref mut name, _)
+                | CommentObject::Function(ref mut name, _)
+                | CommentObject::Type(ref mut name)
+                | CommentObject::Index(ref mut name)
+                | CommentObject::Constraint(ref mut name, _) => name,
+            };
+            if name.schema.is_none() {
+                name.schema = Some(project.default_schema.clone());
             }
         }
 
@@ -424,7 +771,10 @@ impl Package {
                 index.index_type = Some(IndexType::BTree);
             }
 
-            // Set default column sorts
+            // Set default column sorts. Only btree supports ordering out of the box.
+            if index.index_type != Some(IndexType::BTree) {
+                continue;
+            }
             for col in &mut index.columns {
                 if col.order.is_none() {
                     col.order = Some(IndexOrder::Ascending);
@@ -442,6 +792,63 @@ impl Package {
 
         // We also do the promotion here
         self.promote_primary_keys_to_table_constraints();
+        self.promote_column_constraints_to_table_constraints();
+    }
+
+    pub fn promote_column_constraints_to_table_constraints(&mut self) {
+        // Column check and unique constraints are stored by PostgreSQL as table constraints, so we do the same
+        // using the same naming convention. This allows us to compare them against the target database.
+        fn available_name(constraints: &[TableConstraint], base: String) -> String {
+            let mut name = base.to_owned();
+            let mut suffix = 0;
+            while constraints.iter().any(|c| c.name().eq(&name)) {
+                suffix += 1;
+                name = format!("{}{}", base, suffix);
+            }
+            name
+        }
+
+        for table in &mut self.tables {
+            for column in table.columns.iter_mut() {
+                let promoted = column
+                    .constraints
+                    .iter()
+                    .filter(|c| match **c {
+                        ColumnConstraint::Check { .. } | ColumnConstraint::Unique => true,
+                        _ => false,
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if promoted.is_empty() {
+                    continue;
+                }
+                column.constraints.retain(|c| !promoted.contains(c));
+
+                for constraint in promoted {
+                    let constraint = match constraint {
+                        ColumnConstraint::Check { expression, no_inherit } => TableConstraint::Check {
+                            name: available_name(
+                                &table.constraints,
+                                format!("{}_{}_check", table.name.name, column.name),
+                            ),
+                            expression,
+                            no_inherit,
+                        },
+                        ColumnConstraint::Unique => TableConstraint::Unique {
+                            name: available_name(
+                                &table.constraints,
+                                format!("{}_{}_key", table.name.name, column.name),
+                            ),
+                            columns: vec![column.name.to_owned()],
+                            include: None,
+                            parameters: None,
+                        },
+                        _ => continue,
+                    };
+                    table.constraints.push(constraint);
+                }
+            }
+        }
     }
 
     pub fn promote_primary_keys_to_table_constraints(&mut self) {
@@ -487,6 +894,14 @@ impl Package {
             let log = log.new(o!("table" => table.name.to_string()));
             table.graph(&log, &mut graph, None);
         }
+        trace!(log, "Scanning table partitions");
+        for table in &self.tables {
+            if let Some(ref partition) = table.partition_of {
+                if let Some(parent) = self.tables.iter().find(|t| t.name.eq(&partition.parent)) {
+                    graph.add_edge(Node::Table(parent), Node::Table(table), ());
+                }
+            }
+        }
         trace!(log, "Scanning table constraints");
         for table in &self.tables {
             let log = log.new(o!("table" => table.name.to_string()));
@@ -503,6 +918,12 @@ impl Package {
             function.graph(&log, &mut graph, None);
         }
 
+        trace!(log, "Scanning trigger dependencies");
+        for trigger in &self.triggers {
+            let log = log.new(o!("trigger" => trigger.name.to_string()));
+            trigger.graph(&log, &mut graph, None);
+        }
+
         // Then generate the order
         trace!(log, "Sorting graph");
         match petgraph::algo::toposort(&graph, None) {
@@ -517,6 +938,38 @@ impl Package {
         }
     }
 
+    // Views can select from other views so each is ordered after the views it depends on
+    pub fn views_in_dependency_order(&self) -> PsqlpackResult<Vec<&ViewDefinition>> {
+        let mut graph = petgraph::graphmap::DiGraphMap::<usize, ()>::new();
+        for (index, view) in self.views.iter().enumerate() {
+            graph.add_node(index);
+            for (dependency, other) in self.views.iter().enumerate() {
+                if dependency != index && view_depends_on(view, &other.name) {
+                    graph.add_edge(dependency, index, ());
+                }
+            }
+        }
+        match petgraph::algo::toposort(&graph, None) {
+            Err(_) => bail!(GenerationError("Circular reference detected".to_owned())),
+            Ok(order) => Ok(order.into_iter().map(|index| &self.views[index]).collect()),
+        }
+    }
+
+    // The views that depend on the given view, directly or through other views, in the order they can be dropped
+    pub fn dependent_views(&self, name: &ObjectName) -> PsqlpackResult<Vec<&ViewDefinition>> {
+        let mut dependents = vec![name];
+        for view in self.views_in_dependency_order()? {
+            if view.name.ne(name) && dependents.iter().any(|dependency| view_depends_on(view, dependency)) {
+                dependents.push(&view.name);
+            }
+        }
+        Ok(dependents[1..]
+            .iter()
+            .rev()
+            .filter_map(|dependent| self.views.iter().find(|view| view.name.eq(dependent)))
+            .collect())
+    }
+
     // TODO: Stop moving string, consider making this a utility
     fn expand_tilde(input: &str) -> String {
         if input.starts_with('~') {
@@ -634,27 +1087,53 @@ impl Package {
         references
     }
 
+    /// Whether the object a comment describes is defined within this package
+    pub fn defines(&self, object: &CommentObject) -> bool {
+        let table = |name: &ObjectName| self.tables.iter().find(|table| table.name.eq(name));
+        match *object {
+            CommentObject::Table(ref name) | CommentObject::Constraint(ref name, _) => table(name).is_some(),
+            CommentObject::Column(ref name, ref column) => {
+                table(name).map_or(false, |table| table.columns.iter().any(|c| c.name.eq(column)))
+            }
+            CommentObject::Function(ref name, ref types) => self
+                .functions
+                .iter()
+                .any(|function| function.is_identified_by(name, types)),
+            CommentObject::Type(ref name) => self.types.iter().any(|ty| ty.name.eq(name)),
+            CommentObject::Index(ref name) => self
+                .indexes
+                .iter()
+                .any(|index| index.name.eq(&name.name) && index.table.schema.eq(&name.schema)),
+        }
+    }
+
     pub fn validate(&self, references: &[Package]) -> PsqlpackResult<()> {
         // 1. Validate schema existence
         let schemata = self.schemas.iter().map(|schema| &schema.name[..]).collect::<Vec<_>>();
         let names = self
             .tables
             .iter()
-            .map(|t| &t.name)
-            .chain(self.functions.iter().map(|f| &f.name))
+            .map(|t| (&t.name, &t.span))
+            .chain(self.functions.iter().map(|f| (&f.name, &f.span)))
+            .chain(self.procedures.iter().map(|p| (&p.name, &p.span)))
+            .chain(self.aggregates.iter().map(|a| (&a.name, &a.span)))
+            .chain(self.views.iter().map(|v| (&v.name, &v.span)))
+            .chain(self.materialized_views.iter().map(|v| (&v.name, &v.span)))
+            .chain(self.sequences.iter().map(|s| (&s.name, &s.span)))
             .collect::<Vec<_>>();
         let mut errors = names
             .iter()
-            .filter(|o| {
+            .filter(|&&(o, _)| {
                 if let Some(ref s) = o.schema {
                     !schemata.contains(&&s[..])
                 } else {
                     false
                 }
             })
-            .map(|o| ValidationKind::SchemaMissing {
+            .map(|&(o, span)| ValidationKind::SchemaMissing {
                 schema: o.schema.clone().unwrap(),
                 object: o.name.to_owned(),
+                span: span.clone(),
             })
             .collect::<Vec<_>>();
 
@@ -674,6 +1153,7 @@ impl Package {
                             Some(ValidationKind::UnknownType {
                                 ty: name.to_owned(),
                                 table: t.name.to_string(),
+                                span: t.span.clone(),
                             })
                         } else {
                             None
@@ -688,15 +1168,15 @@ impl Package {
         let foreign_keys = self
             .tables
             .iter()
-            .flat_map(|t| t.constraints.clone())
-            .filter_map(|c| match c {
+            .flat_map(|t| t.constraints.iter().map(move |c| (c, &t.span)))
+            .filter_map(|(c, span)| match *c {
                 TableConstraint::Foreign {
-                    name,
-                    columns,
-                    ref_table,
-                    ref_columns,
+                    ref name,
+                    ref columns,
+                    ref ref_table,
+                    ref ref_columns,
                     ..
-                } => Some((name, columns, ref_table, ref_columns)),
+                } => Some((name, columns, ref_table, ref_columns, span)),
                 _ => None,
             })
             .collect::<Vec<_>>();
@@ -706,11 +1186,12 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(_, _, ref table, _)| !self.tables.iter().any(|t| t.name.eq(table)))
+                .filter(|&&(_, _, table, _, _)| !self.tables.iter().any(|t| t.name.eq(table)))
                 .map(
-                    |&(ref name, _, ref table, _)| ValidationKind::TableConstraintInvalidReferenceTable {
+                    |&(name, _, table, _, span)| ValidationKind::TableConstraintInvalidReferenceTable {
                         constraint: name.to_owned(),
                         table: table.to_string(),
+                        span: span.clone(),
                     },
                 ),
         );
@@ -718,7 +1199,7 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(_, _, ref table, ref columns)| {
+                .filter(|&&(_, _, table, columns, _)| {
                     let table = self.tables.iter().find(|t| t.name.eq(table));
                     match table {
                         Some(t) => !columns.iter().all(|rc| t.columns.iter().any(|c| c.name.eq(rc))),
@@ -726,10 +1207,11 @@ impl Package {
                     }
                 })
                 .map(
-                    |&(ref name, _, ref table, ref columns)| ValidationKind::TableConstraintInvalidReferenceColumns {
+                    |&(name, _, table, columns, span)| ValidationKind::TableConstraintInvalidReferenceColumns {
                         constraint: name.to_owned(),
                         table: table.to_string(),
                         columns: columns.clone(),
+                        span: span.clone(),
                     },
                 ),
         );
@@ -737,7 +1219,7 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(ref constraint, ref columns, _, _)| {
+                .filter(|&&(constraint, columns, _, _, _)| {
                     let table = self
                         .tables
                         .iter()
@@ -748,23 +1230,55 @@ impl Package {
                     }
                 })
                 .map(
-                    |&(ref name, ref columns, _, _)| ValidationKind::TableConstraintInvalidSourceColumns {
+                    |&(name, columns, _, _, span)| ValidationKind::TableConstraintInvalidSourceColumns {
                         constraint: name.to_owned(),
                         columns: columns.clone(),
+                        span: span.clone(),
                     },
                 ),
         );
         // iv. (Future) Source column match type is not compatible with reference column type
+        // v. Unique and exclusion constraint columns don't exist
+        for table in &self.tables {
+            for constraint in &table.constraints {
+                let columns = match *constraint {
+                    TableConstraint::Unique {
+                        ref columns,
+                        ref include,
+                        ..
+                    } => columns
+                        .iter()
+                        .chain(include.iter().flatten())
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                    TableConstraint::Exclude { ref elements, .. } => {
+                        elements.iter().map(|e| e.column.to_owned()).collect::<Vec<_>>()
+                    }
+                    _ => continue,
+                };
+                if !columns.iter().all(|rc| table.columns.iter().any(|c| c.name.eq(rc))) {
+                    errors.push(ValidationKind::TableConstraintInvalidSourceColumns {
+                        constraint: constraint.name().to_owned(),
+                        columns,
+                        span: table.span.clone(),
+                    });
+                }
+            }
+        }
 
-        // 4. Validate indexes map to known tables
+        // 4. Validate indexes map to known tables (or materialized views)
         // i. reference table missing
         errors.extend(
             self.indexes
                 .iter()
-                .filter(|&index| !self.tables.iter().any(|t| t.name.eq(&index.table)))
+                .filter(|&index| {
+                    !self.tables.iter().any(|t| t.name.eq(&index.table))
+                        && !self.materialized_views.iter().any(|v| v.name.eq(&index.table))
+                })
                 .map(|ref index| ValidationKind::IndexInvalidReferenceTable {
                     index: index.name.to_string(),
                     table: index.table.to_string(),
+                    span: index.span.clone(),
                 }),
         );
         // ii. reference table exists but columns missing
@@ -773,18 +1287,19 @@ impl Package {
                 .iter()
                 .filter(|&index| {
                     let table = self.tables.iter().find(|t| t.name.eq(&index.table));
-                    match table {
+                    match table.map(|t| self.partition_root(t)) {
                         Some(t) => !index
-                            .columns
+                            .column_names()
                             .iter()
-                            .all(|rc| t.columns.iter().any(|c| c.name.eq(&rc.name))),
+                            .all(|rc| t.columns.iter().any(|c| c.name.eq(rc))),
                         None => false,
                     }
                 })
                 .map(|ref index| ValidationKind::IndexInvalidReferenceColumns {
                     index: index.name.to_string(),
                     table: index.table.to_string(),
-                    columns: index.columns.iter().map(|c| c.name.to_string()).collect(),
+                    columns: index.column_names().iter().map(|c| c.to_string()).collect(),
+                    span: index.span.clone(),
                 }),
         );
 
@@ -803,6 +1318,186 @@ impl Package {
                 .map(|ref function| ValidationKind::UnsupportedFunctionLanguage {
                     language: function.language.clone(),
                     name: function.name.clone(),
+                    span: function.span.clone(),
+                }),
+        );
+        errors.extend(
+            self.procedures
+                .iter()
+                .filter(|&procedure| {
+                    if let FunctionLanguage::Custom(_) = procedure.language {
+                        true
+                    } else {
+                        false
+                    }
+                })
+                .map(|ref procedure| ValidationKind::UnsupportedFunctionLanguage {
+                    language: procedure.language.clone(),
+                    name: procedure.name.clone(),
+                    span: procedure.span.clone(),
+                }),
+        );
+
+        // 6. Validate triggers map to known tables (or views) and functions
+        // i. reference table missing
+        errors.extend(
+            self.triggers
+                .iter()
+                .filter(|&trigger| {
+                    !self.tables.iter().any(|t| t.name.eq(&trigger.table))
+                        && !self.views.iter().any(|v| v.name.eq(&trigger.table))
+                })
+                .map(|ref trigger| ValidationKind::TriggerInvalidReferenceTable {
+                    trigger: trigger.name.to_string(),
+                    table: trigger.table.to_string(),
+                    span: trigger.span.clone(),
+                }),
+        );
+        // ii. function missing - this may be defined by a reference
+        let functions = self
+            .functions
+            .iter()
+            .chain(references.iter().flat_map(|r| r.functions.iter()))
+            .map(|f| &f.name)
+            .collect::<Vec<_>>();
+        errors.extend(
+            self.triggers
+                .iter()
+                .filter(|&trigger| !functions.contains(&&trigger.function))
+                .map(|ref trigger| ValidationKind::TriggerInvalidReferenceFunction {
+                    trigger: trigger.name.to_string(),
+                    function: trigger.function.to_string(),
+                    span: trigger.span.clone(),
+                }),
+        );
+
+        // 7. Validate sequences used by column defaults exist. Serial columns implicitly create
+        //    a sequence named after the table and column so these are valid too.
+        let mut sequences = self
+            .sequences
+            .iter()
+            .chain(references.iter().flat_map(|r| r.sequences.iter()))
+            .map(|s| s.name.clone())
+            .collect::<Vec<_>>();
+        for table in &self.tables {
+            for column in &table.columns {
+                match column.sql_type {
+                    SqlType::Simple(SimpleSqlType::SmallSerial, _)
+                    | SqlType::Simple(SimpleSqlType::Serial, _)
+                    | SqlType::Simple(SimpleSqlType::BigSerial, _) => sequences.push(ObjectName {
+                        schema: table.name.schema.clone(),
+                        name: format!("{}_{}_seq", table.name.name, column.name),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        errors.extend(self.tables.iter().flat_map(|t| {
+            t.columns
+                .iter()
+                .flat_map(|c| c.constraints.iter().map(move |constraint| (c, constraint)))
+                .filter_map(|(c, constraint)| match *constraint {
+                    ColumnConstraint::Default(AnyValue::NextVal(ref sequence)) if !sequences.contains(sequence) => {
+                        Some(ValidationKind::ColumnInvalidReferenceSequence {
+                            table: t.name.to_string(),
+                            column: c.name.to_owned(),
+                            sequence: sequence.to_string(),
+                            span: t.span.clone(),
+                        })
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        }));
+        // ii. A sequence owner must be a known column
+        errors.extend(
+            self.sequences
+                .iter()
+                .filter_map(|sequence| sequence.owned_by.as_ref().map(|owner| (sequence, owner)))
+                .filter(|&(_, owner)| {
+                    !self
+                        .tables
+                        .iter()
+                        .any(|t| t.name.eq(&owner.table) && t.columns.iter().any(|c| c.name.eq(&owner.column)))
+                })
+                .map(|(sequence, owner)| ValidationKind::SequenceInvalidReferenceColumn {
+                    sequence: sequence.name.to_string(),
+                    table: owner.table.to_string(),
+                    column: owner.column.to_owned(),
+                    span: sequence.span.clone(),
+                }),
+        );
+
+        // 8. Validate privileges are granted on known objects. Roles are defined outside of the database
+        //    so they can't be validated.
+        let relations = self
+            .tables
+            .iter()
+            .map(|t| &t.name)
+            .chain(self.views.iter().map(|v| &v.name))
+            .chain(self.materialized_views.iter().map(|v| &v.name))
+            .chain(references.iter().flat_map(|r| r.tables.iter().map(|t| &t.name)))
+            .collect::<Vec<_>>();
+        errors.extend(
+            self.privileges
+                .iter()
+                .filter(|privilege| match privilege.object {
+                    PrivilegeObject::Schema(ref schema) => !schemata.contains(&&schema[..]),
+                    PrivilegeObject::Table(ref name) => !relations.contains(&name),
+                    PrivilegeObject::Sequence(ref name) => !sequences.contains(name),
+                    PrivilegeObject::Function(ref name, ref types) => !self
+                        .functions
+                        .iter()
+                        .chain(references.iter().flat_map(|r| r.functions.iter()))
+                        .any(|function| match *types {
+                            Some(ref types) => function.is_identified_by(name, types),
+                            None => function.name.eq(name),
+                        }),
+                    PrivilegeObject::Role(_) | PrivilegeObject::Default(_) => false,
+                })
+                .map(|privilege| ValidationKind::PrivilegeInvalidReferenceObject {
+                    privilege: privilege.privilege.to_string(),
+                    object: privilege.object.to_string(),
+                    span: privilege.span.clone(),
+                }),
+        );
+
+        // 9. Validate partitions belong to known partitioned tables
+        errors.extend(
+            self.tables
+                .iter()
+                .filter_map(|table| table.partition_of.as_ref().map(|partition| (table, partition)))
+                .filter(|&(_, partition)| {
+                    !self
+                        .tables
+                        .iter()
+                        .any(|t| t.name.eq(&partition.parent) && t.partition_by.is_some())
+                })
+                .map(|(table, partition)| ValidationKind::PartitionInvalidReferenceTable {
+                    partition: table.name.to_string(),
+                    table: partition.parent.to_string(),
+                    span: table.span.clone(),
+                }),
+        );
+
+        // 10. Validate functions are only defined once for a given set of argument types
+        for (index, function) in self.functions.iter().enumerate() {
+            if self.functions[..index].iter().any(|f| f.is_same_function(function)) {
+                errors.push(ValidationKind::DuplicateFunction {
+                    signature: function.signature(),
+                    span: function.span.clone(),
+                });
+            }
+        }
+
+        // 11. Validate comments describe known objects
+        errors.extend(
+            self.comments
+                .iter()
+                .filter(|comment| !self.defines(&comment.object))
+                .map(|comment| ValidationKind::CommentInvalidReferenceObject {
+                    object: comment.object.to_string(),
+                    span: comment.span.clone(),
                 }),
         );
 
@@ -821,54 +1516,213 @@ impl Default for Package {
     }
 }
 
+static Q_RESOLVED_VIEW: &str = "
+    SELECT
+        ARRAY(
+            SELECT attname::text
+            FROM pg_attribute
+            WHERE attrelid = 'pg_temp.psqlpack_resolved_view'::regclass AND attnum > 0 AND NOT attisdropped
+            ORDER BY attnum
+        ) AS columns,
+        pg_get_viewdef('pg_temp.psqlpack_resolved_view'::regclass) AS query";
+
+fn resolve_view(
+    log: &Logger,
+    transaction: &mut Transaction,
+    name: &ObjectName,
+    columns: &Option<Vec<String>>,
+    query: &str,
+) -> PsqlpackResult<Option<ResolvedView>> {
+    let column_list = match *columns {
+        Some(ref columns) => format!(" ({})", formatter::column_list(columns)),
+        None => String::new(),
+    };
+    let mut savepoint = transaction.transaction().chain_err(|| PackageResolveViewsError)?;
+    let sql = format!(
+        "CREATE TEMPORARY VIEW psqlpack_resolved_view{} AS {}",
+        column_list, query
+    );
+    if let Err(e) = savepoint.batch_execute(&sql) {
+        trace!(log, "Unable to resolve view {}: {}", name, e);
+        return Ok(None);
+    }
+    let row = savepoint
+        .query_one(Q_RESOLVED_VIEW, &[])
+        .chain_err(|| PackageResolveViewsError)?;
+    let query: String = row.get(1);
+    let resolved = ResolvedView {
+        columns: row.get(0),
+        query: query.trim().trim_end_matches(';').trim_end().to_owned(),
+    };
+    savepoint.rollback().chain_err(|| PackageResolveViewsError)?;
+    Ok(Some(resolved))
+}
+
+// Whether the query of a view refers to the given name. Names without a schema could resolve to any schema on the
+// search path so are assumed to refer to it.
+fn view_depends_on(view: &ViewDefinition, name: &ObjectName) -> bool {
+    referenced_names(&view.query)
+        .into_iter()
+        .any(|(schema, referenced)| referenced.eq(&name.name) && schema.into_iter().all(|s| name.schema.eq(&Some(s))))
+}
+
 #[derive(Debug)]
 pub enum ValidationKind {
+    AlterTableInvalidReferenceTable {
+        table: String,
+    },
+    ColumnInvalidReferenceSequence {
+        table: String,
+        column: String,
+        sequence: String,
+        span: Span,
+    },
+    CommentInvalidReferenceObject {
+        object: String,
+        span: Span,
+    },
+    DuplicateFunction {
+        signature: String,
+        span: Span,
+    },
     IndexInvalidReferenceTable {
         index: String,
         table: String,
+        span: Span,
     },
     IndexInvalidReferenceColumns {
         index: String,
         table: String,
         columns: Vec<String>,
+        span: Span,
     },
     TableConstraintInvalidReferenceTable {
         constraint: String,
         table: String,
+        span: Span,
     },
     TableConstraintInvalidReferenceColumns {
         constraint: String,
         table: String,
         columns: Vec<String>,
+        span: Span,
     },
     TableConstraintInvalidSourceColumns {
         constraint: String,
         columns: Vec<String>,
+        span: Span,
+    },
+    PrivilegeInvalidReferenceObject {
+        privilege: String,
+        object: String,
+        span: Span,
+    },
+    PolicyInvalidReferenceTable {
+        policy: String,
+        table: String,
+        span: Span,
+    },
+    PartitionInvalidReferenceTable {
+        partition: String,
+        table: String,
+        span: Span,
     },
     SchemaMissing {
         schema: String,
         object: String,
+        span: Span,
+    },
+    SequenceInvalidReferenceColumn {
+        sequence: String,
+        table: String,
+        column: String,
+        span: Span,
     },
     UnknownType {
         ty: ObjectName,
         table: String,
+        span: Span,
+    },
+    TriggerInvalidReferenceTable {
+        trigger: String,
+        table: String,
+        span: Span,
+    },
+    TriggerInvalidReferenceFunction {
+        trigger: String,
+        function: String,
+        span: Span,
     },
     UnsupportedFunctionLanguage {
         language: FunctionLanguage,
         name: ObjectName,
+        span: Span,
     },
 }
 
+impl ValidationKind {
+    // Where the offending object was defined, if it came from the project source
+    pub fn span(&self) -> Option<&Span> {
+        let span = match *self {
+            ValidationKind::AlterTableInvalidReferenceTable { .. } => return None,
+            ValidationKind::ColumnInvalidReferenceSequence { ref span, .. }
+            | ValidationKind::CommentInvalidReferenceObject { ref span, .. }
+            | ValidationKind::DuplicateFunction { ref span, .. }
+            | ValidationKind::IndexInvalidReferenceTable { ref span, .. }
+            | ValidationKind::IndexInvalidReferenceColumns { ref span, .. }
+            | ValidationKind::TableConstraintInvalidReferenceTable { ref span, .. }
+            | ValidationKind::TableConstraintInvalidReferenceColumns { ref span, .. }
+            | ValidationKind::TableConstraintInvalidSourceColumns { ref span, .. }
+            | ValidationKind::PrivilegeInvalidReferenceObject { ref span, .. }
+            | ValidationKind::PolicyInvalidReferenceTable { ref span, .. }
+            | ValidationKind::PartitionInvalidReferenceTable { ref span, .. }
+            | ValidationKind::SchemaMissing { ref span, .. }
+            | ValidationKind::SequenceInvalidReferenceColumn { ref span, .. }
+            | ValidationKind::UnknownType { ref span, .. }
+            | ValidationKind::TriggerInvalidReferenceTable { ref span, .. }
+            | ValidationKind::TriggerInvalidReferenceFunction { ref span, .. }
+            | ValidationKind::UnsupportedFunctionLanguage { ref span, .. } => span,
+        };
+        if span.is_known() {
+            Some(span)
+        } else {
+            None
+        }
+    }
+}
+
 impl fmt::Display for ValidationKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ValidationKind::IndexInvalidReferenceTable { ref index, ref table } => {
-                write!(f, "Index `{}` uses unknown reference table `{}`", index, table)
+            ValidationKind::AlterTableInvalidReferenceTable { ref table } => {
+                write!(f, "ALTER TABLE uses unknown table `{}`", table)
             }
-            ValidationKind::IndexInvalidReferenceColumns {
-                ref index,
+            ValidationKind::ColumnInvalidReferenceSequence {
                 ref table,
-                ref columns,
+                ref column,
+                ref sequence,
+                ..
+            } => write!(
+                f,
+                "Column `{}` on table `{}` uses unknown sequence `{}`",
+                column, table, sequence
+            ),
+            ValidationKind::CommentInvalidReferenceObject { ref object, .. } => {
+                write!(f, "Comment uses unknown object `{}`", object)
+            }
+            ValidationKind::DuplicateFunction { ref signature, .. } => {
+                write!(f, "Function `{}` is defined more than once", signature)
+            }
+            ValidationKind::IndexInvalidReferenceTable {
+                ref index, ref table, ..
+            } => {
+                write!(f, "Index `{}` uses unknown reference table `{}`", index, table)
+            }
+            ValidationKind::IndexInvalidReferenceColumns {
+                ref index,
+                ref table,
+                ref columns,
+                ..
             } => write!(
                 f,
                 "Index `{}` uses unknown reference column(s) on table `{}` (`{}`)",
@@ -879,6 +1733,7 @@ impl fmt::Display for ValidationKind {
             ValidationKind::TableConstraintInvalidReferenceTable {
                 ref constraint,
                 ref table,
+                ..
             } => write!(
                 f,
                 "Foreign Key constraint `{}` uses unknown reference table `{}`",
@@ -888,6 +1743,7 @@ impl fmt::Display for ValidationKind {
                 ref constraint,
                 ref table,
                 ref columns,
+                ..
             } => write!(
                 f,
                 "Foreign Key constraint `{}` uses unknown reference column(s) on table `{}` (`{}`)",
@@ -898,19 +1754,63 @@ impl fmt::Display for ValidationKind {
             ValidationKind::TableConstraintInvalidSourceColumns {
                 ref constraint,
                 ref columns,
+                ..
             } => write!(
                 f,
                 "Foreign Key constraint `{}` uses unknown source column(s) (`{}`)",
                 constraint,
                 columns.join("`, `")
             ),
-            ValidationKind::SchemaMissing { ref schema, ref object } => {
+            ValidationKind::PrivilegeInvalidReferenceObject {
+                ref privilege,
+                ref object,
+                ..
+            } => write!(f, "Privilege `{}` uses unknown object `{}`", privilege, object),
+            ValidationKind::PolicyInvalidReferenceTable {
+                ref policy, ref table, ..
+            } => {
+                write!(f, "Policy `{}` uses unknown table `{}`", policy, table)
+            }
+            ValidationKind::PartitionInvalidReferenceTable {
+                ref partition,
+                ref table,
+                ..
+            } => write!(
+                f,
+                "Partition `{}` uses unknown partitioned table `{}`",
+                partition, table
+            ),
+            ValidationKind::SchemaMissing {
+                ref schema, ref object, ..
+            } => {
                 write!(f, "Schema `{}` missing for object `{}`", schema, object)
             }
-            ValidationKind::UnknownType { ref ty, ref table } => {
+            ValidationKind::SequenceInvalidReferenceColumn {
+                ref sequence,
+                ref table,
+                ref column,
+                ..
+            } => write!(
+                f,
+                "Sequence `{}` is owned by unknown column `{}` on table `{}`",
+                sequence, column, table
+            ),
+            ValidationKind::UnknownType { ref ty, ref table, .. } => {
                 write!(f, "Unknown type `{}` used on table `{}`", ty, table)
             }
-            ValidationKind::UnsupportedFunctionLanguage { ref language, ref name } => write!(
+            ValidationKind::TriggerInvalidReferenceTable {
+                ref trigger, ref table, ..
+            } => {
+                write!(f, "Trigger `{}` uses unknown reference table `{}`", trigger, table)
+            }
+            ValidationKind::TriggerInvalidReferenceFunction {
+                ref trigger,
+                ref function,
+                ..
+            } => write!(f, "Trigger `{}` uses unknown function `{}`", trigger, function),
+            ValidationKind::UnsupportedFunctionLanguage {
+                ref language, ref name, ..
+            } => write!(
                 f,
                 "Unsupported function language `{}` used on function `{}`",
                 language, name,
@@ -925,6 +1825,7 @@ pub enum Node<'def> {
     Column(&'def TableDefinition, &'def ColumnDefinition),
     Constraint(&'def TableDefinition, &'def TableConstraint),
     Function(&'def FunctionDefinition),
+    Trigger(&'def TriggerDefinition),
 }
 
 impl<'def> fmt::Display for Node<'def> {
@@ -936,6 +1837,7 @@ impl<'def> fmt::Display for Node<'def> {
                 write!(f, "Constraint: {}.{}", table.name.to_string(), constraint.name())
             }
             Node::Function(function) => write!(f, "Function:   {}", function.name.to_string()),
+            Node::Trigger(trigger) => write!(f, "Trigger:    {}.{}", trigger.table.to_string(), trigger.name),
         }
     }
 }
@@ -1004,6 +1906,34 @@ impl Graphable for FunctionDefinition {
     }
 }
 
+impl Graphable for TriggerDefinition {
+    fn graph<'graph, 'def: 'graph>(
+        &'def self,
+        log: &Logger,
+        graph: &mut Graph<'graph>,
+        _: Option<&Node<'graph>>,
+    ) -> Node<'graph> {
+        // Triggers depend on both the table and the function being available. Either may be
+        // defined outside of this package (e.g. a view or an extension function) in which case
+        // there is no edge to add.
+        trace!(log, "Adding");
+        let trigger = graph.add_node(Node::Trigger(self));
+        let dependencies = graph
+            .nodes()
+            .filter(|node| match *node {
+                Node::Table(table) => table.name.eq(&self.table),
+                Node::Function(function) => function.name.eq(&self.function),
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        for dependency in dependencies {
+            trace!(log, "Adding edge"; "dependency" => dependency.to_string());
+            graph.add_edge(dependency, trigger, ());
+        }
+        trigger
+    }
+}
+
 impl Graphable for TableConstraint {
     fn graph<'graph, 'def: 'graph>(
         &'def self,
@@ -1011,9 +1941,10 @@ impl Graphable for TableConstraint {
         graph: &mut Graph<'graph>,
         parent: Option<&Node<'graph>>,
     ) -> Node<'graph> {
-        // We currently have two types of table constraints: Primary and Foreign
-        // Primary is easy with a direct dependency to the column
+        // We currently have five types of table constraints: Primary, Foreign, Unique, Exclude and Check
+        // Primary, Unique and Exclude are easy with a direct dependency to the columns
         // Foreign requires a weighted dependency
+        // Check depends on the columns referenced within the expression
         // This does have a parent - namely the table
         let table_node = *parent.unwrap();
         let table = match table_node {
@@ -1084,6 +2015,49 @@ impl Graphable for TableConstraint {
                 graph.add_edge(table_node, constraint, ());
                 constraint
             }
+            TableConstraint::Unique {
+                ref name,
+                ref columns,
+                ref include,
+                ..
+            } => {
+                let log = log.new(o!("unique constraint" => name.to_owned()));
+                // Unique relies on both the key and included columns existing
+                trace!(log, "Adding");
+                let constraint = graph.add_node(Node::Constraint(table, self));
+                for column_name in columns.iter().chain(include.iter().flatten()) {
+                    trace!(log, "Adding edge to column"; "column" => &column_name);
+                    let column = table.columns.iter().find(|x| &x.name == column_name).unwrap();
+                    graph.add_edge(Node::Column(table, column), constraint, ());
+                }
+                graph.add_edge(table_node, constraint, ());
+                constraint
+            }
+            TableConstraint::Exclude {
+                ref name, ref elements, ..
+            } => {
+                let log = log.new(o!("exclusion constraint" => name.to_owned()));
+                trace!(log, "Adding");
+                let constraint = graph.add_node(Node::Constraint(table, self));
+                for element in elements {
+                    trace!(log, "Adding edge to column"; "column" => &element.column);
+                    let column = table.columns.iter().find(|x| x.name == element.column).unwrap();
+                    graph.add_edge(Node::Column(table, column), constraint, ());
+                }
+                graph.add_edge(table_node, constraint, ());
+                constraint
+            }
+            TableConstraint::Check { ref name, .. } => {
+                let log = log.new(o!("check constraint" => name.to_owned()));
+                // We don't parse the expression so we conservatively depend on every column in the table
+                trace!(log, "Adding");
+                let constraint = graph.add_node(Node::Constraint(table, self));
+                for column in &table.columns {
+                    graph.add_edge(Node::Column(table, column), constraint, ());
+                }
+                graph.add_edge(table_node, constraint, ());
+                constraint
+            }
         }
     }
 }
@@ -1098,23 +2072,41 @@ mod tests {
 
     use slog::{Discard, Drain, Logger};
     use spectral::prelude::*;
+    use zip::ZipArchive;
 
     fn package_sql(sql: &str) -> Package {
         let tokens = match lexer::tokenize_stmt(sql) {
             Ok(t) => t,
-            Err(e) => panic!("Syntax error: {}", e.line),
+            Err(e) => panic!("Syntax error: {}", e[0].line),
         };
         let mut package = Package::new();
         match StatementListParser::new().parse(tokens) {
             Ok(statement_list) => {
                 for statement in statement_list {
                     match statement {
+                        ast::Statement::Aggregate(aggregate_definition) => package.push_aggregate(aggregate_definition),
+                        ast::Statement::AlterTable(name, action) => package.alter_table(&name, action, "public").unwrap(),
+                        ast::Statement::Comment(comment_definition) => package.push_comment(comment_definition),
                         ast::Statement::Error(kind) => panic!("Unhandled error detected: {}", kind),
                         ast::Statement::Function(function_definition) => package.push_function(function_definition),
                         ast::Statement::Index(index_definition) => package.push_index(index_definition),
+                        ast::Statement::MaterializedView(view_definition) => {
+                            package.push_materialized_view(view_definition)
+                        }
+                        ast::Statement::Policy(policy_definition) => package.push_policy(policy_definition, "public").unwrap(),
+                        ast::Statement::Privileges(privileges) => {
+                            for privilege in privileges {
+                                package.push_privilege(privilege);
+                            }
+                        }
+                        ast::Statement::Procedure(procedure_definition) => package.push_procedure(procedure_definition),
                         ast::Statement::Schema(schema_definition) => package.push_schema(schema_definition),
+                        ast::Statement::Sequence(sequence_definition) => package.push_sequence(sequence_definition),
                         ast::Statement::Table(table_definition) => package.push_table(table_definition),
+                        ast::Statement::Trigger(trigger_definition) => package.push_trigger(trigger_definition),
                         ast::Statement::Type(type_definition) => package.push_type(type_definition),
+                        ast::Statement::Unsupported { ref error, .. } => panic!("Unsupported statement: {:?}", error),
+                        ast::Statement::View(view_definition) => package.push_view(view_definition),
                     }
                 }
             }
@@ -1211,6 +2203,101 @@ mod tests {
         assert_that!(table.name.name).is_equal_to("hello_world".to_owned());
     }
 
+    #[test]
+    fn it_promotes_column_check_constraints_to_table_constraints() {
+        let mut package = package_sql(
+            "CREATE TABLE products(\
+             price numeric(10,2) NOT NULL CHECK (price > 0), \
+             discount numeric(10,2) CHECK (discount >= 0) CHECK (discount < 100) NO INHERIT);",
+        );
+        let project = Project::default();
+
+        // Set the defaults and make sure the check constraints are named as PostgreSQL would
+        package.set_defaults(&project);
+        let table = &package.tables[0];
+        assert_that!(table.columns[0].constraints).is_equal_to(vec![ast::ColumnConstraint::NotNull]);
+        assert_that!(table.columns[1].constraints).is_empty();
+        assert_that!(table.constraints).is_equal_to(vec![
+            ast::TableConstraint::Check {
+                name: "products_price_check".to_owned(),
+                expression: "price > 0".to_owned(),
+                no_inherit: false,
+            },
+            ast::TableConstraint::Check {
+                name: "products_discount_check".to_owned(),
+                expression: "discount >= 0".to_owned(),
+                no_inherit: false,
+            },
+            ast::TableConstraint::Check {
+                name: "products_discount_check1".to_owned(),
+                expression: "discount < 100".to_owned(),
+                no_inherit: true,
+            },
+        ]);
+    }
+
+    #[test]
+    fn it_promotes_column_unique_constraints_to_table_constraints() {
+        let mut package = package_sql(
+            "CREATE TABLE contacts(email varchar(100) NOT NULL UNIQUE, \
+             CONSTRAINT contacts_email_key UNIQUE (email));",
+        );
+        let project = Project::default();
+
+        // The column constraint is promoted without clashing with the existing name
+        package.set_defaults(&project);
+        let table = &package.tables[0];
+        assert_that!(table.columns[0].constraints).is_equal_to(vec![ast::ColumnConstraint::NotNull]);
+        assert_that!(table.constraints).has_length(2);
+        assert_that!(table.constraints[1]).is_equal_to(ast::TableConstraint::Unique {
+            name: "contacts_email_key1".to_owned(),
+            columns: vec!["email".to_owned()],
+            include: None,
+            parameters: None,
+        });
+    }
+
+    #[test]
+    fn it_sets_domain_defaults() {
+        let mut package = package_sql(
+            "CREATE DOMAIN quantity AS int CHECK (VALUE > 0) CONSTRAINT quantity_check CHECK (VALUE < 100);
+             CREATE DOMAIN rating AS quantity CHECK (VALUE <= 5);",
+        );
+        let project = Project::default();
+
+        // Unnamed constraints are named without clashing and custom types are qualified
+        package.set_defaults(&project);
+        let names = package
+            .types
+            .iter()
+            .map(|t| match t.kind {
+                ast::TypeDefinitionKind::Domain(ref domain) => domain
+                    .constraints
+                    .iter()
+                    .map(|c| c.name.clone().unwrap())
+                    .collect::<Vec<_>>(),
+                ref unexpected => panic!("Unexpected type kind: {}", unexpected),
+            })
+            .collect::<Vec<_>>();
+        assert_that!(names).is_equal_to(vec![
+            vec!["quantity_check1".to_owned(), "quantity_check".to_owned()],
+            vec!["rating_check".to_owned()],
+        ]);
+        match package.types[1].kind {
+            ast::TypeDefinitionKind::Domain(ref domain) => {
+                assert_that!(domain.base_type).is_equal_to(ast::SqlType::Custom(
+                    ast::ObjectName {
+                        schema: Some("public".to_owned()),
+                        name: "quantity".to_owned(),
+                    },
+                    Vec::new(),
+                    None,
+                ));
+            }
+            ref unexpected => panic!("Unexpected type kind: {}", unexpected),
+        }
+    }
+
     #[test]
     fn it_sets_index_defaults() {
         let mut package = package_sql("CREATE INDEX idx_person_name ON person(name);");
@@ -1225,7 +2312,7 @@ mod tests {
             assert_that!(index.table.name).is_equal_to("person".to_owned());
             assert_that!(index.columns).has_length(1);
             let col = &index.columns[0];
-            assert_that!(col.name).is_equal_to("name".to_owned());
+            assert_that!(col.key).is_equal_to(ast::IndexKey::Column("name".to_owned()));
             assert_that!(col.order).is_none();
             assert_that!(col.null_position).is_none();
         }
@@ -1243,7 +2330,7 @@ mod tests {
         assert_that!(index.table.name).is_equal_to("person".to_owned());
         assert_that!(index.columns).has_length(1);
         let col = &index.columns[0];
-        assert_that!(col.name).is_equal_to("name".to_owned());
+        assert_that!(col.key).is_equal_to(ast::IndexKey::Column("name".to_owned()));
         assert_that!(col.order)
             .is_some()
             .is_equal_to(ast::IndexOrder::Ascending);
@@ -1325,6 +2412,27 @@ mod tests {
         assert_fk_constraint!(graph, 7, "public.transaction", "fk_public_transaction__allocation_id");
     }
 
+    #[test]
+    fn it_generates_trigger_ordering() {
+        let package = package_sql(
+            "CREATE TRIGGER audit_items AFTER INSERT ON my.items FOR EACH ROW EXECUTE FUNCTION my.audit();
+             CREATE FUNCTION my.audit() RETURNS trigger AS $$ BEGIN RETURN NEW; END $$ LANGUAGE plpgsql;
+             CREATE TABLE my.items(id int);",
+        );
+        let logger = empty_logger();
+        let graph = package.generate_dependency_graph(&logger);
+
+        // Make sure we generated enough nodes (one table + one column + one function + one trigger).
+        assert_that!(graph).is_ok().has_length(4);
+        let graph = graph.unwrap();
+        let position = |name: &str| graph.iter().position(|node| node.to_string().eq(name)).unwrap();
+        let table = position("Table:      my.items");
+        let function = position("Function:   my.audit");
+        let trigger = position("Trigger:    my.items.audit_items");
+        assert_that!(trigger).is_greater_than(table);
+        assert_that!(trigger).is_greater_than(function);
+    }
+
     #[test]
     fn it_validates_missing_schema_references() {
         let mut package = package_sql("CREATE TABLE my.items(id int);");
@@ -1338,7 +2446,9 @@ mod tests {
         };
         assert_that!(validation_errors).has_length(1);
         match validation_errors[0] {
-            ValidationKind::SchemaMissing { ref schema, ref object } => {
+            ValidationKind::SchemaMissing {
+                ref schema, ref object, ..
+            } => {
                 assert_that!(*schema).is_equal_to("my".to_owned());
                 assert_that!(*object).is_equal_to("items".to_owned());
             }
@@ -1346,7 +2456,10 @@ mod tests {
         }
 
         // Add the schema and try again
-        package.schemas.push(ast::SchemaDefinition { name: "my".to_owned() });
+        package.schemas.push(ast::SchemaDefinition {
+            name: "my".to_owned(),
+            span: ast::Span::default(),
+        });
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
 
@@ -1368,7 +2481,7 @@ mod tests {
         };
         assert_that!(validation_errors).has_length(1);
         match validation_errors[0] {
-            ValidationKind::UnknownType { ref ty, ref table } => {
+            ValidationKind::UnknownType { ref ty, ref table, .. } => {
                 assert_that!(*ty).is_equal_to(ast::ObjectName {
                     schema: Some("public".to_string()),
                     name: "mytype".to_string(),
@@ -1385,6 +2498,7 @@ mod tests {
                 name: "mytype".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(Vec::new()),
+            span: ast::Span::default(),
         });
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
@@ -1411,6 +2525,7 @@ mod tests {
             ValidationKind::TableConstraintInvalidReferenceTable {
                 ref constraint,
                 ref table,
+                ..
             } => {
                 assert_that!(*constraint).is_equal_to("fk_parent_child".to_owned());
                 assert_that!(*table).is_equal_to("my.parent".to_owned());
@@ -1430,6 +2545,12 @@ mod tests {
                 constraints: Vec::new(),
             }],
             constraints: Vec::new(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            span: ast::Span::default(),
         });
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
@@ -1458,6 +2579,7 @@ mod tests {
                 ref constraint,
                 ref table,
                 ref columns,
+                ..
             } => {
                 assert_that!(*constraint).is_equal_to("fk_parent_child".to_owned());
                 assert_that!(*table).is_equal_to("my.parent".to_owned());
@@ -1502,6 +2624,7 @@ mod tests {
             ValidationKind::TableConstraintInvalidSourceColumns {
                 ref constraint,
                 ref columns,
+                ..
             } => {
                 assert_that!(*constraint).is_equal_to("fk_parent_child".to_owned());
                 assert_that!(*columns).has_length(1);
@@ -1522,6 +2645,36 @@ mod tests {
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
 
+    #[test]
+    fn it_validates_missing_source_column_in_unique_constraint() {
+        let package = package_sql(
+            "CREATE SCHEMA my;
+             CREATE TABLE my.bookings(room int, slot int,
+               CONSTRAINT uq_bookings UNIQUE (room) INCLUDE (slt),
+               CONSTRAINT ex_bookings EXCLUDE (room WITH =, slot WITH =));",
+        );
+        let result = package.validate(&Vec::new());
+
+        // Column `slt` is invalid
+        assert_that!(result).is_err();
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(1);
+        match validation_errors[0] {
+            ValidationKind::TableConstraintInvalidSourceColumns {
+                ref constraint,
+                ref columns,
+                ..
+            } => {
+                assert_that!(*constraint).is_equal_to("uq_bookings".to_owned());
+                assert_that!(*columns).is_equal_to(vec!["room".to_owned(), "slt".to_owned()]);
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
     #[test]
     fn it_validates_missing_reference_table_in_index() {
         let mut package = package_sql(
@@ -1539,7 +2692,9 @@ mod tests {
         };
         assert_that!(validation_errors).has_length(1);
         match validation_errors[0] {
-            ValidationKind::IndexInvalidReferenceTable { ref index, ref table } => {
+            ValidationKind::IndexInvalidReferenceTable {
+                ref index, ref table, ..
+            } => {
                 assert_that!(*index).is_equal_to("idx_company_name".to_owned());
                 assert_that!(*table).is_equal_to("my.company".to_owned());
             }
@@ -1565,6 +2720,12 @@ mod tests {
                 },
             ],
             constraints: Vec::new(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            span: ast::Span::default(),
         });
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
@@ -1590,6 +2751,7 @@ mod tests {
                 ref index,
                 ref table,
                 ref columns,
+                ..
             } => {
                 assert_that!(*index).is_equal_to("idx_person_number".to_owned());
                 assert_that!(*table).is_equal_to("my.person".to_owned());
@@ -1610,4 +2772,303 @@ mod tests {
         }
         assert_that!(package.validate(&Vec::new())).is_ok();
     }
+
+    #[test]
+    fn it_validates_missing_references_in_trigger() {
+        let mut package = package_sql(
+            "CREATE SCHEMA my;
+             CREATE TABLE my.person(id int, name varchar(50));
+             CREATE TRIGGER audit_company AFTER INSERT ON my.company EXECUTE FUNCTION my.audit();",
+        );
+        let result = package.validate(&Vec::new());
+
+        // Neither `my.company` nor `my.audit` exist
+        assert_that!(result).is_err();
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(2);
+        match validation_errors[0] {
+            ValidationKind::TriggerInvalidReferenceTable {
+                ref trigger, ref table, ..
+            } => {
+                assert_that!(*trigger).is_equal_to("audit_company".to_owned());
+                assert_that!(*table).is_equal_to("my.company".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+        match validation_errors[1] {
+            ValidationKind::TriggerInvalidReferenceFunction {
+                ref trigger,
+                ref function,
+                ..
+            } => {
+                assert_that!(*trigger).is_equal_to("audit_company".to_owned());
+                assert_that!(*function).is_equal_to("my.audit".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+
+        // Point the trigger at the person table and provide the function through a reference
+        package.triggers[0].table.name = "person".to_owned();
+        let mut reference = Package::new();
+        reference.functions.push(ast::FunctionDefinition {
+            name: ast::ObjectName {
+                schema: Some("my".to_owned()),
+                name: "audit".to_owned(),
+            },
+            arguments: Vec::new(),
+            return_type: ast::FunctionReturnType::SqlType(ast::SqlType::Custom(
+                ast::ObjectName {
+                    schema: None,
+                    name: "trigger".to_owned(),
+                },
+                Vec::new(),
+                None,
+            )),
+            body: "BEGIN RETURN NEW; END".to_owned(),
+            language: ast::FunctionLanguage::PostgreSQL,
+            attributes: Vec::new(),
+            span: ast::Span::default(),
+        });
+        assert_that!(package.validate(&[reference])).is_ok();
+    }
+
+    #[test]
+    fn it_attaches_policies_and_row_security_to_tables() {
+        let mut package = package_sql(
+            "CREATE TABLE accounts(id int, tenant_id int);
+             ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+             ALTER TABLE ONLY public.accounts FORCE ROW LEVEL SECURITY;
+             CREATE POLICY tenant_isolation ON accounts USING (tenant_id = current_setting('app.tenant')::int);",
+        );
+        package.set_defaults(&Project::default());
+        assert_that!(package.validate(&Vec::new())).is_ok();
+
+        let table = &package.tables[0];
+        assert_that!(table.row_security).is_true();
+        assert_that!(table.force_row_security).is_true();
+        assert_that!(table.policies).has_length(1);
+        assert_that!(table.policies[0].table.to_string()).is_equal_to("public.accounts".to_owned());
+
+        // Policies can only be attached to known tables
+        let mut policy = table.policies[0].clone();
+        policy.table.name = "invoices".to_owned();
+        let validation_errors = match package.push_policy(policy, "public") {
+            Err(PsqlpackError(ValidationError(errors), _)) => errors,
+            unexpected => panic!("Unexpected result: {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(1);
+        match validation_errors[0] {
+            ValidationKind::PolicyInvalidReferenceTable {
+                ref policy, ref table, ..
+            } => {
+                assert_that!(*policy).is_equal_to("tenant_isolation".to_owned());
+                assert_that!(*table).is_equal_to("public.invoices".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_orders_and_validates_partitions() {
+        let mut package = package_sql(
+            "CREATE TABLE logs_2020 PARTITION OF logs FOR VALUES FROM ('2020-01-01') TO ('2021-01-01');
+             CREATE TABLE logs(id int, logged date) PARTITION BY RANGE (logged);
+             CREATE TABLE logs_other PARTITION OF events DEFAULT;
+             CREATE INDEX idx_logs_2020_logged ON logs_2020 (logged);",
+        );
+        package.set_defaults(&Project::default());
+        assert_that!(package.tables[0].partition_of.as_ref().unwrap().parent.to_string())
+            .is_equal_to("public.logs".to_owned());
+
+        // Partitions are created after their parent
+        let logger = empty_logger();
+        let graph = package.generate_dependency_graph(&logger);
+        assert_that!(graph).is_ok();
+        let graph = graph.unwrap();
+        let position = |name: &str| graph.iter().position(|node| node.to_string().eq(name)).unwrap();
+        assert_that!(position("Table:      public.logs_2020")).is_greater_than(position("Table:      public.logs"));
+
+        // Indexes on a partition use the columns of the parent however `events` is unknown
+        let result = package.validate(&Vec::new());
+        assert_that!(result).is_err();
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(1);
+        match validation_errors[0] {
+            ValidationKind::PartitionInvalidReferenceTable {
+                ref partition,
+                ref table,
+                ..
+            } => {
+                assert_that!(*partition).is_equal_to("public.logs_other".to_owned());
+                assert_that!(*table).is_equal_to("public.events".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_validates_missing_references_in_sequences() {
+        let mut package = package_sql(
+            "CREATE SCHEMA my;
+             CREATE TABLE my.invoice(id serial, number bigint DEFAULT nextval('my.invoice_number'));
+             CREATE TABLE my.credit(id int DEFAULT nextval('my.invoice_id_seq'));
+             CREATE SEQUENCE my.credit_number OWNED BY my.credit.number;",
+        );
+        let result = package.validate(&Vec::new());
+
+        // `my.invoice_number` doesn't exist and `my.credit` has no number column. The implicit serial
+        // sequence `my.invoice_id_seq` is known however.
+        assert_that!(result).is_err();
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(2);
+        match validation_errors[0] {
+            ValidationKind::ColumnInvalidReferenceSequence {
+                ref table,
+                ref column,
+                ref sequence,
+                ..
+            } => {
+                assert_that!(*table).is_equal_to("my.invoice".to_owned());
+                assert_that!(*column).is_equal_to("number".to_owned());
+                assert_that!(*sequence).is_equal_to("my.invoice_number".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+        match validation_errors[1] {
+            ValidationKind::SequenceInvalidReferenceColumn {
+                ref sequence,
+                ref table,
+                ref column,
+                ..
+            } => {
+                assert_that!(*sequence).is_equal_to("my.credit_number".to_owned());
+                assert_that!(*table).is_equal_to("my.credit".to_owned());
+                assert_that!(*column).is_equal_to("number".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+
+        // Own the sequence by a known column and provide the missing sequence through a reference
+        package.sequences[0].owned_by.as_mut().unwrap().column = "id".to_owned();
+        let mut reference = Package::new();
+        reference.sequences.push(ast::SequenceDefinition {
+            name: ast::ObjectName {
+                schema: Some("my".to_owned()),
+                name: "invoice_number".to_owned(),
+            },
+            increment: None,
+            min_value: None,
+            max_value: None,
+            start: None,
+            cache: None,
+            cycle: false,
+            owned_by: None,
+            span: ast::Span::default(),
+        });
+        assert_that!(package.validate(&[reference])).is_ok();
+    }
+    #[test]
+    fn it_validates_duplicate_function_signatures() {
+        let mut package = package_sql(
+            "CREATE FUNCTION describe(value varchar(10)) RETURNS text AS $$ SELECT 'varchar' $$ LANGUAGE SQL;
+             CREATE FUNCTION describe(value int) RETURNS text AS $$ SELECT 'int' $$ LANGUAGE SQL;
+             CREATE FUNCTION public.describe(other character varying, OUT result text) RETURNS text AS $$ SELECT 'other' $$ LANGUAGE SQL;",
+        );
+        package.set_defaults(&Project::default());
+        let result = package.validate(&Vec::new());
+
+        // Overloads are allowed, however argument names, type modifiers and output arguments don't distinguish them
+        assert_that!(result).is_err();
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(1);
+        match validation_errors[0] {
+            ValidationKind::DuplicateFunction { ref signature, .. } => {
+                assert_that!(*signature).is_equal_to("public.describe(varchar)".to_owned());
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_can_write_overloaded_procedures_to_a_package() {
+        let mut package = package_sql(
+            "CREATE PROCEDURE archive(name text) LANGUAGE SQL AS $$ SELECT 1 $$;
+             CREATE PROCEDURE archive(id int) LANGUAGE SQL AS $$ SELECT 2 $$;",
+        );
+        package.set_defaults(&Project::default());
+
+        let path = std::env::temp_dir().join("psqlpack_overloaded_procedures.psqlpack");
+        let result = package.write_to(&path);
+        assert_that!(result).is_ok();
+        let result = Package::from_packaged_file(&empty_logger(), &path);
+        assert_that!(result).is_ok();
+
+        // Each overload has its own entry rather than one replacing the other
+        let mut archive = ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut entries = (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().name().to_owned())
+            .filter(|name| name.starts_with("procedures/") && name.ends_with(".json"))
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_that!(entries).is_equal_to(vec![
+            "procedures/public.archive(int).json".to_owned(),
+            "procedures/public.archive(text).json".to_owned(),
+        ]);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut signatures = result
+            .unwrap()
+            .procedures
+            .iter()
+            .map(|procedure| procedure.signature())
+            .collect::<Vec<_>>();
+        signatures.sort();
+        assert_that!(signatures).is_equal_to(vec![
+            "public.archive(int)".to_owned(),
+            "public.archive(text)".to_owned(),
+        ]);
+    }
+
+    #[test]
+    fn it_reports_where_invalid_objects_were_defined() {
+        let mut package = package_sql(
+            "CREATE SCHEMA my;
+             CREATE TABLE my.person(id int);
+             CREATE INDEX idx_person_id ON my.people (id);",
+        );
+        for index in package.indexes.iter_mut() {
+            index.span.file = Some("indexes.sql".to_owned());
+        }
+        let result = package.validate(&Vec::new());
+
+        // The index refers to an unknown table and the error points at where the index was defined
+        assert_that!(result).is_err();
+        let error = result.err().unwrap();
+        assert_that!(error.to_string()).contains("indexes.sql:3:14: Index `idx_person_id`");
+        let validation_errors = match error {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_that!(validation_errors).has_length(1);
+        let span = validation_errors[0].span().unwrap();
+        assert_that!(span.start.line).is_equal_to(3);
+        assert_that!(span.end.line).is_equal_to(3);
+
+        // Objects that weren't defined in source have no location
+        package.indexes[0].span = ast::Span::default();
+        let error = package.validate(&Vec::new()).err().unwrap();
+        assert_that!(error.to_string()).contains(" - Index `idx_person_id`");
+    }
 }
@@ -57,6 +57,11 @@ pub struct GenerationOptions {
     /// Default: Error
     #[serde(alias = "dropEnumValues", default = "Toggle::error")]
     pub drop_enum_values: Toggle,
+    /// Composite type attributes may hold data and some type changes can only be made by recreating the type.
+    /// If set to Allow, psqlpack will drop composite attributes and recreate types that can't be altered in place.
+    /// Default: Error
+    #[serde(alias = "dropTypes", default = "Toggle::error")]
+    pub drop_types: Toggle,
     /// Tables may have data in them which may not be intended to be deleted. If set to Allow, psqlpack will drop the table.
     /// Default: Error
     #[serde(alias = "dropTables", default = "Toggle::error")]
@@ -73,6 +78,14 @@ pub struct GenerationOptions {
     /// Default: Allow
     #[serde(alias = "dropForeignKeyConstraints", default = "Toggle::allow")]
     pub drop_foreign_key_constraints: Toggle,
+    /// Check constraints restrict the values allowed within a table. If set to Allow, psqlpack will drop the check constraint.
+    /// Default: Allow
+    #[serde(alias = "dropCheckConstraints", default = "Toggle::allow")]
+    pub drop_check_constraints: Toggle,
+    /// Unique and exclusion constraints restrict duplicate values within a table. If set to Allow, psqlpack will drop the constraint.
+    /// Default: Allow
+    #[serde(alias = "dropUniqueConstraints", default = "Toggle::allow")]
+    pub drop_unique_constraints: Toggle,
     /// Functions may not be intended to be deleted. If set to Allow, psqlpack will drop the function.
     /// Default: Error
     #[serde(alias = "dropFunctions", default = "Toggle::error")]
@@ -81,6 +94,32 @@ pub struct GenerationOptions {
     /// Default: Allow
     #[serde(alias = "dropIndexes", default = "Toggle::allow")]
     pub drop_indexes: Toggle,
+    /// Views may be depended upon by other objects. If set to Allow, psqlpack will drop the view.
+    /// Default: Allow
+    #[serde(alias = "dropViews", default = "Toggle::allow")]
+    pub drop_views: Toggle,
+    /// Materialized views may be depended upon by other objects. If set to Allow, psqlpack will drop the materialized view.
+    /// Default: Allow
+    #[serde(alias = "dropMaterializedViews", default = "Toggle::allow")]
+    pub drop_materialized_views: Toggle,
+    /// Triggers may not be intended to be deleted. If set to Allow, psqlpack will drop the trigger.
+    /// Default: Allow
+    #[serde(alias = "dropTriggers", default = "Toggle::allow")]
+    pub drop_triggers: Toggle,
+    /// Row level security policies may have been created outside of psqlpack. If set to Allow, psqlpack will drop the policy.
+    /// Default: Allow
+    #[serde(alias = "dropPolicies", default = "Toggle::allow")]
+    pub drop_policies: Toggle,
+    /// Sequences hold their current value which would be lost. If set to Allow, psqlpack will drop the sequence.
+    /// Default: Error
+    #[serde(alias = "dropSequences", default = "Toggle::error")]
+    pub drop_sequences: Toggle,
+
+    /// Privileges and role memberships missing from the source may have been granted outside of psqlpack. If set to Allow,
+    /// psqlpack will revoke them. Role memberships are only revoked for roles that the source grants membership of.
+    /// Default: Ignore
+    #[serde(alias = "revokePrivileges", default = "Toggle::ignore")]
+    pub revoke_privileges: Toggle,
 
     /// Extensions may not be intended to be upgraded automatically. If set to Allow, psqlpack will upgrade the extension as necessary.
     /// Default: Ignore
@@ -91,6 +130,11 @@ pub struct GenerationOptions {
     /// Default: true
     #[serde(alias = "forceConcurrentIndexes", default = "Bool::t")]
     pub force_concurrent_indexes: bool,
+
+    /// If set to true, materialized views will be refreshed once all other changes have been applied.
+    /// Default: false
+    #[serde(alias = "refreshMaterializedViews", default)]
+    pub refresh_materialized_views: bool,
 }
 
 impl Default for PublishProfile {
@@ -101,16 +145,28 @@ impl Default for PublishProfile {
                 always_recreate_database: false,
 
                 drop_enum_values: Toggle::Error,
+                drop_types: Toggle::Error,
                 drop_tables: Toggle::Error,
                 drop_columns: Toggle::Error,
                 drop_primary_key_constraints: Toggle::Error,
                 drop_foreign_key_constraints: Toggle::Allow,
+                drop_check_constraints: Toggle::Allow,
+                drop_unique_constraints: Toggle::Allow,
                 drop_functions: Toggle::Error,
                 drop_indexes: Toggle::Allow,
+                drop_views: Toggle::Allow,
+                drop_materialized_views: Toggle::Allow,
+                drop_triggers: Toggle::Allow,
+                drop_policies: Toggle::Allow,
+                drop_sequences: Toggle::Error,
+
+                revoke_privileges: Toggle::Ignore,
 
                 upgrade_extensions: Toggle::Ignore,
 
                 force_concurrent_indexes: true,
+
+                refresh_materialized_views: false,
             },
         }
     }
@@ -160,13 +216,23 @@ mod tests {
               "generationOptions": {
                 "alwaysRecreateDatabase": false,
                 "dropEnumValues": "Error",
+                "dropTypes": "Allow",
                 "dropFunctions": "Error",
                 "dropTables": "Error",
                 "dropColumns": "Error",
                 "dropPrimaryKeyConstraints": "Error",
                 "dropForeignKeyConstraints": "Allow",
+                "dropCheckConstraints": "Error",
+                "dropUniqueConstraints": "Error",
                 "dropIndexes": "Ignore",
-                "forceConcurrentIndexes": false
+                "dropViews": "Error",
+                "dropMaterializedViews": "Error",
+                "dropTriggers": "Error",
+                "dropPolicies": "Error",
+                "dropSequences": "Allow",
+                "revokePrivileges": "Allow",
+                "forceConcurrentIndexes": false,
+                "refreshMaterializedViews": true
               }
             }
         "#;
@@ -176,13 +242,23 @@ mod tests {
         let options = publish_profile.generation_options;
         assert_that!(options.always_recreate_database).is_false();
         assert_that!(options.drop_enum_values).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_types).is_equal_to(Toggle::Allow);
         assert_that!(options.drop_functions).is_equal_to(Toggle::Error);
         assert_that!(options.drop_tables).is_equal_to(Toggle::Error);
         assert_that!(options.drop_columns).is_equal_to(Toggle::Error);
         assert_that!(options.drop_primary_key_constraints).is_equal_to(Toggle::Error);
         assert_that!(options.drop_foreign_key_constraints).is_equal_to(Toggle::Allow);
+        assert_that!(options.drop_check_constraints).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_unique_constraints).is_equal_to(Toggle::Error);
         assert_that!(options.drop_indexes).is_equal_to(Toggle::Ignore);
+        assert_that!(options.drop_views).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_materialized_views).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_triggers).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_policies).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_sequences).is_equal_to(Toggle::Allow);
+        assert_that!(options.revoke_privileges).is_equal_to(Toggle::Allow);
         assert_that!(options.force_concurrent_indexes).is_false();
+        assert_that!(options.refresh_materialized_views).is_true();
     }
 
     #[test]
@@ -193,13 +269,23 @@ mod tests {
             [generationOptions]
             always_recreate_database = false
             drop_enum_values = "Error"
+            drop_types = "Allow"
             drop_functions = "Error"
             drop_tables = "Error"
             drop_columns = "Error"
             drop_primary_key_constraints = "Error"
             drop_foreign_key_constraints = "Allow"
+            drop_check_constraints = "Error"
+            drop_unique_constraints = "Error"
             drop_indexes = "Ignore"
+            drop_views = "Error"
+            drop_materialized_views = "Error"
+            drop_triggers = "Error"
+            drop_policies = "Error"
+            drop_sequences = "Allow"
+            revoke_privileges = "Allow"
             force_concurrent_indexes = false
+            refresh_materialized_views = true
         "#;
         let publish_profile = PublishProfile::from_reader(DATA.as_bytes());
         let publish_profile = publish_profile.unwrap();
@@ -207,12 +293,22 @@ mod tests {
         let options = publish_profile.generation_options;
         assert_that!(options.always_recreate_database).is_false();
         assert_that!(options.drop_enum_values).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_types).is_equal_to(Toggle::Allow);
         assert_that!(options.drop_functions).is_equal_to(Toggle::Error);
         assert_that!(options.drop_tables).is_equal_to(Toggle::Error);
         assert_that!(options.drop_columns).is_equal_to(Toggle::Error);
         assert_that!(options.drop_primary_key_constraints).is_equal_to(Toggle::Error);
         assert_that!(options.drop_foreign_key_constraints).is_equal_to(Toggle::Allow);
+        assert_that!(options.drop_check_constraints).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_unique_constraints).is_equal_to(Toggle::Error);
         assert_that!(options.drop_indexes).is_equal_to(Toggle::Ignore);
+        assert_that!(options.drop_views).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_materialized_views).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_triggers).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_policies).is_equal_to(Toggle::Error);
+        assert_that!(options.drop_sequences).is_equal_to(Toggle::Allow);
+        assert_that!(options.revoke_privileges).is_equal_to(Toggle::Allow);
         assert_that!(options.force_concurrent_indexes).is_false();
+        assert_that!(options.refresh_materialized_views).is_true();
     }
 }
@@ -1,7 +1,8 @@
+use std::cmp;
 use std::default::Default;
 use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 
 use glob::glob;
@@ -12,7 +13,8 @@ use crate::errors::PsqlpackErrorKind::*;
 use crate::errors::{PsqlpackError, PsqlpackResult, PsqlpackResultExt};
 use crate::model::Package;
 use crate::sql::ast::*;
-use crate::sql::lexer;
+use crate::sql::formatter::{FormatOptions, Formatter};
+use crate::sql::lexer::{self, LexicalError};
 use crate::sql::parser::StatementListParser;
 use crate::Semver;
 
@@ -73,6 +75,32 @@ pub struct Project {
     /// An array of search paths to look in outside of the standard paths (./lib, ~/.psqlpack/lib).
     #[serde(alias = "referenceSearchPaths", skip_serializing_if = "Option::is_none")]
     pub reference_search_paths: Option<Vec<String>>,
+
+    /// How statements that psqlpack doesn't understand are handled. If set to Warn, they are reported and skipped.
+    /// If set to PostDeploy, they are run verbatim in a generated post deployment script.
+    /// Default: Error
+    #[serde(alias = "unsupportedStatements", default = "UnsupportedStatements::error")]
+    pub unsupported_statements: UnsupportedStatements,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum UnsupportedStatements {
+    Warn,
+    PostDeploy,
+    Error,
+}
+
+impl UnsupportedStatements {
+    fn error() -> UnsupportedStatements {
+        UnsupportedStatements::Error
+    }
+}
+
+/// The files changed by formatting a project, along with any files that were left as is and why
+#[derive(Debug, Default, PartialEq)]
+pub struct FormattedFiles {
+    pub changed: Vec<PathBuf>,
+    pub skipped: Vec<(PathBuf, String)>,
 }
 
 #[derive(Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -104,6 +132,7 @@ impl Default for Project {
             include_globs: None,
             exclude_globs: None,
             reference_search_paths: None,
+            unsupported_statements: UnsupportedStatements::Error,
         }
     }
 }
@@ -151,36 +180,14 @@ impl Project {
 
     pub fn build_package(&self, log: &Logger) -> PsqlpackResult<Package> {
         let log = log.new(o!("project" => "build_package"));
-
-        // Turn the pre/post into paths to quickly check
-        let parent = match self.project_file_path {
-            Some(ref path) => path.parent().unwrap().canonicalize().unwrap(),
-            None => bail!(GenerationError("Project path not set".to_owned())),
-        };
-        let make_path = |script: &str| {
-            parent
-                .join(Path::new(script))
-                .canonicalize()
-                .chain_err(|| InvalidScriptPath(script.to_owned()))
-        };
-
-        trace!(log, "Canonicalizing predeploy paths");
-        let mut predeploy_paths = Vec::new();
-        for script in &self.pre_deploy_scripts {
-            predeploy_paths.push(make_path(script)?);
-        }
-        trace!(log, "Done predeploy paths"; "count" => predeploy_paths.len());
-
-        trace!(log, "Canonicalizing postdeploy paths");
-        let mut postdeploy_paths = Vec::new();
-        for script in &self.post_deploy_scripts {
-            postdeploy_paths.push(make_path(script)?);
-        }
-        trace!(log, "Done postdeploy paths"; "count" => postdeploy_paths.len());
+        let (parent, predeploy_paths, postdeploy_paths) = self.resolve_paths(&log)?;
 
         // Start the package
         let mut package = Package::new();
         let mut errors: Vec<PsqlpackError> = Vec::new();
+        let mut table_alterations = Vec::new();
+        let mut policies = Vec::new();
+        let mut unsupported = Vec::new();
 
         // Add extensions into package
         if let Some(ref extensions) = self.extensions {
@@ -225,19 +232,17 @@ impl Project {
                 trace!(log, "Tokenizing file");
                 let tokens = match lexer::tokenize_stmt(&contents[..]) {
                     Ok(t) => t,
-                    Err(e) => {
-                        errors.push(
-                            SyntaxError(
-                                format!("{}", path.display()),
-                                e.line.to_owned(),
-                                e.line_number as usize,
-                                e.start_pos as usize,
-                                e.end_pos as usize,
-                            )
-                            .into(),
-                        );
-                        continue;
-                    }
+                    Err(lexical_errors) => match self.unsupported_statements {
+                        UnsupportedStatements::Error => {
+                            errors.extend(syntax_errors(&path, lexical_errors));
+                            continue;
+                        }
+                        // Statements that can't be tokenized are unsupported too
+                        UnsupportedStatements::Warn | UnsupportedStatements::PostDeploy => {
+                            trace!(log, "Tokenizing each statement separately"; "errors" => lexical_errors.len());
+                            lexer::tokenize_stmt_tolerant(&contents[..])
+                        }
+                    },
                 };
                 trace!(log, "Finished tokenizing"; "count" => tokens.len());
 
@@ -247,19 +252,51 @@ impl Project {
                 match StatementListParser::new().parse(tokens) {
                     Ok(statement_list) => {
                         trace!(log, "Finished parsing statements"; "count" => statement_list.len());
-                        for statement in statement_list {
+                        let mut parse_errors = Vec::new();
+                        for mut statement in statement_list {
+                            statement.set_source(&format!("{}", path.display()), &contents);
                             dump_statement!(log, statement);
                             match statement {
+                                Statement::Aggregate(aggregate_definition) => {
+                                    package.push_aggregate(aggregate_definition)
+                                }
+                                Statement::AlterTable(name, action) => table_alterations.push((name, action)),
+                                Statement::Comment(comment_definition) => package.push_comment(comment_definition),
                                 Statement::Error(kind) => {
                                     errors.push(HandledParseError(kind).into());
                                 }
                                 Statement::Function(function_definition) => package.push_function(function_definition),
                                 Statement::Index(index_definition) => package.push_index(index_definition),
+                                Statement::MaterializedView(view_definition) => {
+                                    package.push_materialized_view(view_definition)
+                                }
+                                Statement::Policy(policy_definition) => policies.push(policy_definition),
+                                Statement::Privileges(privileges) => {
+                                    for privilege in privileges {
+                                        package.push_privilege(privilege);
+                                    }
+                                }
+                                Statement::Procedure(procedure_definition) => {
+                                    package.push_procedure(procedure_definition)
+                                }
                                 Statement::Schema(schema_definition) => package.push_schema(schema_definition),
+                                Statement::Sequence(sequence_definition) => package.push_sequence(sequence_definition),
                                 Statement::Table(table_definition) => package.push_table(table_definition),
+                                Statement::Trigger(trigger_definition) => package.push_trigger(trigger_definition),
                                 Statement::Type(type_definition) => package.push_type(type_definition),
+                                Statement::Unsupported { text, span, error } => match self.unsupported_statements {
+                                    UnsupportedStatements::Warn => {
+                                        warn!(log, "Skipping unsupported statement"; "location" => span.to_string())
+                                    }
+                                    UnsupportedStatements::PostDeploy => unsupported.push(text),
+                                    UnsupportedStatements::Error => parse_errors.push(error),
+                                },
+                                Statement::View(view_definition) => package.push_view(view_definition),
                             }
                         }
+                        if !parse_errors.is_empty() {
+                            errors.push(ParseError(format!("{}", path.display()), parse_errors).into());
+                        }
                     }
                     Err(err) => {
                         errors.push(ParseError(format!("{}", path.display()), vec![err]).into());
@@ -269,6 +306,38 @@ impl Project {
             }
         }
 
+        // Unsupported statements are run as is after everything else has been deployed
+        if !unsupported.is_empty() {
+            let contents = unsupported
+                .iter()
+                .map(|text| {
+                    if text.ends_with(';') {
+                        text.to_owned()
+                    } else {
+                        format!("{};", text)
+                    }
+                })
+                .collect::<Vec<_>>();
+            package.push_script(ScriptDefinition {
+                name: "unsupported_statements.sql".to_owned(),
+                kind: ScriptKind::PostDeployment,
+                order: self.post_deploy_scripts.len(),
+                contents: contents.join("\n\n"),
+            });
+        }
+
+        // Table options and policies can only be applied once every table has been loaded
+        for (name, action) in table_alterations {
+            if let Err(error) = package.alter_table(&name, action, &self.default_schema) {
+                errors.push(error);
+            }
+        }
+        for policy in policies {
+            if let Err(error) = package.push_policy(policy, &self.default_schema) {
+                errors.push(error);
+            }
+        }
+
         // Early exit if errors
         if !errors.is_empty() {
             bail!(MultipleErrors(errors));
@@ -285,6 +354,118 @@ impl Project {
         Ok(package)
     }
 
+    // Resolves the project directory along with the pre/post deployment scripts so that they can be quickly checked
+    fn resolve_paths(&self, log: &Logger) -> PsqlpackResult<(PathBuf, Vec<PathBuf>, Vec<PathBuf>)> {
+        let parent = match self.project_file_path {
+            Some(ref path) => path.parent().unwrap().canonicalize().unwrap(),
+            None => bail!(GenerationError("Project path not set".to_owned())),
+        };
+        let make_path = |script: &str| {
+            parent
+                .join(Path::new(script))
+                .canonicalize()
+                .chain_err(|| InvalidScriptPath(script.to_owned()))
+        };
+
+        trace!(log, "Canonicalizing predeploy paths");
+        let mut predeploy_paths = Vec::new();
+        for script in &self.pre_deploy_scripts {
+            predeploy_paths.push(make_path(script)?);
+        }
+        trace!(log, "Done predeploy paths"; "count" => predeploy_paths.len());
+
+        trace!(log, "Canonicalizing postdeploy paths");
+        let mut postdeploy_paths = Vec::new();
+        for script in &self.post_deploy_scripts {
+            postdeploy_paths.push(make_path(script)?);
+        }
+        trace!(log, "Done postdeploy paths"; "count" => postdeploy_paths.len());
+
+        Ok((parent, predeploy_paths, postdeploy_paths))
+    }
+
+    /// Rewrites the project's SQL files in canonical form, returning the files that were changed. Deployment
+    /// scripts are left as is. Files containing comments are skipped since comments can't be preserved, and
+    /// are returned along with that reason.
+    pub fn format_files(&self, log: &Logger, options: &FormatOptions) -> PsqlpackResult<FormattedFiles> {
+        let log = log.new(o!("project" => "format_files"));
+        let (parent, predeploy_paths, postdeploy_paths) = self.resolve_paths(&log)?;
+        let formatter = Formatter::new(options.clone());
+
+        let mut files = FormattedFiles::default();
+        let mut errors: Vec<PsqlpackError> = Vec::new();
+        for path in self.walk_files(&parent)? {
+            let log = log.new(o!("file" => path.to_str().unwrap().to_owned()));
+
+            let real_path = path.to_path_buf().canonicalize().unwrap();
+            if predeploy_paths.contains(&real_path) || postdeploy_paths.contains(&real_path) {
+                trace!(log, "Skipping deployment script");
+                continue;
+            }
+
+            let mut contents = String::new();
+            if let Err(err) = File::open(&path).and_then(|mut f| f.read_to_string(&mut contents)) {
+                error!(log, "Error reading file");
+                errors.push(IOError(format!("{}", path.display()), format!("{}", err)).into());
+                continue;
+            }
+
+            let tokens = match lexer::tokenize_stmt(&contents[..]) {
+                Ok(t) => t,
+                Err(lexical_errors) => {
+                    errors.extend(syntax_errors(&path, lexical_errors));
+                    continue;
+                }
+            };
+
+            // Comments are the only text the lexer doesn't produce tokens for
+            let mut position = 0;
+            let mut commented = false;
+            for (start, _, end) in &tokens {
+                if start.offset > position && !contents[position..start.offset].trim().is_empty() {
+                    commented = true;
+                    break;
+                }
+                position = cmp::max(position, end.offset);
+            }
+            if commented || !contents[position..].trim().is_empty() {
+                trace!(log, "Skipping file containing comments");
+                files
+                    .skipped
+                    .push((path, "contains comments, which formatting would remove".into()));
+                continue;
+            }
+
+            let mut statements = match StatementListParser::new().parse(tokens) {
+                Ok(statements) => statements,
+                Err(err) => {
+                    errors.push(ParseError(format!("{}", path.display()), vec![err]).into());
+                    continue;
+                }
+            };
+
+            // Unsupported statements are written as they were found
+            for statement in &mut statements {
+                statement.set_source(&format!("{}", path.display()), &contents);
+            }
+            let formatted = formatter.statements(&statements);
+            if formatted != contents {
+                trace!(log, "Writing formatted file");
+                if let Err(err) = File::create(&path).and_then(|mut f| f.write_all(formatted.as_bytes())) {
+                    error!(log, "Error writing file");
+                    errors.push(IOError(format!("{}", path.display()), format!("{}", err)).into());
+                    continue;
+                }
+                files.changed.push(path);
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!(MultipleErrors(errors));
+        }
+        Ok(files)
+    }
+
     // Walk the files according to the include and exclude globs. This could be made more efficient with an iterator
     // in the future (may want to extend glob). One downside of the current implementation is that pre/post deploy
     // scripts could be inadvertantly excluded
@@ -324,12 +505,31 @@ impl Project {
     }
 }
 
+fn syntax_errors(path: &Path, lexical_errors: Vec<LexicalError>) -> Vec<PsqlpackError> {
+    lexical_errors
+        .into_iter()
+        .map(|e| {
+            SyntaxError(
+                format!("{}", path.display()),
+                e.line.to_owned(),
+                e.line_number,
+                e.start_pos,
+                e.end_pos,
+            )
+            .into()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::model::project::Project;
-    use crate::{Dependency, Semver};
+    use crate::model::project::{Project, UnsupportedStatements};
+    use crate::{Dependency, FormatOptions, Semver};
+    use slog::{Discard, Drain, Logger};
     use spectral::prelude::*;
+    use std::env;
+    use std::fs;
     use std::path::Path;
 
     #[test]
@@ -365,6 +565,7 @@ mod tests {
             include_globs: None,
             exclude_globs: Some(vec!["**/*org*".into()]),
             reference_search_paths: None,
+            unsupported_statements: UnsupportedStatements::Error,
         };
         let result = project.walk_files(&parent);
 
@@ -394,6 +595,7 @@ mod tests {
             include_globs: None,
             exclude_globs: Some(vec!["**/geo/**/*.sql".into(), "**/geo.*".into()]),
             reference_search_paths: None,
+            unsupported_statements: UnsupportedStatements::Error,
         };
         let result = project.walk_files(&parent);
 
@@ -421,6 +623,7 @@ mod tests {
             include_globs: Some(vec!["**/*org*.sql".into()]),
             exclude_globs: None,
             reference_search_paths: None,
+            unsupported_statements: UnsupportedStatements::Error,
         };
         let result = project.walk_files(&parent);
 
@@ -452,7 +655,8 @@ mod tests {
                     { "name": "postgis" },
                     { "name": "postgis_topology" },
                     { "name": "postgis_tiger_geocoder" }
-                ]
+                ],
+                "unsupportedStatements": "PostDeploy"
             }
         "#;
         let project = Project::from_reader(DATA.as_bytes());
@@ -488,6 +692,7 @@ mod tests {
             name: "postgis_tiger_geocoder".into(),
             version: None,
         });
+        assert_that!(project.unsupported_statements).is_equal_to(UnsupportedStatements::PostDeploy);
     }
 
     #[test]
@@ -511,6 +716,7 @@ mod tests {
                 { name = "postgis_topology" },
                 { name = "postgis_tiger_geocoder" }
             ]
+            unsupported_statements = "Warn"
         "#;
         let project = Project::from_reader(DATA.as_bytes());
         let project = project.unwrap();
@@ -545,5 +751,90 @@ mod tests {
             name: "postgis_tiger_geocoder".into(),
             version: None,
         });
+        assert_that!(project.unsupported_statements).is_equal_to(UnsupportedStatements::Warn);
+    }
+
+    #[test]
+    fn it_can_format_the_files_of_a_project() {
+        let root = env::temp_dir().join("psqlpack_format_files");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        let write = |file: &str, contents: &str| fs::write(root.join(file), contents).unwrap();
+        write(
+            "project.json",
+            r#"{
+                "version": "1.0",
+                "defaultSchema": "public",
+                "preDeployScripts": [],
+                "postDeployScripts": ["scripts/seed.sql"]
+            }"#,
+        );
+        write("contacts.sql", "create table contacts(id int not null,name text);");
+        write("formatted.sql", "CREATE SCHEMA app;\n");
+        write("commented.sql", "-- Kept as is\ncreate table notes(id int);");
+        write("scripts/seed.sql", "insert into contacts values (1, 'one');");
+
+        let log = Logger::root(Discard.fuse(), o!());
+        let project = Project::from_project_file(&log, &root.join("project.json")).unwrap();
+        let files = project.format_files(&log, &FormatOptions::default()).unwrap();
+        assert_that!(files.changed).has_length(1);
+        assert_that!(files.changed[0].ends_with("contacts.sql")).is_true();
+        assert_that!(files.skipped).has_length(1);
+        assert_that!(files.skipped[0].0.ends_with("commented.sql")).is_true();
+        assert_that!(files.skipped[0].1).contains("comments");
+
+        let read = |file: &str| fs::read_to_string(root.join(file)).unwrap();
+        assert_that!(read("contacts.sql"))
+            .is_equal_to("CREATE TABLE contacts (id int NOT NULL, name text);\n".to_owned());
+        assert_that!(read("commented.sql")).is_equal_to("-- Kept as is\ncreate table notes(id int);".to_owned());
+        assert_that!(read("scripts/seed.sql")).is_equal_to("insert into contacts values (1, 'one');".to_owned());
+
+        // Formatting is stable
+        let files = project.format_files(&log, &FormatOptions::default()).unwrap();
+        assert_that!(files.changed).is_empty();
+        assert_that!(files.skipped).has_length(1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_tolerates_statements_that_cannot_be_tokenized() {
+        let root = env::temp_dir().join("psqlpack_untokenizable_statements");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("project.json"),
+            r#"{ "version": "1.0", "defaultSchema": "public", "preDeployScripts": [], "postDeployScripts": [] }"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("contacts.sql"),
+            "CREATE TABLE contacts (id int NOT NULL, name text);
+INSERT INTO contacts VALUES (1, E'it\\'s; escaped');
+CREATE TABLE notes (id int NOT NULL);",
+        )
+        .unwrap();
+
+        let log = Logger::root(Discard.fuse(), o!());
+        let mut project = Project::from_project_file(&log, &root.join("project.json")).unwrap();
+
+        project.unsupported_statements = UnsupportedStatements::Error;
+        assert_that!(project.build_package(&log)).is_err();
+
+        project.unsupported_statements = UnsupportedStatements::Warn;
+        let package = project.build_package(&log);
+        assert_that!(package).is_ok();
+        let package = package.unwrap();
+        assert_that!(package.tables).has_length(2);
+        assert_that!(package.scripts).is_empty();
+
+        project.unsupported_statements = UnsupportedStatements::PostDeploy;
+        let package = project.build_package(&log);
+        assert_that!(package).is_ok();
+        let package = package.unwrap();
+        assert_that!(package.tables).has_length(2);
+        assert_that!(package.scripts).has_length(1);
+        assert_that!(package.scripts[0].contents)
+            .is_equal_to("INSERT INTO contacts VALUES (1, E'it\\'s; escaped');".to_owned());
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -1,8 +1,105 @@
+use lalrpop_util::ParseError;
 use rust_decimal::Decimal;
 
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, PartialEq)]
+use crate::sql::lexer::Token;
+
+// A position within the source text. Lines and columns start at 1, whereas the offset is in bytes
+// from the start of the text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    // The location immediately after the given character
+    pub fn after(self, c: char) -> Location {
+        Location {
+            offset: self.offset + c.len_utf8(),
+            line: self.line,
+            column: self.column + 1,
+        }
+    }
+
+    // Translates a location within a fragment of text into one within the text the fragment starts at `base` in
+    pub fn relative_to(self, base: Location) -> Location {
+        Location {
+            offset: base.offset + self.offset,
+            line: base.line + self.line - 1,
+            column: if self.line == 1 {
+                base.column + self.column - 1
+            } else {
+                self.column
+            },
+        }
+    }
+}
+
+// Where a definition came from in the project source. Definitions loaded from the database have no span.
+// Spans never take part in comparisons so that the same object parsed from different places is still
+// considered equal.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub file: Option<String>,
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Span { file: None, start, end }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.start.line > 0
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _: &Span) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+impl PartialOrd for Span {
+    fn partial_cmp(&self, other: &Span) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Span {
+    fn cmp(&self, _: &Span) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Hash for Span {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.file {
+            Some(ref file) => write!(f, "{}:{}", file, self.start),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum ErrorKind {
     ExtensionNotSupported(String),
 }
@@ -19,14 +116,73 @@ impl fmt::Display for ErrorKind {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Statement {
+    Aggregate(AggregateDefinition),
+    AlterTable(ObjectName, AlterTableAction),
+    Comment(CommentDefinition),
     Error(ErrorKind),
     Function(FunctionDefinition),
     Index(IndexDefinition),
+    MaterializedView(MaterializedViewDefinition),
+    Policy(PolicyDefinition),
+    Privileges(Vec<PrivilegeDefinition>),
+    Procedure(ProcedureDefinition),
     Schema(SchemaDefinition),
+    Sequence(SequenceDefinition),
     Table(TableDefinition),
+    Trigger(TriggerDefinition),
     Type(TypeDefinition),
+    // A statement the grammar doesn't know, along with why it couldn't be parsed
+    Unsupported {
+        text: String,
+        span: Span,
+        error: ParseError<Location, Token, &'static str>,
+    },
+    View(ViewDefinition),
+}
+
+impl Statement {
+    pub fn with_span(mut self, span: Span) -> Self {
+        for s in self.spans_mut() {
+            *s = span.clone();
+        }
+        self
+    }
+
+    // Records the file a statement was parsed from. Unsupported statements keep their original text.
+    pub fn set_source(&mut self, file: &str, contents: &str) {
+        for span in self.spans_mut() {
+            span.file = Some(file.to_owned());
+        }
+        if let Statement::Unsupported {
+            ref mut text, ref span, ..
+        } = *self
+        {
+            *text = contents[span.start.offset..span.end.offset].to_owned();
+        }
+    }
+
+    fn spans_mut(&mut self) -> Vec<&mut Span> {
+        match *self {
+            Statement::Aggregate(ref mut aggregate) => vec![&mut aggregate.span],
+            Statement::AlterTable(..) | Statement::Error(_) => Vec::new(),
+            Statement::Comment(ref mut comment) => vec![&mut comment.span],
+            Statement::Function(ref mut function) => vec![&mut function.span],
+            Statement::Index(ref mut index) => vec![&mut index.span],
+            Statement::MaterializedView(ref mut view) => vec![&mut view.span],
+            Statement::Policy(ref mut policy) => vec![&mut policy.span],
+            Statement::Privileges(ref mut privileges) => privileges.iter_mut().map(|p| &mut p.span).collect(),
+            Statement::Procedure(ref mut procedure) => vec![&mut procedure.span],
+            Statement::Schema(ref mut schema) => vec![&mut schema.span],
+            Statement::Sequence(ref mut sequence) => vec![&mut sequence.span],
+            Statement::Table(ref mut table) => vec![&mut table.span],
+            Statement::Trigger(ref mut trigger) => vec![&mut trigger.span],
+            Statement::Type(ref mut ty) => vec![&mut ty.span],
+            Statement::Unsupported { ref mut span, .. } => vec![span],
+            Statement::View(ref mut view) => vec![&mut view.span],
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
@@ -66,24 +222,143 @@ pub enum SimpleSqlType {
 
     Boolean, // bool
 
-    Date,                 // date
-    DateTime,             // timestamp without time zone
-    DateTimeWithTimeZone, // timestamp with time zone
-    Time,                 // time
-    TimeWithTimeZone,     // time with time zone
+    Date,                                          // date
+    DateTime(Option<u32>),                         // timestamp(p) without time zone
+    DateTimeWithTimeZone(Option<u32>),             // timestamp(p) with time zone
+    Time(Option<u32>),                             // time(p)
+    TimeWithTimeZone(Option<u32>),                 // time(p) with time zone
+    Interval(Option<IntervalFields>, Option<u32>), // interval fields(p)
+
+    Binary, // bytea
+    Uuid,   // uuid
+    Json,   // json
+    Jsonb,  // jsonb
+    Xml,    // xml
+    Oid,    // oid
+
+    Inet,     // inet
+    Cidr,     // cidr
+    MacAddr,  // macaddr
+    MacAddr8, // macaddr8
+
+    TextSearchVector, // tsvector
+    TextSearchQuery,  // tsquery
+
+    Point,   // point
+    Line,    // line
+    Segment, // lseg
+    Box,     // box
+    Path,    // path
+    Polygon, // polygon
+    Circle,  // circle
+
+    IntegerRange,              // int4range
+    BigIntegerRange,           // int8range
+    NumericRange,              // numrange
+    DateTimeRange,             // tsrange
+    DateTimeWithTimeZoneRange, // tstzrange
+    DateRange,                 // daterange
+}
+
+impl SqlType {
+    /// Geometric types, ranges, oid and the float aliases aren't keywords as they are common column or function
+    /// names. Instead they are resolved by name unless qualified by a schema other than pg_catalog.
+    pub fn from_name(name: ObjectName, dim: Option<u32>) -> Self {
+        if name.schema.as_ref().map_or("pg_catalog", |s| &s[..]) == "pg_catalog" {
+            let simple = match &name.name.to_lowercase()[..] {
+                "oid" => Some(SimpleSqlType::Oid),
+                "float4" => Some(SimpleSqlType::Single),
+                "float8" => Some(SimpleSqlType::Double),
+                "point" => Some(SimpleSqlType::Point),
+                "line" => Some(SimpleSqlType::Line),
+                "lseg" => Some(SimpleSqlType::Segment),
+                "box" => Some(SimpleSqlType::Box),
+                "path" => Some(SimpleSqlType::Path),
+                "polygon" => Some(SimpleSqlType::Polygon),
+                "circle" => Some(SimpleSqlType::Circle),
+                "int4range" => Some(SimpleSqlType::IntegerRange),
+                "int8range" => Some(SimpleSqlType::BigIntegerRange),
+                "numrange" => Some(SimpleSqlType::NumericRange),
+                "tsrange" => Some(SimpleSqlType::DateTimeRange),
+                "tstzrange" => Some(SimpleSqlType::DateTimeWithTimeZoneRange),
+                "daterange" => Some(SimpleSqlType::DateRange),
+                _ => None,
+            };
+            if let Some(simple) = simple {
+                return SqlType::Simple(simple, dim);
+            }
+        }
+        SqlType::Custom(name, Vec::new(), dim)
+    }
+
+    /// Type modifiers don't form part of a function signature, so `varchar(10)` is described by the catalog as
+    /// `character varying`. Custom types are only qualified by the catalog when they aren't on the search path.
+    pub fn is_same_argument_type(&self, other: &SqlType) -> bool {
+        fn unmodified(simple_type: &SimpleSqlType) -> SimpleSqlType {
+            match *simple_type {
+                SimpleSqlType::FixedLengthString(_) => SimpleSqlType::FixedLengthString(1),
+                SimpleSqlType::VariableLengthString(_) => SimpleSqlType::UnsizedVariableLengthString,
+                SimpleSqlType::FixedLengthBitString(_) => SimpleSqlType::FixedLengthBitString(1),
+                SimpleSqlType::VariableLengthBitString(_) => SimpleSqlType::VariableLengthBitString(0),
+                SimpleSqlType::Numeric(_) => SimpleSqlType::Numeric(None),
+                SimpleSqlType::DateTime(_) => SimpleSqlType::DateTime(None),
+                SimpleSqlType::DateTimeWithTimeZone(_) => SimpleSqlType::DateTimeWithTimeZone(None),
+                SimpleSqlType::Time(_) => SimpleSqlType::Time(None),
+                SimpleSqlType::TimeWithTimeZone(_) => SimpleSqlType::TimeWithTimeZone(None),
+                SimpleSqlType::Interval(..) => SimpleSqlType::Interval(None, None),
+                ref other => other.clone(),
+            }
+        }
 
-    Uuid, // uuid
+        match (self, other) {
+            (&SqlType::Simple(ref left, left_dim), &SqlType::Simple(ref right, right_dim)) => {
+                unmodified(left) == unmodified(right) && left_dim == right_dim
+            }
+            (&SqlType::Custom(ref left, _, left_dim), &SqlType::Custom(ref right, _, right_dim)) => {
+                left.name == right.name
+                    && (left.schema.is_none() || right.schema.is_none() || left.schema == right.schema)
+                    && left_dim == right_dim
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum IntervalFields {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    YearToMonth,
+    DayToHour,
+    DayToMinute,
+    DayToSecond,
+    HourToMinute,
+    HourToSecond,
+    MinuteToSecond,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum ColumnConstraint {
+    Check { expression: String, no_inherit: bool },
     Default(AnyValue),
+    Generated(String), // GENERATED ALWAYS AS (expression) STORED
+    Identity(IdentityKind, Vec<SequenceOption>),
     NotNull,
     Null,
     Unique,
     PrimaryKey,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum IdentityKind {
+    Always,
+    ByDefault,
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum AnyValue {
     // Optional cast on each of these
@@ -93,11 +368,141 @@ pub enum AnyValue {
     Integer(i32, Option<SqlType>),
     String(String, Option<SqlType>),
     Null(Option<SqlType>),
+    NextVal(ObjectName),    // nextval('sequence')
+    Expression(Expression), // e.g. now() + '1 day'::interval
+}
+
+impl AnyValue {
+    /// nextval is kept distinct from other expressions so that sequence references can be validated
+    pub fn from_expression(expression: Expression) -> Self {
+        if let Expression::FunctionCall(ref name, ref args) = expression {
+            if name.schema.is_none() && name.name.eq_ignore_ascii_case("nextval") && args.len() == 1 {
+                if let Expression::Value(ref value) = args[0] {
+                    if let AnyValue::String(ref sequence, _) = **value {
+                        return AnyValue::NextVal(match sequence.find('.') {
+                            Some(pos) => ObjectName {
+                                schema: Some(sequence[..pos].to_owned()),
+                                name: sequence[pos + 1..].to_owned(),
+                            },
+                            None => ObjectName {
+                                schema: None,
+                                name: sequence.to_owned(),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        AnyValue::Expression(expression)
+    }
+}
+
+/// A small subset of SQL expressions, sufficient to describe most default values
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum Expression {
+    Value(Box<AnyValue>),
+    Keyword(String), // e.g. CURRENT_TIMESTAMP
+    FunctionCall(ObjectName, Vec<Expression>),
+    Cast(Box<Expression>, SqlType),
+    UnaryOperator(String, Box<Expression>),
+    BinaryOperator(Box<Expression>, String, Box<Expression>),
+    Nested(Box<Expression>),
+}
+
+impl Expression {
+    pub fn cast(self, sql_type: Option<SqlType>) -> Self {
+        match sql_type {
+            Some(sql_type) => Expression::Cast(Box::new(self), sql_type),
+            None => self,
+        }
+    }
+
+    /// Builds a tree from a flat list of binary operations. Multiplicative operators bind tighter than
+    /// additive operators, which in turn bind tighter than any other operator.
+    pub fn from_operations(first: Expression, rest: Vec<(String, Expression)>) -> Self {
+        fn precedence(operator: &str) -> u8 {
+            match operator {
+                "*" | "/" | "%" => 3,
+                "+" | "-" => 2,
+                _ => 1,
+            }
+        }
+
+        fn reduce(operands: &mut Vec<Expression>, operator: String) {
+            let right = operands.pop().expect("right operand");
+            let left = operands.pop().expect("left operand");
+            operands.push(Expression::BinaryOperator(Box::new(left), operator, Box::new(right)));
+        }
+
+        let mut operands = vec![first];
+        let mut operators: Vec<String> = Vec::new();
+        for (operator, operand) in rest {
+            while operators
+                .last()
+                .map_or(false, |top| precedence(top) >= precedence(&operator))
+            {
+                let top = operators.pop().unwrap();
+                reduce(&mut operands, top);
+            }
+            operators.push(operator);
+            operands.push(operand);
+        }
+        while let Some(operator) = operators.pop() {
+            reduce(&mut operands, operator);
+        }
+        operands.pop().expect("expression")
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum IndexParameter {
     FillFactor(u32),
+    Deduplicate(bool),
+    Buffering(IndexBuffering),
+    FastUpdate(bool),
+    PendingListLimit(u32),
+    PagesPerRange(u32),
+    Autosummarize(bool),
+    Custom(String, String),
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum IndexBuffering {
+    On,
+    Off,
+    Auto,
+}
+
+impl IndexParameter {
+    /// Storage parameters unknown to us are kept as is since they may belong to an extension's access method
+    pub fn from_option(name: &str, value: &str) -> Result<Self, &'static str> {
+        fn integer(value: &str) -> Result<u32, &'static str> {
+            value.parse::<u32>().map_err(|_| "Index parameter must be an integer")
+        }
+        fn boolean(value: &str) -> Result<bool, &'static str> {
+            match &value.to_lowercase()[..] {
+                "on" | "true" | "yes" | "1" => Ok(true),
+                "off" | "false" | "no" | "0" => Ok(false),
+                _ => Err("Index parameter must be a boolean"),
+            }
+        }
+
+        Ok(match &name.to_lowercase()[..] {
+            "fillfactor" => IndexParameter::FillFactor(integer(value)?),
+            "deduplicate_items" => IndexParameter::Deduplicate(boolean(value)?),
+            "buffering" => IndexParameter::Buffering(match &value.to_lowercase()[..] {
+                "on" => IndexBuffering::On,
+                "off" => IndexBuffering::Off,
+                "auto" => IndexBuffering::Auto,
+                _ => return Err("Index buffering must be on, off or auto"),
+            }),
+            "fastupdate" => IndexParameter::FastUpdate(boolean(value)?),
+            "gin_pending_list_limit" => IndexParameter::PendingListLimit(integer(value)?),
+            "pages_per_range" => IndexParameter::PagesPerRange(integer(value)?),
+            "autosummarize" => IndexParameter::Autosummarize(boolean(value)?),
+            name => IndexParameter::Custom(name.to_owned(), value.to_owned()),
+        })
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
@@ -115,16 +520,43 @@ pub enum TableConstraint {
         match_type: Option<ForeignConstraintMatchType>,
         events: Option<Vec<ForeignConstraintEvent>>,
     },
+    Check {
+        name: String,
+        expression: String,
+        no_inherit: bool,
+    },
+    Unique {
+        name: String,
+        columns: Vec<String>,
+        include: Option<Vec<String>>,
+        parameters: Option<Vec<IndexParameter>>,
+    },
+    Exclude {
+        name: String,
+        index_type: Option<IndexType>,
+        elements: Vec<ExclusionElement>,
+        parameters: Option<Vec<IndexParameter>>,
+    },
 }
 
 impl TableConstraint {
     pub fn name(&self) -> &str {
         match *self {
-            TableConstraint::Primary { ref name, .. } | TableConstraint::Foreign { ref name, .. } => name,
+            TableConstraint::Primary { ref name, .. }
+            | TableConstraint::Foreign { ref name, .. }
+            | TableConstraint::Check { ref name, .. }
+            | TableConstraint::Unique { ref name, .. }
+            | TableConstraint::Exclude { ref name, .. } => name,
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ExclusionElement {
+    pub column: String,
+    pub operator: String,
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum ForeignConstraintMatchType {
     Simple,
@@ -163,39 +595,293 @@ impl ObjectName {
     }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct TableDefinition {
     pub name: ObjectName,
     pub columns: Vec<ColumnDefinition>,
     pub constraints: Vec<TableConstraint>,
+    pub row_security: bool,
+    pub force_row_security: bool,
+    pub policies: Vec<PolicyDefinition>,
+    pub partition_by: Option<PartitionKey>,
+    pub partition_of: Option<TablePartition>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct PartitionKey {
+    pub strategy: PartitionStrategy,
+    pub key: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PartitionStrategy {
+    Range,
+    List,
+    Hash,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct TablePartition {
+    pub parent: ObjectName,
+    pub bound: PartitionBound,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PartitionBound {
+    Default,
+    Range(String, String), // from, to
+    List(String),
+    Hash(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlterTableAction {
+    EnableRowSecurity,
+    DisableRowSecurity,
+    ForceRowSecurity,
+    NoForceRowSecurity,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct PolicyDefinition {
+    pub name: String,
+    pub table: ObjectName,
+    pub permissive: bool,
+    pub command: PolicyCommand,
+    pub roles: Vec<String>,
+    pub using: Option<String>,
+    pub with_check: Option<String>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl PolicyDefinition {
+    pub fn is_same_policy(&self, other: &PolicyDefinition) -> bool {
+        self.name.eq(&other.name) && self.table.eq(&other.table)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PolicyCommand {
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct ColumnDefinition {
     pub name: String,
     pub sql_type: SqlType,
     pub constraints: Vec<ColumnConstraint>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SchemaDefinition {
     pub name: String,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceDefinition {
+    pub name: ObjectName,
+    pub increment: Option<i64>,
+    pub min_value: Option<i64>,
+    pub max_value: Option<i64>,
+    pub start: Option<i64>,
+    pub cache: Option<i64>,
+    pub cycle: bool,
+    pub owned_by: Option<SequenceOwner>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct SequenceOwner {
+    pub table: ObjectName,
+    pub column: String,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum SequenceOption {
+    Increment(i64),
+    MinValue(Option<i64>),
+    MaxValue(Option<i64>),
+    Start(i64),
+    Cache(i64),
+    Cycle(bool),
+    OwnedBy(Option<SequenceOwner>),
+}
+
+impl SequenceDefinition {
+    pub fn from_options(name: ObjectName, options: Vec<SequenceOption>) -> Self {
+        let mut sequence = SequenceDefinition {
+            name,
+            increment: None,
+            min_value: None,
+            max_value: None,
+            start: None,
+            cache: None,
+            cycle: false,
+            owned_by: None,
+            span: Span::default(),
+        };
+        for option in options {
+            match option {
+                SequenceOption::Increment(value) => sequence.increment = Some(value),
+                SequenceOption::MinValue(value) => sequence.min_value = value,
+                SequenceOption::MaxValue(value) => sequence.max_value = value,
+                SequenceOption::Start(value) => sequence.start = Some(value),
+                SequenceOption::Cache(value) => sequence.cache = Some(value),
+                SequenceOption::Cycle(value) => sequence.cycle = value,
+                SequenceOption::OwnedBy(value) => sequence.owned_by = value,
+            }
+        }
+        sequence
+    }
+
+    // The values PostgreSQL uses when an option is not specified (for a bigint sequence)
+    pub fn effective_increment(&self) -> i64 {
+        self.increment.unwrap_or(1)
+    }
+
+    pub fn effective_min_value(&self) -> i64 {
+        self.min_value.unwrap_or(if self.effective_increment() > 0 {
+            1
+        } else {
+            i64::min_value()
+        })
+    }
+
+    pub fn effective_max_value(&self) -> i64 {
+        self.max_value.unwrap_or(if self.effective_increment() > 0 {
+            i64::max_value()
+        } else {
+            -1
+        })
+    }
+
+    pub fn effective_start(&self) -> i64 {
+        self.start.unwrap_or(if self.effective_increment() > 0 {
+            self.effective_min_value()
+        } else {
+            self.effective_max_value()
+        })
+    }
+
+    pub fn effective_cache(&self) -> i64 {
+        self.cache.unwrap_or(1)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TypeDefinition {
     pub name: ObjectName,
     pub kind: TypeDefinitionKind,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TypeDefinitionKind {
-    Composite,
+    Composite(Vec<CompositeTypeAttribute>),
+    Domain(DomainDefinition),
     Enum(Vec<String>),
-    Range,
+    Range(RangeDefinition),
     UserDefined,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompositeTypeAttribute {
+    pub name: String,
+    pub sql_type: SqlType,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainDefinition {
+    pub base_type: SqlType,
+    pub default: Option<AnyValue>,
+    pub not_null: bool,
+    pub constraints: Vec<DomainConstraint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainConstraint {
+    // Unnamed constraints are given a name when the package defaults are set
+    pub name: Option<String>,
+    pub expression: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RangeDefinition {
+    pub subtype: SqlType,
+    pub subtype_opclass: Option<String>,
+    pub collation: Option<String>,
+    pub canonical: Option<String>,
+    pub subtype_diff: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DomainOption {
+    Default(AnyValue),
+    NotNull(bool),
+    Check(DomainConstraint),
+}
+
+impl DomainDefinition {
+    pub fn from_constraints(base_type: SqlType, options: Vec<DomainOption>) -> Self {
+        let mut domain = DomainDefinition {
+            base_type,
+            default: None,
+            not_null: false,
+            constraints: Vec::new(),
+        };
+        for option in options {
+            match option {
+                DomainOption::Default(value) => domain.default = Some(value),
+                DomainOption::NotNull(value) => domain.not_null = value,
+                DomainOption::Check(constraint) => domain.constraints.push(constraint),
+            }
+        }
+        domain
+    }
+}
+
+impl RangeDefinition {
+    pub fn from_options(options: Vec<(String, SqlType)>) -> Result<Self, &'static str> {
+        let mut subtype = None;
+        let mut subtype_opclass = None;
+        let mut collation = None;
+        let mut canonical = None;
+        let mut subtype_diff = None;
+        for (option, value) in options {
+            // Everything other than the subtype refers to another object by name
+            match &option.to_lowercase()[..] {
+                "subtype" => subtype = Some(value),
+                "subtype_opclass" => subtype_opclass = Some(value.to_string()),
+                "collation" => collation = Some(value.to_string()),
+                "canonical" => canonical = Some(value.to_string()),
+                "subtype_diff" => subtype_diff = Some(value.to_string()),
+                _ => return Err("Unsupported range option"),
+            }
+        }
+        match subtype {
+            Some(subtype) => Ok(RangeDefinition {
+                subtype,
+                subtype_opclass,
+                collation,
+                canonical,
+                subtype_diff,
+            }),
+            None => Err("Range types require a subtype"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct ScriptDefinition {
     pub name: String,
@@ -210,16 +896,240 @@ pub enum ScriptKind {
     PostDeployment,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct FunctionDefinition {
     pub name: ObjectName,
     pub arguments: Vec<FunctionArgument>,
     pub return_type: FunctionReturnType,
     pub body: String,
     pub language: FunctionLanguage,
+    pub attributes: Vec<FunctionAttribute>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl FunctionDefinition {
+    pub fn from_options(
+        name: ObjectName,
+        arguments: Vec<FunctionArgument>,
+        return_type: FunctionReturnType,
+        options: Vec<FunctionOption>,
+    ) -> Result<Self, &'static str> {
+        let (body, language, attributes) = split_function_options(options)?;
+        Ok(FunctionDefinition {
+            name,
+            arguments,
+            return_type,
+            body,
+            language,
+            attributes,
+            span: Span::default(),
+        })
+    }
+
+    // Functions may be overloaded so are identified by their argument types as well as their name
+    pub fn is_same_function(&self, other: &FunctionDefinition) -> bool {
+        self.name.eq(&other.name) && same_argument_types(&self.arguments, &other.arguments)
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, argument_types(&self.arguments).join(", "))
+    }
+
+    /// Whether the function is the one identified by the name and input argument types (e.g. when commenting)
+    pub fn is_identified_by(&self, name: &ObjectName, types: &[SqlType]) -> bool {
+        let arguments = input_arguments(&self.arguments);
+        self.name.eq(name)
+            && arguments.len() == types.len()
+            && arguments
+                .iter()
+                .zip(types.iter())
+                .all(|(argument, sql_type)| argument.sql_type.is_same_argument_type(sql_type))
+    }
+}
+
+// Output arguments are part of the result rather than the signature
+fn input_arguments(arguments: &[FunctionArgument]) -> Vec<&FunctionArgument> {
+    arguments
+        .iter()
+        .filter(|argument| argument.mode != Some(FunctionArgumentMode::Out))
+        .collect()
+}
+
+fn same_argument_types(left: &[FunctionArgument], right: &[FunctionArgument]) -> bool {
+    let left = input_arguments(left);
+    let right = input_arguments(right);
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right.iter())
+            .all(|(l, r)| l.sql_type.is_same_argument_type(&r.sql_type))
+}
+
+fn argument_types(arguments: &[FunctionArgument]) -> Vec<String> {
+    input_arguments(arguments)
+        .iter()
+        .map(|argument| match argument.mode {
+            Some(FunctionArgumentMode::Variadic) => format!("VARIADIC {}", argument.sql_type),
+            _ => argument.sql_type.to_string(),
+        })
+        .collect()
+}
+
+// Procedures are declared in the same way as functions, however have no return type
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ProcedureDefinition {
+    pub name: ObjectName,
+    pub arguments: Vec<FunctionArgument>,
+    pub body: String,
+    pub language: FunctionLanguage,
+    pub attributes: Vec<FunctionAttribute>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+impl ProcedureDefinition {
+    pub fn from_options(
+        name: ObjectName,
+        arguments: Vec<FunctionArgument>,
+        options: Vec<FunctionOption>,
+    ) -> Result<Self, &'static str> {
+        let (body, language, attributes) = split_function_options(options)?;
+        Ok(ProcedureDefinition {
+            name,
+            arguments,
+            body,
+            language,
+            attributes,
+            span: Span::default(),
+        })
+    }
+
+    // Like functions, procedures may be overloaded so are identified by their argument types too
+    pub fn is_same_procedure(&self, other: &ProcedureDefinition) -> bool {
+        self.name.eq(&other.name) && same_argument_types(&self.arguments, &other.arguments)
+    }
+
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, argument_types(&self.arguments).join(", "))
+    }
+}
+
+fn split_function_options(
+    options: Vec<FunctionOption>,
+) -> Result<(String, FunctionLanguage, Vec<FunctionAttribute>), &'static str> {
+    let mut body = None;
+    let mut language = None;
+    let mut attributes = Vec::new();
+    for option in options {
+        match option {
+            FunctionOption::Body(value) => body = Some(value),
+            FunctionOption::Language(value) => language = Some(value),
+            FunctionOption::Attribute(attribute) => attributes.push(attribute),
+        }
+    }
+    Ok((
+        body.ok_or("Function body is required")?,
+        language.ok_or("Function language is required")?,
+        attributes,
+    ))
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct AggregateDefinition {
+    pub name: ObjectName,
+    pub arguments: Vec<FunctionArgument>,
+    pub state_function: ObjectName,
+    pub state_type: SqlType,
+    pub final_function: Option<ObjectName>,
+    pub combine_function: Option<ObjectName>,
+    pub initial_condition: Option<String>,
+    pub parallel: Option<ParallelSafety>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+// Only used whilst parsing since aggregate options refer to types, functions or literal values
+pub enum AggregateOptionValue {
+    Type(SqlType),
+    String(String),
+}
+
+impl AggregateDefinition {
+    pub fn from_options(
+        name: ObjectName,
+        arguments: Vec<FunctionArgument>,
+        options: Vec<(String, AggregateOptionValue)>,
+    ) -> Result<Self, &'static str> {
+        // Functions and keywords are parsed as custom types since they can't be told apart
+        fn name_of(value: AggregateOptionValue) -> Result<ObjectName, &'static str> {
+            match value {
+                AggregateOptionValue::Type(SqlType::Custom(name, ref modifiers, None)) if modifiers.is_empty() => {
+                    Ok(name)
+                }
+                _ => Err("Aggregate option must be a name"),
+            }
+        }
+
+        let mut state_function = None;
+        let mut state_type = None;
+        let mut final_function = None;
+        let mut combine_function = None;
+        let mut initial_condition = None;
+        let mut parallel = None;
+        for (option, value) in options {
+            match &option.to_lowercase()[..] {
+                "sfunc" => state_function = Some(name_of(value)?),
+                "stype" => match value {
+                    AggregateOptionValue::Type(sql_type) => state_type = Some(sql_type),
+                    AggregateOptionValue::String(_) => return Err("Aggregate state type must be a type"),
+                },
+                "finalfunc" => final_function = Some(name_of(value)?),
+                "combinefunc" => combine_function = Some(name_of(value)?),
+                "initcond" => match value {
+                    AggregateOptionValue::String(condition) => initial_condition = Some(condition),
+                    AggregateOptionValue::Type(_) => return Err("Aggregate initial condition must be a string"),
+                },
+                "parallel" => {
+                    parallel = Some(match &name_of(value)?.name.to_lowercase()[..] {
+                        "safe" => ParallelSafety::Safe,
+                        "restricted" => ParallelSafety::Restricted,
+                        "unsafe" => ParallelSafety::Unsafe,
+                        _ => return Err("Unsupported aggregate parallel safety"),
+                    })
+                }
+                _ => return Err("Unsupported aggregate option"),
+            }
+        }
+        Ok(AggregateDefinition {
+            name,
+            arguments,
+            state_function: state_function.ok_or("Aggregate state function is required")?,
+            state_type: state_type.ok_or("Aggregate state type is required")?,
+            final_function,
+            combine_function,
+            initial_condition,
+            parallel,
+            span: Span::default(),
+        })
+    }
+
+    // Aggregates may be overloaded so are identified by their argument types as well as their name
+    pub fn is_same_aggregate(&self, other: &AggregateDefinition) -> bool {
+        self.name.eq(&other.name) && same_argument_types(&self.arguments, &other.arguments)
+    }
+
+    // Aggregates must always be referred to along with their argument types
+    pub fn signature(&self) -> String {
+        if self.arguments.is_empty() {
+            format!("{}(*)", self.name)
+        } else {
+            format!("{}({})", self.name, argument_types(&self.arguments).join(", "))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct FunctionArgument {
     pub mode: Option<FunctionArgumentMode>,
     pub name: Option<String>,
@@ -227,7 +1137,7 @@ pub struct FunctionArgument {
     pub default: Option<AnyValue>,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum FunctionArgumentMode {
     In,
     InOut,
@@ -235,23 +1145,129 @@ pub enum FunctionArgumentMode {
     Variadic,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub enum FunctionReturnType {
-    Table(Vec<ColumnDefinition>),
-    SetOf(SqlType),
-    SqlType(SqlType),
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionReturnType {
+    Table(Vec<ColumnDefinition>),
+    SetOf(SqlType),
+    SqlType(SqlType),
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionLanguage {
+    C,
+    Internal,
+    PostgreSQL,
+    SQL,
+    Custom(String),
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionAttribute {
+    Volatility(FunctionVolatility),
+    Strict(bool),
+    SecurityDefiner(bool),
+    Leakproof(bool),
+    Parallel(ParallelSafety),
+    Cost(u32),
+    Rows(u32),
+    // Configuration values are kept unquoted, with one entry per list element (e.g. for search_path)
+    Set(String, Vec<String>),
+}
+
+// Only used whilst parsing since the body, language and attributes may be specified in any order
+pub enum FunctionOption {
+    Body(String),
+    Language(FunctionLanguage),
+    Attribute(FunctionAttribute),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionVolatility {
+    Immutable,
+    Stable,
+    Volatile,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum ParallelSafety {
+    Unsafe,
+    Restricted,
+    Safe,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct TriggerDefinition {
+    pub name: String,
+    pub table: ObjectName,
+    pub timing: TriggerTiming,
+    pub events: Vec<TriggerEvent>,
+    pub level: TriggerLevel,
+    pub condition: Option<String>,
+    pub function: ObjectName,
+    pub arguments: Vec<String>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl TriggerDefinition {
+    pub fn is_same_trigger(&self, other: &TriggerDefinition) -> bool {
+        self.name.eq(&other.name) && self.table.eq(&other.table)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerTiming {
+    Before,
+    After,
+    InsteadOf,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerEvent {
+    Insert,
+    Update(Option<Vec<String>>),
+    Delete,
+    Truncate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerLevel {
+    Row,
+    Statement,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    pub name: ObjectName,
+    pub columns: Option<Vec<String>>,
+    pub query: String,
+    #[serde(skip)]
+    pub resolved: Option<ResolvedView>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+// The output columns and query of a view as the target database would store them. The database rewrites
+// queries (e.g. qualifying columns) so this is what a view in the source is compared against when publishing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedView {
+    pub columns: Vec<String>,
+    pub query: String,
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub enum FunctionLanguage {
-    C,
-    Internal,
-    PostgreSQL,
-    SQL,
-    Custom(String),
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MaterializedViewDefinition {
+    pub name: ObjectName,
+    pub columns: Option<Vec<String>>,
+    pub query: String,
+    pub with_data: bool,
+    #[serde(skip)]
+    pub resolved: Option<ResolvedView>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct IndexDefinition {
     pub name: String,
     pub table: ObjectName,
@@ -259,12 +1275,16 @@ pub struct IndexDefinition {
 
     pub unique: bool,
     pub index_type: Option<IndexType>,
+    pub include: Option<Vec<String>>,
     pub storage_parameters: Option<Vec<IndexParameter>>,
+    pub predicate: Option<String>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 impl IndexDefinition {
     pub fn fully_qualified_name(&self) -> String {
-        format!("{}.{}", self.schema(), self.name)
+        format!("{}.{}", quote_ident(self.schema()), quote_ident(&self.name))
     }
 
     pub fn is_same_index(&self, other: &IndexDefinition) -> bool {
@@ -274,35 +1294,304 @@ impl IndexDefinition {
     pub fn schema(&self) -> &str {
         self.table.schema()
     }
+
+    /// The plain and included columns referenced by the index. Expressions are not included.
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns
+            .iter()
+            .filter_map(|c| match c.key {
+                IndexKey::Column(ref name) => Some(&name[..]),
+                IndexKey::Expression(_) => None,
+            })
+            .chain(self.include.iter().flat_map(|i| i.iter().map(|c| &c[..])))
+            .collect()
+    }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum IndexType {
     BTree,
     Hash,
     Gist,
     Gin,
+    SpGist,
+    Brin,
+    Custom(String),
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl IndexType {
+    pub fn from_name(name: &str) -> Self {
+        match &name.to_lowercase()[..] {
+            "btree" => IndexType::BTree,
+            "hash" => IndexType::Hash,
+            "gist" => IndexType::Gist,
+            "gin" => IndexType::Gin,
+            "spgist" => IndexType::SpGist,
+            "brin" => IndexType::Brin,
+            _ => IndexType::Custom(name.to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct IndexColumn {
-    pub name: String,
+    pub key: IndexKey,
+    pub collation: Option<String>,
+    pub operator_class: Option<String>,
     pub order: Option<IndexOrder>,
     pub null_position: Option<IndexPosition>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IndexKey {
+    Column(String),
+    Expression(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum IndexOrder {
     Ascending,
     Descending,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum IndexPosition {
     First,
     Last,
 }
 
+/// A single privilege (or role membership) granted to, or revoked from, a grantee. Statements granting
+/// multiple privileges are expanded so that each privilege can be compared individually.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrivilegeDefinition {
+    pub action: PrivilegeAction,
+    pub object: PrivilegeObject,
+    pub privilege: Privilege,
+    pub grantee: String,
+    pub grant_option: bool, // WITH GRANT OPTION, or WITH ADMIN OPTION for role memberships
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl PrivilegeDefinition {
+    pub fn expand(
+        action: PrivilegeAction,
+        privileges: Option<Vec<Privilege>>,
+        objects: Vec<PrivilegeObject>,
+        grantees: Vec<String>,
+        grant_option: bool,
+    ) -> Result<Vec<Self>, &'static str> {
+        let mut expanded = Vec::new();
+        for object in objects {
+            // ALL PRIVILEGES expands to every privilege applicable to the object
+            let privileges = match privileges {
+                Some(ref privileges) => {
+                    if privileges.iter().any(|p| !object.privileges().contains(p)) {
+                        return Err("Privilege type is not applicable to the object");
+                    }
+                    privileges.clone()
+                }
+                None => object.privileges().to_vec(),
+            };
+            for grantee in &grantees {
+                // PUBLIC is a keyword rather than a role so we keep it consistently cased
+                let grantee = if grantee.eq_ignore_ascii_case("public") {
+                    "PUBLIC".to_owned()
+                } else {
+                    grantee.to_owned()
+                };
+                for privilege in &privileges {
+                    expanded.push(PrivilegeDefinition {
+                        action: action.clone(),
+                        object: object.clone(),
+                        privilege: privilege.clone(),
+                        grantee: grantee.clone(),
+                        grant_option,
+                        span: Span::default(),
+                    });
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    pub fn is_same_privilege(&self, other: &PrivilegeDefinition) -> bool {
+        self.object.is_same_object(&other.object)
+            && self.privilege.eq(&other.privilege)
+            && self.grantee.eq(&other.grantee)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrivilegeAction {
+    Grant,
+    Revoke,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    References,
+    Trigger,
+    Create,
+    Usage,
+    Execute,
+    Member, // Role membership
+}
+
+impl Privilege {
+    /// Privilege names as reported by aclexplode
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match &name.to_uppercase()[..] {
+            "SELECT" => Privilege::Select,
+            "INSERT" => Privilege::Insert,
+            "UPDATE" => Privilege::Update,
+            "DELETE" => Privilege::Delete,
+            "TRUNCATE" => Privilege::Truncate,
+            "REFERENCES" => Privilege::References,
+            "TRIGGER" => Privilege::Trigger,
+            "CREATE" => Privilege::Create,
+            "USAGE" => Privilege::Usage,
+            "EXECUTE" => Privilege::Execute,
+            "MEMBER" => Privilege::Member,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrivilegeObject {
+    Role(String),
+    Schema(String),
+    Table(ObjectName), // Includes views and materialized views
+    Sequence(ObjectName),
+    Function(ObjectName, Option<Vec<SqlType>>), // Input argument types, if written, identify an overload
+    Default(DefaultPrivilegeScope),             // ALTER DEFAULT PRIVILEGES
+}
+
+impl PrivilegeObject {
+    pub fn function(name: ObjectName, arguments: Option<Vec<FunctionArgument>>) -> Self {
+        let types = arguments.map(|arguments| {
+            input_arguments(&arguments)
+                .into_iter()
+                .map(|argument| argument.sql_type.clone())
+                .collect()
+        });
+        PrivilegeObject::Function(name, types)
+    }
+
+    /// Functions without an argument list are the only function with that name
+    pub fn is_same_object(&self, other: &PrivilegeObject) -> bool {
+        match (self, other) {
+            (
+                PrivilegeObject::Function(name, Some(types)),
+                PrivilegeObject::Function(other_name, Some(other_types)),
+            ) => {
+                name.eq(other_name)
+                    && types.len() == other_types.len()
+                    && types
+                        .iter()
+                        .zip(other_types.iter())
+                        .all(|(t, o)| t.is_same_argument_type(o))
+            }
+            (PrivilegeObject::Function(name, _), PrivilegeObject::Function(other_name, _)) => name.eq(other_name),
+            _ => self.eq(other),
+        }
+    }
+
+    pub fn privileges(&self) -> &'static [Privilege] {
+        const TABLE: &[Privilege] = &[
+            Privilege::Select,
+            Privilege::Insert,
+            Privilege::Update,
+            Privilege::Delete,
+            Privilege::Truncate,
+            Privilege::References,
+            Privilege::Trigger,
+        ];
+        const SEQUENCE: &[Privilege] = &[Privilege::Usage, Privilege::Select, Privilege::Update];
+        const FUNCTION: &[Privilege] = &[Privilege::Execute];
+
+        match *self {
+            PrivilegeObject::Role(_) => &[Privilege::Member],
+            PrivilegeObject::Schema(_) => &[Privilege::Usage, Privilege::Create],
+            PrivilegeObject::Table(_) => TABLE,
+            PrivilegeObject::Sequence(_) => SEQUENCE,
+            PrivilegeObject::Function(..) => FUNCTION,
+            PrivilegeObject::Default(ref scope) => match scope.kind {
+                DefaultPrivilegeKind::Tables => TABLE,
+                DefaultPrivilegeKind::Sequences => SEQUENCE,
+                DefaultPrivilegeKind::Functions => FUNCTION,
+            },
+        }
+    }
+}
+
+/// A comment on an object, with a NULL comment removing any existing comment. Comments are kept apart from
+/// the object itself so that changing a comment never requires the object to change.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CommentDefinition {
+    pub object: CommentObject,
+    pub comment: Option<String>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommentObject {
+    Table(ObjectName),
+    Column(ObjectName, String),         // table, column
+    Function(ObjectName, Vec<SqlType>), // Only input argument types identify a function
+    Type(ObjectName),                   // Includes domains
+    Index(ObjectName),
+    Constraint(ObjectName, String), // table, constraint
+}
+
+impl CommentObject {
+    pub fn function(name: ObjectName, arguments: Vec<FunctionArgument>) -> Self {
+        let types = input_arguments(&arguments)
+            .into_iter()
+            .map(|argument| argument.sql_type.clone())
+            .collect();
+        CommentObject::Function(name, types)
+    }
+
+    pub fn is_same_object(&self, other: &CommentObject) -> bool {
+        match (self, other) {
+            (
+                &CommentObject::Function(ref name, ref types),
+                &CommentObject::Function(ref other_name, ref other_types),
+            ) => {
+                name.eq(other_name)
+                    && types.len() == other_types.len()
+                    && types
+                        .iter()
+                        .zip(other_types.iter())
+                        .all(|(t, o)| t.is_same_argument_type(o))
+            }
+            _ => self.eq(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DefaultPrivilegeScope {
+    pub role: Option<String>, // The current user if not specified
+    pub schema: Option<String>,
+    pub kind: DefaultPrivilegeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DefaultPrivilegeKind {
+    Tables,
+    Sequences,
+    Functions,
+}
+
 impl fmt::Display for AnyValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let sql_type = match *self {
@@ -340,6 +1629,8 @@ impl fmt::Display for AnyValue {
                 write!(f, "NULL")?;
                 sql_type
             }
+            AnyValue::NextVal(ref sequence) => return write!(f, "nextval('{}'::regclass)", sequence),
+            AnyValue::Expression(ref expression) => return write!(f, "{}", expression),
         };
         if let Some(sql_type) = sql_type {
             write!(f, "::{}", sql_type)?;
@@ -348,6 +1639,100 @@ impl fmt::Display for AnyValue {
     }
 }
 
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expression::Value(ref value) => write!(f, "{}", value),
+            Expression::Keyword(ref keyword) => write!(f, "{}", keyword),
+            Expression::FunctionCall(ref name, ref args) => {
+                write!(f, "{}(", name)?;
+                let mut comma = false;
+                for arg in args {
+                    if comma {
+                        write!(f, ", ")?;
+                    } else {
+                        comma = true;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expression::Cast(ref expression, ref sql_type) => write!(f, "{}::{}", expression, sql_type),
+            Expression::UnaryOperator(ref operator, ref expression) => write!(f, "{}{}", operator, expression),
+            Expression::BinaryOperator(ref left, ref operator, ref right) => {
+                write!(f, "{} {} {}", left, operator, right)
+            }
+            Expression::Nested(ref expression) => write!(f, "({})", expression),
+        }
+    }
+}
+
+impl fmt::Display for ExclusionElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} WITH {}", quote_ident(&self.column), self.operator)
+    }
+}
+
+impl fmt::Display for CompositeTypeAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", quote_ident(&self.name), self.sql_type)
+    }
+}
+
+impl fmt::Display for IndexKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexKey::Column(ref name) => write!(f, "{}", quote_ident(name)),
+            IndexKey::Expression(ref expression) => write!(f, "({})", expression),
+        }
+    }
+}
+
+impl fmt::Display for IndexType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexType::BTree => write!(f, "btree"),
+            IndexType::Hash => write!(f, "hash"),
+            IndexType::Gist => write!(f, "gist"),
+            IndexType::Gin => write!(f, "gin"),
+            IndexType::SpGist => write!(f, "spgist"),
+            IndexType::Brin => write!(f, "brin"),
+            IndexType::Custom(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for IndexParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn on_off(value: bool) -> &'static str {
+            if value {
+                "on"
+            } else {
+                "off"
+            }
+        }
+
+        match *self {
+            IndexParameter::FillFactor(value) => write!(f, "FILLFACTOR={}", value),
+            IndexParameter::Deduplicate(value) => write!(f, "deduplicate_items={}", on_off(value)),
+            IndexParameter::Buffering(ref value) => write!(
+                f,
+                "buffering={}",
+                match *value {
+                    IndexBuffering::On => "on",
+                    IndexBuffering::Off => "off",
+                    IndexBuffering::Auto => "auto",
+                }
+            ),
+            IndexParameter::FastUpdate(value) => write!(f, "fastupdate={}", on_off(value)),
+            IndexParameter::PendingListLimit(value) => write!(f, "gin_pending_list_limit={}", value),
+            IndexParameter::PagesPerRange(value) => write!(f, "pages_per_range={}", value),
+            IndexParameter::Autosummarize(value) => write!(f, "autosummarize={}", on_off(value)),
+            IndexParameter::Custom(ref name, ref value) => write!(f, "{}='{}'", name, value.replace('\'', "''")),
+        }
+    }
+}
+
 impl fmt::Display for ForeignConstraintMatchType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -373,15 +1758,15 @@ impl fmt::Display for ForeignConstraintAction {
 impl fmt::Display for FunctionArgument {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(ref mode) = self.mode {
-            write!(f, "{}", mode)?;
+            write!(f, "{} ", mode)?;
         }
         if let Some(ref name) = self.name {
-            write!(f, "{} {}", name, self.sql_type)?;
+            write!(f, "{} {}", quote_ident(name), self.sql_type)?;
         } else {
             write!(f, "{}", self.sql_type)?;
         }
         if let Some(ref default) = self.default {
-            write!(f, "{}", default)?;
+            write!(f, " DEFAULT {}", default)?;
         }
         Ok(())
     }
@@ -410,11 +1795,211 @@ impl fmt::Display for FunctionLanguage {
     }
 }
 
+impl fmt::Display for FunctionAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FunctionAttribute::Volatility(volatility) => write!(f, "{}", volatility),
+            FunctionAttribute::Strict(true) => write!(f, "STRICT"),
+            FunctionAttribute::Strict(false) => write!(f, "CALLED ON NULL INPUT"),
+            FunctionAttribute::SecurityDefiner(true) => write!(f, "SECURITY DEFINER"),
+            FunctionAttribute::SecurityDefiner(false) => write!(f, "SECURITY INVOKER"),
+            FunctionAttribute::Leakproof(true) => write!(f, "LEAKPROOF"),
+            FunctionAttribute::Leakproof(false) => write!(f, "NOT LEAKPROOF"),
+            FunctionAttribute::Parallel(safety) => write!(f, "PARALLEL {}", safety),
+            FunctionAttribute::Cost(cost) => write!(f, "COST {}", cost),
+            FunctionAttribute::Rows(rows) => write!(f, "ROWS {}", rows),
+            FunctionAttribute::Set(ref name, ref values) => {
+                // List elements are identifiers whereas anything else is safest as a string literal
+                let list = name.eq_ignore_ascii_case("search_path");
+                let simple = |value: &str| {
+                    value
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                };
+                let values = values
+                    .iter()
+                    .map(|value| {
+                        if list && simple(value) {
+                            value.to_owned()
+                        } else if list {
+                            format!("\"{}\"", value.replace('"', "\"\""))
+                        } else {
+                            format!("'{}'", value.replace('\'', "''"))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "SET {} = {}", name, values.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for FunctionVolatility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FunctionVolatility::Immutable => write!(f, "IMMUTABLE"),
+            FunctionVolatility::Stable => write!(f, "STABLE"),
+            FunctionVolatility::Volatile => write!(f, "VOLATILE"),
+        }
+    }
+}
+
+impl fmt::Display for ParallelSafety {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParallelSafety::Unsafe => write!(f, "UNSAFE"),
+            ParallelSafety::Restricted => write!(f, "RESTRICTED"),
+            ParallelSafety::Safe => write!(f, "SAFE"),
+        }
+    }
+}
+
+// Keywords that can't be used as a bare identifier in every position (i.e. everything other than the
+// unreserved keywords). These are always quoted to keep generated SQL unambiguous.
+const RESERVED_KEYWORDS: &str = "\
+    all analyse analyze and any array as asc asymmetric authorization between bigint binary bit boolean \
+    both case cast char character check coalesce collate collation column concurrently constraint create \
+    cross current_catalog current_date current_role current_schema current_time current_timestamp \
+    current_user dec decimal default deferrable desc distinct do else end except exists extract false \
+    fetch float for foreign freeze from full grant greatest group grouping having ilike in initially \
+    inner inout int integer intersect interval into is isnull join lateral leading least left like limit \
+    localtime localtimestamp national natural nchar none normalize not notnull null nullif numeric \
+    offset on only or order out outer overlaps overlay placing position precision primary real \
+    references returning right row select session_user setof similar smallint some substring symmetric \
+    table tablesample then time timestamp to trailing treat trim true union unique user using values \
+    varchar variadic verbose when where window with";
+
+/// Quotes an identifier for use within SQL if it would otherwise be case folded or misinterpreted,
+/// i.e. it contains upper case or special characters or is a reserved keyword.
+pub fn quote_ident(ident: &str) -> String {
+    let mut chars = ident.chars();
+    let simple = match chars.next() {
+        Some(c) if c.is_ascii_lowercase() || c == '_' => {
+            chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$')
+        }
+        _ => false,
+    };
+    if simple && !RESERVED_KEYWORDS.split_whitespace().any(|keyword| keyword == ident) {
+        ident.to_owned()
+    } else {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+}
+
+/// Quotes a role name for use within SQL, leaving the PUBLIC pseudo role and special role keywords as is.
+pub fn quote_role(role: &str) -> String {
+    match role {
+        "PUBLIC" | "current_role" | "current_user" | "session_user" => role.to_owned(),
+        _ => quote_ident(role),
+    }
+}
+
 impl fmt::Display for ObjectName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.schema {
-            Some(ref s) => write!(f, "{}.{}", s, self.name),
-            None => write!(f, "{}", self.name),
+            Some(ref s) => write!(f, "{}.{}", quote_ident(s), quote_ident(&self.name)),
+            None => write!(f, "{}", quote_ident(&self.name)),
+        }
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Privilege::Select => write!(f, "SELECT"),
+            Privilege::Insert => write!(f, "INSERT"),
+            Privilege::Update => write!(f, "UPDATE"),
+            Privilege::Delete => write!(f, "DELETE"),
+            Privilege::Truncate => write!(f, "TRUNCATE"),
+            Privilege::References => write!(f, "REFERENCES"),
+            Privilege::Trigger => write!(f, "TRIGGER"),
+            Privilege::Create => write!(f, "CREATE"),
+            Privilege::Usage => write!(f, "USAGE"),
+            Privilege::Execute => write!(f, "EXECUTE"),
+            Privilege::Member => write!(f, "MEMBER"),
+        }
+    }
+}
+
+impl fmt::Display for CommentObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommentObject::Table(ref name) => write!(f, "TABLE {}", name),
+            CommentObject::Column(ref table, ref column) => write!(f, "COLUMN {}.{}", table, quote_ident(column)),
+            CommentObject::Function(ref name, ref types) => {
+                let types = types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                write!(f, "FUNCTION {}({})", name, types.join(", "))
+            }
+            CommentObject::Type(ref name) => write!(f, "TYPE {}", name),
+            CommentObject::Index(ref name) => write!(f, "INDEX {}", name),
+            CommentObject::Constraint(ref table, ref name) => {
+                write!(f, "CONSTRAINT {} ON {}", quote_ident(name), table)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PrivilegeObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PrivilegeObject::Role(ref name) => write!(f, "ROLE {}", quote_role(name)),
+            PrivilegeObject::Schema(ref name) => write!(f, "SCHEMA {}", quote_ident(name)),
+            PrivilegeObject::Table(ref name) => write!(f, "TABLE {}", name),
+            PrivilegeObject::Sequence(ref name) => write!(f, "SEQUENCE {}", name),
+            PrivilegeObject::Function(ref name, None) => write!(f, "FUNCTION {}", name),
+            PrivilegeObject::Function(ref name, Some(ref types)) => {
+                let types = types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                write!(f, "FUNCTION {}({})", name, types.join(", "))
+            }
+            PrivilegeObject::Default(ref scope) => {
+                write!(f, "DEFAULT PRIVILEGES")?;
+                if let Some(ref role) = scope.role {
+                    write!(f, " FOR ROLE {}", quote_role(role))?;
+                }
+                if let Some(ref schema) = scope.schema {
+                    write!(f, " IN SCHEMA {}", quote_ident(schema))?;
+                }
+                write!(f, " ON {}", scope.kind)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DefaultPrivilegeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DefaultPrivilegeKind::Tables => write!(f, "TABLES"),
+            DefaultPrivilegeKind::Sequences => write!(f, "SEQUENCES"),
+            DefaultPrivilegeKind::Functions => write!(f, "FUNCTIONS"),
+        }
+    }
+}
+
+impl fmt::Display for IdentityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IdentityKind::Always => write!(f, "ALWAYS"),
+            IdentityKind::ByDefault => write!(f, "BY DEFAULT"),
+        }
+    }
+}
+
+impl fmt::Display for SequenceOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SequenceOption::Increment(value) => write!(f, "INCREMENT BY {}", value),
+            SequenceOption::MinValue(Some(value)) => write!(f, "MINVALUE {}", value),
+            SequenceOption::MinValue(None) => write!(f, "NO MINVALUE"),
+            SequenceOption::MaxValue(Some(value)) => write!(f, "MAXVALUE {}", value),
+            SequenceOption::MaxValue(None) => write!(f, "NO MAXVALUE"),
+            SequenceOption::Start(value) => write!(f, "START WITH {}", value),
+            SequenceOption::Cache(value) => write!(f, "CACHE {}", value),
+            SequenceOption::Cycle(true) => write!(f, "CYCLE"),
+            SequenceOption::Cycle(false) => write!(f, "NO CYCLE"),
+            SequenceOption::OwnedBy(Some(ref owner)) => {
+                write!(f, "OWNED BY {}.{}", owner.table, quote_ident(&owner.column))
+            }
+            SequenceOption::OwnedBy(None) => write!(f, "OWNED BY NONE"),
         }
     }
 }
@@ -454,12 +2039,91 @@ impl fmt::Display for SqlType {
     }
 }
 
+impl fmt::Display for PartitionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.strategy {
+            PartitionStrategy::Range => write!(f, "RANGE ({})", self.key),
+            PartitionStrategy::List => write!(f, "LIST ({})", self.key),
+            PartitionStrategy::Hash => write!(f, "HASH ({})", self.key),
+        }
+    }
+}
+
+impl fmt::Display for PartitionBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PartitionBound::Default => write!(f, "DEFAULT"),
+            PartitionBound::Range(ref from, ref to) => write!(f, "FOR VALUES FROM ({}) TO ({})", from, to),
+            PartitionBound::List(ref values) => write!(f, "FOR VALUES IN ({})", values),
+            PartitionBound::Hash(ref options) => write!(f, "FOR VALUES WITH ({})", options),
+        }
+    }
+}
+
+impl fmt::Display for AlterTableAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlterTableAction::EnableRowSecurity => write!(f, "ENABLE ROW LEVEL SECURITY"),
+            AlterTableAction::DisableRowSecurity => write!(f, "DISABLE ROW LEVEL SECURITY"),
+            AlterTableAction::ForceRowSecurity => write!(f, "FORCE ROW LEVEL SECURITY"),
+            AlterTableAction::NoForceRowSecurity => write!(f, "NO FORCE ROW LEVEL SECURITY"),
+        }
+    }
+}
+
+impl fmt::Display for PolicyCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolicyCommand::All => write!(f, "ALL"),
+            PolicyCommand::Select => write!(f, "SELECT"),
+            PolicyCommand::Insert => write!(f, "INSERT"),
+            PolicyCommand::Update => write!(f, "UPDATE"),
+            PolicyCommand::Delete => write!(f, "DELETE"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerTiming::Before => write!(f, "BEFORE"),
+            TriggerTiming::After => write!(f, "AFTER"),
+            TriggerTiming::InsteadOf => write!(f, "INSTEAD OF"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerEvent::Insert => write!(f, "INSERT"),
+            TriggerEvent::Update(Some(ref columns)) => {
+                let columns = columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>();
+                write!(f, "UPDATE OF {}", columns.join(", "))
+            }
+            TriggerEvent::Update(None) => write!(f, "UPDATE"),
+            TriggerEvent::Delete => write!(f, "DELETE"),
+            TriggerEvent::Truncate => write!(f, "TRUNCATE"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerLevel::Row => write!(f, "ROW"),
+            TriggerLevel::Statement => write!(f, "STATEMENT"),
+        }
+    }
+}
+
 impl fmt::Display for TypeDefinitionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            TypeDefinitionKind::Composite(_) => write!(f, "Composite"),
+            TypeDefinitionKind::Domain(_) => write!(f, "Domain"),
             TypeDefinitionKind::Enum(_) => write!(f, "Enum"),
-            TypeDefinitionKind::Composite => write!(f, "Composite"),
-            TypeDefinitionKind::Range => write!(f, "Range"),
+            TypeDefinitionKind::Range(_) => write!(f, "Range"),
             TypeDefinitionKind::UserDefined => write!(f, "User Defined"),
         }
     }
@@ -467,6 +2131,14 @@ impl fmt::Display for TypeDefinitionKind {
 
 impl fmt::Display for SimpleSqlType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn precision(p: Option<u32>) -> String {
+            if let Some(p) = p {
+                format!("({})", p)
+            } else {
+                String::new()
+            }
+        }
+
         match *self {
             SimpleSqlType::FixedLengthString(size) => write!(f, "char({})", size),
             SimpleSqlType::VariableLengthString(size) => write!(f, "varchar({})", size),
@@ -493,12 +2165,62 @@ impl fmt::Display for SimpleSqlType {
             SimpleSqlType::Boolean => write!(f, "bool"),
 
             SimpleSqlType::Date => write!(f, "date"),
-            SimpleSqlType::DateTime => write!(f, "timestamp without time zone"),
-            SimpleSqlType::DateTimeWithTimeZone => write!(f, "timestamp with time zone"),
-            SimpleSqlType::Time => write!(f, "time"),
-            SimpleSqlType::TimeWithTimeZone => write!(f, "time with time zone"),
+            SimpleSqlType::DateTime(p) => write!(f, "timestamp{} without time zone", precision(p)),
+            SimpleSqlType::DateTimeWithTimeZone(p) => write!(f, "timestamp{} with time zone", precision(p)),
+            SimpleSqlType::Time(p) => write!(f, "time{}", precision(p)),
+            SimpleSqlType::TimeWithTimeZone(p) => write!(f, "time{} with time zone", precision(p)),
+            SimpleSqlType::Interval(Some(fields), p) => write!(f, "interval {}{}", fields, precision(p)),
+            SimpleSqlType::Interval(None, p) => write!(f, "interval{}", precision(p)),
 
+            SimpleSqlType::Binary => write!(f, "bytea"),
             SimpleSqlType::Uuid => write!(f, "uuid"),
+            SimpleSqlType::Json => write!(f, "json"),
+            SimpleSqlType::Jsonb => write!(f, "jsonb"),
+            SimpleSqlType::Xml => write!(f, "xml"),
+            SimpleSqlType::Oid => write!(f, "oid"),
+
+            SimpleSqlType::Inet => write!(f, "inet"),
+            SimpleSqlType::Cidr => write!(f, "cidr"),
+            SimpleSqlType::MacAddr => write!(f, "macaddr"),
+            SimpleSqlType::MacAddr8 => write!(f, "macaddr8"),
+
+            SimpleSqlType::TextSearchVector => write!(f, "tsvector"),
+            SimpleSqlType::TextSearchQuery => write!(f, "tsquery"),
+
+            SimpleSqlType::Point => write!(f, "point"),
+            SimpleSqlType::Line => write!(f, "line"),
+            SimpleSqlType::Segment => write!(f, "lseg"),
+            SimpleSqlType::Box => write!(f, "box"),
+            SimpleSqlType::Path => write!(f, "path"),
+            SimpleSqlType::Polygon => write!(f, "polygon"),
+            SimpleSqlType::Circle => write!(f, "circle"),
+
+            SimpleSqlType::IntegerRange => write!(f, "int4range"),
+            SimpleSqlType::BigIntegerRange => write!(f, "int8range"),
+            SimpleSqlType::NumericRange => write!(f, "numrange"),
+            SimpleSqlType::DateTimeRange => write!(f, "tsrange"),
+            SimpleSqlType::DateTimeWithTimeZoneRange => write!(f, "tstzrange"),
+            SimpleSqlType::DateRange => write!(f, "daterange"),
+        }
+    }
+}
+
+impl fmt::Display for IntervalFields {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntervalFields::Year => write!(f, "year"),
+            IntervalFields::Month => write!(f, "month"),
+            IntervalFields::Day => write!(f, "day"),
+            IntervalFields::Hour => write!(f, "hour"),
+            IntervalFields::Minute => write!(f, "minute"),
+            IntervalFields::Second => write!(f, "second"),
+            IntervalFields::YearToMonth => write!(f, "year to month"),
+            IntervalFields::DayToHour => write!(f, "day to hour"),
+            IntervalFields::DayToMinute => write!(f, "day to minute"),
+            IntervalFields::DayToSecond => write!(f, "day to second"),
+            IntervalFields::HourToMinute => write!(f, "hour to minute"),
+            IntervalFields::HourToSecond => write!(f, "hour to second"),
+            IntervalFields::MinuteToSecond => write!(f, "minute to second"),
         }
     }
 }
@@ -0,0 +1,1475 @@
+//! Renders statements and definitions back to canonical SQL.
+//!
+//! Lists (e.g. the columns of a table) and clauses (e.g. the options of a function) are kept on a single
+//! line where they fit within the line width, otherwise each item is placed on its own line. Expressions,
+//! queries and bodies are written exactly as they were defined.
+
+use crate::sql::ast::*;
+use crate::sql::lexer;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// The number of spaces list items are indented by when a list doesn't fit on one line
+    pub indent: usize,
+    /// Whether keywords are written in upper or lower case. Type names are always written in lower case.
+    pub keyword_case: KeywordCase,
+    /// The width lines are kept within where possible
+    pub line_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 4,
+            keyword_case: KeywordCase::Upper,
+            line_width: 80,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Formatter {
+    options: FormatOptions,
+}
+
+impl Formatter {
+    pub fn new(options: FormatOptions) -> Self {
+        Formatter { options }
+    }
+
+    /// Renders the statements as a script, with each statement terminated and separated by a blank line.
+    pub fn statements(&self, statements: &[Statement]) -> String {
+        let statements = statements
+            .iter()
+            .map(|statement| format!("{};\n", self.statement(statement)))
+            .collect::<Vec<_>>();
+        statements.join("\n")
+    }
+
+    /// Renders a statement without a terminating semicolon. Privileges are written as one statement per
+    /// object and grantee, and unsupported statements are written as they were defined.
+    pub fn statement(&self, statement: &Statement) -> String {
+        match *statement {
+            Statement::Aggregate(ref aggregate) => self.aggregate(aggregate),
+            Statement::AlterTable(ref name, action) => self.alter_table(name, action),
+            Statement::Comment(ref comment) => self.comment(&comment.object, comment.comment.as_ref().map(|c| &c[..])),
+            Statement::Error(ErrorKind::ExtensionNotSupported(ref name)) => {
+                self.finish(format!("CREATE EXTENSION {}", quote_ident(name)))
+            }
+            Statement::Function(ref function) => self.function(function),
+            Statement::Index(ref index) => self.index(index, false),
+            Statement::MaterializedView(ref view) => self.materialized_view(view),
+            Statement::Policy(ref policy) => self.policy(policy),
+            Statement::Privileges(ref privileges) => {
+                let mut statements: Vec<String> = Vec::new();
+                let mut start = 0;
+                for end in 1..=privileges.len() {
+                    let first = &privileges[start];
+                    let grouped = end < privileges.len() && {
+                        let next = &privileges[end];
+                        next.action == first.action
+                            && next.object == first.object
+                            && next.grantee == first.grantee
+                            && next.grant_option == first.grant_option
+                    };
+                    if !grouped {
+                        let types = privileges[start..end]
+                            .iter()
+                            .map(|p| p.privilege.clone())
+                            .collect::<Vec<_>>();
+                        statements.push(self.privileges(
+                            &first.action,
+                            &first.object,
+                            &types,
+                            &first.grantee,
+                            first.grant_option,
+                        ));
+                        start = end;
+                    }
+                }
+                statements.join(";\n")
+            }
+            Statement::Procedure(ref procedure) => self.procedure(procedure),
+            Statement::Schema(ref schema) => self.schema(schema),
+            Statement::Sequence(ref sequence) => self.sequence(sequence, true),
+            Statement::Table(ref table) => self.table(table, true),
+            Statement::Trigger(ref trigger) => self.trigger(trigger),
+            Statement::Type(ref ty) => self.type_definition(ty),
+            Statement::Unsupported { ref text, .. } => text.trim().trim_end_matches(';').trim_end().to_owned(),
+            Statement::View(ref view) => self.view(view, false),
+        }
+    }
+
+    pub fn aggregate(&self, aggregate: &AggregateDefinition) -> String {
+        let arguments = if aggregate.arguments.is_empty() {
+            "*".to_owned()
+        } else {
+            display_list(&aggregate.arguments)
+        };
+        let mut options = vec![
+            format!("SFUNC = {}", aggregate.state_function),
+            format!("STYPE = {}", aggregate.state_type),
+        ];
+        if let Some(ref function) = aggregate.final_function {
+            options.push(format!("FINALFUNC = {}", function));
+        }
+        if let Some(ref function) = aggregate.combine_function {
+            options.push(format!("COMBINEFUNC = {}", function));
+        }
+        if let Some(ref condition) = aggregate.initial_condition {
+            options.push(format!("INITCOND = {}", quote_string(condition)));
+        }
+        if let Some(parallel) = aggregate.parallel {
+            options.push(format!("PARALLEL = {}", parallel));
+        }
+        let head = format!("CREATE AGGREGATE {} ({}) ", aggregate.name, arguments);
+        self.finish(self.list(&head, &options))
+    }
+
+    pub fn alter_table(&self, name: &ObjectName, action: AlterTableAction) -> String {
+        self.finish(format!("ALTER TABLE {} {}", name, action))
+    }
+
+    /// A table column, as declared within CREATE TABLE or ALTER TABLE ADD COLUMN
+    pub fn column(&self, column: &ColumnDefinition) -> String {
+        self.finish(column_sql(column))
+    }
+
+    pub fn comment(&self, object: &CommentObject, comment: Option<&str>) -> String {
+        match comment {
+            Some(comment) => self.finish(format!("COMMENT ON {} IS {}", object, quote_string(comment))),
+            None => self.finish(format!("COMMENT ON {} IS NULL", object)),
+        }
+    }
+
+    /// Functions are always created with CREATE OR REPLACE
+    pub fn function(&self, function: &FunctionDefinition) -> String {
+        let head = format!("CREATE OR REPLACE FUNCTION {} ", function.name);
+        let mut clauses = vec![self.list(&head, &display_items(&function.arguments))];
+        clauses.push(match function.return_type {
+            FunctionReturnType::Table(ref columns) => {
+                let columns = columns
+                    .iter()
+                    .map(|column| format!("{} {}", quote_ident(&column.name), column.sql_type))
+                    .collect::<Vec<_>>();
+                self.list("RETURNS TABLE ", &columns)
+            }
+            FunctionReturnType::SetOf(ref sql_type) => format!("RETURNS SETOF {}", sql_type),
+            FunctionReturnType::SqlType(ref sql_type) => format!("RETURNS {}", sql_type),
+        });
+        clauses.push(format!("AS {}", dollar_quote(&function.body)));
+        clauses.push(format!("LANGUAGE {}", function.language));
+        clauses.extend(display_items(&function.attributes));
+        self.finish(self.clauses(&clauses))
+    }
+
+    pub fn index(&self, index: &IndexDefinition, concurrently: bool) -> String {
+        let mut head = "CREATE ".to_owned();
+        if index.unique {
+            head.push_str("UNIQUE ");
+        }
+        head.push_str("INDEX ");
+        if concurrently {
+            head.push_str("CONCURRENTLY ");
+        }
+        head.push_str(&format!("{} ON {} ", quote_ident(&index.name), index.table));
+        if let Some(ref method) = index.index_type {
+            head.push_str(&format!("USING {} ", method));
+        }
+        let columns = index.columns.iter().map(index_column_sql).collect::<Vec<_>>();
+        let mut clauses = vec![self.list(&head, &columns)];
+        if let Some(ref include) = index.include {
+            clauses.push(format!("INCLUDE ({})", column_list(include)));
+        }
+        if let Some(ref parameters) = index.storage_parameters {
+            clauses.push(format!("WITH ({})", display_list(parameters)));
+        }
+        if let Some(ref predicate) = index.predicate {
+            clauses.push(format!("WHERE {}", predicate));
+        }
+        self.finish(self.clauses(&clauses))
+    }
+
+    pub fn materialized_view(&self, view: &MaterializedViewDefinition) -> String {
+        let mut head = format!("CREATE MATERIALIZED VIEW {}", view.name);
+        if let Some(ref columns) = view.columns {
+            head.push_str(&format!(" ({})", column_list(columns)));
+        }
+        let mut clauses = vec![self.query(&head, &view.query)];
+        if !view.with_data {
+            clauses.push("WITH NO DATA".to_owned());
+        }
+        self.finish(self.clauses(&clauses))
+    }
+
+    pub fn policy(&self, policy: &PolicyDefinition) -> String {
+        let mut clauses = vec![format!(
+            "CREATE POLICY {} ON {}",
+            quote_ident(&policy.name),
+            policy.table
+        )];
+        // Policies are permissive by default, and restrictive policies require PostgreSQL 10
+        if !policy.permissive {
+            clauses.push("AS RESTRICTIVE".to_owned());
+        }
+        clauses.push(format!("FOR {}", policy.command));
+        clauses.push(format!("TO {}", role_list(&policy.roles)));
+        if let Some(ref using) = policy.using {
+            clauses.push(format!("USING ({})", using));
+        }
+        if let Some(ref with_check) = policy.with_check {
+            clauses.push(format!("WITH CHECK ({})", with_check));
+        }
+        self.finish(self.clauses(&clauses))
+    }
+
+    /// Grants or revokes privileges on a single object for a single grantee
+    pub fn privileges(
+        &self,
+        action: &PrivilegeAction,
+        object: &PrivilegeObject,
+        privileges: &[Privilege],
+        grantee: &str,
+        grant_option: bool,
+    ) -> String {
+        let (object, prefix) = match *object {
+            PrivilegeObject::Role(ref role) => {
+                return self.finish(match *action {
+                    PrivilegeAction::Grant => format!(
+                        "GRANT {} TO {}{}",
+                        quote_role(role),
+                        quote_role(grantee),
+                        if grant_option { " WITH ADMIN OPTION" } else { "" }
+                    ),
+                    PrivilegeAction::Revoke => format!(
+                        "REVOKE {}{} FROM {}",
+                        if grant_option { "ADMIN OPTION FOR " } else { "" },
+                        quote_role(role),
+                        quote_role(grantee)
+                    ),
+                });
+            }
+            PrivilegeObject::Default(ref scope) => {
+                let mut prefix = "ALTER DEFAULT PRIVILEGES ".to_owned();
+                if let Some(ref role) = scope.role {
+                    prefix.push_str(&format!("FOR ROLE {} ", quote_role(role)));
+                }
+                if let Some(ref schema) = scope.schema {
+                    prefix.push_str(&format!("IN SCHEMA {} ", quote_ident(schema)));
+                }
+                (scope.kind.to_string(), prefix)
+            }
+            ref object => (object.to_string(), String::new()),
+        };
+        self.finish(match *action {
+            PrivilegeAction::Grant => format!(
+                "{}GRANT {} ON {} TO {}{}",
+                prefix,
+                display_list(privileges),
+                object,
+                quote_role(grantee),
+                if grant_option { " WITH GRANT OPTION" } else { "" }
+            ),
+            PrivilegeAction::Revoke => format!(
+                "{}REVOKE {}{} ON {} FROM {}",
+                prefix,
+                if grant_option { "GRANT OPTION FOR " } else { "" },
+                display_list(privileges),
+                object,
+                quote_role(grantee)
+            ),
+        })
+    }
+
+    /// Procedures are always created with CREATE OR REPLACE
+    pub fn procedure(&self, procedure: &ProcedureDefinition) -> String {
+        let head = format!("CREATE OR REPLACE PROCEDURE {} ", procedure.name);
+        let mut clauses = vec![self.list(&head, &display_items(&procedure.arguments))];
+        clauses.push(format!("AS {}", dollar_quote(&procedure.body)));
+        clauses.push(format!("LANGUAGE {}", procedure.language));
+        clauses.extend(display_items(&procedure.attributes));
+        self.finish(self.clauses(&clauses))
+    }
+
+    pub fn schema(&self, schema: &SchemaDefinition) -> String {
+        self.finish(format!("CREATE SCHEMA {}", quote_ident(&schema.name)))
+    }
+
+    /// Ownership refers to a table column, so may need to be set once the table exists
+    pub fn sequence(&self, sequence: &SequenceDefinition, owner: bool) -> String {
+        let mut options = Vec::new();
+        if let Some(increment) = sequence.increment {
+            options.push(SequenceOption::Increment(increment));
+        }
+        if let Some(min_value) = sequence.min_value {
+            options.push(SequenceOption::MinValue(Some(min_value)));
+        }
+        if let Some(max_value) = sequence.max_value {
+            options.push(SequenceOption::MaxValue(Some(max_value)));
+        }
+        if let Some(start) = sequence.start {
+            options.push(SequenceOption::Start(start));
+        }
+        if let Some(cache) = sequence.cache {
+            options.push(SequenceOption::Cache(cache));
+        }
+        if sequence.cycle {
+            options.push(SequenceOption::Cycle(true));
+        }
+        if owner {
+            if let Some(ref owned_by) = sequence.owned_by {
+                options.push(SequenceOption::OwnedBy(Some(owned_by.clone())));
+            }
+        }
+        let mut clauses = vec![format!("CREATE SEQUENCE {}", sequence.name)];
+        clauses.extend(display_items(&options));
+        self.finish(self.clauses(&clauses))
+    }
+
+    /// Table constraints refer to other tables, so may need to be added once every table exists
+    pub fn table(&self, table: &TableDefinition, constraints: bool) -> String {
+        let mut clauses = Vec::new();
+        if let Some(ref partition) = table.partition_of {
+            clauses.push(format!("CREATE TABLE {} PARTITION OF {}", table.name, partition.parent));
+            clauses.push(partition.bound.to_string());
+        } else {
+            let mut items = table
+                .columns
+                .iter()
+                .map(|column| self.item(&column_clauses(column)))
+                .collect::<Vec<_>>();
+            if constraints {
+                items.extend(
+                    table
+                        .constraints
+                        .iter()
+                        .map(|constraint| self.item(&table_constraint_clauses(constraint))),
+                );
+            }
+            clauses.push(self.list(&format!("CREATE TABLE {} ", table.name), &items));
+        }
+        if let Some(ref key) = table.partition_by {
+            clauses.push(format!("PARTITION BY {}", key));
+        }
+        self.finish(self.clauses(&clauses))
+    }
+
+    /// A table constraint, as declared within CREATE TABLE or ALTER TABLE ADD
+    pub fn table_constraint(&self, constraint: &TableConstraint) -> String {
+        self.finish(table_constraint_sql(constraint))
+    }
+
+    pub fn trigger(&self, trigger: &TriggerDefinition) -> String {
+        let events = trigger.events.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        let mut clauses = vec![
+            format!(
+                "CREATE TRIGGER {} {} {}",
+                quote_ident(&trigger.name),
+                trigger.timing,
+                events.join(" OR ")
+            ),
+            format!("ON {}", trigger.table),
+            format!("FOR EACH {}", trigger.level),
+        ];
+        if let Some(ref condition) = trigger.condition {
+            clauses.push(format!("WHEN ({})", condition));
+        }
+        let arguments = trigger.arguments.iter().map(|a| quote_string(a)).collect::<Vec<_>>();
+        clauses.push(format!(
+            "EXECUTE PROCEDURE {}({})",
+            trigger.function,
+            arguments.join(", ")
+        ));
+        self.finish(self.clauses(&clauses))
+    }
+
+    pub fn type_definition(&self, ty: &TypeDefinition) -> String {
+        let sql = match ty.kind {
+            TypeDefinitionKind::Composite(ref attributes) => {
+                self.list(&format!("CREATE TYPE {} AS ", ty.name), &display_items(attributes))
+            }
+            TypeDefinitionKind::Domain(ref domain) => {
+                let mut clauses = vec![format!("CREATE DOMAIN {} AS {}", ty.name, domain.base_type)];
+                if let Some(ref default) = domain.default {
+                    clauses.push(format!("DEFAULT {}", default));
+                }
+                if domain.not_null {
+                    clauses.push("NOT NULL".to_owned());
+                }
+                clauses.extend(
+                    domain
+                        .constraints
+                        .iter()
+                        .map(|c| domain_constraint_sql(c).trim_start().to_owned()),
+                );
+                self.clauses(&clauses)
+            }
+            TypeDefinitionKind::Enum(ref values) => {
+                let values = values.iter().map(|v| quote_string(v)).collect::<Vec<_>>();
+                self.list(&format!("CREATE TYPE {} AS ENUM ", ty.name), &values)
+            }
+            TypeDefinitionKind::Range(ref range) => {
+                let mut options = vec![format!("SUBTYPE = {}", range.subtype)];
+                let named = [
+                    ("SUBTYPE_OPCLASS", &range.subtype_opclass),
+                    ("COLLATION", &range.collation),
+                    ("CANONICAL", &range.canonical),
+                    ("SUBTYPE_DIFF", &range.subtype_diff),
+                ];
+                for &(option, value) in &named {
+                    if let Some(ref value) = *value {
+                        options.push(format!("{} = {}", option, value));
+                    }
+                }
+                self.list(&format!("CREATE TYPE {} AS RANGE ", ty.name), &options)
+            }
+            // Guarded against when generating the change set
+            TypeDefinitionKind::UserDefined => panic!("User defined types can not be created: {}", ty.name),
+        };
+        self.finish(sql)
+    }
+
+    pub fn view(&self, view: &ViewDefinition, replace: bool) -> String {
+        let mut head = if replace {
+            format!("CREATE OR REPLACE VIEW {}", view.name)
+        } else {
+            format!("CREATE VIEW {}", view.name)
+        };
+        if let Some(ref columns) = view.columns {
+            head.push_str(&format!(" ({})", column_list(columns)));
+        }
+        self.finish(self.query(&head, &view.query))
+    }
+
+    // Whether the text fits on a single line
+    fn fits(&self, text: &str) -> bool {
+        !text.contains('\n') && text.chars().count() <= self.options.line_width
+    }
+
+    // Brackets the items after the head, placing each item on its own indented line if they don't all fit on
+    // the last line of the head
+    fn list(&self, head: &str, items: &[String]) -> String {
+        let inline = format!("{}({})", head, items.join(", "));
+        let last_line = inline.rsplit('\n').next().unwrap_or("");
+        if items.is_empty() || (self.fits(last_line) && !items.iter().any(|item| item.contains('\n'))) {
+            return inline;
+        }
+        let indent = " ".repeat(self.options.indent);
+        let items = items
+            .iter()
+            .map(|item| format!("{}{}", indent, item))
+            .collect::<Vec<_>>();
+        format!("{}(\n{}\n)", head, items.join(",\n"))
+    }
+
+    // Keeps a list item on one line if it fits once indented, otherwise each clause after the first starts a
+    // new line indented beneath it
+    fn item(&self, clauses: &[String]) -> String {
+        let inline = clauses.join(" ");
+        if self.fits(&format!("{}{}", " ".repeat(self.options.indent), inline)) {
+            inline
+        } else {
+            clauses.join(&format!("\n{}", " ".repeat(self.options.indent * 2)))
+        }
+    }
+
+    // Joins the clauses on a single line if they fit, otherwise each clause starts a new line
+    fn clauses(&self, clauses: &[String]) -> String {
+        let inline = clauses.join(" ");
+        if self.fits(&inline) {
+            inline
+        } else {
+            clauses.join("\n")
+        }
+    }
+
+    // Queries are kept verbatim, starting on a new line if they don't fit after AS
+    fn query(&self, head: &str, query: &str) -> String {
+        let inline = format!("{} AS {}", head, query);
+        if self.fits(&inline) {
+            inline
+        } else {
+            format!("{} AS\n{}", head, query)
+        }
+    }
+
+    // Keywords are rendered in upper case so are lower cased afterwards if required. Anything the lexer
+    // doesn't see as a keyword, such as identifiers, strings and verbatim expressions, is left alone.
+    fn finish(&self, sql: String) -> String {
+        if self.options.keyword_case == KeywordCase::Upper {
+            return sql;
+        }
+        let tokens = match lexer::tokenize_stmt(&sql) {
+            Ok(tokens) => tokens,
+            Err(_) => return sql,
+        };
+        let mut bytes = sql.into_bytes();
+        for (start, token, end) in tokens {
+            let word = &bytes[start.offset..end.offset];
+            if token.is_keyword() && word.iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()) {
+                bytes[start.offset..end.offset].make_ascii_lowercase();
+            }
+        }
+        String::from_utf8(bytes).expect("ASCII keywords")
+    }
+}
+
+fn display_items<T: ToString>(items: &[T]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+fn display_list<T: ToString>(items: &[T]) -> String {
+    display_items(items).join(", ")
+}
+
+fn quote_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn column_sql(column: &ColumnDefinition) -> String {
+    column_clauses(column).join(" ")
+}
+
+// The name and type of a column, followed by a clause for each constraint
+fn column_clauses(column: &ColumnDefinition) -> Vec<String> {
+    let mut clauses = vec![format!("{} {}", quote_ident(&column.name), column.sql_type)];
+    for constraint in &column.constraints {
+        clauses.push(column_constraint_sql(constraint).trim_start().to_owned());
+    }
+    clauses
+}
+
+fn table_constraint_sql(constraint: &TableConstraint) -> String {
+    table_constraint_clauses(constraint).join(" ")
+}
+
+fn table_constraint_clauses(constraint: &TableConstraint) -> Vec<String> {
+    match *constraint {
+        TableConstraint::Primary {
+            ref name,
+            ref columns,
+            ref parameters,
+        } => {
+            let mut clauses = vec![format!(
+                "CONSTRAINT {} PRIMARY KEY ({})",
+                quote_ident(name),
+                column_list(columns)
+            )];
+            clauses.extend(index_parameters_clause(parameters));
+            clauses
+        }
+        TableConstraint::Foreign {
+            ref name,
+            ref columns,
+            ref ref_table,
+            ref ref_columns,
+            ref match_type,
+            ref events,
+        } => {
+            let mut clauses = vec![
+                format!(
+                    "CONSTRAINT {} FOREIGN KEY ({})",
+                    quote_ident(name),
+                    column_list(columns)
+                ),
+                format!("REFERENCES {} ({})", ref_table, column_list(ref_columns)),
+            ];
+            if let Some(ref m) = *match_type {
+                clauses.push(m.to_string());
+            }
+            if let Some(ref events) = *events {
+                for e in events {
+                    clauses.push(match *e {
+                        ForeignConstraintEvent::Delete(ref action) => format!("ON DELETE {}", action),
+                        ForeignConstraintEvent::Update(ref action) => format!("ON UPDATE {}", action),
+                    });
+                }
+            }
+            clauses
+        }
+        TableConstraint::Check {
+            ref name,
+            ref expression,
+            no_inherit,
+        } => vec![
+            format!("CONSTRAINT {}", quote_ident(name)),
+            check_constraint_sql(expression, no_inherit).trim_start().to_owned(),
+        ],
+        TableConstraint::Unique {
+            ref name,
+            ref columns,
+            ref include,
+            ref parameters,
+        } => {
+            let mut clauses = vec![format!(
+                "CONSTRAINT {} UNIQUE ({})",
+                quote_ident(name),
+                column_list(columns)
+            )];
+            if let Some(ref include) = *include {
+                clauses.push(format!("INCLUDE ({})", column_list(include)));
+            }
+            clauses.extend(index_parameters_clause(parameters));
+            clauses
+        }
+        TableConstraint::Exclude {
+            ref name,
+            ref index_type,
+            ref elements,
+            ref parameters,
+        } => {
+            let mut sql = format!("CONSTRAINT {} EXCLUDE", quote_ident(name));
+            if let Some(ref index_type) = *index_type {
+                sql.push_str(&format!(" USING {}", index_type));
+            }
+            sql.push_str(&format!(" ({})", display_list(elements)));
+            let mut clauses = vec![sql];
+            clauses.extend(index_parameters_clause(parameters));
+            clauses
+        }
+    }
+}
+
+fn index_column_sql(column: &IndexColumn) -> String {
+    let mut sql = column.key.to_string();
+    if let Some(ref collation) = column.collation {
+        sql.push_str(&format!(" COLLATE \"{}\"", collation));
+    }
+    if let Some(ref operator_class) = column.operator_class {
+        sql.push_str(&format!(" {}", operator_class));
+    }
+    if let Some(ref order) = column.order {
+        sql.push_str(match order {
+            IndexOrder::Ascending => " ASC",
+            IndexOrder::Descending => " DESC",
+        });
+    }
+    if let Some(ref position) = column.null_position {
+        sql.push_str(match position {
+            IndexPosition::First => " NULLS FIRST",
+            IndexPosition::Last => " NULLS LAST",
+        });
+    }
+    sql
+}
+
+fn index_parameters_clause(parameters: &Option<Vec<IndexParameter>>) -> Option<String> {
+    parameters
+        .as_ref()
+        .map(|parameters| format!("WITH ({})", display_list(parameters)))
+}
+
+pub(crate) fn column_list(columns: &[String]) -> String {
+    columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>().join(", ")
+}
+
+pub(crate) fn role_list(roles: &[String]) -> String {
+    roles.iter().map(|r| quote_role(r)).collect::<Vec<_>>().join(", ")
+}
+
+pub(crate) fn domain_constraint_sql(constraint: &DomainConstraint) -> String {
+    match constraint.name {
+        Some(ref name) => format!(" CONSTRAINT {} CHECK ({})", quote_ident(name), constraint.expression),
+        None => format!(" CHECK ({})", constraint.expression),
+    }
+}
+
+pub(crate) fn identity_sql(kind: IdentityKind, options: &[SequenceOption]) -> String {
+    if options.is_empty() {
+        format!(" GENERATED {} AS IDENTITY", kind)
+    } else {
+        let options = options.iter().map(|o| o.to_string()).collect::<Vec<_>>();
+        format!(" GENERATED {} AS IDENTITY ({})", kind, options.join(" "))
+    }
+}
+
+pub(crate) fn column_constraint_sql(constraint: &ColumnConstraint) -> String {
+    match *constraint {
+        ColumnConstraint::Default(ref any_type) => format!(" DEFAULT {}", any_type),
+        ColumnConstraint::NotNull => " NOT NULL".to_owned(),
+        ColumnConstraint::Null => " NULL".to_owned(),
+        ColumnConstraint::Unique => " UNIQUE".to_owned(),
+        ColumnConstraint::PrimaryKey => " PRIMARY KEY".to_owned(),
+        ColumnConstraint::Check {
+            ref expression,
+            no_inherit,
+        } => check_constraint_sql(expression, no_inherit),
+        ColumnConstraint::Identity(kind, ref options) => identity_sql(kind, options),
+        ColumnConstraint::Generated(ref expression) => format!(" GENERATED ALWAYS AS ({}) STORED", expression),
+    }
+}
+
+// Bodies are dollar quoted using a tag that doesn't appear within the body itself
+pub(crate) fn dollar_quote(body: &str) -> String {
+    let mut tag = String::new();
+    let mut suffix = 0;
+    loop {
+        let delimiter = format!("${}$", tag);
+        // The body can't end with part of the delimiter either, e.g. a trailing $ for $$
+        if format!("{}{}", body, delimiter).find(&delimiter) == Some(body.len()) {
+            return format!("{}{}{}", delimiter, body, delimiter);
+        }
+        suffix += 1;
+        tag = if suffix == 1 {
+            "body".to_owned()
+        } else {
+            format!("body{}", suffix)
+        };
+    }
+}
+
+fn check_constraint_sql(expression: &str, no_inherit: bool) -> String {
+    if no_inherit {
+        format!(" CHECK ({}) NO INHERIT", expression)
+    } else {
+        format!(" CHECK ({})", expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sql::lexer::Token;
+    use crate::sql::parser::StatementListParser;
+
+    use glob::glob;
+    use lalrpop_util::ParseError;
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+    use spectral::prelude::*;
+    use std::fs;
+
+    fn parse(sql: &str) -> Vec<Statement> {
+        let tokens = lexer::tokenize_stmt(sql).unwrap_or_else(|e| panic!("Failed to tokenize {}: {:?}", sql, e));
+        StatementListParser::new()
+            .parse(tokens)
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {:?}", sql, e))
+    }
+
+    // Privileges are written as one statement per object and grantee, so are compared individually
+    fn flatten(statements: Vec<Statement>) -> Vec<Statement> {
+        let mut flattened = Vec::new();
+        for statement in statements {
+            match statement {
+                Statement::Privileges(privileges) => {
+                    flattened.extend(privileges.into_iter().map(|p| Statement::Privileges(vec![p])))
+                }
+                statement => flattened.push(statement),
+            }
+        }
+        flattened
+    }
+
+    fn contacts() -> Statement {
+        parse(
+            "CREATE TABLE my.contacts (id serial PRIMARY KEY, first_name varchar(50) NOT NULL, \
+             last_name varchar(50) NOT NULL, CONSTRAINT uq_name UNIQUE (first_name, last_name))",
+        )
+        .remove(0)
+    }
+
+    #[test]
+    fn it_keeps_short_lists_on_one_line() {
+        let formatter = Formatter::default();
+        let sql = formatter.statement(&parse("CREATE TABLE my.contacts (id serial, name text)")[0]);
+        assert_that!(sql).is_equal_to("CREATE TABLE my.contacts (id serial, name text)".to_owned());
+        let sql = formatter.statement(&parse("CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')")[0]);
+        assert_that!(sql).is_equal_to("CREATE TYPE mood AS ENUM ('sad', 'ok', 'happy')".to_owned());
+    }
+
+    #[test]
+    fn it_breaks_long_lists_onto_indented_lines() {
+        let sql = Formatter::default().statement(&contacts());
+        assert_that!(sql).is_equal_to(
+            "CREATE TABLE my.contacts (\n    \
+             id serial PRIMARY KEY,\n    \
+             first_name varchar(50) NOT NULL,\n    \
+             last_name varchar(50) NOT NULL,\n    \
+             CONSTRAINT uq_name UNIQUE (first_name, last_name)\n\
+             )"
+            .to_owned(),
+        );
+
+        let formatter = Formatter::new(FormatOptions {
+            indent: 2,
+            line_width: 40,
+            ..FormatOptions::default()
+        });
+        let sql = formatter.statement(&parse("CREATE TABLE my.contacts (id serial, name text)")[0]);
+        assert_that!(sql).is_equal_to("CREATE TABLE my.contacts (\n  id serial,\n  name text\n)".to_owned());
+    }
+
+    #[test]
+    fn it_breaks_long_clauses_onto_new_lines() {
+        let formatter = Formatter::default();
+        let trigger = parse(
+            "CREATE TRIGGER audit_contacts AFTER INSERT OR UPDATE ON my.contacts \
+             FOR EACH ROW WHEN (NEW.id > 0) EXECUTE PROCEDURE my.audit('contacts')",
+        );
+        assert_that!(formatter.statement(&trigger[0])).is_equal_to(
+            "CREATE TRIGGER audit_contacts AFTER INSERT OR UPDATE\n\
+             ON my.contacts\n\
+             FOR EACH ROW\n\
+             WHEN (NEW.id > 0)\n\
+             EXECUTE PROCEDURE my.audit('contacts')"
+                .to_owned(),
+        );
+        let policy = parse("CREATE POLICY own_rows ON my.contacts FOR SELECT USING (active)");
+        assert_that!(formatter.statement(&policy[0]))
+            .is_equal_to("CREATE POLICY own_rows ON my.contacts FOR SELECT TO PUBLIC USING (active)".to_owned());
+    }
+
+    #[test]
+    fn it_breaks_long_columns_and_table_constraints_onto_new_lines() {
+        let formatter = Formatter::new(FormatOptions {
+            line_width: 60,
+            ..FormatOptions::default()
+        });
+        let table = parse(
+            "CREATE TABLE sales.order_lines (
+                id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY (START WITH 1000 INCREMENT BY 10),
+                order_id int NOT NULL,
+                CONSTRAINT fk_order_lines_orders FOREIGN KEY (order_id) REFERENCES sales.orders (id) \
+                ON DELETE CASCADE ON UPDATE RESTRICT
+            )",
+        );
+        let sql = formatter.statement(&table[0]);
+        assert_that!(sql).is_equal_to(
+            "CREATE TABLE sales.order_lines (\n    \
+             id bigint\n        \
+             NOT NULL\n        \
+             GENERATED BY DEFAULT AS IDENTITY (START WITH 1000 INCREMENT BY 10),\n    \
+             order_id int NOT NULL,\n    \
+             CONSTRAINT fk_order_lines_orders FOREIGN KEY (order_id)\n        \
+             REFERENCES sales.orders (id)\n        \
+             ON DELETE CASCADE\n        \
+             ON UPDATE RESTRICT\n\
+             )"
+            .to_owned(),
+        );
+        assert_that!(parse(&sql)).is_equal_to(table);
+    }
+
+    #[test]
+    fn it_can_write_keywords_in_lower_case() {
+        let formatter = Formatter::new(FormatOptions {
+            keyword_case: KeywordCase::Lower,
+            ..FormatOptions::default()
+        });
+        let statements = parse(
+            "CREATE VIEW my.\"Active Contacts\" AS SELECT id FROM contacts WHERE active = 'YES';
+             GRANT SELECT, INSERT ON TABLE my.contacts TO \"ADMIN\" WITH GRANT OPTION;
+             CREATE MATERIALIZED VIEW my.totals AS SELECT sum(id) FROM contacts WITH NO DATA;",
+        );
+        assert_that!(formatter.statements(&statements)).is_equal_to(
+            "create view my.\"Active Contacts\" as SELECT id FROM contacts WHERE active = 'YES';\n\n\
+             grant select, insert on table my.contacts to \"ADMIN\" with grant option;\n\n\
+             create materialized view my.totals as SELECT sum(id) FROM contacts with no data;\n"
+                .to_owned(),
+        );
+    }
+
+    #[test]
+    fn it_dollar_quotes_bodies_with_a_tag_not_found_in_the_body() {
+        assert_that!(dollar_quote(" SELECT 1 ")).is_equal_to("$$ SELECT 1 $$".to_owned());
+        assert_that!(dollar_quote(" EXECUTE format($$SELECT %I$$, name); "))
+            .is_equal_to("$body$ EXECUTE format($$SELECT %I$$, name); $body$".to_owned());
+        assert_that!(dollar_quote(" $$ $body$ ")).is_equal_to("$body2$ $$ $body$ $body2$".to_owned());
+        // A trailing $ would otherwise merge with the closing delimiter
+        assert_that!(dollar_quote("SELECT $")).is_equal_to("$body$SELECT $$body$".to_owned());
+    }
+
+    #[test]
+    fn it_round_trips_the_sample_projects() {
+        let pattern = format!("{}/../samples/**/*.sql", env!("CARGO_MANIFEST_DIR"));
+        let options = [
+            FormatOptions::default(),
+            FormatOptions {
+                indent: 2,
+                keyword_case: KeywordCase::Lower,
+                line_width: 20,
+            },
+        ];
+        let mut count = 0;
+        for path in glob(&pattern).unwrap() {
+            let path = path.unwrap();
+            // Seed scripts are run as is rather than parsed
+            if path.components().any(|c| c.as_os_str() == "scripts") {
+                continue;
+            }
+            let contents = fs::read_to_string(&path).unwrap();
+            let statements = flatten(parse(&contents));
+            for options in &options {
+                let sql = Formatter::new(options.clone()).statements(&statements);
+                assert_that!(flatten(parse(&sql))).named(&sql).is_equal_to(&statements);
+            }
+            count += 1;
+        }
+        assert_that!(count).is_greater_than(0);
+    }
+
+    const IDENTS: &[&str] = &[
+        "id",
+        "first_name",
+        "col1",
+        "Mixed Case",
+        "select",
+        "order",
+        "amount$",
+        "_row",
+    ];
+    const STRINGS: &[&str] = &["", "active", "YES", "select * from x", "a \"quoted\" word", "café"];
+    const CONDITIONS: &[&str] = &[
+        "id > 0",
+        "first_name <> ''",
+        "(a = 1) OR (b IS NULL)",
+        "NEW.id <> OLD.id",
+    ];
+    const QUERIES: &[&str] = &[
+        "SELECT 1",
+        "SELECT id, first_name FROM public.contacts WHERE active",
+        "SELECT c.id FROM my.\"Contacts\" c JOIN my.orders o ON o.contact_id = c.id ORDER BY c.id",
+    ];
+    const ROLES: &[&str] = &["app_user", "Admin", "readers"];
+    // Statements the grammar doesn't know, none of which contain a keyword that starts a known statement
+    const UNSUPPORTED: &[&str] = &[
+        "SET search_path TO my, public",
+        "VACUUM ANALYZE my.contacts",
+        "NOTIFY changes, 'contacts'",
+    ];
+    const BODIES: &[&str] = &[
+        "SELECT 1",
+        "SELECT $1 || 'x'",
+        "BEGIN\n    RETURN NEW;\nEND;",
+        "SELECT '$$'",
+    ];
+
+    fn pick<'a, T, G: Gen>(g: &mut G, items: &'a [T]) -> &'a T {
+        &items[usize::arbitrary(g) % items.len()]
+    }
+
+    fn ident<G: Gen>(g: &mut G) -> String {
+        (*pick(g, IDENTS)).to_owned()
+    }
+
+    // Between one and three distinct identifiers
+    fn idents<G: Gen>(g: &mut G) -> Vec<String> {
+        let count = 1 + usize::arbitrary(g) % 3;
+        let mut idents = IDENTS.iter().map(|i| (*i).to_owned()).collect::<Vec<_>>();
+        let offset = usize::arbitrary(g) % idents.len();
+        idents.rotate_left(offset);
+        idents.truncate(count);
+        idents
+    }
+
+    fn role<G: Gen>(g: &mut G) -> String {
+        (*pick(g, ROLES)).to_owned()
+    }
+
+    // Anyone can be granted a privilege, but PUBLIC can't be granted as a role
+    fn grantee<G: Gen>(g: &mut G) -> String {
+        if bool::arbitrary(g) {
+            "PUBLIC".to_owned()
+        } else {
+            role(g)
+        }
+    }
+
+    fn string<G: Gen>(g: &mut G) -> String {
+        (*pick(g, STRINGS)).to_owned()
+    }
+
+    fn condition<G: Gen>(g: &mut G) -> String {
+        (*pick(g, CONDITIONS)).to_owned()
+    }
+
+    fn object_name<G: Gen>(g: &mut G) -> ObjectName {
+        ObjectName {
+            schema: if bool::arbitrary(g) { Some(ident(g)) } else { None },
+            name: ident(g),
+        }
+    }
+
+    fn sql_type<G: Gen>(g: &mut G) -> SqlType {
+        let simple = pick(
+            g,
+            &[
+                SimpleSqlType::Text,
+                SimpleSqlType::Integer,
+                SimpleSqlType::BigInteger,
+                SimpleSqlType::Boolean,
+                SimpleSqlType::VariableLengthString(50),
+                SimpleSqlType::Numeric(Some((10, 2))),
+            ],
+        )
+        .clone();
+        match u8::arbitrary(g) % 4 {
+            0 => SqlType::Custom(object_name(g), Vec::new(), None),
+            1 => SqlType::Simple(simple, Some(1)),
+            _ => SqlType::Simple(simple, None),
+        }
+    }
+
+    fn column<G: Gen>(g: &mut G) -> ColumnDefinition {
+        let mut constraints = Vec::new();
+        for _ in 0..usize::arbitrary(g) % 3 {
+            constraints.push(match u8::arbitrary(g) % 6 {
+                0 => ColumnConstraint::NotNull,
+                1 => ColumnConstraint::Null,
+                2 => ColumnConstraint::PrimaryKey,
+                3 => ColumnConstraint::Unique,
+                4 => ColumnConstraint::Default(AnyValue::Integer(i32::from(u16::arbitrary(g)), None)),
+                _ => ColumnConstraint::Check {
+                    expression: condition(g),
+                    no_inherit: bool::arbitrary(g),
+                },
+            });
+        }
+        ColumnDefinition {
+            name: ident(g),
+            sql_type: sql_type(g),
+            constraints,
+        }
+    }
+
+    fn table_constraint<G: Gen>(g: &mut G) -> TableConstraint {
+        match u8::arbitrary(g) % 4 {
+            0 => TableConstraint::Primary {
+                name: ident(g),
+                columns: idents(g),
+                parameters: None,
+            },
+            1 => TableConstraint::Foreign {
+                name: ident(g),
+                columns: idents(g),
+                ref_table: object_name(g),
+                ref_columns: idents(g),
+                match_type: None,
+                events: None,
+            },
+            2 => TableConstraint::Check {
+                name: ident(g),
+                expression: condition(g),
+                no_inherit: bool::arbitrary(g),
+            },
+            _ => TableConstraint::Unique {
+                name: ident(g),
+                columns: idents(g),
+                include: None,
+                parameters: None,
+            },
+        }
+    }
+
+    fn table<G: Gen>(g: &mut G) -> TableDefinition {
+        TableDefinition {
+            name: object_name(g),
+            columns: (0..1 + usize::arbitrary(g) % 6).map(|_| column(g)).collect(),
+            constraints: (0..usize::arbitrary(g) % 3).map(|_| table_constraint(g)).collect(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            span: Span::default(),
+        }
+    }
+
+    // Range options other than the subtype are names, as written by the parser
+    fn option_name<G: Gen>(g: &mut G) -> Option<String> {
+        if bool::arbitrary(g) {
+            Some(SqlType::Custom(object_name(g), Vec::new(), None).to_string())
+        } else {
+            None
+        }
+    }
+
+    fn type_definition<G: Gen>(g: &mut G) -> TypeDefinition {
+        let kind = match u8::arbitrary(g) % 4 {
+            0 => TypeDefinitionKind::Enum(idents(g)),
+            1 => TypeDefinitionKind::Composite(
+                idents(g)
+                    .into_iter()
+                    .map(|name| CompositeTypeAttribute {
+                        name,
+                        sql_type: sql_type(g),
+                    })
+                    .collect(),
+            ),
+            2 => TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: sql_type(g),
+                default: if bool::arbitrary(g) {
+                    Some(AnyValue::String(string(g), None))
+                } else {
+                    None
+                },
+                not_null: bool::arbitrary(g),
+                constraints: (0..usize::arbitrary(g) % 3)
+                    .map(|_| DomainConstraint {
+                        name: if bool::arbitrary(g) { Some(ident(g)) } else { None },
+                        expression: condition(g),
+                    })
+                    .collect(),
+            }),
+            _ => TypeDefinitionKind::Range(RangeDefinition {
+                subtype: sql_type(g),
+                subtype_opclass: option_name(g),
+                collation: option_name(g),
+                canonical: option_name(g),
+                subtype_diff: option_name(g),
+            }),
+        };
+        TypeDefinition {
+            name: object_name(g),
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    fn sequence<G: Gen>(g: &mut G) -> SequenceDefinition {
+        let option = |g: &mut G| {
+            if bool::arbitrary(g) {
+                Some(1 + i64::from(u16::arbitrary(g)))
+            } else {
+                None
+            }
+        };
+        SequenceDefinition {
+            name: object_name(g),
+            increment: option(g),
+            min_value: option(g),
+            max_value: option(g),
+            start: option(g),
+            cache: option(g),
+            cycle: bool::arbitrary(g),
+            owned_by: None,
+            span: Span::default(),
+        }
+    }
+
+    fn index<G: Gen>(g: &mut G) -> IndexDefinition {
+        let columns = idents(g)
+            .into_iter()
+            .map(|name| IndexColumn {
+                key: IndexKey::Column(name),
+                collation: None,
+                operator_class: None,
+                order: if bool::arbitrary(g) {
+                    Some(IndexOrder::Descending)
+                } else {
+                    None
+                },
+                null_position: if bool::arbitrary(g) {
+                    Some(IndexPosition::Last)
+                } else {
+                    None
+                },
+            })
+            .collect();
+        IndexDefinition {
+            name: ident(g),
+            table: object_name(g),
+            columns,
+            unique: bool::arbitrary(g),
+            index_type: None,
+            include: None,
+            storage_parameters: None,
+            predicate: if bool::arbitrary(g) { Some(condition(g)) } else { None },
+            span: Span::default(),
+        }
+    }
+
+    fn function_arguments<G: Gen>(g: &mut G) -> Vec<FunctionArgument> {
+        (0..usize::arbitrary(g) % 3)
+            .map(|_| FunctionArgument {
+                mode: pick(
+                    g,
+                    &[
+                        None,
+                        Some(FunctionArgumentMode::In),
+                        Some(FunctionArgumentMode::InOut),
+                        Some(FunctionArgumentMode::Out),
+                        Some(FunctionArgumentMode::Variadic),
+                    ],
+                )
+                .clone(),
+                name: if bool::arbitrary(g) { Some(ident(g)) } else { None },
+                sql_type: sql_type(g),
+                default: if bool::arbitrary(g) {
+                    Some(AnyValue::Integer(i32::from(u16::arbitrary(g)), None))
+                } else {
+                    None
+                },
+            })
+            .collect()
+    }
+
+    fn function<G: Gen>(g: &mut G) -> FunctionDefinition {
+        let arguments = function_arguments(g);
+        let attributes = if bool::arbitrary(g) {
+            vec![FunctionAttribute::Volatility(FunctionVolatility::Stable)]
+        } else {
+            Vec::new()
+        };
+        FunctionDefinition {
+            name: object_name(g),
+            arguments,
+            return_type: FunctionReturnType::SqlType(sql_type(g)),
+            body: (*pick(g, BODIES)).to_owned(),
+            language: pick(g, &[FunctionLanguage::SQL, FunctionLanguage::PostgreSQL]).clone(),
+            attributes,
+            span: Span::default(),
+        }
+    }
+
+    fn procedure<G: Gen>(g: &mut G) -> ProcedureDefinition {
+        ProcedureDefinition {
+            name: object_name(g),
+            arguments: function_arguments(g),
+            body: (*pick(g, BODIES)).to_owned(),
+            language: pick(g, &[FunctionLanguage::SQL, FunctionLanguage::PostgreSQL]).clone(),
+            attributes: Vec::new(),
+            span: Span::default(),
+        }
+    }
+
+    fn aggregate<G: Gen>(g: &mut G) -> AggregateDefinition {
+        let option = |g: &mut G| if bool::arbitrary(g) { Some(object_name(g)) } else { None };
+        AggregateDefinition {
+            name: object_name(g),
+            arguments: (0..usize::arbitrary(g) % 3)
+                .map(|_| FunctionArgument {
+                    mode: None,
+                    name: if bool::arbitrary(g) { Some(ident(g)) } else { None },
+                    sql_type: sql_type(g),
+                    default: None,
+                })
+                .collect(),
+            state_function: object_name(g),
+            state_type: sql_type(g),
+            final_function: option(g),
+            combine_function: option(g),
+            initial_condition: if bool::arbitrary(g) { Some(string(g)) } else { None },
+            parallel: if bool::arbitrary(g) {
+                Some(*pick(
+                    g,
+                    &[ParallelSafety::Unsafe, ParallelSafety::Restricted, ParallelSafety::Safe],
+                ))
+            } else {
+                None
+            },
+            span: Span::default(),
+        }
+    }
+
+    fn trigger<G: Gen>(g: &mut G) -> TriggerDefinition {
+        let mut events = vec![TriggerEvent::Insert];
+        if bool::arbitrary(g) {
+            events.push(TriggerEvent::Update(None));
+        }
+        if bool::arbitrary(g) {
+            events.push(TriggerEvent::Delete);
+        }
+        TriggerDefinition {
+            name: ident(g),
+            table: object_name(g),
+            timing: *pick(g, &[TriggerTiming::Before, TriggerTiming::After]),
+            events,
+            level: *pick(g, &[TriggerLevel::Row, TriggerLevel::Statement]),
+            condition: if bool::arbitrary(g) { Some(condition(g)) } else { None },
+            function: object_name(g),
+            arguments: (0..usize::arbitrary(g) % 3).map(|_| string(g)).collect(),
+            span: Span::default(),
+        }
+    }
+
+    fn policy<G: Gen>(g: &mut G) -> PolicyDefinition {
+        PolicyDefinition {
+            name: ident(g),
+            table: object_name(g),
+            permissive: bool::arbitrary(g),
+            command: *pick(
+                g,
+                &[
+                    PolicyCommand::All,
+                    PolicyCommand::Select,
+                    PolicyCommand::Insert,
+                    PolicyCommand::Update,
+                    PolicyCommand::Delete,
+                ],
+            ),
+            roles: vec![grantee(g)],
+            using: if bool::arbitrary(g) { Some(condition(g)) } else { None },
+            with_check: if bool::arbitrary(g) { Some(condition(g)) } else { None },
+            span: Span::default(),
+        }
+    }
+
+    fn privileges<G: Gen>(g: &mut G) -> Vec<PrivilegeDefinition> {
+        let action = pick(g, &[PrivilegeAction::Grant, PrivilegeAction::Revoke]).clone();
+        let object = match u8::arbitrary(g) % 6 {
+            0 => PrivilegeObject::Role(role(g)),
+            1 => PrivilegeObject::Schema(ident(g)),
+            2 => PrivilegeObject::Table(object_name(g)),
+            3 => PrivilegeObject::Sequence(object_name(g)),
+            4 => PrivilegeObject::function(
+                object_name(g),
+                if bool::arbitrary(g) {
+                    Some(function_arguments(g))
+                } else {
+                    None
+                },
+            ),
+            _ => PrivilegeObject::Default(DefaultPrivilegeScope {
+                role: if bool::arbitrary(g) { Some(role(g)) } else { None },
+                schema: if bool::arbitrary(g) { Some(ident(g)) } else { None },
+                kind: pick(
+                    g,
+                    &[
+                        DefaultPrivilegeKind::Tables,
+                        DefaultPrivilegeKind::Sequences,
+                        DefaultPrivilegeKind::Functions,
+                    ],
+                )
+                .clone(),
+            }),
+        };
+        let grantee = grantee(g);
+        let grant_option = bool::arbitrary(g);
+        let mut privileges = object.privileges().to_vec();
+        privileges.truncate(1 + usize::arbitrary(g) % privileges.len());
+        privileges
+            .into_iter()
+            .map(|privilege| PrivilegeDefinition {
+                action: action.clone(),
+                object: object.clone(),
+                privilege,
+                grantee: grantee.clone(),
+                grant_option,
+                span: Span::default(),
+            })
+            .collect()
+    }
+
+    // Covers every kind of statement. User defined base types are left out since they can't be written back.
+    #[derive(Clone, Debug)]
+    struct ArbitraryStatement(Statement);
+
+    impl Arbitrary for ArbitraryStatement {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let statement = match u8::arbitrary(g) % 17 {
+                0 => Statement::Schema(SchemaDefinition {
+                    name: ident(g),
+                    span: Span::default(),
+                }),
+                1 => Statement::Table(table(g)),
+                2 => Statement::Type(type_definition(g)),
+                3 => Statement::Sequence(sequence(g)),
+                4 => Statement::Index(index(g)),
+                5 => Statement::Function(function(g)),
+                6 => Statement::Trigger(trigger(g)),
+                7 => Statement::Policy(policy(g)),
+                8 => Statement::Privileges(privileges(g)),
+                9 => Statement::Comment(CommentDefinition {
+                    object: match u8::arbitrary(g) % 6 {
+                        0 => CommentObject::Table(object_name(g)),
+                        1 => CommentObject::Column(object_name(g), ident(g)),
+                        2 => CommentObject::function(object_name(g), function_arguments(g)),
+                        3 => CommentObject::Type(object_name(g)),
+                        4 => CommentObject::Index(object_name(g)),
+                        _ => CommentObject::Constraint(object_name(g), ident(g)),
+                    },
+                    comment: if bool::arbitrary(g) { Some(string(g)) } else { None },
+                    span: Span::default(),
+                }),
+                10 => Statement::View(ViewDefinition {
+                    name: object_name(g),
+                    columns: if bool::arbitrary(g) { Some(idents(g)) } else { None },
+                    query: (*pick(g, QUERIES)).to_owned(),
+                    resolved: None,
+                    span: Span::default(),
+                }),
+                11 => Statement::Procedure(procedure(g)),
+                12 => Statement::Aggregate(aggregate(g)),
+                13 => Statement::AlterTable(
+                    object_name(g),
+                    *pick(
+                        g,
+                        &[
+                            AlterTableAction::EnableRowSecurity,
+                            AlterTableAction::DisableRowSecurity,
+                            AlterTableAction::ForceRowSecurity,
+                            AlterTableAction::NoForceRowSecurity,
+                        ],
+                    ),
+                ),
+                14 => Statement::Error(ErrorKind::ExtensionNotSupported(ident(g))),
+                15 => Statement::Unsupported {
+                    text: (*pick(g, UNSUPPORTED)).to_owned(),
+                    span: Span::default(),
+                    error: unsupported(),
+                },
+                _ => Statement::MaterializedView(MaterializedViewDefinition {
+                    name: object_name(g),
+                    columns: if bool::arbitrary(g) { Some(idents(g)) } else { None },
+                    query: (*pick(g, QUERIES)).to_owned(),
+                    with_data: bool::arbitrary(g),
+                    resolved: None,
+                    span: Span::default(),
+                }),
+            };
+            ArbitraryStatement(statement)
+        }
+    }
+
+    // Why an unsupported statement couldn't be parsed depends on its position, so isn't compared
+    fn unsupported() -> ParseError<Location, Token, &'static str> {
+        ParseError::User { error: "unsupported" }
+    }
+
+    // Unsupported statements are compared by their text, which only the source can provide
+    fn comparable(mut statement: Statement, sql: &str) -> Statement {
+        statement.set_source("formatted.sql", sql);
+        match statement {
+            Statement::Unsupported { text, span, .. } => Statement::Unsupported {
+                text: text.trim_end_matches(';').to_owned(),
+                span,
+                error: unsupported(),
+            },
+            statement => statement,
+        }
+    }
+
+    #[test]
+    fn it_parses_formatted_statements_back_to_the_same_definitions() {
+        fn round_trips(statements: Vec<ArbitraryStatement>, indent: u8, lower: bool, line_width: u8) -> bool {
+            let statements = flatten(statements.into_iter().map(|s| s.0).collect());
+            if statements.is_empty() {
+                return true;
+            }
+            let formatter = Formatter::new(FormatOptions {
+                indent: usize::from(indent % 8),
+                keyword_case: if lower { KeywordCase::Lower } else { KeywordCase::Upper },
+                line_width: usize::from(line_width),
+            });
+            let sql = formatter.statements(&statements);
+            let parsed = flatten(parse(&sql))
+                .into_iter()
+                .map(|statement| comparable(statement, &sql))
+                .collect::<Vec<_>>();
+            if parsed != statements {
+                panic!("{}\nparsed as\n{:#?}", sql, parsed);
+            }
+            true
+        }
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(round_trips as fn(Vec<ArbitraryStatement>, u8, bool, u8) -> bool);
+    }
+}
@@ -9,6 +9,7 @@ use std::fmt;
 use std::iter::FromIterator;
 
 use self::context::*;
+use crate::sql::ast::Location;
 
 // TODO: Add in some sort of message or reason.
 #[derive(Debug)]
@@ -23,6 +24,7 @@ pub struct LexicalError<'input> {
 
 mod context {
     use super::LexicalError;
+    use crate::sql::ast::Location;
 
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum LexerState {
@@ -34,21 +36,52 @@ mod context {
         QuotedIdentifier,
 
         LiteralStart,
-        LiteralEnd,
         LiteralBody,
+
+        Query(QueryPart),
+        Condition,
+    }
+
+    // Where a verbatim query is up to, so that a semicolon within a string or comment doesn't end it
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum QueryPart {
+        Text,
+        Quoted(char),
+        DollarQuoted(usize, usize), // Where the opening $tag$ starts within the query, and the length of the tag
+        LineComment,
+        BlockComment,
     }
 
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum NormalVariant {
+        Alter,
+        AlterTable,
         Any,
         Body,
+        Comment,
+        Function,
+        Generated,
+        Identity,
+        Index,
+        Interval,
+        Partition,
+        Policy,
+        Privilege,
+        ReturnTable,
+        Sequence,
+        Trigger,
+        View,
     }
 
     pub struct Context {
         current_line: usize,
         current_position: usize,
+        current_offset: usize,
         pub last_char: char,
 
+        // Where the token currently being read started
+        pub start: Location,
+
         pub buffer: Vec<char>,
         pub literal: Vec<char>,
 
@@ -60,8 +93,11 @@ mod context {
             Context {
                 current_line: 0,
                 current_position: 0,
+                current_offset: 0,
                 last_char: '\0',
 
+                start: Location::default(),
+
                 buffer: Vec::new(),
                 literal: Vec::new(),
 
@@ -69,17 +105,43 @@ mod context {
             }
         }
 
-        pub fn new_line(&mut self) {
+        pub fn new_line(&mut self, offset: usize) {
             self.current_line += 1;
             self.current_position = 0;
+            self.current_offset = offset;
             self.last_char = '\0'; // Start fresh
         }
 
         pub fn next_char(&mut self, c: char) {
             self.current_position += 1;
+            self.current_offset += c.len_utf8();
             self.last_char = c;
         }
 
+        // The location of the current character
+        pub fn location(&self) -> Location {
+            Location {
+                offset: self.current_offset,
+                line: self.current_line,
+                column: self.current_position + 1,
+            }
+        }
+
+        // The span of a token ending just before the current character (e.g. a buffer ended by whitespace)
+        pub fn buffer_span(&self) -> (Location, Location) {
+            (self.start, self.location())
+        }
+
+        // The span of a token ending with the current character (e.g. a closing quote)
+        pub fn closing_span(&self, c: char) -> (Location, Location) {
+            (self.start, self.location().after(c))
+        }
+
+        // The span of a token made up of only the current character
+        pub fn char_span(&self, c: char) -> (Location, Location) {
+            (self.location(), self.location().after(c))
+        }
+
         pub fn create_error<'input, T: Into<String>>(&self, line: &'input str, reason: T) -> LexicalError<'input> {
             LexicalError {
                 line,
@@ -91,8 +153,23 @@ mod context {
                     .iter()
                     .map(|s| match s {
                         LexerState::Normal(variant) => match variant {
+                            NormalVariant::Alter => "Normal(Alter)",
+                            NormalVariant::AlterTable => "Normal(AlterTable)",
                             NormalVariant::Any => "Normal(Any)",
                             NormalVariant::Body => "Normal(Body)",
+                            NormalVariant::Comment => "Normal(Comment)",
+                            NormalVariant::Function => "Normal(Function)",
+                            NormalVariant::Generated => "Normal(Generated)",
+                            NormalVariant::Identity => "Normal(Identity)",
+                            NormalVariant::Index => "Normal(Index)",
+                            NormalVariant::Interval => "Normal(Interval)",
+                            NormalVariant::Partition => "Normal(Partition)",
+                            NormalVariant::Policy => "Normal(Policy)",
+                            NormalVariant::Privilege => "Normal(Privilege)",
+                            NormalVariant::ReturnTable => "Normal(ReturnTable)",
+                            NormalVariant::Sequence => "Normal(Sequence)",
+                            NormalVariant::Trigger => "Normal(Trigger)",
+                            NormalVariant::View => "Normal(View)",
                         },
                         LexerState::Comment1 => "CommentLine",
                         LexerState::Comment2 => "CommentBlock",
@@ -100,7 +177,8 @@ mod context {
                         LexerState::QuotedIdentifier => "QuotedIdentifier",
                         LexerState::LiteralStart => "LiteralBegin",
                         LexerState::LiteralBody => "Literal",
-                        LexerState::LiteralEnd => "LiteralEnd",
+                        LexerState::Query(_) => "Query",
+                        LexerState::Condition => "Condition",
                     })
                     .collect::<Vec<_>>()
                     .join(" -> "),
@@ -108,6 +186,12 @@ mod context {
             }
         }
 
+        pub fn reset(&mut self, start: NormalVariant) {
+            self.buffer.clear();
+            self.literal.clear();
+            self.state = vec![LexerState::Normal(start)];
+        }
+
         pub fn push_state(&mut self, state: LexerState) {
             self.state.push(state);
         }
@@ -134,65 +218,150 @@ mod context {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Token {
     ACTION,
+    ADMIN,
+    AFTER,
+    AGGREGATE,
+    ALL,
+    ALTER,
+    ALWAYS,
     ARRAY,
     AS,
     ASC,
+    BEFORE,
     BIGINT,
     BIGSERIAL,
     BIT,
     BOOL,
     BOOLEAN,
     BTREE,
+    BY,
+    BYTEA,
+    CACHE,
+    CALLED,
     CASCADE,
+    CIDR,
+    COLUMN,
+    COMMENT,
     CONSTRAINT,
     CHAR,
     CHARACTER,
+    CHECK,
+    COLLATE,
+    COST,
     CREATE,
+    CYCLE,
+    DATA,
     DATE,
+    DAY,
+    DECIMAL,
     DEFAULT,
+    DEFINER,
     DELETE,
     DESC,
+    DISABLE,
+    DOMAIN,
     DOUBLE,
+    EACH,
+    ENABLE,
     ENUM,
+    EXCLUDE,
+    EXECUTE,
     EXTENSION,
+    EXTERNAL,
     FILLFACTOR,
     FIRST,
+    FLOAT,
+    FOR,
+    FORCE,
     FOREIGN,
+    FROM,
     FULL,
     FUNCTION,
+    FUNCTIONS,
+    GENERATED,
     GIN,
     GIST,
+    GRANT,
     HASH,
+    HOUR,
+    IDENTITY,
+    IMMUTABLE,
     IN,
+    INCLUDE,
+    INCREMENT,
     INDEX,
+    INET,
+    INHERIT,
     INOUT,
+    INPUT,
+    INSERT,
+    INSTEAD,
     INT,
     INT2,
     INT4,
     INT8,
     INTEGER,
+    INTERVAL,
+    INVOKER,
+    IS,
+    JSON,
+    JSONB,
     KEY,
     LANGUAGE,
     LAST,
+    LEAKPROOF,
+    LEVEL,
+    LIST,
+    MACADDR,
+    MACADDR8,
     MATCH,
+    MATERIALIZED,
+    MAXVALUE,
+    MINUTE,
+    MINVALUE,
     MONEY,
+    MONTH,
     NO,
+    NONE,
     NOT,
     NULL,
     NULLS,
     NUMERIC,
+    OF,
     ON,
+    ONLY,
+    OPTION,
     OR,
     OUT,
+    OWNED,
+    PARALLEL,
     PARTIAL,
+    PARTITION,
+    PERMISSIVE,
+    POLICY,
     PRECISION,
     PRIMARY,
+    PRIVILEGES,
+    PROCEDURE,
+    RANGE,
     REAL,
     REFERENCES,
     REPLACE,
     RESTRICT,
+    RESTRICTED,
+    RESTRICTIVE,
     RETURNS,
+    REVOKE,
+    ROLE,
+    ROW,
+    ROWS,
+    SAFE,
     SCHEMA,
+    SECOND,
+    SECURITY,
+    SELECT,
+    SEQUENCE,
+    SEQUENCES,
     SERIAL,
     SERIAL2,
     SERIAL4,
@@ -202,23 +371,43 @@ pub enum Token {
     SIMPLE,
     SMALLINT,
     SMALLSERIAL,
+    STABLE,
+    START,
+    STATEMENT,
+    STORED,
+    STRICT,
     TABLE,
+    TABLES,
     TEXT,
     TIME,
     TIMESTAMP,
     TIMESTAMPTZ,
     TIMETZ,
+    TO,
+    TRIGGER,
+    TRUNCATE,
+    TSQUERY,
+    TSVECTOR,
     TYPE,
     UNIQUE,
+    UNSAFE,
     UPDATE,
+    USAGE,
     USING,
     UUID,
+    VALUES,
     VARBIT,
     VARCHAR,
     VARIADIC,
     VARYING,
+    VIEW,
+    VOLATILE,
+    WHEN,
+    WHERE,
     WITH,
     WITHOUT,
+    XML,
+    YEAR,
     ZONE,
 
     Identifier(String),
@@ -227,6 +416,11 @@ pub enum Token {
     Boolean(bool),
     StringValue(String),
     Literal(String),
+    Query(String),
+    Condition(String),
+    Operator(String),
+    // A statement that couldn't be tokenized, kept verbatim
+    Unsupported(String),
 
     LeftBracket,
     RightBracket,
@@ -240,69 +434,182 @@ pub enum Token {
     Equals,
 }
 
+impl Token {
+    /// Whether the token is a keyword, as opposed to an identifier, value or punctuation.
+    pub fn is_keyword(&self) -> bool {
+        match *self {
+            Token::Identifier(_)
+            | Token::Digit(_)
+            | Token::Decimal(_)
+            | Token::Boolean(_)
+            | Token::StringValue(_)
+            | Token::Literal(_)
+            | Token::Query(_)
+            | Token::Condition(_)
+            | Token::Operator(_)
+            | Token::Unsupported(_)
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::LeftSquare
+            | Token::RightSquare
+            | Token::Colon
+            | Token::Comma
+            | Token::Period
+            | Token::Semicolon
+            | Token::Equals => false,
+            _ => true,
+        }
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Token::ACTION => write!(f, "ACTION"),
+            Token::ADMIN => write!(f, "ADMIN"),
+            Token::AFTER => write!(f, "AFTER"),
+            Token::AGGREGATE => write!(f, "AGGREGATE"),
+            Token::ALL => write!(f, "ALL"),
+            Token::ALTER => write!(f, "ALTER"),
+            Token::ALWAYS => write!(f, "ALWAYS"),
             Token::ARRAY => write!(f, "ARRAY"),
             Token::AS => write!(f, "AS"),
             Token::ASC => write!(f, "ASC"),
+            Token::BEFORE => write!(f, "BEFORE"),
             Token::BIGINT => write!(f, "BIGINT"),
             Token::BIGSERIAL => write!(f, "BIGSERIAL"),
             Token::BIT => write!(f, "BIT"),
             Token::BOOL => write!(f, "BOOL"),
             Token::BOOLEAN => write!(f, "BOOLEAN"),
             Token::BTREE => write!(f, "BTREE"),
+            Token::BY => write!(f, "BY"),
+            Token::BYTEA => write!(f, "BYTEA"),
+            Token::CACHE => write!(f, "CACHE"),
+            Token::CALLED => write!(f, "CALLED"),
             Token::CASCADE => write!(f, "CASCADE"),
+            Token::CIDR => write!(f, "CIDR"),
+            Token::COLUMN => write!(f, "COLUMN"),
+            Token::COMMENT => write!(f, "COMMENT"),
             Token::CONSTRAINT => write!(f, "CONSTRAINT"),
             Token::CHAR => write!(f, "CHAR"),
             Token::CHARACTER => write!(f, "CHARACTER"),
+            Token::CHECK => write!(f, "CHECK"),
+            Token::COLLATE => write!(f, "COLLATE"),
+            Token::COST => write!(f, "COST"),
             Token::CREATE => write!(f, "CREATE"),
+            Token::CYCLE => write!(f, "CYCLE"),
+            Token::DATA => write!(f, "DATA"),
             Token::DATE => write!(f, "DATE"),
+            Token::DAY => write!(f, "DAY"),
+            Token::DECIMAL => write!(f, "DECIMAL"),
             Token::DEFAULT => write!(f, "DEFAULT"),
+            Token::DEFINER => write!(f, "DEFINER"),
             Token::DELETE => write!(f, "DELETE"),
             Token::DESC => write!(f, "DESC"),
+            Token::DISABLE => write!(f, "DISABLE"),
+            Token::DOMAIN => write!(f, "DOMAIN"),
             Token::DOUBLE => write!(f, "DOUBLE"),
+            Token::EACH => write!(f, "EACH"),
+            Token::ENABLE => write!(f, "ENABLE"),
             Token::ENUM => write!(f, "ENUM"),
+            Token::EXCLUDE => write!(f, "EXCLUDE"),
+            Token::EXECUTE => write!(f, "EXECUTE"),
             Token::EXTENSION => write!(f, "EXTENSION"),
+            Token::EXTERNAL => write!(f, "EXTERNAL"),
             Token::FILLFACTOR => write!(f, "FILLFACTOR"),
             Token::FIRST => write!(f, "FIRST"),
+            Token::FLOAT => write!(f, "FLOAT"),
+            Token::FOR => write!(f, "FOR"),
+            Token::FORCE => write!(f, "FORCE"),
             Token::FOREIGN => write!(f, "FOREIGN"),
+            Token::FROM => write!(f, "FROM"),
             Token::FULL => write!(f, "FULL"),
             Token::FUNCTION => write!(f, "FUNCTION"),
+            Token::FUNCTIONS => write!(f, "FUNCTIONS"),
+            Token::GENERATED => write!(f, "GENERATED"),
             Token::GIN => write!(f, "GIN"),
             Token::GIST => write!(f, "GIST"),
+            Token::GRANT => write!(f, "GRANT"),
             Token::HASH => write!(f, "HASH"),
+            Token::HOUR => write!(f, "HOUR"),
+            Token::IDENTITY => write!(f, "IDENTITY"),
+            Token::IMMUTABLE => write!(f, "IMMUTABLE"),
             Token::IN => write!(f, "IN"),
+            Token::INCLUDE => write!(f, "INCLUDE"),
+            Token::INCREMENT => write!(f, "INCREMENT"),
             Token::INDEX => write!(f, "INDEX"),
+            Token::INET => write!(f, "INET"),
+            Token::INHERIT => write!(f, "INHERIT"),
             Token::INOUT => write!(f, "INOUT"),
+            Token::INPUT => write!(f, "INPUT"),
+            Token::INSERT => write!(f, "INSERT"),
+            Token::INSTEAD => write!(f, "INSTEAD"),
             Token::INT => write!(f, "INT"),
             Token::INT2 => write!(f, "INT2"),
             Token::INT4 => write!(f, "INT4"),
             Token::INT8 => write!(f, "INT8"),
             Token::INTEGER => write!(f, "INTEGER"),
+            Token::INTERVAL => write!(f, "INTERVAL"),
+            Token::INVOKER => write!(f, "INVOKER"),
+            Token::IS => write!(f, "IS"),
+            Token::JSON => write!(f, "JSON"),
+            Token::JSONB => write!(f, "JSONB"),
             Token::KEY => write!(f, "KEY"),
             Token::LANGUAGE => write!(f, "LANGUAGE"),
             Token::LAST => write!(f, "LAST"),
+            Token::LEAKPROOF => write!(f, "LEAKPROOF"),
+            Token::LEVEL => write!(f, "LEVEL"),
+            Token::LIST => write!(f, "LIST"),
+            Token::MACADDR => write!(f, "MACADDR"),
+            Token::MACADDR8 => write!(f, "MACADDR8"),
             Token::MATCH => write!(f, "MATCH"),
+            Token::MATERIALIZED => write!(f, "MATERIALIZED"),
+            Token::MAXVALUE => write!(f, "MAXVALUE"),
+            Token::MINUTE => write!(f, "MINUTE"),
+            Token::MINVALUE => write!(f, "MINVALUE"),
             Token::MONEY => write!(f, "MONEY"),
+            Token::MONTH => write!(f, "MONTH"),
             Token::NO => write!(f, "NO"),
+            Token::NONE => write!(f, "NONE"),
             Token::NOT => write!(f, "NOT"),
             Token::NULL => write!(f, "NULL"),
             Token::NULLS => write!(f, "NULLS"),
             Token::NUMERIC => write!(f, "NUMERIC"),
+            Token::OF => write!(f, "OF"),
             Token::ON => write!(f, "ON"),
+            Token::ONLY => write!(f, "ONLY"),
+            Token::OPTION => write!(f, "OPTION"),
             Token::OR => write!(f, "OR"),
             Token::OUT => write!(f, "OUT"),
+            Token::OWNED => write!(f, "OWNED"),
+            Token::PARALLEL => write!(f, "PARALLEL"),
             Token::PARTIAL => write!(f, "PARTIAL"),
+            Token::PARTITION => write!(f, "PARTITION"),
+            Token::PERMISSIVE => write!(f, "PERMISSIVE"),
+            Token::POLICY => write!(f, "POLICY"),
             Token::PRECISION => write!(f, "PRECISION"),
             Token::PRIMARY => write!(f, "PRIMARY"),
+            Token::PRIVILEGES => write!(f, "PRIVILEGES"),
+            Token::PROCEDURE => write!(f, "PROCEDURE"),
+            Token::RANGE => write!(f, "RANGE"),
             Token::REAL => write!(f, "REAL"),
             Token::REFERENCES => write!(f, "REFERENCES"),
             Token::REPLACE => write!(f, "REPLACE"),
             Token::RESTRICT => write!(f, "RESTRICT"),
+            Token::RESTRICTED => write!(f, "RESTRICTED"),
+            Token::RESTRICTIVE => write!(f, "RESTRICTIVE"),
             Token::RETURNS => write!(f, "RETURNS"),
+            Token::REVOKE => write!(f, "REVOKE"),
+            Token::ROLE => write!(f, "ROLE"),
+            Token::ROW => write!(f, "ROW"),
+            Token::ROWS => write!(f, "ROWS"),
+            Token::SAFE => write!(f, "SAFE"),
             Token::SCHEMA => write!(f, "SCHEMA"),
+            Token::SECOND => write!(f, "SECOND"),
+            Token::SECURITY => write!(f, "SECURITY"),
+            Token::SELECT => write!(f, "SELECT"),
+            Token::SEQUENCE => write!(f, "SEQUENCE"),
+            Token::SEQUENCES => write!(f, "SEQUENCES"),
             Token::SERIAL => write!(f, "SERIAL"),
             Token::SERIAL2 => write!(f, "SERIAL2"),
             Token::SERIAL4 => write!(f, "SERIAL4"),
@@ -312,23 +619,43 @@ impl fmt::Display for Token {
             Token::SIMPLE => write!(f, "SIMPLE"),
             Token::SMALLINT => write!(f, "SMALLINT"),
             Token::SMALLSERIAL => write!(f, "SMALLSERIAL"),
+            Token::STABLE => write!(f, "STABLE"),
+            Token::START => write!(f, "START"),
+            Token::STATEMENT => write!(f, "STATEMENT"),
+            Token::STORED => write!(f, "STORED"),
+            Token::STRICT => write!(f, "STRICT"),
             Token::TABLE => write!(f, "TABLE"),
+            Token::TABLES => write!(f, "TABLES"),
             Token::TEXT => write!(f, "TEXT"),
             Token::TIME => write!(f, "TIME"),
             Token::TIMESTAMP => write!(f, "TIMESTAMP"),
             Token::TIMESTAMPTZ => write!(f, "TIMESTAMPTZ"),
             Token::TIMETZ => write!(f, "TIMETZ"),
+            Token::TO => write!(f, "TO"),
+            Token::TRIGGER => write!(f, "TRIGGER"),
+            Token::TRUNCATE => write!(f, "TRUNCATE"),
+            Token::TSQUERY => write!(f, "TSQUERY"),
+            Token::TSVECTOR => write!(f, "TSVECTOR"),
             Token::TYPE => write!(f, "TYPE"),
             Token::UNIQUE => write!(f, "UNIQUE"),
+            Token::UNSAFE => write!(f, "UNSAFE"),
             Token::UPDATE => write!(f, "UPDATE"),
+            Token::USAGE => write!(f, "USAGE"),
             Token::USING => write!(f, "USING"),
             Token::UUID => write!(f, "UUID"),
+            Token::VALUES => write!(f, "VALUES"),
             Token::VARBIT => write!(f, "VARBIT"),
             Token::VARCHAR => write!(f, "VARCHAR"),
             Token::VARIADIC => write!(f, "VARIADIC"),
             Token::VARYING => write!(f, "VARYING"),
+            Token::VIEW => write!(f, "VIEW"),
+            Token::VOLATILE => write!(f, "VOLATILE"),
+            Token::WHEN => write!(f, "WHEN"),
+            Token::WHERE => write!(f, "WHERE"),
             Token::WITH => write!(f, "WITH"),
             Token::WITHOUT => write!(f, "WITHOUT"),
+            Token::XML => write!(f, "XML"),
+            Token::YEAR => write!(f, "YEAR"),
             Token::ZONE => write!(f, "ZONE"),
 
             Token::Identifier(ref ident) => write!(f, "Ident({})", ident),
@@ -337,6 +664,10 @@ impl fmt::Display for Token {
             Token::Boolean(b) => write!(f, "{}", if b { "TRUE" } else { "FALSE" }),
             Token::StringValue(ref s) => write!(f, "'{}'", s),
             Token::Literal(ref s) => write!(f, "$$ {} $$", s),
+            Token::Query(ref s) => write!(f, "{}", s),
+            Token::Condition(ref s) => write!(f, "({})", s),
+            Token::Operator(ref s) => write!(f, "{}", s),
+            Token::Unsupported(ref s) => write!(f, "{}", s),
 
             Token::LeftBracket => write!(f, "("),
             Token::RightBracket => write!(f, ")"),
@@ -352,20 +683,25 @@ impl fmt::Display for Token {
     }
 }
 
+// A token along with where it starts and ends within the text, as consumed by the parser
+pub type SpannedToken = (Location, Token, Location);
+
 lazy_static! {
-    static ref IDENTIFIER: Regex = Regex::new("^[a-zA-Z][a-zA-Z0-9_]*$").unwrap();
-    static ref DECIMAL: Regex = Regex::new("^\\d+\\.\\d+$").unwrap();
-    static ref DIGIT: Regex = Regex::new("^\\d+$").unwrap();
+    static ref IDENTIFIER: Regex = Regex::new("^[a-zA-Z_][a-zA-Z0-9_$]*$").unwrap();
+    static ref DECIMAL: Regex = Regex::new("^-?\\d+\\.\\d+$").unwrap();
+    static ref DIGIT: Regex = Regex::new("^-?\\d+$").unwrap();
+    static ref OPERATOR: Regex = Regex::new("^[-+*/<>=~!@#%^&|`?]+$").unwrap();
+    static ref WITH_DATA: Regex = Regex::new("(?is)^(.*?)\\s+(WITH)\\s+(?:(NO)\\s+)?(DATA)$").unwrap();
 }
 
 macro_rules! tokenize_normal_buffer {
     ($context:ident, $line:ident, $tokens:ident) => {{
         if $context.buffer.len() > 0 {
-            let token = match self::create_normal_token(&mut $context) {
+            let token = match self::create_normal_token($context) {
                 Some(t) => t,
                 None => return Err($context.create_error($line, "unexpected token")),
             };
-            push_token!($tokens, token);
+            push_token!($tokens, token, $context.buffer_span());
             $context.buffer.clear();
         }
     }};
@@ -391,10 +727,11 @@ macro_rules! match_keyword_replace_state {
 }
 
 macro_rules! push_token {
-    ($tokens:ident, $symbol:expr) => {
+    ($tokens:ident, $symbol:expr, $span:expr) => {{
         //println!("{}", $symbol);
-        $tokens.push($symbol);
-    };
+        let (start, end) = $span;
+        $tokens.push((start, $symbol, end));
+    }};
 }
 
 fn create_normal_token(context: &mut Context) -> Option<Token> {
@@ -405,6 +742,20 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     };
 
     let value = String::from_iter(context.buffer.clone());
+
+    // Anything other than an interval field completes the interval type
+    if let NormalVariant::Interval = variant {
+        match_keyword!(value, DAY);
+        match_keyword!(value, HOUR);
+        match_keyword!(value, MINUTE);
+        match_keyword!(value, MONTH);
+        match_keyword!(value, SECOND);
+        match_keyword!(value, TO);
+        match_keyword!(value, YEAR);
+        context.pop_state();
+        return create_normal_token(context);
+    }
+
     if "true".eq_ignore_ascii_case(&value[..]) {
         return Some(Token::Boolean(true));
     }
@@ -417,13 +768,234 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
         match_keyword!(value, CREATE);
         match_keyword!(value, OR);
         match_keyword!(value, REPLACE);
+        match_keyword!(value, MATERIALIZED);
 
         // Any of the below will switch state. This only gets reset on statement end.
+        match_keyword_replace_state!(context, NormalVariant::Body, value, AGGREGATE);
+        match_keyword_replace_state!(context, NormalVariant::Alter, value, ALTER);
+        match_keyword_replace_state!(context, NormalVariant::Comment, value, COMMENT);
+        match_keyword_replace_state!(context, NormalVariant::Body, value, DOMAIN);
         match_keyword_replace_state!(context, NormalVariant::Body, value, EXTENSION);
         match_keyword_replace_state!(context, NormalVariant::Body, value, FUNCTION);
-        match_keyword_replace_state!(context, NormalVariant::Body, value, INDEX);
+        match_keyword_replace_state!(context, NormalVariant::Privilege, value, GRANT);
+        match_keyword_replace_state!(context, NormalVariant::Index, value, INDEX);
+        match_keyword_replace_state!(context, NormalVariant::Policy, value, POLICY);
+        match_keyword_replace_state!(context, NormalVariant::Function, value, PROCEDURE);
+        match_keyword_replace_state!(context, NormalVariant::Privilege, value, REVOKE);
         match_keyword_replace_state!(context, NormalVariant::Body, value, SCHEMA);
+        match_keyword_replace_state!(context, NormalVariant::Sequence, value, SEQUENCE);
         match_keyword_replace_state!(context, NormalVariant::Body, value, TABLE);
+        match_keyword_replace_state!(context, NormalVariant::Trigger, value, TRIGGER);
+        match_keyword_replace_state!(context, NormalVariant::Body, value, TYPE);
+        match_keyword_replace_state!(context, NormalVariant::View, value, VIEW);
+    }
+
+    // ALTER is only supported for default privileges and table options
+    if let NormalVariant::Alter = variant {
+        match_keyword_replace_state!(context, NormalVariant::Privilege, value, PRIVILEGES);
+        match_keyword_replace_state!(context, NormalVariant::AlterTable, value, TABLE);
+    }
+
+    // Object kinds are only keywords within COMMENT ON since COLUMN in particular is a common column name
+    if let NormalVariant::Comment = variant {
+        match_keyword!(value, COLUMN);
+        match_keyword!(value, DOMAIN);
+        match_keyword!(value, FUNCTION);
+        match_keyword!(value, INDEX);
+        match_keyword!(value, IS);
+    }
+
+    // Table options are only keywords within ALTER TABLE since LEVEL in particular is a common column name
+    if let NormalVariant::AlterTable = variant {
+        match_keyword!(value, DISABLE);
+        match_keyword!(value, ENABLE);
+        match_keyword!(value, FORCE);
+        match_keyword!(value, LEVEL);
+        match_keyword!(value, ONLY);
+        match_keyword!(value, ROW);
+        match_keyword!(value, SECURITY);
+    }
+
+    // Partitioning follows the column list of a table, or replaces it entirely for a partition
+    if let NormalVariant::Body = variant {
+        match_keyword_replace_state!(context, NormalVariant::Partition, value, PARTITION);
+
+        // Function attributes follow the return type. Since the arguments have been consumed by then
+        // their names can't be mistaken for attributes.
+        match_keyword_replace_state!(context, NormalVariant::Function, value, RETURNS);
+
+        // Generated columns return to the column list once the generation clause is complete
+        if "GENERATED".eq_ignore_ascii_case(&value[..]) {
+            context.push_state(LexerState::Normal(NormalVariant::Generated));
+            return Some(Token::GENERATED);
+        }
+    }
+
+    // A generation expression is captured as a condition and ends with STORED, while an identity ends
+    // immediately unless followed by sequence options
+    if let NormalVariant::Generated = variant {
+        match_keyword!(value, ALWAYS);
+        match_keyword!(value, BY);
+        if "IDENTITY".eq_ignore_ascii_case(&value[..]) {
+            context.pop_state();
+            return Some(Token::IDENTITY);
+        }
+        if "STORED".eq_ignore_ascii_case(&value[..]) {
+            context.pop_state();
+            return Some(Token::STORED);
+        }
+    }
+
+    // Function attributes are only keywords outside of a returned table since many are common column names
+    if let NormalVariant::Function = variant {
+        match_keyword!(value, CALLED);
+        match_keyword!(value, COST);
+        match_keyword!(value, DEFINER);
+        match_keyword!(value, EXTERNAL);
+        match_keyword!(value, IMMUTABLE);
+        match_keyword!(value, INPUT);
+        match_keyword!(value, INVOKER);
+        match_keyword!(value, LEAKPROOF);
+        match_keyword!(value, PARALLEL);
+        match_keyword!(value, RESTRICTED);
+        match_keyword!(value, ROWS);
+        match_keyword!(value, SAFE);
+        match_keyword!(value, SECURITY);
+        match_keyword!(value, STABLE);
+        match_keyword!(value, STRICT);
+        match_keyword!(value, TO);
+        match_keyword!(value, UNSAFE);
+        match_keyword!(value, VOLATILE);
+    }
+
+    // Partition keys and bounds are captured verbatim in the same way as a check expression
+    if let NormalVariant::Partition = variant {
+        let condition = match &value.to_uppercase()[..] {
+            "FROM" => Some(Token::FROM),
+            "HASH" => Some(Token::HASH),
+            "IN" => Some(Token::IN),
+            "LIST" => Some(Token::LIST),
+            "RANGE" => Some(Token::RANGE),
+            "TO" => Some(Token::TO),
+            "WITH" => Some(Token::WITH),
+            _ => None,
+        };
+        if condition.is_some() {
+            context.push_state(LexerState::Condition);
+            return condition;
+        }
+        match_keyword!(value, BY);
+        match_keyword!(value, FOR);
+        match_keyword!(value, OF);
+        match_keyword!(value, PARTITION);
+        match_keyword!(value, VALUES);
+    }
+
+    // A policy expression is captured verbatim in the same way as a check expression
+    if let NormalVariant::Policy = variant {
+        if "USING".eq_ignore_ascii_case(&value[..]) {
+            context.push_state(LexerState::Condition);
+            return Some(Token::USING);
+        }
+        match_keyword!(value, ALL);
+        match_keyword!(value, FOR);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, PERMISSIVE);
+        match_keyword!(value, RESTRICTIVE);
+        match_keyword!(value, SELECT);
+        match_keyword!(value, TO);
+    }
+
+    // A view query is captured verbatim from AS until the end of the statement.
+    if let NormalVariant::View = variant {
+        if "AS".eq_ignore_ascii_case(&value[..]) {
+            context.replace_state(LexerState::Query(QueryPart::Text));
+            return Some(Token::AS);
+        }
+    }
+
+    // An index predicate is captured verbatim from WHERE until the end of the statement.
+    if let NormalVariant::Index = variant {
+        if "WHERE".eq_ignore_ascii_case(&value[..]) {
+            context.replace_state(LexerState::Query(QueryPart::Text));
+            return Some(Token::WHERE);
+        }
+        match_keyword!(value, COLLATE);
+        match_keyword!(value, ONLY);
+    }
+
+    // Likewise, sequence options are only keywords within a sequence or identity definition
+    if variant == NormalVariant::Sequence || variant == NormalVariant::Identity {
+        match_keyword!(value, BY);
+        match_keyword!(value, CACHE);
+        match_keyword!(value, CYCLE);
+        match_keyword!(value, INCREMENT);
+        match_keyword!(value, MAXVALUE);
+        match_keyword!(value, MINVALUE);
+        match_keyword!(value, NONE);
+        match_keyword!(value, OWNED);
+        match_keyword!(value, START);
+    }
+
+    // Privilege types and object kinds are only keywords within GRANT, REVOKE and ALTER DEFAULT PRIVILEGES
+    if let NormalVariant::Privilege = variant {
+        match_keyword!(value, ADMIN);
+        match_keyword!(value, ALL);
+        match_keyword!(value, CREATE);
+        match_keyword!(value, EXECUTE);
+        match_keyword!(value, FOR);
+        match_keyword!(value, FROM);
+        match_keyword!(value, FUNCTION);
+        match_keyword!(value, FUNCTIONS);
+        match_keyword!(value, GRANT);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, OPTION);
+        match_keyword!(value, PRIVILEGES);
+        match_keyword!(value, REVOKE);
+        match_keyword!(value, ROLE);
+        match_keyword!(value, SCHEMA);
+        match_keyword!(value, SELECT);
+        match_keyword!(value, SEQUENCE);
+        match_keyword!(value, SEQUENCES);
+        match_keyword!(value, TABLES);
+        match_keyword!(value, TO);
+        match_keyword!(value, TRIGGER);
+        match_keyword!(value, TRUNCATE);
+        match_keyword!(value, USAGE);
+    }
+
+    // Trigger keywords are only matched within a trigger definition since many of them are common
+    // column names.
+    if let NormalVariant::Trigger = variant {
+        if "WHEN".eq_ignore_ascii_case(&value[..]) {
+            context.push_state(LexerState::Condition);
+            return Some(Token::WHEN);
+        }
+        match_keyword!(value, AFTER);
+        match_keyword!(value, BEFORE);
+        match_keyword!(value, EACH);
+        match_keyword!(value, EXECUTE);
+        match_keyword!(value, FOR);
+        match_keyword!(value, FUNCTION);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, INSTEAD);
+        match_keyword!(value, OF);
+        match_keyword!(value, PROCEDURE);
+        match_keyword!(value, ROW);
+        match_keyword!(value, STATEMENT);
+        match_keyword!(value, TRUNCATE);
+    }
+
+    // A check expression is captured verbatim in the same way as a trigger condition
+    if "CHECK".eq_ignore_ascii_case(&value[..]) {
+        context.push_state(LexerState::Condition);
+        return Some(Token::CHECK);
+    }
+
+    // Interval fields are only keywords directly following INTERVAL since they are common column names
+    if "INTERVAL".eq_ignore_ascii_case(&value[..]) {
+        context.push_state(LexerState::Normal(NormalVariant::Interval));
+        return Some(Token::INTERVAL);
     }
 
     match_keyword!(value, ACTION);
@@ -436,33 +1008,45 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, BOOL);
     match_keyword!(value, BOOLEAN);
     match_keyword!(value, BTREE);
+    match_keyword!(value, BYTEA);
     match_keyword!(value, CASCADE);
+    match_keyword!(value, CIDR);
     match_keyword!(value, CONSTRAINT);
     match_keyword!(value, CHAR);
     match_keyword!(value, CHARACTER);
     match_keyword!(value, DATE);
+    match_keyword!(value, DECIMAL);
     match_keyword!(value, DEFAULT);
     match_keyword!(value, DELETE);
     match_keyword!(value, DESC);
     match_keyword!(value, DOUBLE);
     match_keyword!(value, ENUM);
+    match_keyword!(value, EXCLUDE);
     match_keyword!(value, FILLFACTOR);
     match_keyword!(value, FIRST);
+    match_keyword!(value, FLOAT);
     match_keyword!(value, FOREIGN);
     match_keyword!(value, FULL);
     match_keyword!(value, GIN);
     match_keyword!(value, GIST);
     match_keyword!(value, HASH);
     match_keyword!(value, IN);
+    match_keyword!(value, INCLUDE);
+    match_keyword!(value, INET);
+    match_keyword!(value, INHERIT);
     match_keyword!(value, INOUT);
     match_keyword!(value, INT);
     match_keyword!(value, INT2);
     match_keyword!(value, INT4);
     match_keyword!(value, INT8);
     match_keyword!(value, INTEGER);
+    match_keyword!(value, JSON);
+    match_keyword!(value, JSONB);
     match_keyword!(value, KEY);
     match_keyword!(value, LANGUAGE);
     match_keyword!(value, LAST);
+    match_keyword!(value, MACADDR);
+    match_keyword!(value, MACADDR8);
     match_keyword!(value, MATCH);
     match_keyword!(value, MONEY);
     match_keyword!(value, NO);
@@ -489,6 +1073,9 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, SIMPLE);
     match_keyword!(value, SMALLINT);
     match_keyword!(value, SMALLSERIAL);
+    match_keyword!(value, TSQUERY);
+    match_keyword!(value, TSVECTOR);
+    match_keyword!(value, XML);
     match_keyword!(value, TABLE); // The one exception
     match_keyword!(value, TEXT);
     match_keyword!(value, TIME);
@@ -510,247 +1097,589 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
 
     // Regex
     if IDENTIFIER.is_match(&value[..]) {
-        return Some(Token::Identifier(value));
+        // Unquoted identifiers are case insensitive, so are folded to lower case like Postgres does
+        return Some(Token::Identifier(value.to_lowercase()));
     }
     if DECIMAL.is_match(&value[..]) {
         return Some(Token::Decimal(value.parse::<Decimal>().unwrap()));
     }
     if DIGIT.is_match(&value[..]) {
-        return Some(Token::Digit(value.parse::<i32>().unwrap()));
+        // Integers too large for a digit (e.g. sequence bounds) are represented as a decimal
+        return match value.parse::<i32>() {
+            Ok(digit) => Some(Token::Digit(digit)),
+            Err(_) => value.parse::<Decimal>().ok().map(Token::Decimal),
+        };
+    }
+    if OPERATOR.is_match(&value[..]) {
+        return Some(Token::Operator(value));
     }
 
     // Error
     None
 }
 
-pub fn tokenize_body(text: &str) -> Result<Vec<Token>, LexicalError> {
+// Materialized views may specify WITH [NO] DATA after the query. Since this can't be part of the
+// query itself we split it off into separate tokens. The buffer starts with the first character of the query.
+fn query_tokens(buffer: &[char], start: Location) -> Vec<SpannedToken> {
+    let data = String::from_iter(buffer);
+    let data = data.trim_end();
+    // The location of a byte offset within the query
+    let at = |offset: usize| {
+        data[..offset].chars().fold(start, |location, c| match c {
+            '\n' => Location {
+                offset: location.offset + 1,
+                line: location.line + 1,
+                column: 1,
+            },
+            c => location.after(c),
+        })
+    };
+    match WITH_DATA.captures(data) {
+        Some(captures) => {
+            let query = captures[1].trim_end();
+            let mut tokens = vec![(start, Token::Query(query.into()), at(query.len()))];
+            for (group, token) in [(2, Token::WITH), (3, Token::NO), (4, Token::DATA)] {
+                if let Some(word) = captures.get(group) {
+                    tokens.push((at(word.start()), token, at(word.end())));
+                }
+            }
+            tokens
+        }
+        None => vec![(start, Token::Query(data.into()), at(data.len()))],
+    }
+}
+
+// Index columns may be expressions, either bracketed or a function call. Generated columns are always
+// bracketed expressions.
+fn starts_expression(context: &Context, tokens: &[SpannedToken]) -> bool {
+    match context.peek_state() {
+        LexerState::Normal(NormalVariant::Index) => match self::last_token(tokens) {
+            Some(&Token::LeftBracket) | Some(&Token::Comma) => true,
+            _ => false,
+        },
+        LexerState::Normal(NormalVariant::Generated) => self::last_token(tokens) == Some(&Token::AS),
+        _ => false,
+    }
+}
+
+fn last_token(tokens: &[SpannedToken]) -> Option<&Token> {
+    tokens.last().map(|&(_, ref token, _)| token)
+}
+
+fn bracket_depth(buffer: &[char]) -> i32 {
+    let mut quote = None;
+    let mut depth = 0;
+    for c in buffer {
+        match quote {
+            Some(q) if q == *c => quote = None,
+            Some(_) => {}
+            None if *c == '\'' || *c == '"' => quote = Some(*c),
+            None if *c == '(' => depth += 1,
+            None if *c == ')' => depth -= 1,
+            None => {}
+        }
+    }
+    depth
+}
+
+// Whether the buffer ends with the delimiter closing a dollar quoted literal with the given tag
+fn closes_literal(buffer: &[char], tag: &[char]) -> bool {
+    let length = tag.len() + 2;
+    buffer.len() >= length
+        && buffer[buffer.len() - length] == '$'
+        && buffer[buffer.len() - length + 1..buffer.len() - 1] == *tag
+        && buffer[buffer.len() - 1] == '$'
+}
+
+// Whether the buffer ends with the opening delimiter of a dollar quoted string (e.g. $tag$), returning where it
+// starts and the length of its tag. A $ following a word or a tag starting with a digit is something else.
+fn opens_dollar_quote(buffer: &[char]) -> Option<(usize, usize)> {
+    let end = buffer.len() - 1;
+    let start = buffer[..end]
+        .iter()
+        .rposition(|c| !(c.is_alphanumeric() || *c == '_'))?;
+    let preceding = if start == 0 { None } else { Some(buffer[start - 1]) };
+    let is_tag = buffer[start] == '$'
+        && buffer.get(start + 1).filter(|c| c.is_ascii_digit()).is_none()
+        && preceding
+            .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+            .is_none();
+    if is_tag {
+        Some((start, end - start - 1))
+    } else {
+        None
+    }
+}
+
+pub fn tokenize_body(text: &str) -> Result<Vec<SpannedToken>, Vec<LexicalError>> {
     tokenize(text, NormalVariant::Body)
 }
 
-pub fn tokenize_stmt(text: &str) -> Result<Vec<Token>, LexicalError> {
+pub fn tokenize_partition(text: &str) -> Result<Vec<SpannedToken>, Vec<LexicalError>> {
+    tokenize(text, NormalVariant::Partition)
+}
+
+pub fn tokenize_stmt(text: &str) -> Result<Vec<SpannedToken>, Vec<LexicalError>> {
     tokenize(text, NormalVariant::Any)
 }
 
-fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError> {
+// Tokenizes each statement separately so that a statement which can't be tokenized doesn't prevent the rest
+// of the text from being understood. Such a statement is kept verbatim as a single unsupported token.
+pub fn tokenize_stmt_tolerant(text: &str) -> Vec<SpannedToken> {
+    let mut tokens = Vec::new();
+    for (start, end) in self::split_statements(text) {
+        let statement = &text[start..end];
+        let base = self::location_of(text, start);
+        match tokenize(statement, NormalVariant::Any) {
+            Ok(statement_tokens) => tokens.extend(
+                statement_tokens
+                    .into_iter()
+                    .map(|(from, token, to)| (from.relative_to(base), token, to.relative_to(base))),
+            ),
+            Err(_) => {
+                let leading = statement.len() - statement.trim_start().len();
+                let body = statement.trim().trim_end_matches(';').trim_end();
+                let from = self::location_of(text, start + leading);
+                let to = self::location_of(text, start + leading + body.len());
+                tokens.push((from, Token::Unsupported(body.into()), to));
+                if statement.trim_end().ends_with(';') {
+                    let semicolon = start + statement.trim_end().len() - 1;
+                    let at = self::location_of(text, semicolon);
+                    tokens.push((at, Token::Semicolon, at.after(';')));
+                }
+            }
+        }
+    }
+    tokens
+}
+
+// The byte ranges of each statement within the text, including the semicolon that ends it. This is deliberately
+// looser than the lexer: it only needs to know enough to find a semicolon outside of brackets, quotes, dollar
+// quoted literals and comments.
+fn split_statements(text: &str) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'$';
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = text[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..].find("*/").map_or(bytes.len(), |end| i + end + 4);
+                continue;
+            }
+            quote @ b'\'' | quote @ b'"' => {
+                // An E'' string may escape its quote with a backslash
+                let escapes = quote == b'\''
+                    && i > 0
+                    && (bytes[i - 1] == b'E' || bytes[i - 1] == b'e')
+                    && (i < 2 || !is_word(bytes[i - 2]));
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if escapes && bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'$' if i == 0 || !is_word(bytes[i - 1]) => {
+                let tag_end = bytes[i + 1..]
+                    .iter()
+                    .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+                    .map(|length| i + 1 + length);
+                if let Some(tag_end) = tag_end {
+                    if bytes[tag_end] == b'$' && !bytes[i + 1].is_ascii_digit() {
+                        let tag = &text[i..=tag_end];
+                        i = text[tag_end + 1..]
+                            .find(tag)
+                            .map_or(bytes.len(), |end| tag_end + 1 + end + tag.len());
+                        continue;
+                    }
+                }
+            }
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b';' if depth <= 0 => {
+                statements.push((start, i + 1));
+                start = i + 1;
+                depth = 0;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < bytes.len() {
+        statements.push((start, bytes.len()));
+    }
+    statements
+}
+
+fn location_of(text: &str, offset: usize) -> Location {
+    let preceding = &text[..offset];
+    let line_start = preceding.rfind('\n').map_or(0, |newline| newline + 1);
+    Location {
+        offset,
+        line: preceding.matches('\n').count() + 1,
+        column: preceding[line_start..].chars().count() + 1,
+    }
+}
+
+fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<SpannedToken>, Vec<LexicalError>> {
     // This tokenizer is whitespace dependent by default, i.e. whitespace is relevant.
     let mut tokens = Vec::new();
+    let mut errors = Vec::new();
     let mut context = Context::new(start);
 
-    // Loop through each character, halting on whitespace
     // Our outer loop works by newline
-    let lines: Vec<&str> = text.split('\n').collect();
-    for line in lines {
-        context.new_line();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        context.new_line(offset);
+        offset += line.len() + 1;
+        if let Err(error) = self::tokenize_line(&mut context, line, &mut tokens) {
+            // Recover by skipping the rest of the line so that every error in the text is reported
+            errors.push(error);
+            context.reset(start);
+        }
+    }
+
+    // A query may be terminated by the end of the text instead of a semicolon
+    if let LexerState::Query(_) = context.peek_state() {
+        tokens.extend(self::query_tokens(&context.buffer, context.start));
+    }
+
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
 
-        for c in line.chars() {
+// Loop through each character, halting on whitespace
+fn tokenize_line<'input>(
+    context: &mut Context,
+    line: &'input str,
+    tokens: &mut Vec<SpannedToken>,
+) -> Result<(), LexicalError<'input>> {
+    for c in line.chars() {
+        // Mark where a token starts. Queries and conditions start with their first significant character.
+        if context.buffer.is_empty() {
             match context.peek_state() {
-                LexerState::Normal(_) => {
-                    // Check if we should be entering the comment state
-                    if context.last_char == '-' && c == '-' {
-                        // take off the previous item as it was a comment character and push the buffer
-                        context.buffer.pop();
-                        tokenize_normal_buffer!(context, line, tokens);
-                        context.push_state(LexerState::Comment1);
-                    } else if context.last_char == '/' && c == '*' {
-                        // take off the previous item as it was a comment character and push the buffer
-                        context.buffer.pop();
-                        tokenize_normal_buffer!(context, line, tokens);
-                        context.push_state(LexerState::Comment2);
-                    } else if c == '\'' {
-                        if context.buffer.is_empty() {
-                            context.push_state(LexerState::String);
-                        } else {
-                            // Invalid state - must be something like xx'dd
-                            return Err(context.create_error(line, "' was unexpected"));
-                        }
-                    } else if c == '"' {
-                        if context.buffer.is_empty() {
-                            context.push_state(LexerState::QuotedIdentifier);
-                        } else {
-                            // Invalid state - Must be something like xx"dd
-                            return Err(context.create_error(line, "\" was unexpected"));
-                        }
-                    } else if c == '$' {
-                        if context.buffer.is_empty() {
-                            context.push_state(LexerState::LiteralStart);
-                        } else {
-                            // Unsupported state in our lexer
-                            return Err(context.create_error(line, "$ was unexpected"));
+                LexerState::Normal(_) => context.start = context.location(),
+                LexerState::Query(_) | LexerState::Condition if !c.is_whitespace() => {
+                    context.start = context.location()
+                }
+                _ => {}
+            }
+        }
+
+        match context.peek_state() {
+            LexerState::Normal(_) => {
+                // Check if we should be entering the comment state
+                if context.last_char == '-' && c == '-' {
+                    // take off the previous item as it was a comment character and push the buffer
+                    context.buffer.pop();
+                    tokenize_normal_buffer!(context, line, tokens);
+                    context.push_state(LexerState::Comment1);
+                } else if context.last_char == '/' && c == '*' {
+                    // take off the previous item as it was a comment character and push the buffer
+                    context.buffer.pop();
+                    tokenize_normal_buffer!(context, line, tokens);
+                    context.push_state(LexerState::Comment2);
+                } else if c == '\'' {
+                    if context.buffer.is_empty() {
+                        context.push_state(LexerState::String);
+                    } else {
+                        // Invalid state - must be something like xx'dd
+                        return Err(context.create_error(line, "' was unexpected"));
+                    }
+                } else if c == '"' {
+                    if context.buffer.is_empty() && context.last_char == '"' {
+                        // A doubled quote is an escaped quote within the previous quoted identifier
+                        if let Some((start, Token::Identifier(ident), _)) = tokens.pop() {
+                            context.start = start;
+                            context.buffer.extend(ident.chars());
+                            context.buffer.push(c);
                         }
-                    } else if c.is_whitespace() {
-                        // Simple check for whitespace
-                        tokenize_normal_buffer!(context, line, tokens);
+                        context.push_state(LexerState::QuotedIdentifier);
+                    } else if context.buffer.is_empty() {
+                        context.push_state(LexerState::QuotedIdentifier);
                     } else {
-                        // If it is a symbol then don't bother with the buffer
-                        match c {
-                            '(' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::LeftBracket);
-                            }
-                            ')' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::RightBracket);
+                        // Invalid state - Must be something like xx"dd
+                        return Err(context.create_error(line, "\" was unexpected"));
+                    }
+                } else if c == '$' {
+                    if context.buffer.is_empty() {
+                        context.push_state(LexerState::LiteralStart);
+                    } else if context.buffer[0].is_alphabetic() || context.buffer[0] == '_' {
+                        // Identifiers may contain (but not start with) a dollar sign
+                        context.buffer.push(c);
+                    } else {
+                        // Unsupported state in our lexer
+                        return Err(context.create_error(line, "$ was unexpected"));
+                    }
+                } else if c.is_whitespace() {
+                    // Simple check for whitespace
+                    tokenize_normal_buffer!(context, line, tokens);
+                } else {
+                    // If it is a symbol then don't bother with the buffer
+                    match c {
+                        '(' if self::starts_expression(context, tokens) => {
+                            // An expression is captured as a condition. A function call keeps its name.
+                            context.buffer.push(c);
+                            context.push_state(LexerState::Condition);
+                        }
+                        '(' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            // The precision always completes an interval type
+                            if let LexerState::Normal(NormalVariant::Interval) = context.peek_state() {
+                                context.pop_state();
                             }
-                            ',' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::Comma);
+                            // A condition captures the bracket as part of the expression
+                            if let LexerState::Condition = context.peek_state() {
+                                context.buffer.push(c);
+                            } else {
+                                // Identity options are optional so are only entered upon a bracket
+                                if self::last_token(tokens) == Some(&Token::IDENTITY) {
+                                    context.push_state(LexerState::Normal(NormalVariant::Identity));
+                                }
+                                // Likewise, a returned table (or a type modifier within) suspends function attributes
+                                match context.peek_state() {
+                                    LexerState::Normal(NormalVariant::Function)
+                                    | LexerState::Normal(NormalVariant::ReturnTable) => {
+                                        context.push_state(LexerState::Normal(NormalVariant::ReturnTable))
+                                    }
+                                    _ => {}
+                                }
+                                push_token!(tokens, Token::LeftBracket, context.char_span(c));
                             }
-                            ':' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::Colon);
+                        }
+                        ')' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::RightBracket, context.char_span(c));
+                            if let LexerState::Normal(NormalVariant::Interval) = context.peek_state() {
+                                context.pop_state();
                             }
-                            ';' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::Semicolon);
-                                context.replace_state(LexerState::Normal(NormalVariant::Any));
+                            match context.peek_state() {
+                                LexerState::Normal(NormalVariant::Identity)
+                                | LexerState::Normal(NormalVariant::ReturnTable) => context.pop_state(),
+                                _ => {}
                             }
-                            '=' => {
-                                tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::Equals);
+                        }
+                        ',' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::Comma, context.char_span(c));
+                            if let LexerState::Normal(NormalVariant::Interval) = context.peek_state() {
+                                context.pop_state();
                             }
-                            '.' => {
-                                // If it is just a plain digit in the buffer, then allow it to continue.
-                                if context.buffer.iter().all(|c: &char| c.is_digit(10)) {
-                                    context.buffer.push(c);
-                                } else {
-                                    tokenize_normal_buffer!(context, line, tokens);
-                                    push_token!(tokens, Token::Period);
-                                }
+                        }
+                        ':' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::Colon, context.char_span(c));
+                        }
+                        ';' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::Semicolon, context.char_span(c));
+                            if let LexerState::Normal(NormalVariant::Interval) = context.peek_state() {
+                                context.pop_state();
                             }
-                            '[' => {
+                            context.replace_state(LexerState::Normal(NormalVariant::Any));
+                        }
+                        '=' => {
+                            // Equals may also be part of a larger operator (e.g. <=)
+                            if !context.buffer.is_empty() && OPERATOR.is_match(&String::from_iter(&context.buffer)) {
+                                context.buffer.push(c);
+                            } else {
                                 tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::LeftSquare);
+                                push_token!(tokens, Token::Equals, context.char_span(c));
                             }
-                            ']' => {
+                        }
+                        '.' => {
+                            // If it is just a plain (possibly negative) digit in the buffer, then allow it to continue.
+                            let digits = match context.buffer.first() {
+                                Some('-') => &context.buffer[1..],
+                                _ => &context.buffer[..],
+                            };
+                            if !digits.is_empty() && digits.iter().all(|c: &char| c.is_digit(10)) {
+                                context.buffer.push(c);
+                            } else {
                                 tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::RightSquare);
+                                push_token!(tokens, Token::Period, context.char_span(c));
                             }
-                            _ => context.buffer.push(c),
                         }
+                        '[' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::LeftSquare, context.char_span(c));
+                        }
+                        ']' => {
+                            tokenize_normal_buffer!(context, line, tokens);
+                            push_token!(tokens, Token::RightSquare, context.char_span(c));
+                        }
+                        _ => context.buffer.push(c),
                     }
                 }
-                LexerState::Comment1 => {
-                    // Ignore comments
-                }
-                LexerState::Comment2 => {
-                    if context.last_char == '*' && c == '/' {
-                        context.pop_state();
-                    }
-                    // Ignore comments
+            }
+            LexerState::Comment1 => {
+                // Ignore comments
+            }
+            LexerState::Comment2 => {
+                if context.last_char == '*' && c == '/' {
+                    context.pop_state();
                 }
-                LexerState::String => {
-                    if c == '\'' {
-                        push_token!(tokens, Token::StringValue(String::from_iter(context.buffer.clone())));
-                        context.buffer.clear();
-                        context.pop_state();
-                    } else {
-                        context.buffer.push(c);
-                    }
+                // Ignore comments
+            }
+            LexerState::String => {
+                if c == '\'' {
+                    push_token!(
+                        tokens,
+                        Token::StringValue(String::from_iter(context.buffer.clone())),
+                        context.closing_span(c)
+                    );
+                    context.buffer.clear();
+                    context.pop_state();
+                } else {
+                    context.buffer.push(c);
                 }
-                LexerState::QuotedIdentifier => {
-                    if c == '"' {
-                        push_token!(tokens, Token::Identifier(String::from_iter(context.buffer.clone())));
-                        context.buffer.clear();
-                        context.pop_state();
-                    } else {
-                        context.buffer.push(c);
-                    }
+            }
+            LexerState::QuotedIdentifier => {
+                if c == '"' {
+                    push_token!(
+                        tokens,
+                        Token::Identifier(String::from_iter(context.buffer.clone())),
+                        context.closing_span(c)
+                    );
+                    context.buffer.clear();
+                    context.pop_state();
+                } else {
+                    context.buffer.push(c);
                 }
-                LexerState::LiteralStart => {
-                    if c == '$' {
-                        context.replace_state(LexerState::LiteralBody);
-                    } else {
-                        context.literal.push(c);
+            }
+            LexerState::LiteralStart => {
+                if c == '$' {
+                    context.replace_state(LexerState::LiteralBody);
+                } else if c.is_alphanumeric() || c == '_' {
+                    if context.literal.is_empty() && c.is_ascii_digit() {
+                        return Err(context.create_error(line, "dollar quote tag can't start with a digit"));
                     }
+                    context.literal.push(c);
+                } else {
+                    return Err(context.create_error(line, "invalid character in dollar quote tag"));
                 }
-                LexerState::LiteralEnd => {
-                    if c == '$' {
-                        if context.literal.is_empty() {
-                            context.pop_state();
-                        } else {
-                            // Error: literal name mismatch
-                            return Err(context.create_error(line, "literal name mismatch - leftover characters"));
-                        }
-                    } else if context.literal.is_empty() {
-                        // Error: literal name mismatch
-                        return Err(context.create_error(line, "literal name mismatch - exhausted characters"));
-                    } else {
-                        let l = context.literal.pop().unwrap();
-                        if l != c {
-                            // Error: literal name mismatch
-                            return Err(context.create_error(line, "literal name mismatch - unexpected character"));
+            }
+            LexerState::Query(part) => {
+                if part == QueryPart::Text && c == ';' {
+                    tokens.extend(self::query_tokens(&context.buffer, context.start));
+                    push_token!(tokens, Token::Semicolon, context.char_span(c));
+                    context.buffer.clear();
+                    context.replace_state(LexerState::Normal(NormalVariant::Any));
+                } else if context.buffer.is_empty() && c.is_whitespace() {
+                    // The query starts with its first significant character
+                } else {
+                    // Comments and quotes are kept as part of the query
+                    context.buffer.push(c);
+                    let next = match part {
+                        QueryPart::Text if c == '\'' || c == '"' => QueryPart::Quoted(c),
+                        QueryPart::Text if context.last_char == '-' && c == '-' => QueryPart::LineComment,
+                        QueryPart::Text if context.last_char == '/' && c == '*' => QueryPart::BlockComment,
+                        QueryPart::Text if c == '$' => match self::opens_dollar_quote(&context.buffer) {
+                            Some((start, length)) => QueryPart::DollarQuoted(start, length),
+                            None => part,
+                        },
+                        QueryPart::Quoted(quote) if quote == c => QueryPart::Text,
+                        QueryPart::DollarQuoted(start, length)
+                            if c == '$'
+                                && context.buffer.len() > start + 2 * length + 3
+                                && self::closes_literal(
+                                    &context.buffer,
+                                    &context.buffer[start + 1..start + 1 + length],
+                                ) =>
+                        {
+                            QueryPart::Text
                         }
+                        QueryPart::BlockComment if context.last_char == '*' && c == '/' => QueryPart::Text,
+                        _ => part,
+                    };
+                    if next != part {
+                        context.replace_state(LexerState::Query(next));
                     }
                 }
-                LexerState::LiteralBody => {
-                    // We only escape from a literal body if the next few characters are
-                    // in fact part of the literal. For example, we may be using $1 as a positional
-                    // argument.
-                    // Since we don't have a lookahead system implemented (yet) we do some naive checks
-                    // here.
-                    if context.last_char == '$' {
-                        if context.literal.is_empty() {
-                            if c == '$' {
-                                // We've parsed a complete literal
-                                context.buffer.pop(); // Pop off the previous $
-                                                      // Add the token
-                                let data = String::from_iter(context.buffer.clone());
-                                push_token!(tokens, Token::Literal(data.trim().into()));
-                                context.buffer.clear();
-                                context.pop_state();
-                            } else {
-                                // We're still in the buffer
-                                context.buffer.push(c);
-                            }
-                        } else {
-                            // This is a naive check only looking at the first character
-                            if context.literal[0] == c {
-                                context.buffer.pop(); // Pop off the previous $
-                                let data = String::from_iter(context.buffer.clone());
-                                push_token!(tokens, Token::Literal(data.trim().into()));
-                                context.buffer.clear();
-                                context.literal.reverse();
-                                context.literal.pop(); // we've already confirmed the first char
-                                context.replace_state(LexerState::LiteralEnd);
-                            } else {
-                                // We're still in the buffer
-                                context.buffer.push(c);
-                            }
-                        }
-                    } else {
+            }
+            LexerState::Condition => {
+                // A condition is captured verbatim until the brackets are balanced
+                if context.buffer.is_empty() {
+                    if c == '(' {
                         context.buffer.push(c);
+                    } else if !c.is_whitespace() {
+                        return Err(context.create_error(line, "expected ( to start condition"));
+                    }
+                } else {
+                    context.buffer.push(c);
+                    if c == ')' && self::bracket_depth(&context.buffer) == 0 {
+                        let data = if context.buffer[0] == '(' {
+                            String::from_iter(&context.buffer[1..context.buffer.len() - 1])
+                        } else {
+                            String::from_iter(&context.buffer)
+                        };
+                        push_token!(tokens, Token::Condition(data.trim().into()), context.closing_span(c));
+                        context.buffer.clear();
+                        context.pop_state();
                     }
                 }
             }
-
-            // Move the current_position
-            context.next_char(c);
-        }
-
-        // If we were a single line comment, we go back to a normal state on a new line
-        match context.peek_state() {
-            LexerState::Normal(_) => {
-                // We may also have a full buffer
-                tokenize_normal_buffer!(context, line, tokens);
-            }
-            LexerState::Comment1 => {
-                // End of a line finishes the comment
-                context.pop_state();
-            }
-            LexerState::Comment2 => {
-                // Do nothing at the end of a line - it's a multi-line comment
-            }
-            LexerState::String | LexerState::QuotedIdentifier | LexerState::LiteralStart | LexerState::LiteralEnd => {
-                // If we're in these states at the end of a line it's an error
-                // (e.g. at the moment we don't support multi-line strings)
-                return Err(context.create_error(line, "end of line was unexpected"));
-            }
             LexerState::LiteralBody => {
-                // Add a new line onto the buffer
-                context.buffer.push('\n');
+                // The body continues until the closing delimiter (i.e. $tag$) so it may contain
+                // positional arguments (e.g. $1) and differently tagged literals.
+                context.buffer.push(c);
+                if c == '$' && self::closes_literal(&context.buffer, &context.literal) {
+                    let data = &context.buffer[..context.buffer.len() - context.literal.len() - 2];
+                    push_token!(
+                        tokens,
+                        Token::Literal(String::from_iter(data).trim().into()),
+                        context.closing_span(c)
+                    );
+                    context.buffer.clear();
+                    context.literal.clear();
+                    context.pop_state();
+                }
             }
         }
+
+        // Move the current_position
+        context.next_char(c);
+    }
+
+    // If we were a single line comment, we go back to a normal state on a new line
+    match context.peek_state() {
+        LexerState::Normal(_) => {
+            // We may also have a full buffer
+            tokenize_normal_buffer!(context, line, tokens);
+        }
+        LexerState::Comment1 => {
+            // End of a line finishes the comment
+            context.pop_state();
+        }
+        LexerState::Comment2 => {
+            // Do nothing at the end of a line - it's a multi-line comment
+        }
+        LexerState::String | LexerState::QuotedIdentifier | LexerState::LiteralStart => {
+            // If we're in these states at the end of a line it's an error
+            // (e.g. at the moment we don't support multi-line strings)
+            return Err(context.create_error(line, "end of line was unexpected"));
+        }
+        LexerState::Query(QueryPart::LineComment) => {
+            // End of a line finishes the comment, but not the query
+            context.replace_state(LexerState::Query(QueryPart::Text));
+            context.buffer.push('\n');
+        }
+        LexerState::Query(_) if context.buffer.is_empty() => {
+            // The query hasn't started yet
+        }
+        LexerState::LiteralBody | LexerState::Query(_) | LexerState::Condition => {
+            // Add a new line onto the buffer
+            context.buffer.push('\n');
+        }
     }
 
-    Ok(tokens)
+    Ok(())
 }
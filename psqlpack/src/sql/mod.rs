@@ -1,5 +1,6 @@
 pub mod ast;
 mod bootstrap;
+pub mod formatter;
 pub mod lexer;
 
 pub use bootstrap::parser;
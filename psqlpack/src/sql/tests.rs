@@ -1,6 +1,9 @@
 use crate::sql::ast::*;
 use crate::sql::lexer;
-use crate::sql::parser::{FunctionArgumentListParser, StatementListParser};
+use crate::sql::parser::{AnyValueParser, FunctionArgumentListParser, SqlTypeParser, StatementListParser};
+
+use lalrpop_util::ParseError;
+use rust_decimal::Decimal;
 
 use spectral::prelude::*;
 
@@ -37,6 +40,8 @@ fn it_can_parse_a_basic_function_definition() {
         return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Integer, None)),
         body: "SELECT index".into(),
         language: FunctionLanguage::SQL,
+        attributes: Vec::new(),
+        span: Span::default(),
     }));
 }
 
@@ -68,6 +73,8 @@ fn it_can_parse_a_function_definition_with_simple_literals() {
         return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Integer, None)),
         body: "SELECT 1".into(),
         language: FunctionLanguage::SQL,
+        attributes: Vec::new(),
+        span: Span::default(),
     }));
 }
 
@@ -120,6 +127,8 @@ fn it_can_parse_a_function_definition_returning_table() {
                    ORDER BY countries.iso"
             .into(),
         language: FunctionLanguage::SQL,
+        attributes: Vec::new(),
+        span: Span::default(),
     }));
 }
 
@@ -179,9 +188,62 @@ fn it_can_parse_a_function_definition_with_parameters() {
                    ORDER BY states.iso"
             .into(),
         language: FunctionLanguage::SQL,
+        attributes: Vec::new(),
+        span: Span::default(),
     }));
 }
 
+#[test]
+fn it_can_parse_a_function_definition_with_a_custom_dollar_quote_tag() {
+    let sql = "CREATE FUNCTION public.fn_count(name text)
+               RETURNS bigint
+               AS $body$
+               DECLARE result bigint;
+               BEGIN
+                   EXECUTE format($$SELECT count(*) FROM %I$$, name) INTO result;
+                   RETURN result + $1::bigint * 0;
+               END
+               $body$
+               LANGUAGE plpgsql;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    match statements[0] {
+        Statement::Function(ref function) => {
+            assert_that!(function.body).is_equal_to(
+                "DECLARE result bigint;
+               BEGIN
+                   EXECUTE format($$SELECT count(*) FROM %I$$, name) INTO result;
+                   RETURN result + $1::bigint * 0;
+               END"
+                .to_owned(),
+            );
+            assert_that!(function.language).is_equal_to(FunctionLanguage::PostgreSQL);
+        }
+        ref other => panic!("Expected function, got {:?}", other),
+    }
+}
+
+#[test]
+fn it_reports_every_lexical_error() {
+    let sql = "CREATE TABLE contacts (id int, na\"me text);
+               CREATE TABLE people (id int);
+               CREATE FUNCTION fn_one() RETURNS int AS $1tag$ SELECT 1 $1tag$ LANGUAGE SQL;";
+
+    let errors = match lexer::tokenize_stmt(sql) {
+        Ok(_) => panic!("Expected lexical errors"),
+        Err(errors) => errors,
+    };
+    let lines = errors.iter().map(|e| e.line_number).collect::<Vec<_>>();
+    assert_that!(lines).is_equal_to(vec![1, 3]);
+}
+
 #[test]
 fn it_can_parse_function_arguments() {
     let sql = "geom geometry,
@@ -292,3 +354,1543 @@ fn it_can_parse_function_arguments() {
         },
     ]);
 }
+
+#[test]
+fn it_can_parse_a_view_definition() {
+    let sql = "CREATE OR REPLACE VIEW public.active_contacts AS
+               SELECT id, name FROM public.contacts WHERE status = 'active;';
+               CREATE TABLE public.contacts(id int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::View(ViewDefinition {
+        name: ObjectName {
+            schema: Some("public".into()),
+            name: "active_contacts".into(),
+        },
+        columns: None,
+        query: "SELECT id, name FROM public.contacts WHERE status = 'active;'".into(),
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_view_definition_with_comments() {
+    let sql = "CREATE VIEW public.active_contacts AS
+               SELECT id, name -- don't include the email; it's private
+               FROM public.contacts /* isn't; */ WHERE status = 'active';
+               CREATE TABLE public.contacts(id int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+    let stmt = &statements[0];
+
+    // Comments are kept as part of the query
+    assert_that!(*stmt).is_equal_to(Statement::View(ViewDefinition {
+        name: ObjectName {
+            schema: Some("public".into()),
+            name: "active_contacts".into(),
+        },
+        columns: None,
+        query: "SELECT id, name -- don't include the email; it's private\n               \
+                FROM public.contacts /* isn't; */ WHERE status = 'active'"
+            .into(),
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_view_definition_with_dollar_quotes() {
+    let sql = "CREATE VIEW public.separators AS
+               SELECT $$x;y$$ AS plain, $sep$;$$;$sep$ AS tagged, $1 FROM public.contacts;
+               CREATE TABLE public.contacts(id int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+    let stmt = &statements[0];
+
+    // Semicolons within a dollar quoted string don't end the query
+    assert_that!(*stmt).is_equal_to(Statement::View(ViewDefinition {
+        name: ObjectName {
+            schema: Some("public".into()),
+            name: "separators".into(),
+        },
+        columns: None,
+        query: "SELECT $$x;y$$ AS plain, $sep$;$$;$sep$ AS tagged, $1 FROM public.contacts".into(),
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_view_definition_with_columns() {
+    let sql = "CREATE VIEW contact_names (contact_id, contact_name) AS
+               SELECT c.id, c.first_name || ' ' || c.last_name
+               FROM contacts c";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::View(ViewDefinition {
+        name: ObjectName {
+            schema: None,
+            name: "contact_names".into(),
+        },
+        columns: Some(vec!["contact_id".into(), "contact_name".into()]),
+        query: "SELECT c.id, c.first_name || ' ' || c.last_name\n               FROM contacts c".into(),
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_materialized_view_definition() {
+    let sql = "CREATE MATERIALIZED VIEW reporting.daily_totals AS
+               SELECT created_on, sum(amount) AS total FROM orders GROUP BY created_on
+               WITH NO DATA;
+               CREATE INDEX idx_daily_totals ON reporting.daily_totals (created_on);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::MaterializedView(MaterializedViewDefinition {
+        name: ObjectName {
+            schema: Some("reporting".into()),
+            name: "daily_totals".into(),
+        },
+        columns: None,
+        query: "SELECT created_on, sum(amount) AS total FROM orders GROUP BY created_on".into(),
+        with_data: false,
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_materialized_view_definition_with_data() {
+    let sql = "CREATE MATERIALIZED VIEW order_totals (id, total) AS SELECT id, amount FROM orders WITH DATA";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::MaterializedView(MaterializedViewDefinition {
+        name: ObjectName {
+            schema: None,
+            name: "order_totals".into(),
+        },
+        columns: Some(vec!["id".into(), "total".into()]),
+        query: "SELECT id, amount FROM orders".into(),
+        with_data: true,
+        resolved: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_trigger_definition() {
+    let sql = "CREATE TRIGGER audit_orders
+               AFTER INSERT OR UPDATE OF amount, status OR DELETE ON public.orders
+               FOR EACH ROW
+               WHEN (NEW.status <> 'void (pending)')
+               EXECUTE FUNCTION audit.log_change('orders', 1);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::Trigger(TriggerDefinition {
+        name: "audit_orders".into(),
+        table: ObjectName {
+            schema: Some("public".into()),
+            name: "orders".into(),
+        },
+        timing: TriggerTiming::After,
+        events: vec![
+            TriggerEvent::Insert,
+            TriggerEvent::Update(Some(vec!["amount".into(), "status".into()])),
+            TriggerEvent::Delete,
+        ],
+        level: TriggerLevel::Row,
+        condition: Some("NEW.status <> 'void (pending)'".into()),
+        function: ObjectName {
+            schema: Some("audit".into()),
+            name: "log_change".into(),
+        },
+        arguments: vec!["orders".into(), "1".into()],
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_statement_level_trigger_definition() {
+    let sql = "CREATE TRIGGER refresh_totals INSTEAD OF TRUNCATE ON orders EXECUTE PROCEDURE refresh()";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::Trigger(TriggerDefinition {
+        name: "refresh_totals".into(),
+        table: ObjectName {
+            schema: None,
+            name: "orders".into(),
+        },
+        timing: TriggerTiming::InsteadOf,
+        events: vec![TriggerEvent::Truncate],
+        level: TriggerLevel::Statement,
+        condition: None,
+        function: ObjectName {
+            schema: None,
+            name: "refresh".into(),
+        },
+        arguments: Vec::new(),
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_sequence_definition() {
+    let sql = "CREATE SEQUENCE audit.event_id INCREMENT BY -1 MINVALUE -9223372036854775808 NO MAXVALUE
+        START WITH -1 CACHE 10 CYCLE OWNED BY audit.events.id;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::Sequence(SequenceDefinition {
+        name: ObjectName {
+            schema: Some("audit".into()),
+            name: "event_id".into(),
+        },
+        increment: Some(-1),
+        min_value: Some(i64::min_value()),
+        max_value: None,
+        start: Some(-1),
+        cache: Some(10),
+        cycle: true,
+        owned_by: Some(SequenceOwner {
+            table: ObjectName {
+                schema: Some("audit".into()),
+                name: "events".into(),
+            },
+            column: "id".into(),
+        }),
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_sequence_definition_without_options() {
+    let sql = "CREATE SEQUENCE invoice_number";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let stmt = &statements[0];
+
+    assert_that!(*stmt).is_equal_to(Statement::Sequence(SequenceDefinition {
+        name: ObjectName {
+            schema: None,
+            name: "invoice_number".into(),
+        },
+        increment: None,
+        min_value: None,
+        max_value: None,
+        start: None,
+        cache: None,
+        cycle: false,
+        owned_by: None,
+        span: Span::default(),
+    }));
+}
+
+#[test]
+fn it_can_parse_a_nextval_column_default() {
+    let sql = "CREATE TABLE invoices (number bigint NOT NULL DEFAULT nextval('public.invoice_number'::regclass))";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let table = match statements[0] {
+        Statement::Table(ref table) => table,
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    };
+    assert_that!(table.columns[0].constraints).contains(ColumnConstraint::Default(AnyValue::NextVal(ObjectName {
+        schema: Some("public".into()),
+        name: "invoice_number".into(),
+    })));
+}
+
+#[test]
+fn it_can_parse_check_constraints() {
+    let sql = "CREATE TABLE products (
+        price numeric(10,2) NOT NULL CHECK (price > 0),
+        discount numeric(10,2) CHECK(discount >= 0 AND (discount < 100)),
+        CONSTRAINT ck_discount CHECK (discount < price) NO INHERIT,
+        CONSTRAINT ck_status CHECK (status IN ('active', 'void'))
+    )";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let table = match statements[0] {
+        Statement::Table(ref table) => table,
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    };
+    assert_that!(table.columns[0].constraints).contains(ColumnConstraint::Check {
+        expression: "price > 0".into(),
+        no_inherit: false,
+    });
+    assert_that!(table.columns[1].constraints).contains(ColumnConstraint::Check {
+        expression: "discount >= 0 AND (discount < 100)".into(),
+        no_inherit: false,
+    });
+    assert_that!(table.constraints).is_equal_to(vec![
+        TableConstraint::Check {
+            name: "ck_discount".into(),
+            expression: "discount < price".into(),
+            no_inherit: true,
+        },
+        TableConstraint::Check {
+            name: "ck_status".into(),
+            expression: "status IN ('active', 'void')".into(),
+            no_inherit: false,
+        },
+    ]);
+}
+
+#[test]
+fn it_can_parse_unique_and_exclusion_constraints() {
+    let sql = "CREATE TABLE bookings (
+        email varchar(100) NOT NULL UNIQUE,
+        room int NOT NULL,
+        during tsrange NOT NULL,
+        CONSTRAINT uq_bookings_room UNIQUE (room, email) INCLUDE (during) WITH (FILLFACTOR=70),
+        CONSTRAINT ex_bookings_overlap EXCLUDE USING gist (room WITH =, during WITH &&)
+    )";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let table = match statements[0] {
+        Statement::Table(ref table) => table,
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    };
+    assert_that!(table.columns[0].constraints).contains(ColumnConstraint::Unique);
+    assert_that!(table.constraints).is_equal_to(vec![
+        TableConstraint::Unique {
+            name: "uq_bookings_room".into(),
+            columns: vec!["room".into(), "email".into()],
+            include: Some(vec!["during".into()]),
+            parameters: Some(vec![IndexParameter::FillFactor(70)]),
+        },
+        TableConstraint::Exclude {
+            name: "ex_bookings_overlap".into(),
+            index_type: Some(IndexType::Gist),
+            elements: vec![
+                ExclusionElement {
+                    column: "room".into(),
+                    operator: "=".into(),
+                },
+                ExclusionElement {
+                    column: "during".into(),
+                    operator: "&&".into(),
+                },
+            ],
+            parameters: None,
+        },
+    ]);
+}
+
+#[test]
+fn it_can_parse_composite_domain_and_range_types() {
+    let sql = "CREATE TYPE address AS (street varchar(100), zip int);
+        CREATE DOMAIN positive AS numeric(10,2) DEFAULT 1 NOT NULL CHECK (VALUE > 0);
+        CREATE DOMAIN label text CONSTRAINT label_length CHECK (char_length(VALUE) < 20);
+        CREATE TYPE floatrange AS RANGE (subtype = double precision, subtype_diff = float8mi);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).is_equal_to(vec![
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "address".into(),
+            },
+            kind: TypeDefinitionKind::Composite(vec![
+                CompositeTypeAttribute {
+                    name: "street".into(),
+                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+                },
+                CompositeTypeAttribute {
+                    name: "zip".into(),
+                    sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                },
+            ]),
+            span: Span::default(),
+        }),
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "positive".into(),
+            },
+            kind: TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: SqlType::Simple(SimpleSqlType::Numeric(Some((10, 2))), None),
+                default: Some(AnyValue::Integer(1, None)),
+                not_null: true,
+                constraints: vec![DomainConstraint {
+                    name: None,
+                    expression: "VALUE > 0".into(),
+                }],
+            }),
+            span: Span::default(),
+        }),
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "label".into(),
+            },
+            kind: TypeDefinitionKind::Domain(DomainDefinition {
+                base_type: SqlType::Simple(SimpleSqlType::Text, None),
+                default: None,
+                not_null: false,
+                constraints: vec![DomainConstraint {
+                    name: Some("label_length".into()),
+                    expression: "char_length(VALUE) < 20".into(),
+                }],
+            }),
+            span: Span::default(),
+        }),
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "floatrange".into(),
+            },
+            kind: TypeDefinitionKind::Range(RangeDefinition {
+                subtype: SqlType::Simple(SimpleSqlType::Double, None),
+                subtype_opclass: None,
+                collation: None,
+                canonical: None,
+                subtype_diff: Some("float8mi".into()),
+            }),
+            span: Span::default(),
+        }),
+    ]);
+}
+
+#[test]
+fn it_can_parse_partial_and_expression_indexes() {
+    let sql = "CREATE UNIQUE INDEX users_email_idx ON users (lower(email)) INCLUDE (name) WHERE deleted_at IS NULL;
+        CREATE INDEX users_name_idx ON public.users USING btree (name COLLATE \"C\" text_pattern_ops DESC, ((id + 1))) WITH (fillfactor='70');";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).is_equal_to(vec![
+        Statement::Index(IndexDefinition {
+            name: "users_email_idx".into(),
+            table: ObjectName {
+                schema: None,
+                name: "users".into(),
+            },
+            columns: vec![IndexColumn {
+                key: IndexKey::Expression("lower(email)".into()),
+                collation: None,
+                operator_class: None,
+                order: None,
+                null_position: None,
+            }],
+            unique: true,
+            index_type: None,
+            include: Some(vec!["name".into()]),
+            storage_parameters: None,
+            predicate: Some("deleted_at IS NULL".into()),
+            span: Span::default(),
+        }),
+        Statement::Index(IndexDefinition {
+            name: "users_name_idx".into(),
+            table: ObjectName {
+                schema: Some("public".into()),
+                name: "users".into(),
+            },
+            columns: vec![
+                IndexColumn {
+                    key: IndexKey::Column("name".into()),
+                    collation: Some("C".into()),
+                    operator_class: Some("text_pattern_ops".into()),
+                    order: Some(IndexOrder::Descending),
+                    null_position: None,
+                },
+                IndexColumn {
+                    key: IndexKey::Expression("(id + 1)".into()),
+                    collation: None,
+                    operator_class: None,
+                    order: None,
+                    null_position: None,
+                },
+            ],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            include: None,
+            storage_parameters: Some(vec![IndexParameter::FillFactor(70)]),
+            predicate: None,
+            span: Span::default(),
+        }),
+    ]);
+}
+
+#[test]
+fn it_can_parse_index_methods_and_storage_parameters() {
+    let sql = "CREATE INDEX events_created_idx ON events USING brin (created_at) WITH (pages_per_range = 32, autosummarize = on);
+        CREATE INDEX events_tags_idx ON events USING gin (tags) WITH (fastupdate = off, gin_pending_list_limit = 512);
+        CREATE INDEX events_bloom_idx ON events USING bloom (kind, source) WITH (length = 80, col1 = '2');";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(3);
+    let indexes = statements
+        .iter()
+        .map(|statement| match *statement {
+            Statement::Index(ref index) => (index.index_type.clone(), index.storage_parameters.clone()),
+            ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+        })
+        .collect::<Vec<_>>();
+    assert_that!(indexes).is_equal_to(vec![
+        (
+            Some(IndexType::Brin),
+            Some(vec![
+                IndexParameter::PagesPerRange(32),
+                IndexParameter::Autosummarize(true),
+            ]),
+        ),
+        (
+            Some(IndexType::Gin),
+            Some(vec![
+                IndexParameter::FastUpdate(false),
+                IndexParameter::PendingListLimit(512),
+            ]),
+        ),
+        (
+            Some(IndexType::Custom("bloom".into())),
+            Some(vec![
+                IndexParameter::Custom("length".into(), "80".into()),
+                IndexParameter::Custom("col1".into(), "2".into()),
+            ]),
+        ),
+    ]);
+}
+
+
+#[test]
+fn it_can_parse_grant_and_revoke_statements() {
+    let sql = "GRANT ALL ON TABLE contacts, public.orders TO reporting WITH GRANT OPTION;
+        REVOKE GRANT OPTION FOR EXECUTE ON FUNCTION fn_audit(integer) FROM public CASCADE;
+        GRANT staff TO alice, bob WITH ADMIN OPTION;
+        ALTER DEFAULT PRIVILEGES FOR ROLE app_owner IN SCHEMA public GRANT SELECT ON TABLES TO reporting;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(4);
+    let privileges = statements
+        .iter()
+        .map(|statement| match *statement {
+            Statement::Privileges(ref privileges) => privileges.clone(),
+            ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+        })
+        .collect::<Vec<_>>();
+
+    // ALL expands to every table privilege for each table
+    assert_that!(privileges[0]).has_length(14);
+    assert_that!(privileges[0][0]).is_equal_to(PrivilegeDefinition {
+        action: PrivilegeAction::Grant,
+        object: PrivilegeObject::Table(ObjectName {
+            schema: None,
+            name: "contacts".into(),
+        }),
+        privilege: Privilege::Select,
+        grantee: "reporting".into(),
+        grant_option: true,
+        span: Span::default(),
+    });
+    assert_that!(privileges[1]).is_equal_to(vec![PrivilegeDefinition {
+        action: PrivilegeAction::Revoke,
+        object: PrivilegeObject::Function(
+            ObjectName {
+                schema: None,
+                name: "fn_audit".into(),
+            },
+            Some(vec![SqlType::Simple(SimpleSqlType::Integer, None)]),
+        ),
+        privilege: Privilege::Execute,
+        grantee: "PUBLIC".into(),
+        grant_option: true,
+        span: Span::default(),
+    }]);
+    assert_that!(privileges[2]).is_equal_to(vec![
+        PrivilegeDefinition {
+            action: PrivilegeAction::Grant,
+            object: PrivilegeObject::Role("staff".into()),
+            privilege: Privilege::Member,
+            grantee: "alice".into(),
+            grant_option: true,
+            span: Span::default(),
+        },
+        PrivilegeDefinition {
+            action: PrivilegeAction::Grant,
+            object: PrivilegeObject::Role("staff".into()),
+            privilege: Privilege::Member,
+            grantee: "bob".into(),
+            grant_option: true,
+            span: Span::default(),
+        },
+    ]);
+    assert_that!(privileges[3]).is_equal_to(vec![PrivilegeDefinition {
+        action: PrivilegeAction::Grant,
+        object: PrivilegeObject::Default(DefaultPrivilegeScope {
+            role: Some("app_owner".into()),
+            schema: Some("public".into()),
+            kind: DefaultPrivilegeKind::Tables,
+        }),
+        privilege: Privilege::Select,
+        grantee: "reporting".into(),
+        grant_option: false,
+        span: Span::default(),
+    }]);
+}
+#[test]
+fn it_can_parse_row_level_security_and_policies() {
+    let sql = "ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+        ALTER TABLE ONLY public.accounts NO FORCE ROW LEVEL SECURITY;
+        CREATE POLICY tenant_isolation ON accounts USING (tenant_id = current_setting('app.tenant')::uuid);
+        CREATE POLICY admin_update ON public.accounts AS RESTRICTIVE FOR UPDATE TO admin, public
+            USING (is_admin()) WITH CHECK (level > 0);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).is_equal_to(vec![
+        Statement::AlterTable(
+            ObjectName {
+                schema: None,
+                name: "accounts".into(),
+            },
+            AlterTableAction::EnableRowSecurity,
+        ),
+        Statement::AlterTable(
+            ObjectName {
+                schema: Some("public".into()),
+                name: "accounts".into(),
+            },
+            AlterTableAction::NoForceRowSecurity,
+        ),
+        Statement::Policy(PolicyDefinition {
+            name: "tenant_isolation".into(),
+            table: ObjectName {
+                schema: None,
+                name: "accounts".into(),
+            },
+            permissive: true,
+            command: PolicyCommand::All,
+            roles: vec!["PUBLIC".into()],
+            using: Some("tenant_id = current_setting('app.tenant')::uuid".into()),
+            with_check: None,
+            span: Span::default(),
+        }),
+        Statement::Policy(PolicyDefinition {
+            name: "admin_update".into(),
+            table: ObjectName {
+                schema: Some("public".into()),
+                name: "accounts".into(),
+            },
+            permissive: false,
+            command: PolicyCommand::Update,
+            roles: vec!["admin".into(), "PUBLIC".into()],
+            using: Some("is_admin()".into()),
+            with_check: Some("level > 0".into()),
+            span: Span::default(),
+        }),
+    ]);
+}
+
+#[test]
+fn it_can_parse_partitioned_tables() {
+    let sql = "CREATE TABLE measurement (city_id int NOT NULL, logdate date NOT NULL) PARTITION BY RANGE (logdate);
+        CREATE TABLE measurement_y2020 PARTITION OF measurement FOR VALUES FROM ('2020-01-01') TO ('2021-01-01');
+        CREATE TABLE public.measurement_other PARTITION OF public.measurement DEFAULT;
+        CREATE TABLE cities (id int, region text) PARTITION BY LIST (region);
+        CREATE TABLE cities_west PARTITION OF cities FOR VALUES IN ('CA', 'OR') PARTITION BY HASH (id);
+        CREATE TABLE cities_west_0 PARTITION OF cities_west FOR VALUES WITH (MODULUS 2, REMAINDER 0);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(6);
+
+    let tables: Vec<TableDefinition> = statements
+        .into_iter()
+        .map(|s| match s {
+            Statement::Table(table) => table,
+            other => panic!("Expected table, got {:?}", other),
+        })
+        .collect();
+
+    assert_that!(tables[0].columns).has_length(2);
+    assert_that!(tables[0].partition_by).is_equal_to(Some(PartitionKey {
+        strategy: PartitionStrategy::Range,
+        key: "logdate".into(),
+    }));
+    assert_that!(tables[0].partition_of).is_none();
+
+    assert_that!(tables[1].columns).is_empty();
+    assert_that!(tables[1].partition_by).is_none();
+    assert_that!(tables[1].partition_of).is_equal_to(Some(TablePartition {
+        parent: ObjectName {
+            schema: None,
+            name: "measurement".into(),
+        },
+        bound: PartitionBound::Range("'2020-01-01'".into(), "'2021-01-01'".into()),
+    }));
+
+    assert_that!(tables[2].partition_of).is_equal_to(Some(TablePartition {
+        parent: ObjectName {
+            schema: Some("public".into()),
+            name: "measurement".into(),
+        },
+        bound: PartitionBound::Default,
+    }));
+
+    assert_that!(tables[3].partition_by).is_equal_to(Some(PartitionKey {
+        strategy: PartitionStrategy::List,
+        key: "region".into(),
+    }));
+
+    assert_that!(tables[4].partition_of).is_equal_to(Some(TablePartition {
+        parent: ObjectName {
+            schema: None,
+            name: "cities".into(),
+        },
+        bound: PartitionBound::List("'CA', 'OR'".into()),
+    }));
+    assert_that!(tables[4].partition_by).is_equal_to(Some(PartitionKey {
+        strategy: PartitionStrategy::Hash,
+        key: "id".into(),
+    }));
+
+    assert_that!(tables[5].partition_of).is_equal_to(Some(TablePartition {
+        parent: ObjectName {
+            schema: None,
+            name: "cities_west".into(),
+        },
+        bound: PartitionBound::Hash("MODULUS 2, REMAINDER 0".into()),
+    }));
+}
+
+#[test]
+fn it_can_parse_identity_and_generated_columns() {
+    let sql = "CREATE TABLE orders (
+            id int GENERATED ALWAYS AS IDENTITY,
+            code bigint GENERATED BY DEFAULT AS IDENTITY (START WITH 10 INCREMENT BY 5) NOT NULL,
+            price numeric NOT NULL,
+            total numeric GENERATED ALWAYS AS (price * 2) STORED
+        );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+
+    let table = match statements.remove(0) {
+        Statement::Table(table) => table,
+        other => panic!("Expected table, got {:?}", other),
+    };
+    assert_that!(table.columns).has_length(4);
+    assert_that!(table.columns[0].constraints)
+        .is_equal_to(vec![ColumnConstraint::Identity(IdentityKind::Always, Vec::new())]);
+    assert_that!(table.columns[1].constraints).is_equal_to(vec![
+        ColumnConstraint::Identity(
+            IdentityKind::ByDefault,
+            vec![SequenceOption::Start(10), SequenceOption::Increment(5)],
+        ),
+        ColumnConstraint::NotNull,
+    ]);
+    assert_that!(table.columns[2].constraints).is_equal_to(vec![ColumnConstraint::NotNull]);
+    assert_that!(table.columns[3].constraints).is_equal_to(vec![ColumnConstraint::Generated("price * 2".into())]);
+}
+
+#[test]
+fn it_can_parse_built_in_types() {
+    let types = vec![
+        ("json", SimpleSqlType::Json),
+        ("jsonb", SimpleSqlType::Jsonb),
+        ("bytea", SimpleSqlType::Binary),
+        ("xml", SimpleSqlType::Xml),
+        ("oid", SimpleSqlType::Oid),
+        ("inet", SimpleSqlType::Inet),
+        ("cidr", SimpleSqlType::Cidr),
+        ("macaddr", SimpleSqlType::MacAddr),
+        ("macaddr8", SimpleSqlType::MacAddr8),
+        ("tsvector", SimpleSqlType::TextSearchVector),
+        ("tsquery", SimpleSqlType::TextSearchQuery),
+        ("point", SimpleSqlType::Point),
+        ("line", SimpleSqlType::Line),
+        ("lseg", SimpleSqlType::Segment),
+        ("box", SimpleSqlType::Box),
+        ("path", SimpleSqlType::Path),
+        ("polygon", SimpleSqlType::Polygon),
+        ("pg_catalog.circle", SimpleSqlType::Circle),
+        ("interval", SimpleSqlType::Interval(None, None)),
+        ("interval(3)", SimpleSqlType::Interval(None, Some(3))),
+        (
+            "INTERVAL YEAR",
+            SimpleSqlType::Interval(Some(IntervalFields::Year), None),
+        ),
+        (
+            "interval day to second(3)",
+            SimpleSqlType::Interval(Some(IntervalFields::DayToSecond), Some(3)),
+        ),
+        ("timestamp", SimpleSqlType::DateTime(None)),
+        ("timestamp(3) without time zone", SimpleSqlType::DateTime(Some(3))),
+        ("timestamptz(6)", SimpleSqlType::DateTimeWithTimeZone(Some(6))),
+        ("time(0)", SimpleSqlType::Time(Some(0))),
+        ("time(2) with time zone", SimpleSqlType::TimeWithTimeZone(Some(2))),
+        ("float4", SimpleSqlType::Single),
+        ("float8", SimpleSqlType::Double),
+        ("float", SimpleSqlType::Double),
+        ("float(24)", SimpleSqlType::Single),
+        ("float(25)", SimpleSqlType::Double),
+        ("decimal", SimpleSqlType::Numeric(None)),
+        ("decimal(12)", SimpleSqlType::Numeric(Some((12, 0)))),
+        ("decimal(10,2)", SimpleSqlType::Numeric(Some((10, 2)))),
+        ("int4range", SimpleSqlType::IntegerRange),
+        ("int8range", SimpleSqlType::BigIntegerRange),
+        ("numrange", SimpleSqlType::NumericRange),
+        ("tsrange", SimpleSqlType::DateTimeRange),
+        ("tstzrange", SimpleSqlType::DateTimeWithTimeZoneRange),
+        ("pg_catalog.daterange", SimpleSqlType::DateRange),
+    ];
+
+    for (sql_type, expected) in types {
+        let sql = format!("CREATE TABLE t (c {}, day int, path text);", sql_type);
+        let tokens = lexer::tokenize_stmt(&sql);
+        assert_that!(tokens).is_ok();
+        let tokens = tokens.unwrap();
+
+        let statements = StatementListParser::new().parse(tokens);
+        assert_that!(statements).named(&sql).is_ok();
+        let mut statements = statements.unwrap();
+        let table = match statements.remove(0) {
+            Statement::Table(table) => table,
+            other => panic!("Expected table, got {:?}", other),
+        };
+        assert_that!(table.columns).has_length(3);
+        assert_that!(table.columns[0].sql_type).is_equal_to(SqlType::Simple(expected.clone(), None));
+        assert_that!(table.columns[1].name).is_equal_to("day".to_owned());
+        assert_that!(table.columns[2].name).is_equal_to("path".to_owned());
+
+        // The formatted type is parsed back to the same type, as it is when read from the catalog
+        let formatted = expected.to_string();
+        let tokens = lexer::tokenize_body(&formatted);
+        assert_that!(tokens).is_ok();
+        let parsed = SqlTypeParser::new().parse(tokens.unwrap());
+        assert_that!(parsed).named(&formatted).is_ok();
+        assert_that!(parsed.unwrap()).is_equal_to(SqlType::Simple(expected, None));
+    }
+
+    // A schema other than pg_catalog refers to a custom type
+    let tokens = lexer::tokenize_body("geo.point").unwrap();
+    let parsed = SqlTypeParser::new().parse(tokens);
+    assert_that!(parsed).is_ok();
+    assert_that!(parsed.unwrap()).is_equal_to(SqlType::Custom(
+        ObjectName {
+            schema: Some("geo".into()),
+            name: "point".into(),
+        },
+        Vec::new(),
+        None,
+    ));
+
+    // Float precision is limited to that of double precision
+    let tokens = lexer::tokenize_body("float(54)").unwrap();
+    assert_that!(SqlTypeParser::new().parse(tokens)).is_err();
+}
+
+#[test]
+fn it_can_parse_a_range_of_a_float_alias() {
+    let sql = "CREATE TYPE measurements AS RANGE (subtype = float8);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).is_equal_to(vec![Statement::Type(TypeDefinition {
+        name: ObjectName {
+            schema: None,
+            name: "measurements".into(),
+        },
+        kind: TypeDefinitionKind::Range(RangeDefinition {
+            subtype: SqlType::Simple(SimpleSqlType::Double, None),
+            subtype_opclass: None,
+            collation: None,
+            canonical: None,
+            subtype_diff: None,
+        }),
+        span: Span::default(),
+    })]);
+}
+
+#[test]
+fn it_can_parse_expression_defaults() {
+    let sql = "CREATE TABLE events (
+            id uuid DEFAULT gen_random_uuid(),
+            created timestamptz DEFAULT now() NOT NULL,
+            updated timestamp DEFAULT CURRENT_TIMESTAMP,
+            counter bigint DEFAULT nextval('counter_seq'::regclass),
+            expires timestamptz DEFAULT (now() + '1 day'::interval)
+        );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+
+    let table = match statements.remove(0) {
+        Statement::Table(table) => table,
+        other => panic!("Expected table, got {:?}", other),
+    };
+    assert_that!(table.columns).has_length(5);
+    let now = Expression::FunctionCall(
+        ObjectName {
+            schema: None,
+            name: "now".into(),
+        },
+        Vec::new(),
+    );
+    assert_that!(table.columns[0].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::Expression(
+        Expression::FunctionCall(
+            ObjectName {
+                schema: None,
+                name: "gen_random_uuid".into(),
+            },
+            Vec::new(),
+        ),
+    ))]);
+    assert_that!(table.columns[1].constraints).is_equal_to(vec![
+        ColumnConstraint::Default(AnyValue::Expression(now.clone())),
+        ColumnConstraint::NotNull,
+    ]);
+    assert_that!(table.columns[2].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::Expression(
+        Expression::Keyword("CURRENT_TIMESTAMP".into()),
+    ))]);
+    assert_that!(table.columns[3].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::NextVal(
+        ObjectName {
+            schema: None,
+            name: "counter_seq".into(),
+        },
+    ))]);
+    assert_that!(table.columns[4].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::Expression(
+        Expression::Nested(Box::new(Expression::BinaryOperator(
+            Box::new(now),
+            "+".into(),
+            Box::new(Expression::Value(Box::new(AnyValue::String(
+                "1 day".into(),
+                Some(SqlType::Simple(SimpleSqlType::Interval(None, None), None)),
+            )))),
+        ))),
+    ))]);
+}
+
+#[test]
+fn it_can_parse_negative_decimal_defaults() {
+    let sql = "CREATE TABLE prices (
+            price numeric(10,2) DEFAULT -1.50,
+            discount numeric DEFAULT 0.25
+        );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+
+    let table = match statements.remove(0) {
+        Statement::Table(table) => table,
+        other => panic!("Expected table, got {:?}", other),
+    };
+    assert_that!(table.columns).has_length(2);
+    assert_that!(table.columns[0].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::Decimal(
+        Decimal::new(-150, 2),
+        None,
+    ))]);
+    assert_that!(table.columns[1].constraints).is_equal_to(vec![ColumnConstraint::Default(AnyValue::Decimal(
+        Decimal::new(25, 2),
+        None,
+    ))]);
+}
+
+#[test]
+fn it_can_parse_expressions_as_returned_by_postgres() {
+    let values = vec![
+        ("now()", "now()"),
+        ("CURRENT_TIMESTAMP", "CURRENT_TIMESTAMP"),
+        ("'x'::character varying", "'x'::varchar"),
+        ("1 + 2 * 3", "1 + 2 * 3"),
+        ("(1 + 2) * 3", "(1 + 2) * 3"),
+        (
+            "lower(('ABC'::text || 'def'::text))",
+            "lower(('ABC'::text || 'def'::text))",
+        ),
+        ("'-1'::integer", "'-1'::int"),
+        ("public.uuid_generate_v4()", "public.uuid_generate_v4()"),
+    ];
+
+    for (sql, expected) in values {
+        let tokens = lexer::tokenize_body(sql);
+        assert_that!(tokens).is_ok();
+        let tokens = tokens.unwrap();
+
+        let value = AnyValueParser::new().parse(tokens);
+        assert_that!(value).named(sql).is_ok();
+        assert_that!(value.unwrap().to_string())
+            .named(sql)
+            .is_equal_to(expected.to_owned());
+    }
+
+    // Operator precedence is preserved in the tree
+    let tokens = lexer::tokenize_body("1 + 2 * 3").unwrap();
+    let value = AnyValueParser::new().parse(tokens).unwrap();
+    let integer = |i| Box::new(Expression::Value(Box::new(AnyValue::Integer(i, None))));
+    assert_that!(value).is_equal_to(AnyValue::Expression(Expression::BinaryOperator(
+        integer(1),
+        "+".into(),
+        Box::new(Expression::BinaryOperator(integer(2), "*".into(), integer(3))),
+    )));
+}
+
+#[test]
+fn it_can_parse_function_attributes() {
+    let sql = "CREATE FUNCTION audit.record(rows int, stable text)
+               RETURNS TABLE (cost numeric(10, 2), safe interval)
+               LANGUAGE plpgsql
+               STABLE EXTERNAL SECURITY DEFINER
+               RETURNS NULL ON NULL INPUT
+               NOT LEAKPROOF PARALLEL RESTRICTED COST 10 ROWS 5
+               SET search_path = audit, '$user'
+               SET work_mem TO '64MB'
+               AS $$ BEGIN END $$;
+
+               CREATE FUNCTION one() RETURNS int AS $$ SELECT 1 $$ LANGUAGE SQL IMMUTABLE CALLED ON NULL INPUT;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+
+    let function = match statements.remove(0) {
+        Statement::Function(function) => function,
+        other => panic!("Expected function, got {:?}", other),
+    };
+    assert_that!(function.arguments).has_length(2);
+    assert_that!(function.language).is_equal_to(FunctionLanguage::PostgreSQL);
+    assert_that!(function.body.trim()).is_equal_to("BEGIN END");
+    match function.return_type {
+        FunctionReturnType::Table(ref columns) => assert_that!(*columns).has_length(2),
+        ref other => panic!("Expected table, got {:?}", other),
+    }
+    assert_that!(function.attributes).is_equal_to(vec![
+        FunctionAttribute::Volatility(FunctionVolatility::Stable),
+        FunctionAttribute::SecurityDefiner(true),
+        FunctionAttribute::Strict(true),
+        FunctionAttribute::Leakproof(false),
+        FunctionAttribute::Parallel(ParallelSafety::Restricted),
+        FunctionAttribute::Cost(10),
+        FunctionAttribute::Rows(5),
+        FunctionAttribute::Set("search_path".into(), vec!["audit".into(), "$user".into()]),
+        FunctionAttribute::Set("work_mem".into(), vec!["64MB".into()]),
+    ]);
+    let attributes = function
+        .attributes
+        .iter()
+        .map(|attribute| attribute.to_string())
+        .collect::<Vec<_>>();
+    assert_that!(attributes[7]).is_equal_to("SET search_path = audit, \"$user\"".to_owned());
+    assert_that!(attributes[8]).is_equal_to("SET work_mem = '64MB'".to_owned());
+
+    let function = match statements.remove(0) {
+        Statement::Function(function) => function,
+        other => panic!("Expected function, got {:?}", other),
+    };
+    assert_that!(function.attributes).is_equal_to(vec![
+        FunctionAttribute::Volatility(FunctionVolatility::Immutable),
+        FunctionAttribute::Strict(false),
+    ]);
+}
+
+#[test]
+fn it_can_parse_procedures() {
+    let sql = "CREATE OR REPLACE PROCEDURE audit.purge(cost int, INOUT rows int DEFAULT 0)
+               LANGUAGE plpgsql
+               SECURITY DEFINER
+               AS $$ BEGIN END $$;
+
+               CREATE PROCEDURE noop() LANGUAGE SQL AS $$ SELECT 1 $$;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+
+    let procedure = match statements.remove(0) {
+        Statement::Procedure(procedure) => procedure,
+        other => panic!("Expected procedure, got {:?}", other),
+    };
+    assert_that!(procedure.name.to_string()).is_equal_to("audit.purge".to_owned());
+    assert_that!(procedure.arguments).has_length(2);
+    assert_that!(procedure.language).is_equal_to(FunctionLanguage::PostgreSQL);
+    assert_that!(procedure.body.trim()).is_equal_to("BEGIN END");
+    assert_that!(procedure.attributes).is_equal_to(vec![FunctionAttribute::SecurityDefiner(true)]);
+
+    let procedure = match statements.remove(0) {
+        Statement::Procedure(procedure) => procedure,
+        other => panic!("Expected procedure, got {:?}", other),
+    };
+    assert_that!(procedure.arguments).is_empty();
+    assert_that!(procedure.language).is_equal_to(FunctionLanguage::SQL);
+}
+
+#[test]
+fn it_can_parse_aggregates() {
+    let sql = "CREATE AGGREGATE stats.my_avg(numeric) (
+                   SFUNC = stats.avg_accum,
+                   STYPE = numeric[],
+                   FINALFUNC = stats.avg_final,
+                   INITCOND = '{0,0}',
+                   PARALLEL = SAFE
+               );
+
+               CREATE OR REPLACE AGGREGATE tally(*) (SFUNC = int8inc, STYPE = bigint, INITCOND = '0');";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let mut statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+
+    let aggregate = match statements.remove(0) {
+        Statement::Aggregate(aggregate) => aggregate,
+        other => panic!("Expected aggregate, got {:?}", other),
+    };
+    assert_that!(aggregate.signature()).is_equal_to("stats.my_avg(numeric)".to_owned());
+    assert_that!(aggregate.state_function.to_string()).is_equal_to("stats.avg_accum".to_owned());
+    assert_that!(aggregate.state_type.to_string()).is_equal_to("numeric[]".to_owned());
+    assert_that!(aggregate.final_function.map(|f| f.to_string())).is_equal_to(Some("stats.avg_final".to_owned()));
+    assert_that!(aggregate.combine_function).is_none();
+    assert_that!(aggregate.initial_condition).is_equal_to(Some("{0,0}".to_owned()));
+    assert_that!(aggregate.parallel).is_equal_to(Some(ParallelSafety::Safe));
+
+    let aggregate = match statements.remove(0) {
+        Statement::Aggregate(aggregate) => aggregate,
+        other => panic!("Expected aggregate, got {:?}", other),
+    };
+    assert_that!(aggregate.signature()).is_equal_to("tally(*)".to_owned());
+    assert_that!(aggregate.state_type).is_equal_to(SqlType::Simple(SimpleSqlType::BigInteger, None));
+}
+
+#[test]
+fn it_can_parse_comments() {
+    let sql = "COMMENT ON TABLE public.contacts IS 'People we''ve met';
+               COMMENT ON COLUMN contacts.first_name IS 'Given name';
+               COMMENT ON COLUMN public.contacts.last_name IS '';
+               COMMENT ON FUNCTION fn_describe(name varchar(10), OUT result text) IS 'Describes a name';
+               COMMENT ON FUNCTION public.fn_now() IS 'Now';
+               COMMENT ON TYPE mood IS 'Feelings';
+               COMMENT ON DOMAIN public.positive IS 'Greater than zero';
+               COMMENT ON INDEX idx_contacts_name IS 'Lookups by name';
+               COMMENT ON CONSTRAINT contacts_pkey ON public.contacts IS NULL;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let comments = statements
+        .unwrap()
+        .into_iter()
+        .map(|statement| match statement {
+            Statement::Comment(comment) => comment,
+            other => panic!("Expected comment, got {:?}", other),
+        })
+        .collect::<Vec<_>>();
+    let described = comments
+        .iter()
+        .map(|comment| (comment.object.to_string(), comment.comment.clone()))
+        .collect::<Vec<_>>();
+    assert_that!(described).is_equal_to(vec![
+        ("TABLE public.contacts".to_owned(), Some("People we've met".to_owned())),
+        ("COLUMN contacts.first_name".to_owned(), Some("Given name".to_owned())),
+        ("COLUMN public.contacts.last_name".to_owned(), Some("".to_owned())),
+        (
+            "FUNCTION fn_describe(varchar(10))".to_owned(),
+            Some("Describes a name".to_owned()),
+        ),
+        ("FUNCTION public.fn_now()".to_owned(), Some("Now".to_owned())),
+        ("TYPE mood".to_owned(), Some("Feelings".to_owned())),
+        ("TYPE public.positive".to_owned(), Some("Greater than zero".to_owned())),
+        ("INDEX idx_contacts_name".to_owned(), Some("Lookups by name".to_owned())),
+        ("CONSTRAINT contacts_pkey ON public.contacts".to_owned(), None),
+    ]);
+}
+
+#[test]
+fn it_can_parse_quoted_identifiers() {
+    let sql = "CREATE TABLE \"Sales\".\"Order\" (
+                   \"Id\" serial NOT NULL,
+                   CustomerName text,
+                   \"user\" text,
+                   \"say \"\"hi\"\"\" text,
+                   _Hidden text,
+                   Total$ money
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(1);
+    let table = match statements[0] {
+        Statement::Table(ref table) => table,
+        ref other => panic!("Expected table, got {:?}", other),
+    };
+
+    // Unquoted identifiers are folded to lower case while quoted identifiers are kept as is
+    assert_that!(table.name).is_equal_to(ObjectName {
+        schema: Some("Sales".into()),
+        name: "Order".into(),
+    });
+    let columns = table.columns.iter().map(|c| c.name.to_owned()).collect::<Vec<_>>();
+    assert_that!(columns).is_equal_to(vec![
+        "Id".to_owned(),
+        "customername".to_owned(),
+        "user".to_owned(),
+        "say \"hi\"".to_owned(),
+        "_hidden".to_owned(),
+        "total$".to_owned(),
+    ]);
+
+    // Identifiers are quoted again when they need to be
+    assert_that!(table.name.to_string()).is_equal_to("\"Sales\".\"Order\"".to_owned());
+    let quoted = table.columns.iter().map(|c| quote_ident(&c.name)).collect::<Vec<_>>();
+    assert_that!(quoted).is_equal_to(vec![
+        "\"Id\"".to_owned(),
+        "customername".to_owned(),
+        "\"user\"".to_owned(),
+        "\"say \"\"hi\"\"\"".to_owned(),
+        "_hidden".to_owned(),
+        "total$".to_owned(),
+    ]);
+}
+
+#[test]
+fn it_records_where_each_statement_was_defined() {
+    let sql = "CREATE SCHEMA my;
+
+               CREATE TABLE my.\"Items\" (
+                   id int
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    // Tokens know where they are too, including those that span quotes
+    assert_that!(tokens[0]).is_equal_to((
+        Location {
+            offset: 0,
+            line: 1,
+            column: 1,
+        },
+        lexer::Token::CREATE,
+        Location {
+            offset: 6,
+            line: 1,
+            column: 7,
+        },
+    ));
+    let (start, _, end) = tokens
+        .iter()
+        .find(|&&(_, ref token, _)| *token == lexer::Token::Identifier("Items".into()))
+        .unwrap();
+    assert_that!(sql[start.offset..end.offset].to_owned()).is_equal_to("\"Items\"".to_owned());
+    assert_that!(start.line).is_equal_to(3);
+    assert_that!(start.column).is_equal_to(32);
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok();
+    let statements = statements.unwrap();
+    assert_that!(statements).has_length(2);
+    let schema = match statements[0] {
+        Statement::Schema(ref schema) => schema,
+        ref other => panic!("Expected schema, got {:?}", other),
+    };
+    assert_that!(sql[schema.span.start.offset..schema.span.end.offset].to_owned())
+        .is_equal_to("CREATE SCHEMA my;".to_owned());
+    let table = match statements[1] {
+        Statement::Table(ref table) => table,
+        ref other => panic!("Expected table, got {:?}", other),
+    };
+    assert_that!(table.span.start).is_equal_to(Location {
+        offset: 34,
+        line: 3,
+        column: 16,
+    });
+    assert_that!(table.span.end).is_equal_to(Location {
+        offset: sql.len(),
+        line: 5,
+        column: 18,
+    });
+    assert_that!(table.span.to_string()).is_equal_to("3:16".to_owned());
+}
+
+#[test]
+fn it_reports_where_a_parse_error_occurred() {
+    let sql = "CREATE SCHEMA my;
+               CREATE SCHEMA ;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok().has_length(2);
+    let statements = statements.unwrap();
+
+    match statements[1] {
+        Statement::Unsupported {
+            error:
+                ParseError::UnrecognizedToken {
+                    token: (start, lexer::Token::Semicolon, _),
+                    ..
+                },
+            ..
+        } => {
+            assert_that!(start.line).is_equal_to(2);
+            assert_that!(start.column).is_equal_to(30);
+        }
+        ref unexpected => panic!("Expected unrecognized token, got {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_skips_statements_it_does_not_understand() {
+    let sql = "SET client_min_messages = warning;
+CREATE SCHEMA my;
+DO $$ BEGIN PERFORM 1; END $$;
+CREATE COLLATION my.german (provider = icu, locale = 'de');
+CREATE SCHEMA other;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert_that!(tokens).is_ok();
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok().has_length(5);
+    let mut statements = statements.unwrap();
+    for statement in statements.iter_mut() {
+        statement.set_source("unknown.sql", sql);
+    }
+
+    let unsupported: Vec<(String, String)> = statements
+        .iter()
+        .filter_map(|statement| match *statement {
+            Statement::Unsupported { ref text, ref span, .. } => Some((text.to_owned(), span.to_string())),
+            _ => None,
+        })
+        .collect();
+    assert_that!(unsupported).is_equal_to(vec![
+        (
+            "SET client_min_messages = warning;".to_owned(),
+            "unknown.sql:1:1".to_owned(),
+        ),
+        (
+            "DO $$ BEGIN PERFORM 1; END $$;".to_owned(),
+            "unknown.sql:3:1".to_owned(),
+        ),
+        (
+            "CREATE COLLATION my.german (provider = icu, locale = 'de');".to_owned(),
+            "unknown.sql:4:1".to_owned(),
+        ),
+    ]);
+
+    match statements[4] {
+        Statement::Schema(ref schema) => assert_that!(schema.name).is_equal_to("other".to_owned()),
+        ref unexpected => panic!("Expected schema, got {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_skips_statements_that_cannot_be_tokenized() {
+    let sql = "CREATE SCHEMA my;
+INSERT INTO my.notes VALUES (E'it\\'s; (escaped');
+DO $body$ BEGIN RAISE NOTICE 'done;'; END $body$; CREATE SCHEMA other;";
+
+    assert_that!(lexer::tokenize_stmt(sql)).is_err();
+    let tokens = lexer::tokenize_stmt_tolerant(sql);
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert_that!(statements).is_ok().has_length(4);
+    let mut statements = statements.unwrap();
+    for statement in statements.iter_mut() {
+        statement.set_source("unknown.sql", sql);
+    }
+
+    match statements[1] {
+        Statement::Unsupported { ref text, ref span, .. } => {
+            assert_that!(*text).is_equal_to("INSERT INTO my.notes VALUES (E'it\\'s; (escaped');".to_owned());
+            assert_that!(span.to_string()).is_equal_to("unknown.sql:2:1".to_owned());
+        }
+        ref unexpected => panic!("Expected unsupported statement, got {:?}", unexpected),
+    }
+    match statements[3] {
+        Statement::Schema(ref schema) => {
+            assert_that!(schema.name).is_equal_to("other".to_owned());
+            assert_that!(schema.span.to_string()).is_equal_to("unknown.sql:3:51".to_owned());
+        }
+        ref unexpected => panic!("Expected schema, got {:?}", unexpected),
+    }
+}
@@ -55,6 +55,7 @@ macro_rules! generate_simple_package {
             let mut package = Package::new();
             package.push_schema(SchemaDefinition {
                 name: $namespace.to_string(),
+                span: Span::default(),
             });
             let table_name = ObjectName {
                 schema: Some($namespace.to_string()),
@@ -75,18 +76,29 @@ macro_rules! generate_simple_package {
                     },
                 ],
                 constraints: Vec::new(),
+                row_security: false,
+                force_row_security: false,
+                policies: Vec::new(),
+                partition_by: None,
+                partition_of: None,
+                span: Span::default(),
             });
             package.push_index(IndexDefinition {
                 name: "idx_contacts_name".to_owned(),
                 table: table_name,
                 columns: vec![IndexColumn {
-                    name: "name".to_owned(),
+                    key: IndexKey::Column("name".to_owned()),
+                    collation: None,
+                    operator_class: None,
                     order: None,
                     null_position: None,
                 }],
                 unique: false,
                 index_type: None,
+                include: None,
                 storage_parameters: None,
+                predicate: None,
+                span: Span::default(),
             });
             package.set_defaults(&Project::default());
             package.validate(&Vec::new()).unwrap();
@@ -159,7 +171,7 @@ macro_rules! assert_simple_package {
         assert_that!(index.storage_parameters).is_none();
         assert_that!(index.columns).named("index.columns").has_length(1);
         let index_col = &index.columns[0];
-        assert_that!(index_col.name).is_equal_to("name".to_string());
+        assert_that!(index_col.key).is_equal_to(IndexKey::Column("name".to_string()));
         assert_that!(index_col.order)
             .is_some()
             .is_equal_to(IndexOrder::Ascending);
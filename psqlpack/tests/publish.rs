@@ -15,7 +15,10 @@ use spectral::prelude::*;
 macro_rules! publish_package {
     ($db_name:ident, $connection:ident, $package:ident) => {{
         // Use the default publish profile
-        let mut publish_profile = PublishProfile::default();
+        publish_package!($db_name, $connection, $package, PublishProfile::default())
+    }};
+    ($db_name:ident, $connection:ident, $package:ident, $publish_profile:expr) => {{
+        let mut publish_profile = $publish_profile;
         publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
         publish_profile.generation_options.drop_columns = Toggle::Allow; // We allow this in some tests
         publish_profile.generation_options.drop_indexes = Toggle::Allow; // We allow this in some tests
@@ -24,6 +27,7 @@ macro_rules! publish_package {
         let log = Logger::root(Discard.fuse(), o!());
         let capabilities = Capabilities::from_connection(&log, &$connection).unwrap();
         let target_package = Package::from_connection(&log, &$connection, &capabilities).unwrap();
+        $package.resolve_views(&log, &$connection, &capabilities).unwrap();
 
         // Generate delta and apply
         let delta = Delta::generate(
@@ -58,7 +62,7 @@ fn it_can_create_a_database_that_doesnt_exist() {
     drop_db!(client, connection.database());
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -77,7 +81,7 @@ fn it_can_add_a_new_table_to_an_existing_database() {
     drop_table!(client, NAMESPACE, "contacts");
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -105,7 +109,7 @@ fn it_can_add_a_new_column_to_an_existing_table() {
         .unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -133,7 +137,7 @@ fn it_can_modify_an_existing_column_on_a_table() {
         .unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -156,7 +160,7 @@ fn it_can_drop_an_existing_column_on_a_table() {
     client.batch_execute(&format!("CREATE TABLE {}.contacts (id serial PRIMARY KEY NOT NULL, name character varying(50) NOT NULL, last_name character varying(10))", NAMESPACE)).unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -184,7 +188,7 @@ fn it_can_add_a_new_index_to_an_existing_table() {
         .unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -213,7 +217,7 @@ fn it_can_modify_an_index_on_a_table() {
         .unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
@@ -248,7 +252,855 @@ fn it_can_drop_an_index_on_a_table() {
         .unwrap();
 
     // Publish with basic assert
-    let package = generate_simple_package!(NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+}
+
+#[test]
+fn it_can_grant_privileges_on_a_table() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_grant_privileges_on_a_table";
+    const ROLE: &str = "psqlpack_reporting";
+
+    // Preliminary: create a database without the table and make sure the role exists
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+    client
+        .batch_execute(&format!(
+            "DO $$BEGIN IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = '{0}') THEN CREATE ROLE {0}; END IF; END$$",
+            ROLE
+        ))
+        .unwrap();
+
+    // Publish with privileges granted on the table
+    let mut package = generate_simple_package!(NAMESPACE);
+    let privileges = PrivilegeDefinition::expand(
+        PrivilegeAction::Grant,
+        Some(vec![Privilege::Select, Privilege::Insert]),
+        vec![PrivilegeObject::Table(ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "contacts".to_owned(),
+        })],
+        vec![ROLE.to_owned()],
+        false,
+    )
+    .unwrap();
+    for privilege in privileges.iter() {
+        package.push_privilege(privilege.clone());
+    }
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
+    for privilege in privileges.iter() {
+        assert_that!(final_package.privileges).contains(privilege);
+    }
+}
+
+#[test]
+fn it_can_add_row_level_security_policies_to_a_table() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_row_level_security_policies_to_a_table";
+
+    // Preliminary: create a database without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Publish with row level security enabled and a policy restricting access by name
+    let mut package = generate_simple_package!(NAMESPACE);
+    let table_name = ObjectName {
+        schema: Some(NAMESPACE.to_owned()),
+        name: "contacts".to_owned(),
+    };
+    package
+        .alter_table(&table_name, AlterTableAction::EnableRowSecurity, "public")
+        .unwrap();
+    package
+        .push_policy(
+            PolicyDefinition {
+                name: "own_contacts".to_owned(),
+                table: table_name.clone(),
+                permissive: true,
+                command: PolicyCommand::Select,
+                roles: vec!["PUBLIC".to_owned()],
+                using: Some("name = current_user".to_owned()),
+                with_check: None,
+                span: Span::default(),
+            },
+            "public",
+        )
+        .unwrap();
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    let table = final_package.tables.iter().find(|t| t.name == table_name).unwrap();
+    assert_that!(table.row_security).is_true();
+    assert_that!(table.force_row_security).is_false();
+    assert_that!(table.policies).has_length(1);
+    let policy = &table.policies[0];
+    assert_that!(policy.name).is_equal_to("own_contacts".to_owned());
+    assert_that!(policy.command).is_equal_to(PolicyCommand::Select);
+    assert_that!(policy.roles).is_equal_to(vec!["PUBLIC".to_owned()]);
+    assert_that!(policy.using).is_equal_to(Some("((name)::text = CURRENT_USER)".to_owned()));
+}
+
+#[test]
+fn it_can_publish_a_partitioned_table() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_a_partitioned_table";
+
+    // Preliminary: create a database without the table. Dropping the parent drops the partitions.
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Partition the table by id and split it into two partitions
+    let mut package = generate_simple_package!(NAMESPACE);
+    let table_name = ObjectName {
+        schema: Some(NAMESPACE.to_owned()),
+        name: "contacts".to_owned(),
+    };
+    package.tables[0].partition_by = Some(PartitionKey {
+        strategy: PartitionStrategy::Hash,
+        key: "id".to_owned(),
+    });
+    for remainder in 0..2 {
+        package.push_table(TableDefinition {
+            name: ObjectName {
+                schema: Some(NAMESPACE.to_owned()),
+                name: format!("contacts_{}", remainder),
+            },
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            row_security: false,
+            force_row_security: false,
+            policies: Vec::new(),
+            partition_by: None,
+            partition_of: Some(TablePartition {
+                parent: table_name.clone(),
+                bound: PartitionBound::Hash(format!("MODULUS 2, REMAINDER {}", remainder)),
+            }),
+            span: Span::default(),
+        });
+    }
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    let table = final_package.tables.iter().find(|t| t.name == table_name).unwrap();
+    assert_that!(table.partition_by).is_equal_to(Some(PartitionKey {
+        strategy: PartitionStrategy::Hash,
+        key: "id".to_owned(),
+    }));
+    let partition = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.name == "contacts_1" && t.name.schema == table_name.schema)
+        .unwrap();
+    assert_that!(partition.partition_of).is_equal_to(Some(TablePartition {
+        parent: table_name.clone(),
+        bound: PartitionBound::Hash("modulus 2, remainder 1".to_owned()),
+    }));
+
+    // The index on the partitioned table is implied for each partition
+    let indexes = final_package
+        .indexes
+        .iter()
+        .filter(|i| i.table.schema == table_name.schema)
+        .count();
+    assert_that!(indexes).is_equal_to(1);
+}
+
+#[test]
+fn it_can_publish_identity_and_generated_columns() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_identity_and_generated_columns";
+
+    // Preliminary: create a database without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Add an identity column and a column generated from the name
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "code".into(),
+        sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
+        constraints: vec![
+            ColumnConstraint::NotNull,
+            ColumnConstraint::Identity(
+                IdentityKind::ByDefault,
+                vec![SequenceOption::Start(10), SequenceOption::Increment(5)],
+            ),
+        ],
+    });
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "name_length".into(),
+        sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+        constraints: vec![ColumnConstraint::Generated("length(name)".into())],
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.schema == Some(NAMESPACE.to_owned()) && t.name.name == "contacts")
+        .unwrap();
+    assert_that!(table.columns).has_length(4);
+    assert_that!(table.columns[2].constraints).is_equal_to(vec![
+        ColumnConstraint::NotNull,
+        ColumnConstraint::Identity(
+            IdentityKind::ByDefault,
+            vec![SequenceOption::Increment(5), SequenceOption::Start(10)],
+        ),
+    ]);
+    let generated = table.columns[3].constraints.iter().any(|c| match *c {
+        ColumnConstraint::Generated(ref expression) => expression.contains("length"),
+        _ => false,
+    });
+    assert_that!(generated).is_true();
+
+    // The serial column is still recognized as such rather than as an identity
+    assert_that!(table.columns[0].sql_type).is_equal_to(SqlType::Simple(SimpleSqlType::Serial, None));
+}
+
+#[test]
+fn it_can_publish_built_in_types() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_built_in_types";
+
+    // Preliminary: create a database without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Add a column for each of the built in types
+    let types = vec![
+        SimpleSqlType::Json,
+        SimpleSqlType::Jsonb,
+        SimpleSqlType::Binary,
+        SimpleSqlType::Xml,
+        SimpleSqlType::Oid,
+        SimpleSqlType::Inet,
+        SimpleSqlType::Cidr,
+        SimpleSqlType::MacAddr,
+        SimpleSqlType::MacAddr8,
+        SimpleSqlType::TextSearchVector,
+        SimpleSqlType::TextSearchQuery,
+        SimpleSqlType::Point,
+        SimpleSqlType::Line,
+        SimpleSqlType::Segment,
+        SimpleSqlType::Box,
+        SimpleSqlType::Path,
+        SimpleSqlType::Polygon,
+        SimpleSqlType::Circle,
+        SimpleSqlType::Interval(None, None),
+        SimpleSqlType::Interval(Some(IntervalFields::DayToSecond), Some(3)),
+        SimpleSqlType::DateTime(Some(3)),
+        SimpleSqlType::DateTimeWithTimeZone(Some(0)),
+        SimpleSqlType::Time(Some(6)),
+        SimpleSqlType::TimeWithTimeZone(None),
+        SimpleSqlType::IntegerRange,
+        SimpleSqlType::BigIntegerRange,
+        SimpleSqlType::NumericRange,
+        SimpleSqlType::DateTimeRange,
+        SimpleSqlType::DateTimeWithTimeZoneRange,
+        SimpleSqlType::DateRange,
+    ];
+    let mut package = generate_simple_package!(NAMESPACE);
+    for (i, simple) in types.iter().enumerate() {
+        package.tables[0].columns.push(ColumnDefinition {
+            name: format!("col_{}", i),
+            sql_type: SqlType::Simple(simple.clone(), None),
+            constraints: vec![ColumnConstraint::Null],
+        });
+    }
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.schema == Some(NAMESPACE.to_owned()) && t.name.name == "contacts")
+        .unwrap();
+    assert_that!(table.columns).has_length(2 + types.len());
+    for (column, simple) in table.columns.iter().skip(2).zip(types) {
+        assert_that!(column.sql_type)
+            .named(&column.name)
+            .is_equal_to(SqlType::Simple(simple, None));
+    }
+}
+
+#[test]
+fn it_can_publish_expression_defaults() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_expression_defaults";
+
+    // Preliminary: create a database without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Add a column defaulting to a function call and give the name a literal default
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.tables[0].columns[1]
+        .constraints
+        .push(ColumnConstraint::Default(AnyValue::String("unknown".into(), None)));
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "created".into(),
+        sql_type: SqlType::Simple(SimpleSqlType::DateTimeWithTimeZone(None), None),
+        constraints: vec![
+            ColumnConstraint::NotNull,
+            ColumnConstraint::Default(AnyValue::Expression(Expression::FunctionCall(
+                ObjectName {
+                    schema: None,
+                    name: "now".into(),
+                },
+                Vec::new(),
+            ))),
+        ],
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.schema == Some(NAMESPACE.to_owned()) && t.name.name == "contacts")
+        .unwrap();
+    assert_that!(table.columns).has_length(3);
+    let default = |column: &ColumnDefinition| {
+        column.constraints.iter().find_map(|c| match *c {
+            ColumnConstraint::Default(ref value) => Some(value.to_string()),
+            _ => None,
+        })
+    };
+    assert_that!(default(&table.columns[0])).is_none();
+    assert_that!(default(&table.columns[1])).is_equal_to(Some("'unknown'::varchar".to_owned()));
+    assert_that!(default(&table.columns[2])).is_equal_to(Some("now()".to_owned()));
+}
+
+#[test]
+fn it_can_publish_function_attributes() {
+    // Functions aren't dropped by default so a separate database is used
+    const DB_NAME: &str = "psqlpack_function_db";
+    const NAMESPACE: &str = "it_can_publish_function_attributes";
+
+    // Preliminary: create a database with the schema for the function
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let _ = create_db!(connection);
+
+    // Add a security definer function to the package
+    let mut package = generate_simple_package!(NAMESPACE);
+    let attributes = vec![
+        FunctionAttribute::Volatility(FunctionVolatility::Stable),
+        FunctionAttribute::Strict(true),
+        FunctionAttribute::SecurityDefiner(true),
+        FunctionAttribute::Parallel(ParallelSafety::Safe),
+        FunctionAttribute::Cost(10),
+        FunctionAttribute::Set("search_path".into(), vec![NAMESPACE.into(), "pg_temp".into()]),
+    ];
+    package.push_function(FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "contact_count".to_owned(),
+        },
+        arguments: Vec::new(),
+        return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::BigInteger, None)),
+        body: format!(" SELECT count(*) FROM {}.contacts ", NAMESPACE),
+        language: FunctionLanguage::SQL,
+        attributes: attributes.clone(),
+        span: Span::default(),
+    });
+    package.push_comment(CommentDefinition {
+        object: CommentObject::Function(
+            ObjectName {
+                schema: Some(NAMESPACE.to_owned()),
+                name: "contact_count".to_owned(),
+            },
+            Vec::new(),
+        ),
+        comment: Some("Counts every contact".to_owned()),
+        span: Span::default(),
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let function = final_package
+        .functions
+        .iter()
+        .find(|f| f.name.schema == Some(NAMESPACE.to_owned()) && f.name.name == "contact_count")
+        .unwrap();
+    assert_that!(function.attributes).is_equal_to(attributes);
+
+    // Publishing the same package again leaves the unchanged function (and its comment) alone
+    let log = Logger::root(Discard.fuse(), o!());
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let publish_profile = PublishProfile::default();
+    let target_package = Some(final_package);
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let script = std::env::temp_dir().join(format!("{}.sql", NAMESPACE));
+    delta.write_sql(&log, &script).unwrap();
+    let sql = std::fs::read_to_string(&script).unwrap();
+    assert_that!(sql.contains("contact_count")).is_false();
+}
+
+#[test]
+fn it_can_publish_procedures_and_aggregates() {
+    // Functions aren't dropped by default so a separate database is used
+    const DB_NAME: &str = "psqlpack_procedure_db";
+    const NAMESPACE: &str = "it_can_publish_procedures_and_aggregates";
+
+    // Preliminary: create a database with the schema for the procedure
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let _ = create_db!(connection);
+
+    // Add a procedure along with an aggregate built from a built in function
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_procedure(ProcedureDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "clear_contacts".to_owned(),
+        },
+        arguments: Vec::new(),
+        body: format!(" DELETE FROM {}.contacts ", NAMESPACE),
+        language: FunctionLanguage::SQL,
+        attributes: vec![FunctionAttribute::SecurityDefiner(true)],
+        span: Span::default(),
+    });
+    package.push_aggregate(AggregateDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "my_sum".to_owned(),
+        },
+        arguments: vec![FunctionArgument {
+            mode: None,
+            name: None,
+            sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            default: None,
+        }],
+        state_function: ObjectName {
+            schema: None,
+            name: "int4pl".to_owned(),
+        },
+        state_type: SqlType::Simple(SimpleSqlType::Integer, None),
+        final_function: None,
+        combine_function: None,
+        initial_condition: Some("0".to_owned()),
+        parallel: None,
+        span: Span::default(),
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let procedure = final_package
+        .procedures
+        .iter()
+        .find(|p| p.name.schema == Some(NAMESPACE.to_owned()) && p.name.name == "clear_contacts")
+        .unwrap();
+    assert_that!(procedure.attributes).is_equal_to(vec![FunctionAttribute::SecurityDefiner(true)]);
+    assert!(!final_package.functions.iter().any(|f| f.name.name == "my_sum"));
+    let aggregate = final_package
+        .aggregates
+        .iter()
+        .find(|a| a.name.schema == Some(NAMESPACE.to_owned()) && a.name.name == "my_sum")
+        .unwrap();
+    assert_that!(aggregate.signature()).is_equal_to(format!("{}.my_sum(int)", NAMESPACE));
+    assert_that!(aggregate.state_function.to_string()).is_equal_to("pg_catalog.int4pl".to_owned());
+    assert_that!(aggregate.initial_condition).is_equal_to(Some("0".to_owned()));
+
+    // Publishing again leaves the aggregate as is rather than attempting to create it again
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let aggregates = final_package.aggregates.iter().filter(|a| a.name.name == "my_sum");
+    assert_that!(aggregates.count()).is_equal_to(1);
+
+    // The unchanged procedure isn't replaced either
+    let log = Logger::root(Discard.fuse(), o!());
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let publish_profile = PublishProfile::default();
+    let target_package = Some(final_package);
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let script = std::env::temp_dir().join(format!("{}.sql", NAMESPACE));
+    delta.write_sql(&log, &script).unwrap();
+    let sql = std::fs::read_to_string(&script).unwrap();
+    assert_that!(sql.contains("clear_contacts")).is_false();
+}
+
+#[test]
+fn it_can_publish_overloaded_functions() {
+    // Functions are dropped in this test so a separate database is used
+    const DB_NAME: &str = "psqlpack_overload_db";
+    const NAMESPACE: &str = "it_can_publish_overloaded_functions";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let _ = create_db!(connection);
+
+    fn describe(argument_types: Vec<SqlType>) -> FunctionDefinition {
+        FunctionDefinition {
+            name: ObjectName {
+                schema: Some(NAMESPACE.to_owned()),
+                name: "describe".to_owned(),
+            },
+            arguments: argument_types
+                .into_iter()
+                .map(|sql_type| FunctionArgument {
+                    mode: None,
+                    name: None,
+                    sql_type,
+                    default: None,
+                })
+                .collect(),
+            return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Text, None)),
+            body: " SELECT 'value' ".to_owned(),
+            language: FunctionLanguage::SQL,
+            attributes: Vec::new(),
+            span: Span::default(),
+        }
+    }
+    fn signatures(package: &Package) -> Vec<String> {
+        let mut signatures = package
+            .functions
+            .iter()
+            .filter(|f| f.name.schema == Some(NAMESPACE.to_owned()))
+            .map(|f| f.signature())
+            .collect::<Vec<_>>();
+        signatures.sort();
+        signatures
+    }
+    let integer = || SqlType::Simple(SimpleSqlType::Integer, None);
+    let text = || SqlType::Simple(SimpleSqlType::Text, None);
+    let publish_profile = || {
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+        publish_profile
+    };
+
+    // Both overloads are created alongside each other
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_function(describe(vec![integer()]));
+    package.push_function(describe(vec![text()]));
+    let final_package = publish_package!(DB_NAME, connection, package, publish_profile());
+    assert_that!(signatures(&final_package)).is_equal_to(vec![
+        format!("{}.describe(int)", NAMESPACE),
+        format!("{}.describe(text)", NAMESPACE),
+    ]);
+
+    // Changing the arguments of one overload drops its previous signature only
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_function(describe(vec![integer(), integer()]));
+    package.push_function(describe(vec![text()]));
+    let final_package = publish_package!(DB_NAME, connection, package, publish_profile());
+    assert_that!(signatures(&final_package)).is_equal_to(vec![
+        format!("{}.describe(int, int)", NAMESPACE),
+        format!("{}.describe(text)", NAMESPACE),
+    ]);
+}
+
+#[test]
+fn it_can_publish_comments() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_comments";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    fn comments(package: &Package) -> Vec<(String, Option<String>)> {
+        let mut comments = package
+            .comments
+            .iter()
+            .filter(|c| c.object.to_string().contains(NAMESPACE))
+            .map(|c| (c.object.to_string(), c.comment.clone()))
+            .collect::<Vec<_>>();
+        comments.sort();
+        comments
+    }
+    let contacts = || ObjectName {
+        schema: Some(NAMESPACE.to_owned()),
+        name: "contacts".to_owned(),
+    };
+    let comment = |object, comment: &str| CommentDefinition {
+        object,
+        comment: Some(comment.to_owned()),
+        span: Span::default(),
+    };
+
+    // Comment on the table, a column, the index and the primary key
+    let mut package = generate_simple_package!(NAMESPACE);
+    let primary_key = package.tables[0].constraints[0].name().to_owned();
+    let index = ObjectName {
+        schema: Some(NAMESPACE.to_owned()),
+        name: "idx_contacts_name".to_owned(),
+    };
+    package.push_comment(comment(CommentObject::Table(contacts()), "People we've met"));
+    package.push_comment(comment(CommentObject::Column(contacts(), "name".into()), "Full name"));
+    package.push_comment(comment(CommentObject::Index(index), "Lookups by name"));
+    let constraint = CommentObject::Constraint(contacts(), primary_key.clone());
+    package.push_comment(comment(constraint, "Identity"));
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_that!(comments(&final_package)).is_equal_to(vec![
+        (
+            format!("COLUMN {}.contacts.name", NAMESPACE),
+            Some("Full name".to_owned()),
+        ),
+        (
+            format!("CONSTRAINT {} ON {}.contacts", primary_key, NAMESPACE),
+            Some("Identity".to_owned()),
+        ),
+        (
+            format!("INDEX {}.idx_contacts_name", NAMESPACE),
+            Some("Lookups by name".to_owned()),
+        ),
+        (
+            format!("TABLE {}.contacts", NAMESPACE),
+            Some("People we've met".to_owned()),
+        ),
+    ]);
+
+    // Comments that are no longer in the source are removed
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_comment(comment(CommentObject::Table(contacts()), "Everyone"));
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_that!(comments(&final_package)).is_equal_to(vec![(
+        format!("TABLE {}.contacts", NAMESPACE),
+        Some("Everyone".to_owned()),
+    )]);
+}
+
+#[test]
+fn it_can_publish_quoted_identifiers() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_quoted_identifiers";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "\"Order\"");
+
+    // A mixed case table with a reserved word and mixed case columns
+    let table_name = ObjectName {
+        schema: Some(NAMESPACE.to_owned()),
+        name: "Order".to_owned(),
+    };
+    let column = |name: &str, sql_type, constraints| ColumnDefinition {
+        name: name.to_owned(),
+        sql_type: SqlType::Simple(sql_type, None),
+        constraints,
+    };
+    let mut package = Package::new();
+    package.push_schema(SchemaDefinition {
+        name: NAMESPACE.to_owned(),
+        span: Span::default(),
+    });
+    package.push_table(TableDefinition {
+        name: table_name.clone(),
+        columns: vec![
+            column("Id", SimpleSqlType::Serial, vec![ColumnConstraint::NotNull]),
+            column("user", SimpleSqlType::Text, vec![ColumnConstraint::NotNull]),
+            column("CustomerName", SimpleSqlType::Text, Vec::new()),
+        ],
+        constraints: vec![TableConstraint::Primary {
+            name: "Order_pkey".to_owned(),
+            columns: vec!["Id".to_owned()],
+            parameters: None,
+        }],
+        row_security: false,
+        force_row_security: false,
+        policies: Vec::new(),
+        partition_by: None,
+        partition_of: None,
+        span: Span::default(),
+    });
+    package.push_index(IndexDefinition {
+        name: "IX_Order_user".to_owned(),
+        table: table_name.clone(),
+        columns: vec![IndexColumn {
+            key: IndexKey::Column("user".to_owned()),
+            collation: None,
+            operator_class: None,
+            order: None,
+            null_position: None,
+        }],
+        unique: false,
+        index_type: None,
+        include: None,
+        storage_parameters: None,
+        predicate: None,
+        span: Span::default(),
+    });
+    package.push_comment(CommentDefinition {
+        object: CommentObject::Column(table_name.clone(), "CustomerName".to_owned()),
+        comment: Some("Who placed the order".to_owned()),
+        span: Span::default(),
+    });
+    package.set_defaults(&Project::default());
+    package.validate(&Vec::new()).unwrap();
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    // Names are retrieved exactly as they were defined
+    let table = final_package.tables.iter().find(|t| t.name == table_name).unwrap();
+    let columns = table.columns.iter().map(|c| c.name.to_owned()).collect::<Vec<_>>();
+    assert_that!(columns).is_equal_to(vec!["Id".to_owned(), "user".to_owned(), "CustomerName".to_owned()]);
+    assert_that!(table.constraints[0].name()).is_equal_to("Order_pkey");
+    let index = final_package.indexes.iter().find(|i| i.table == table_name).unwrap();
+    assert_that!(index.name).is_equal_to("IX_Order_user".to_owned());
+
+    // Publishing the same package again leaves the table alone
+    let log = Logger::root(Discard.fuse(), o!());
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
+    publish_profile.generation_options.drop_indexes = Toggle::Ignore;
+    let target_package = Some(final_package);
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let script = std::env::temp_dir().join(format!("{}.sql", NAMESPACE));
+    delta.write_sql(&log, &script).unwrap();
+    let sql = std::fs::read_to_string(&script).unwrap();
+    assert_that!(sql.contains("Order")).is_false();
+}
+
+#[test]
+fn it_can_publish_functions_with_nested_dollar_quotes() {
+    // Functions aren't dropped by default so a separate database is used
+    const DB_NAME: &str = "psqlpack_dollar_quote_db";
+    const NAMESPACE: &str = "it_can_publish_functions_with_nested_dollar_quotes";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let _ = create_db!(connection);
+
+    // The body quotes a dynamic query with $$ itself
+    let body = format!(
+        "
+DECLARE result bigint;
+BEGIN
+    EXECUTE format($$SELECT count(*) FROM %I.contacts$$, '{}') INTO result;
+    RETURN result;
+END
+",
+        NAMESPACE
+    );
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_function(FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "contact_count".to_owned(),
+        },
+        arguments: Vec::new(),
+        return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::BigInteger, None)),
+        body: body.clone(),
+        language: FunctionLanguage::PostgreSQL,
+        attributes: Vec::new(),
+        span: Span::default(),
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    let function = final_package
+        .functions
+        .iter()
+        .find(|f| f.name.schema == Some(NAMESPACE.to_owned()) && f.name.name == "contact_count")
+        .unwrap();
+    assert_that!(function.body).is_equal_to(body);
+
+    let mut client = connection.connect_database().unwrap();
+    let query = format!("SELECT {}.contact_count()", NAMESPACE);
+    let count: i64 = client.query_one(&query[..], &[]).unwrap().get(0);
+    assert_that!(count).is_equal_to(0);
+}
+
+#[test]
+fn it_can_publish_views() {
+    const DB_NAME: &str = "psqlpack_view_db";
+    const NAMESPACE: &str = "it_can_publish_views";
+
+    // Preliminary: start from a clean schema since views are left behind by previous runs
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    client
+        .batch_execute(&format!("DROP SCHEMA IF EXISTS {} CASCADE", NAMESPACE))
+        .unwrap();
+
+    let view = |query: &str| ViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "contact_names".to_owned(),
+        },
+        columns: None,
+        query: query.to_owned(),
+        resolved: None,
+        span: Span::default(),
+    };
+    let materialized_view = || MaterializedViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_owned()),
+            name: "contact_counts".to_owned(),
+        },
+        columns: Some(vec!["name".to_owned(), "total".to_owned()]),
+        query: format!("SELECT name, count(*) FROM {}.contacts GROUP BY name", NAMESPACE),
+        with_data: true,
+        resolved: None,
+        span: Span::default(),
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_view(view(&format!("SELECT id, name FROM {}.contacts", NAMESPACE)));
+    package.push_materialized_view(materialized_view());
+    publish_package!(DB_NAME, connection, package);
+
+    // Publishing the same package again leaves the views alone, even though the database rewrote the queries
+    let log = Logger::root(Discard.fuse(), o!());
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    package.resolve_views(&log, &connection, &capabilities).unwrap();
+    let publish_profile = PublishProfile::default();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let script = std::env::temp_dir().join(format!("{}.sql", NAMESPACE));
+    delta.write_sql(&log, &script).unwrap();
+    let sql = std::fs::read_to_string(&script).unwrap();
+    assert_that!(sql.contains("contact_names")).is_false();
+    assert_that!(sql.contains("contact_counts")).is_false();
+
+    // Removing a column can't be done by replacing the view so it is recreated
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_view(view(&format!("SELECT id FROM {}.contacts", NAMESPACE)));
+    package.push_materialized_view(materialized_view());
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let view = final_package
+        .views
+        .iter()
+        .find(|v| v.name.schema == Some(NAMESPACE.to_owned()) && v.name.name == "contact_names")
+        .unwrap();
+    assert_that!(view.columns).is_equal_to(Some(vec!["id".to_owned()]));
 }